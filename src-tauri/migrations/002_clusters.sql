@@ -0,0 +1,32 @@
+-- =====================================================
+-- TABLE: clusters
+-- PURPOSE: Topics discovered by clustering clip embeddings
+-- EXAMPLES: "kubernetes, deployment, manifest", "invoice, customer, payment"
+-- NOTE: Rebuilt from scratch on every clustering run
+-- =====================================================
+CREATE TABLE IF NOT EXISTS clusters (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    label TEXT NOT NULL,
+    -- Human readable label built from the top terms
+    terms TEXT NOT NULL,
+    -- JSON array of top TF-IDF terms, best first
+    size INTEGER NOT NULL,
+    -- Number of clips in the cluster
+    model TEXT NOT NULL,
+    -- Embedding model the clustering ran on
+    created_at INTEGER NOT NULL
+);
+-- =====================================================
+-- TABLE: clip_clusters (Junction Table)
+-- PURPOSE: Which cluster a clip belongs to (at most one)
+-- NOTE: Clips not assigned to any cluster are treated as noise
+-- =====================================================
+CREATE TABLE IF NOT EXISTS clip_clusters (
+    clip_id TEXT PRIMARY KEY NOT NULL,
+    cluster_id INTEGER NOT NULL,
+    score REAL NOT NULL,
+    -- Cosine similarity to the cluster centroid
+    FOREIGN KEY (clip_id) REFERENCES clips(id) ON DELETE CASCADE,
+    FOREIGN KEY (cluster_id) REFERENCES clusters(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_clip_clusters_cluster ON clip_clusters(cluster_id, score DESC);
@@ -0,0 +1,35 @@
+-- =====================================================
+-- FULL-TEXT SEARCH (FTS5 trigram): Typo-tolerant / CJK search
+-- PURPOSE: Candidate lookup for fuzzy search ("kuberentes" → "kubernetes")
+-- NOTE: Indexes every 3-character sequence, so it also works for text
+--       without word boundaries (Chinese, Japanese) where unicode61 fails
+-- =====================================================
+CREATE VIRTUAL TABLE IF NOT EXISTS clips_trigram USING fts5(
+    content_text,
+    content = clips,
+    content_rowid = rowid,
+    tokenize = 'trigram remove_diacritics 1'
+);
+-- Index existing clips
+INSERT INTO clips_trigram(clips_trigram) VALUES('rebuild');
+-- Triggers to keep the trigram table in sync with clips table
+-- (only text changes re-index, touch/pin/favorite updates are skipped)
+CREATE TRIGGER IF NOT EXISTS clips_trigram_insert
+AFTER
+INSERT ON clips BEGIN
+INSERT INTO clips_trigram(rowid, content_text)
+VALUES (new.rowid, new.content_text);
+END;
+CREATE TRIGGER IF NOT EXISTS clips_trigram_delete
+AFTER DELETE ON clips BEGIN
+INSERT INTO clips_trigram(clips_trigram, rowid, content_text)
+VALUES ('delete', old.rowid, old.content_text);
+END;
+CREATE TRIGGER IF NOT EXISTS clips_trigram_update
+AFTER
+UPDATE OF content_text ON clips BEGIN
+INSERT INTO clips_trigram(clips_trigram, rowid, content_text)
+VALUES ('delete', old.rowid, old.content_text);
+INSERT INTO clips_trigram(rowid, content_text)
+VALUES (new.rowid, new.content_text);
+END;
@@ -0,0 +1,38 @@
+-- =====================================================
+-- TABLE: clip_changes
+-- PURPOSE: Change feed for the UI ("what changed since my cursor?")
+-- NOTE: One row per clip, moved to a new seq on every change, so the table
+--       never holds more than one entry per clip (deleted clips keep a
+--       tombstone row). Replaces polling by updated_at, which missed deletes
+--       and changes within the same second.
+-- =====================================================
+CREATE TABLE IF NOT EXISTS clip_changes (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    clip_id TEXT NOT NULL UNIQUE,
+    deleted INTEGER NOT NULL DEFAULT 0 -- 1 = tombstone
+);
+CREATE TRIGGER IF NOT EXISTS clip_changes_insert
+AFTER
+INSERT ON clips BEGIN
+DELETE FROM clip_changes
+WHERE clip_id = new.id;
+INSERT INTO clip_changes(clip_id, deleted)
+VALUES (new.id, 0);
+END;
+CREATE TRIGGER IF NOT EXISTS clip_changes_update
+AFTER
+UPDATE ON clips BEGIN
+DELETE FROM clip_changes
+WHERE clip_id = new.id;
+INSERT INTO clip_changes(clip_id, deleted)
+VALUES (new.id, 0);
+END;
+CREATE TRIGGER IF NOT EXISTS clip_changes_delete
+AFTER DELETE ON clips BEGIN
+DELETE FROM clip_changes
+WHERE clip_id = old.id;
+INSERT INTO clip_changes(clip_id, deleted)
+VALUES (old.id, 1);
+END;
+-- Keyset pagination orders by (updated_at, id)
+CREATE INDEX IF NOT EXISTS idx_clips_updated_id ON clips(updated_at DESC, id DESC);
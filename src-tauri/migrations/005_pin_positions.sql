@@ -0,0 +1,31 @@
+-- =====================================================
+-- COLUMN: clips.pin_position
+-- PURPOSE: User-defined order of pinned clips (1 = first)
+-- NOTE: Kept dense (1..n) and set exactly when is_pinned = 1.
+--       The first nine pins double as quick-paste slots 1-9.
+-- =====================================================
+ALTER TABLE clips
+ADD COLUMN pin_position INTEGER;
+-- Existing pins keep their current (most recent first) order
+UPDATE clips
+SET pin_position = (
+        SELECT COUNT(*)
+        FROM clips AS newer
+        WHERE newer.is_pinned = 1
+            AND (newer.updated_at, newer.id) >= (clips.updated_at, clips.id)
+    )
+WHERE is_pinned = 1;
+-- Deleting a pinned clip closes the gap it leaves
+CREATE TRIGGER IF NOT EXISTS clips_pin_position_delete
+AFTER DELETE ON clips
+WHEN old.pin_position IS NOT NULL BEGIN
+UPDATE clips
+SET pin_position = pin_position - 1
+WHERE pin_position > old.pin_position;
+END;
+-- Browse order: pins first (by position), then most recent
+CREATE INDEX IF NOT EXISTS idx_clips_pin_order ON clips(
+    IFNULL(pin_position, 2147483647),
+    updated_at DESC,
+    id DESC
+);
@@ -0,0 +1,9 @@
+-- =====================================================
+-- COLUMN: clips.content_markdown
+-- PURPOSE: Markdown rendering of content_html (tables, lists, code blocks,
+--          links and images kept), used for "paste as Markdown" and exports
+-- NOTE: Derived data, set when an html clip is saved. NULL for other types
+--       and for html clips saved before this migration (converted on demand).
+-- =====================================================
+ALTER TABLE clips
+ADD COLUMN content_markdown TEXT;
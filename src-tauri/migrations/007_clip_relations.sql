@@ -0,0 +1,19 @@
+-- =====================================================
+-- TABLE: clip_relations
+-- PURPOSE: Lineage of derived clips (transform results, OCR text, ...)
+-- NOTE: One row per derived clip. Deleting the original only drops the link
+--       (the derived clip becomes a root) unless the caller deletes its
+--       descendants too.
+-- =====================================================
+CREATE TABLE IF NOT EXISTS clip_relations (
+    clip_id TEXT PRIMARY KEY,
+    -- Derived clip
+    derived_from TEXT NOT NULL,
+    -- Clip it was made from
+    transform_id TEXT,
+    -- How, e.g. 'json_prettify'
+    created_at INTEGER NOT NULL,
+    FOREIGN KEY (clip_id) REFERENCES clips(id) ON DELETE CASCADE,
+    FOREIGN KEY (derived_from) REFERENCES clips(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_clip_relations_derived_from ON clip_relations(derived_from);
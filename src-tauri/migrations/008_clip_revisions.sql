@@ -0,0 +1,46 @@
+-- =====================================================
+-- TABLE: clip_revisions
+-- PURPOSE: Previous versions of edited clips, for diffing and reverting
+-- NOTE: A row is written each time a clip's content is replaced (edits and
+--       reverts alike), holding the content as it was before the change.
+-- =====================================================
+CREATE TABLE IF NOT EXISTS clip_revisions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    clip_id TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    content_text TEXT,
+    content_html TEXT,
+    content_rtf TEXT,
+    content_markdown TEXT,
+    detected_type TEXT,
+    metadata TEXT,
+    content_hash TEXT,
+    created_at INTEGER NOT NULL,
+    -- When this version was replaced
+    FOREIGN KEY (clip_id) REFERENCES clips(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_clip_revisions_clip ON clip_revisions(clip_id, id DESC);
+-- =====================================================
+-- TRIGGERS: clips_fts sync
+-- PURPOSE: Drop the old words when a clip's text is edited
+-- NOTE: A plain DELETE on an external content table removes the words of the
+--       row as it is now (already the new text), leaving the old ones
+--       indexed. Same 'delete' command as the trigram triggers instead.
+-- =====================================================
+DROP TRIGGER IF EXISTS clips_fts_delete;
+DROP TRIGGER IF EXISTS clips_fts_update;
+CREATE TRIGGER IF NOT EXISTS clips_fts_delete
+AFTER DELETE ON clips BEGIN
+INSERT INTO clips_fts(clips_fts, rowid, id, content_text)
+VALUES ('delete', old.rowid, old.id, old.content_text);
+END;
+CREATE TRIGGER IF NOT EXISTS clips_fts_update
+AFTER
+UPDATE OF content_text ON clips BEGIN
+INSERT INTO clips_fts(clips_fts, rowid, id, content_text)
+VALUES ('delete', old.rowid, old.id, old.content_text);
+INSERT INTO clips_fts(rowid, id, content_text)
+VALUES (new.rowid, new.id, new.content_text);
+END;
+-- Drop words left behind by the old triggers
+INSERT INTO clips_fts(clips_fts) VALUES('rebuild');
@@ -0,0 +1,17 @@
+-- =====================================================
+-- TABLE: prompt_templates
+-- PURPOSE: User-defined AI prompts ("rewrite as Jira ticket", ...), offered
+--          next to the built-in ones
+-- NOTE: Shared between users through JSON export/import, matched by name
+-- =====================================================
+CREATE TABLE IF NOT EXISTS prompt_templates (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    template TEXT NOT NULL,
+    -- Message sent to the model; {{text}}, {{language}} and {{app_name}} are filled in
+    content_types TEXT NOT NULL DEFAULT '[]',
+    -- JSON array of detected types it is offered for, e.g. ["code"]; [] = any text
+    created_at INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL
+);
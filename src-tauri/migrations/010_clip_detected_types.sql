@@ -0,0 +1,15 @@
+-- =====================================================
+-- TABLE: clip_detected_types
+-- PURPOSE: Secondary content types of a clip (a URL inside JSON, emails in a
+--          CSV, ...), so type filters match them too. The primary type stays
+--          in clips.detected_type.
+-- =====================================================
+CREATE TABLE IF NOT EXISTS clip_detected_types (
+    clip_id TEXT NOT NULL,
+    detected_type TEXT NOT NULL,
+    confidence REAL NOT NULL,
+    -- 0..1, lower for types found inside the text
+    PRIMARY KEY (clip_id, detected_type),
+    FOREIGN KEY (clip_id) REFERENCES clips(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_clip_detected_types_type ON clip_detected_types(detected_type);
@@ -0,0 +1,19 @@
+-- =====================================================
+-- TABLE: clip_entities
+-- PURPOSE: URLs, emails, phone numbers, IP addresses, paths, hex colors,
+--          UUIDs, hashes and ticket ids found anywhere in a clip's text, so
+--          they can be listed across clips ("all URLs copied this week").
+-- =====================================================
+CREATE TABLE IF NOT EXISTS clip_entities (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    clip_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    -- url, email, phone, ipv4, ipv6, path, color, uuid, hash, ticket
+    value TEXT NOT NULL,
+    span_start INTEGER NOT NULL,
+    span_end INTEGER NOT NULL,
+    -- Byte offsets into clips.content_text
+    FOREIGN KEY (clip_id) REFERENCES clips(id) ON DELETE CASCADE
+);
+CREATE INDEX IF NOT EXISTS idx_clip_entities_clip ON clip_entities(clip_id, span_start);
+CREATE INDEX IF NOT EXISTS idx_clip_entities_kind ON clip_entities(kind, value);
@@ -0,0 +1,17 @@
+-- =====================================================
+-- TABLE: backfills
+-- PURPOSE: Progress of one-time jobs deriving data for clips saved before a
+--          feature existed (services/backfill.rs). A job covers the clips up
+--          to max_rowid in rowid order, in batches, and resumes after
+--          last_rowid when the app quits mid-way.
+-- NOTE: Each job is added by the migration of its feature, with the newest
+--       clip at that time. Later clips get the data when they are saved.
+-- =====================================================
+CREATE TABLE IF NOT EXISTS backfills (
+    name TEXT PRIMARY KEY NOT NULL,
+    last_rowid INTEGER NOT NULL DEFAULT 0,
+    max_rowid INTEGER NOT NULL
+);
+-- Secondary types (clip_detected_types) of existing clips
+INSERT OR IGNORE INTO backfills (name, max_rowid)
+SELECT 'detected_types', IFNULL(MAX(rowid), 0) FROM clips;
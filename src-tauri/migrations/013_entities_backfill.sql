@@ -0,0 +1,7 @@
+-- =====================================================
+-- BACKFILL: entities
+-- PURPOSE: Extract clip_entities for the clips saved before they existed
+--          (see 012_backfills.sql)
+-- =====================================================
+INSERT OR IGNORE INTO backfills (name, max_rowid)
+SELECT 'entities', IFNULL(MAX(rowid), 0) FROM clips;
@@ -0,0 +1,29 @@
+-- =====================================================
+-- TABLE: change_feed
+-- PURPOSE: Lets clip_changes drop old tombstones. It kept one per clip ever
+--          deleted; tombstones older than a retention window are now pruned
+--          whenever clips are deleted (ClipRepository::prune_tombstones).
+-- NOTE: pruned_seq is the newest pruned tombstone. A cursor before it may
+--       have missed deletes, so get_changes answers it with reset = true and
+--       the client reloads its clips instead of applying changes.
+-- =====================================================
+CREATE TABLE IF NOT EXISTS change_feed (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    pruned_seq INTEGER NOT NULL
+);
+INSERT OR IGNORE INTO change_feed (id, pruned_seq)
+VALUES (1, 0);
+-- Unix seconds of the change; 0 for tombstones from before this migration,
+-- which go with the first prune
+ALTER TABLE clip_changes
+ADD COLUMN changed_at INTEGER NOT NULL DEFAULT 0;
+DROP TRIGGER IF EXISTS clip_changes_delete;
+CREATE TRIGGER IF NOT EXISTS clip_changes_delete
+AFTER DELETE ON clips BEGIN
+DELETE FROM clip_changes
+WHERE clip_id = old.id;
+INSERT INTO clip_changes(clip_id, deleted, changed_at)
+VALUES (old.id, 1, CAST(strftime('%s', 'now') AS INTEGER));
+END;
+CREATE INDEX IF NOT EXISTS idx_clip_changes_tombstones ON clip_changes(changed_at)
+WHERE deleted = 1;
@@ -0,0 +1,8 @@
+-- =====================================================
+-- BACKFILL: image_hashes
+-- PURPOSE: Store the perceptual hash of the images saved before hashes were
+--          stored, so "more like this" needn't decode them on request
+--          (see 012_backfills.sql)
+-- =====================================================
+INSERT OR IGNORE INTO backfills (name, max_rowid)
+SELECT 'image_hashes', IFNULL(MAX(rowid), 0) FROM clips;
@@ -1,10 +1,36 @@
 // Tauri commands (IPC handlers)
-use crate::models::{AppSettings, ClipItem};
+use crate::models::settings::{
+    EmbeddingBackendKind, PasteFormat, SearchTab, ShortcutAction, ShortcutBinding,
+};
+use crate::models::{
+    AppSettings, ClipChanges, ClipEntity, ClipFilters, ClipItem, ClipRevision, Cluster,
+    DetectedType, EntitySummary, NewPromptTemplate, Page, PromptTemplate, SearchHit, SearchMode,
+};
+use crate::plugins::action_pack::{PluginInfo, PluginManager};
+use crate::repositories::clip_query::{self, ClipQuery, ClipSort, Cursor};
 use crate::repositories::{ClipRepository, SettingsRepository};
-use crate::services::clipboard::ClipboardService;
+use crate::services::ai::{AiOptions, AiOutput, AiService, OpenAiChatProvider, PromptInfo};
+use crate::services::clipboard::{CaptureState, ClipboardService};
+use crate::services::clipboard_platform::ClipboardContent;
+use crate::services::clustering::ClusteringService;
+use crate::services::detectors::{DetectorList, DetectorService};
+use crate::services::edit::EditService;
+use crate::services::entities::EntityKind;
+use crate::services::external_editor::EditorSession;
+use crate::services::fuzzy;
+use crate::services::ocr::OcrService;
 use crate::services::paste;
+use crate::services::paste_format;
+use crate::services::paste_stack::{PasteStack, StackOrder};
+use crate::services::prompt_template::PromptTemplateService;
+use crate::services::scripting::{ScriptInfo, ScriptOptions, ScriptOutput, ScriptService};
 use crate::services::semantic::SemanticService;
-use std::sync::Arc;
+use crate::services::shortcuts::{self, ShortcutStatus};
+use crate::services::similarity::SimilarityService;
+use crate::services::transform::{
+    TransformInfo, TransformOptions, TransformOutput, TransformService,
+};
+use std::sync::{Arc, Mutex};
 use tauri::State;
 
 pub struct AppState {
@@ -12,8 +38,28 @@ pub struct AppState {
     pub clipboard_service: Arc<ClipboardService>,
     pub settings_repository: Arc<SettingsRepository>,
     pub semantic_service: Arc<SemanticService>,
+    pub similarity_service: Arc<SimilarityService>,
+    pub clustering_service: Arc<ClusteringService>,
+    pub transform_service: Arc<TransformService>,
+    pub edit_service: Arc<EditService>,
+    pub ocr_service: Arc<OcrService>,
+    pub ai_service: Arc<AiService>,
+    pub prompt_template_service: Arc<PromptTemplateService>,
+    pub script_service: Arc<ScriptService>,
+    pub detector_service: Arc<DetectorService>,
+    pub plugin_manager: Arc<PluginManager>,
+    pub shortcut_status: Arc<Mutex<Vec<ShortcutStatus>>>,
+    pub paste_stack: Arc<Mutex<PasteStack>>,
+    /// Current cycle-history step and when it was taken
+    pub history_cycle: Arc<Mutex<Option<(i32, std::time::Instant)>>>,
 }
 
+/// Delay between a paste shortcut and the simulated paste keystroke.
+const SHORTCUT_PASTE_DELAY_MS: u64 = 150;
+
+/// Presses of the cycle-history shortcut further apart than this start over.
+const HISTORY_CYCLE_RESET: std::time::Duration = std::time::Duration::from_secs(2);
+
 // ============================================================================
 // Clip Commands
 // ============================================================================
@@ -33,31 +79,39 @@ pub async fn get_recent_clips(
 #[tauri::command]
 pub async fn get_recent_clips_paginated(
     limit: Option<i32>,
-    offset: Option<i32>,
+    cursor: Option<String>,
     favorites_only: Option<bool>,
     pinned_only: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<Vec<ClipItem>, String> {
+) -> Result<Page<ClipItem>, String> {
+    let query = ClipQuery::new()
+        .filters(ClipFilters {
+            favorites_only: favorites_only.unwrap_or(false),
+            pinned_only: pinned_only.unwrap_or(false),
+            ..ClipFilters::default()
+        })
+        .after(cursor.as_deref())
+        .map_err(|e| e.to_string())?
+        .limit(limit.unwrap_or(50));
+
     state
         .repository
-        .get_recent_paginated(
-            limit.unwrap_or(50),
-            offset.unwrap_or(0),
-            favorites_only.unwrap_or(false),
-            pinned_only.unwrap_or(false),
-        )
+        .query_clips(&query)
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Clips inserted, updated or deleted since `cursor`. Call without a cursor
+/// to get the current position, then pass the returned cursor each time. A
+/// result with `reset` means the cursor was too old: reload all clips.
 #[tauri::command]
-pub async fn get_clips_after_timestamp(
-    timestamp: i64,
+pub async fn get_clip_changes(
+    cursor: Option<String>,
     state: State<'_, AppState>,
-) -> Result<Vec<ClipItem>, String> {
+) -> Result<ClipChanges, String> {
     state
         .repository
-        .get_after_timestamp(timestamp)
+        .get_changes(cursor.as_deref())
         .await
         .map_err(|e| e.to_string())
 }
@@ -74,6 +128,87 @@ pub async fn get_clip_by_id(
         .map_err(|e| e.to_string())
 }
 
+/// Content types of a clip besides its `detectedType` (a URL inside JSON,
+/// emails in a CSV, ...), most confident first
+#[tauri::command]
+pub async fn get_detected_types(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<DetectedType>, String> {
+    state
+        .repository
+        .get_detected_types(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Entities found in a clip's text (URLs, emails, IP addresses, ...) in text
+/// order, optionally of one kind
+#[tauri::command]
+pub async fn get_clip_entities(
+    id: String,
+    kind: Option<EntityKind>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ClipEntity>, String> {
+    state
+        .repository
+        .get_entities(&id, kind.as_ref().map(EntityKind::as_str))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Distinct entities across clips used since `since` (Unix seconds), most
+/// recently seen first ("all URLs I've copied this week")
+#[tauri::command]
+pub async fn search_entities(
+    kind: Option<EntityKind>,
+    since: Option<i64>,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<EntitySummary>, String> {
+    state
+        .repository
+        .search_entities(
+            kind.as_ref().map(EntityKind::as_str),
+            since,
+            limit.unwrap_or(50),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Copies every distinct entity of `kind` in a clip, one per line ("copy all
+/// emails"). Returns how many were copied.
+#[tauri::command]
+pub async fn copy_clip_entities(
+    id: String,
+    kind: EntityKind,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    let entities = state
+        .repository
+        .get_entities(&id, Some(kind.as_str()))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut values: Vec<&str> = Vec::new();
+    for entity in &entities {
+        if !values.contains(&entity.value.as_str()) {
+            values.push(&entity.value);
+        }
+    }
+    if values.is_empty() {
+        return Ok(0);
+    }
+
+    state
+        .clipboard_service
+        .set_text(&values.join("\n"))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(values.len())
+}
+
 #[tauri::command]
 pub async fn search_clips(
     query: String,
@@ -88,126 +223,279 @@ pub async fn search_clips(
         query,
         filter_types,
         Some(limit_val),
-        Some(0),
+        None,
         Some(false),
         Some(false),
         use_semantic_search,
         similarity_threshold,
+        None,
+        None,
         state,
     )
     .await
+    .map(|page| page.items)
 }
 
+/// Searches clips (FTS, fuzzy or semantic). `cursor` is the `nextCursor` of
+/// the previous page.
 #[tauri::command]
 pub async fn search_clips_paginated(
     query: String,
     filter_types: Option<Vec<String>>,
     limit: Option<i32>,
-    offset: Option<i32>,
+    cursor: Option<String>,
     favorites_only: Option<bool>,
     pinned_only: Option<bool>,
     use_semantic_search: bool,
     similarity_threshold: Option<f32>,
+    cluster_id: Option<i64>,
+    fuzzy: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<Vec<ClipItem>, String> {
+) -> Result<Page<ClipItem>, String> {
+    let limit_val = limit.unwrap_or(50);
+    let filters = ClipFilters {
+        filter_types,
+        favorites_only: favorites_only.unwrap_or(false),
+        pinned_only: pinned_only.unwrap_or(false),
+        cluster_id,
+    };
+    let threshold = similarity_threshold.unwrap_or(0.3); // Default threshold
+
+    if use_semantic_search && state.semantic_service.is_ready() && !query.trim().is_empty() {
+        let (page, ..) = semantic_search_page(
+            &state,
+            &query,
+            &filters,
+            threshold,
+            limit_val,
+            cursor.as_deref(),
+        )
+        .await?;
+        return Ok(page);
+    }
+
+    // Typo-tolerant search: trigram candidates re-ranked by edit distance
+    if fuzzy.unwrap_or(false) && !query.trim().is_empty() {
+        let offset = Cursor::decode_offset(cursor.as_deref()).map_err(|e| e.to_string())?;
+        let clips = fuzzy::search(&state.repository, &query, &filters, limit_val + 1, offset as i32)
+            .await
+            .map_err(|e| e.to_string())?;
+        return Ok(clip_query::offset_page(clips, limit_val, offset));
+    }
+
+    // Fallback to Full Text Search (FTS)
+    let clip_query = ClipQuery::new()
+        .filters(filters)
+        .text(&query)
+        .after(cursor.as_deref())
+        .map_err(|e| e.to_string())?
+        .limit(limit_val);
+
+    state
+        .repository
+        .query_clips(&clip_query)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Same search as `search_clips_paginated`, but each result says where the
+/// query matched: FTS5 highlight offsets and snippet, fuzzy-matched words, or
+/// the closest chunk of a semantic hit.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn search_clip_hits(
+    query: String,
+    filter_types: Option<Vec<String>>,
+    limit: Option<i32>,
+    cursor: Option<String>,
+    favorites_only: Option<bool>,
+    pinned_only: Option<bool>,
+    use_semantic_search: bool,
+    similarity_threshold: Option<f32>,
+    cluster_id: Option<i64>,
+    fuzzy: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<Page<SearchHit>, String> {
     let limit_val = limit.unwrap_or(50);
-    let offset_val = offset.unwrap_or(0);
-    let fav_val = favorites_only.unwrap_or(false);
-    let pin_val = pinned_only.unwrap_or(false);
+    let filters = ClipFilters {
+        filter_types,
+        favorites_only: favorites_only.unwrap_or(false),
+        pinned_only: pinned_only.unwrap_or(false),
+        cluster_id,
+    };
     let threshold = similarity_threshold.unwrap_or(0.3); // Default threshold
 
     if use_semantic_search && state.semantic_service.is_ready() && !query.trim().is_empty() {
-        // Run semantic search
-        let query_vector = state
+        let (page, query_vector, model) = semantic_search_page(
+            &state,
+            &query,
+            &filters,
+            threshold,
+            limit_val,
+            cursor.as_deref(),
+        )
+        .await?;
+
+        let texts: Vec<&str> = page
+            .items
+            .iter()
+            .map(|c| c.content_text.as_deref().unwrap_or(""))
+            .collect();
+        let chunks = state
             .semantic_service
-            .embed(query.clone())
+            .best_chunks(&query_vector, &model, &texts)
             .await
             .map_err(|e| e.to_string())?;
 
-        // Fetch embeddings with filters
-        let all_embeddings = state
-            .repository
-            .get_embeddings_with_filters(filter_types.clone(), fav_val, pin_val)
+        return Ok(Page {
+            items: page
+                .items
+                .into_iter()
+                .zip(chunks)
+                .map(|(clip, chunk)| {
+                    SearchHit::new(clip, SearchMode::Semantic, chunk.into_iter().collect())
+                })
+                .collect(),
+            next_cursor: page.next_cursor,
+        });
+    }
+
+    if fuzzy.unwrap_or(false) && !query.trim().is_empty() {
+        let offset = Cursor::decode_offset(cursor.as_deref()).map_err(|e| e.to_string())?;
+        let clips = fuzzy::search(&state.repository, &query, &filters, limit_val + 1, offset as i32)
             .await
             .map_err(|e| e.to_string())?;
 
-        // Score all embeddings against query and filter by threshold
-        let mut scored_clips: Vec<(String, f32)> = all_embeddings
+        let hits = clips
             .into_iter()
-            .filter_map(|emb| {
-                let vec_float =
-                    crate::services::semantic::SemanticService::bytes_to_vector(&emb.vector);
-                let score = crate::services::semantic::SemanticService::cosine_similarity(
-                    &query_vector,
-                    &vec_float,
-                );
-
-                if score >= threshold {
-                    Some((emb.clip_id, score))
-                } else {
-                    None
-                }
+            .map(|clip| {
+                let ranges = fuzzy::match_ranges(&query, clip.content_text.as_deref().unwrap_or(""));
+                SearchHit::new(clip, SearchMode::Fuzzy, ranges)
             })
             .collect();
+        return Ok(clip_query::offset_page(hits, limit_val, offset));
+    }
 
-        // Sort by score DESC
-        scored_clips.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    let clip_query = ClipQuery::new()
+        .filters(filters)
+        .text(&query)
+        .after(cursor.as_deref())
+        .map_err(|e| e.to_string())?
+        .limit(limit_val);
 
-        // Paginate in memory
-        let start = offset_val as usize;
-        let end = (start + limit_val as usize).min(scored_clips.len());
+    state
+        .repository
+        .search_hits(&clip_query)
+        .await
+        .map_err(|e| e.to_string())
+}
 
-        if start >= scored_clips.len() {
-            return Ok(Vec::new());
-        }
+/// Semantic search over stored embeddings: one page of clips scored against the
+/// query, best first. Also returns the query vector and the model that embedded
+/// it for follow-up scoring.
+async fn semantic_search_page(
+    state: &AppState,
+    query: &str,
+    filters: &ClipFilters,
+    threshold: f32,
+    limit_val: i32,
+    cursor: Option<&str>,
+) -> Result<(Page<ClipItem>, Vec<f32>, String), String> {
+    let offset_val = Cursor::decode_offset(cursor).map_err(|e| e.to_string())?;
+
+    let (model, _) = state
+        .semantic_service
+        .get_model_info()
+        .ok_or_else(|| "Semantic model is not loaded yet.".to_string())?;
 
-        let page_ids: Vec<String> = scored_clips[start..end]
-            .iter()
-            .map(|(id, _)| id.clone())
-            .collect();
+    // Run semantic search
+    let query_vector = state
+        .semantic_service
+        .embed(query.to_string())
+        .await
+        .map_err(|e| e.to_string())?;
 
-        // Fetch actual clips
-        let mut clips = state
-            .repository
-            .get_clips_by_ids(&page_ids)
-            .await
-            .map_err(|e: anyhow::Error| e.to_string())?;
+    // Fetch embeddings with filters
+    let all_embeddings = state
+        .repository
+        .get_embeddings_with_filters(filters)
+        .await
+        .map_err(|e| e.to_string())?;
 
-        // Sort clips to match the scored order and assign scores
-        clips.sort_by_key(|c| {
-            page_ids
-                .iter()
-                .position(|id| id == &c.id)
-                .unwrap_or(std::usize::MAX)
-        });
+    // Score all embeddings against query and filter by threshold. Vectors
+    // from another model live in a different space, skip them
+    let mut scored_clips: Vec<(String, f32)> = all_embeddings
+        .into_iter()
+        .filter(|emb| emb.model == model)
+        .filter_map(|emb| {
+            let vec_float =
+                crate::services::semantic::SemanticService::bytes_to_vector(&emb.vector);
+            let score = crate::services::semantic::SemanticService::cosine_similarity(
+                &query_vector,
+                &vec_float,
+            );
 
-        for clip in &mut clips {
-            if let Some((_, score)) = scored_clips.iter().find(|(id, _)| id == &clip.id) {
-                clip.similarity_score = Some(*score);
+            if score >= threshold {
+                Some((emb.clip_id, score))
+            } else {
+                None
             }
-        }
+        })
+        .collect();
+
+    // Sort by score DESC
+    scored_clips.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    // Paginate in memory (one extra id tells whether a next page exists)
+    let start = offset_val as usize;
+    let end = (start + limit_val.max(0) as usize + 1).min(scored_clips.len());
 
-        return Ok(clips);
+    if start >= scored_clips.len() {
+        return Ok((
+            clip_query::offset_page(Vec::new(), limit_val, offset_val),
+            query_vector,
+            model,
+        ));
     }
 
-    // Fallback to Full Text Search (FTS)
-    state
+    let page_ids: Vec<String> = scored_clips[start..end]
+        .iter()
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    // Fetch actual clips
+    let mut clips = state
         .repository
-        .search_paginated(
-            &query,
-            filter_types,
-            limit_val,
-            offset_val,
-            fav_val,
-            pin_val,
-        )
+        .get_clips_by_ids(&page_ids)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e: anyhow::Error| e.to_string())?;
+
+    // Sort clips to match the scored order and assign scores
+    clips.sort_by_key(|c| {
+        page_ids
+            .iter()
+            .position(|id| id == &c.id)
+            .unwrap_or(usize::MAX)
+    });
+
+    for clip in &mut clips {
+        if let Some((_, score)) = scored_clips.iter().find(|(id, _)| id == &clip.id) {
+            clip.similarity_score = Some(*score);
+        }
+    }
+
+    Ok((clip_query::offset_page(clips, limit_val, offset_val), query_vector, model))
 }
 
+/// Deletes a clip. With `cascade`, clips derived from it (transform results,
+/// their own results, ...) are deleted too; otherwise they become roots.
 #[tauri::command]
-pub async fn delete_clip(id: String, state: State<'_, AppState>) -> Result<(), String> {
-    // 1. Fetch clip to get file paths
+pub async fn delete_clip(
+    id: String,
+    cascade: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    // 1. Fetch clips to get file paths
     let clip = state
         .repository
         .get_by_id(&id)
@@ -215,35 +503,84 @@ pub async fn delete_clip(id: String, state: State<'_, AppState>) -> Result<(), S
         .map_err(|e| e.to_string())?
         .ok_or_else(|| "Clip not found".to_string())?;
 
-    // 2. Delete files FIRST (before DB record)
+    let mut clips = vec![clip];
+    if cascade.unwrap_or(false) {
+        let descendants = state
+            .repository
+            .get_descendants(&id)
+            .await
+            .map_err(|e| e.to_string())?;
+        clips.extend(descendants);
+    }
+
+    for clip in clips {
+        // 2. Delete files FIRST (before DB record)
+        state
+            .clipboard_service
+            .cleanup_clip_files(&clip)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // 3. Delete DB record
+        state
+            .repository
+            .delete(&clip.id)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn toggle_favorite(id: String, state: State<'_, AppState>) -> Result<bool, String> {
     state
-        .clipboard_service
-        .cleanup_clip_files(&clip)
+        .repository
+        .toggle_favorite(&id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| e.to_string())
+}
 
-    // 3. Delete DB record
+#[tauri::command]
+pub async fn toggle_pin(id: String, state: State<'_, AppState>) -> Result<bool, String> {
     state
         .repository
-        .delete(&id)
+        .toggle_pin(&id)
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Pinned clips in pin order (the first nine are the quick-paste slots).
 #[tauri::command]
-pub async fn toggle_favorite(id: String, state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn get_pinned_clips(state: State<'_, AppState>) -> Result<Vec<ClipItem>, String> {
     state
         .repository
-        .toggle_favorite(&id)
+        .get_pinned_clips()
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Sets the pin order; pinned clips missing from `ids` keep their relative
+/// order after the listed ones.
 #[tauri::command]
-pub async fn toggle_pin(id: String, state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn reorder_pins(ids: Vec<String>, state: State<'_, AppState>) -> Result<(), String> {
     state
         .repository
-        .toggle_pin(&id)
+        .reorder_pins(&ids)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Moves one pinned clip to `position` (1-based).
+#[tauri::command]
+pub async fn move_pin(
+    id: String,
+    position: i64,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state
+        .repository
+        .move_pin(&id, position)
         .await
         .map_err(|e| e.to_string())
 }
@@ -282,10 +619,18 @@ pub async fn clear_all_clips(state: State<'_, AppState>) -> Result<(), String> {
 pub async fn copy_to_clipboard(
     text: String,
     id: Option<String>,
+    format: Option<PasteFormat>,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
-    // If no ID, just copy plain text (for non-clip text)
+    // If no ID, just copy plain text (for non-clip text), as HTML only if asked
     if id.is_none() {
+        if format == Some(PasteFormat::Html) {
+            let content = ClipboardContent::Html {
+                html: paste_format::text_to_html(&text),
+                plain: text,
+            };
+            return write_clipboard_content(&state.clipboard_service, &content).await;
+        }
         state
             .clipboard_service
             .set_text(&text)
@@ -320,9 +665,46 @@ pub async fn copy_to_clipboard(
               clip.pdf_path.as_ref().map(|_| "set"),
               clip.image_path.as_ref().map(|_| "set"));
 
-    // 3. Reconstruct ClipboardContent based on content_type
-    use crate::services::clipboard_platform::ClipboardContent;
+    // 3. Convert to the requested format, or reconstruct ClipboardContent
+    //    based on content_type (Auto)
+    let format = format.unwrap_or_else(|| default_paste_format(&state));
+    let content = formatted_clip_content(clip, text, format).await?;
+
+    // 4. Write all formats to clipboard, pre-seeding the monitor hash
+    write_clipboard_content(&state.clipboard_service, &content).await?;
+
+    eprintln!("[COPY] copy_to_clipboard complete");
+    Ok(())
+}
+
+/// Paste format from settings, for calls that don't pass one.
+fn default_paste_format(state: &AppState) -> PasteFormat {
+    state
+        .settings_repository
+        .load()
+        .map(|settings| settings.default_paste_format)
+        .unwrap_or_default()
+}
+
+/// Clipboard content of a clip converted to `format`; `Auto` (and clips with
+/// no text form) keep every captured format.
+async fn formatted_clip_content(
+    clip: ClipItem,
+    fallback_text: String,
+    format: PasteFormat,
+) -> Result<ClipboardContent, String> {
+    match paste_format::render(&clip, format) {
+        Some(content) => Ok(content),
+        None => clip_clipboard_content(clip, fallback_text).await,
+    }
+}
 
+/// Rebuilds the clipboard formats of a stored clip (reading its files from
+/// disk). Unknown content types fall back to `fallback_text`.
+async fn clip_clipboard_content(
+    clip: ClipItem,
+    fallback_text: String,
+) -> Result<ClipboardContent, String> {
     let content = match clip.content_type.as_str() {
         "text" => ClipboardContent::Text {
             content: clip.content_text.unwrap_or_default(),
@@ -468,70 +850,187 @@ pub async fn copy_to_clipboard(
 
         _ => {
             // Fallback to plain text
-            ClipboardContent::Text { content: text }
+            ClipboardContent::Text {
+                content: fallback_text,
+            }
         }
     };
 
-    // 4. Write all formats to clipboard
+    Ok(content)
+}
+
+/// Writes all formats to the clipboard and tells the monitor, so our own
+/// write isn't captured as a new clip.
+async fn write_clipboard_content(
+    clipboard_service: &ClipboardService,
+    content: &ClipboardContent,
+) -> Result<(), String> {
     eprintln!("[COPY] Writing content to clipboard...");
-    crate::services::clipboard_platform::write_clipboard(&content)
+    crate::services::clipboard_platform::write_clipboard(content)
         .map_err(|e| format!("Failed to write clipboard: {}", e))?;
     eprintln!("[COPY] ✓ Clipboard write complete");
 
-    // 5. Pre-seed monitor hash to prevent re-capturing our own paste
+    // Pre-seed monitor hash to prevent re-capturing our own paste
     eprintln!("[COPY] Pre-seeding monitor hash...");
     {
-        let monitor = state.clipboard_service.get_monitor();
+        let monitor = clipboard_service.get_monitor();
         let mut monitor = monitor.lock().await;
-        monitor.notify_wrote(&content);
+        monitor.notify_wrote(content);
     }
     eprintln!("[COPY] ✓ Monitor notified");
 
-    eprintln!("[COPY] copy_to_clipboard complete");
     Ok(())
 }
 
+// ============================================================================
+// Paste Stack Commands
+// ============================================================================
+
+/// Queues clips for sequential pasting (replacing any previous queue); each
+/// press of the paste-stack shortcut pastes the next one.
 #[tauri::command]
-pub fn get_clipboard_text(state: State<'_, AppState>) -> Result<String, String> {
-    state
-        .clipboard_service
-        .get_text()
-        .map_err(|e: anyhow::Error| e.to_string())
+pub fn start_paste_stack(
+    ids: Vec<String>,
+    order: Option<StackOrder>,
+    send_tab: Option<bool>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<PasteStack, String> {
+    let mut stack = state.paste_stack.lock().map_err(|e| e.to_string())?;
+    stack.start(ids, order.unwrap_or_default(), send_tab.unwrap_or(false));
+    Ok(publish_paste_stack(&app, &stack))
 }
 
-/// Quick Paste: copy clip → hide window (OS refocuses previous app) → simulate Ctrl+V/⌘V
+/// Adds one clip to the end of the queue.
 #[tauri::command]
-pub async fn paste_clip(
-    text: String,
-    id: Option<String>,
+pub fn push_paste_stack(
+    id: String,
     app: tauri::AppHandle,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    use tauri::Manager;
+) -> Result<PasteStack, String> {
+    let mut stack = state.paste_stack.lock().map_err(|e| e.to_string())?;
+    stack.push(id);
+    Ok(publish_paste_stack(&app, &stack))
+}
 
-    // 1. Copy text to clipboard
-    if let Some(ref clip_id) = id {
-        state
-            .repository
-            .touch(clip_id)
-            .await
-            .map_err(|e: anyhow::Error| e.to_string())?;
-    }
-    state
-        .clipboard_service
-        .set_text(&text)
-        .await
-        .map_err(|e: anyhow::Error| e.to_string())?;
+#[tauri::command]
+pub fn get_paste_stack(state: State<'_, AppState>) -> Result<PasteStack, String> {
+    Ok(state.paste_stack.lock().map_err(|e| e.to_string())?.clone())
+}
 
-    // 2. Hide the overlay window — OS auto-refocuses previous app
-    if let Some(window) = app.get_webview_window("main") {
-        let _ = window.hide();
-    }
+#[tauri::command]
+pub fn clear_paste_stack(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<PasteStack, String> {
+    let mut stack = state.paste_stack.lock().map_err(|e| e.to_string())?;
+    stack.clear();
+    Ok(publish_paste_stack(&app, &stack))
+}
 
-    // 3. Wait for OS to settle the focus change
-    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+/// Sends the queue to the frontend ("paste-stack" event) and returns a copy.
+fn publish_paste_stack(app: &tauri::AppHandle, stack: &PasteStack) -> PasteStack {
+    use tauri::Emitter;
 
-    // 4. Simulate paste keystroke (Ctrl+V / ⌘V)
+    let _ = app.emit("paste-stack", stack);
+    stack.clone()
+}
+
+/// Pastes the next queued clip into the focused app, then presses Tab if the
+/// stack asks for it and more clips are left. Clips deleted since queueing
+/// are skipped.
+async fn paste_stack_next(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app_state(app)?;
+
+    let clip = loop {
+        let next = {
+            let mut stack = state.paste_stack.lock().map_err(|e| e.to_string())?;
+            let next = stack.pop_next();
+            publish_paste_stack(app, &stack);
+            next
+        };
+        let Some(id) = next else {
+            return Ok(());
+        };
+        if let Some(clip) = state
+            .repository
+            .get_by_id(&id)
+            .await
+            .map_err(|e| e.to_string())?
+        {
+            break clip;
+        }
+    };
+
+    let content = formatted_clip_content(clip, String::new(), default_paste_format(&state)).await?;
+    write_clipboard_content(&state.clipboard_service, &content).await?;
+
+    tokio::time::sleep(std::time::Duration::from_millis(SHORTCUT_PASTE_DELAY_MS)).await;
+    paste::simulate_paste().map_err(|e| e.to_string())?;
+
+    let send_tab = {
+        let stack = state.paste_stack.lock().map_err(|e| e.to_string())?;
+        stack.send_tab() && !stack.is_empty()
+    };
+    if send_tab {
+        // Let the target app handle the paste before moving focus
+        tokio::time::sleep(std::time::Duration::from_millis(SHORTCUT_PASTE_DELAY_MS)).await;
+        paste::simulate_tab().map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_capture_state(state: State<'_, AppState>) -> Result<CaptureState, String> {
+    Ok(state.clipboard_service.capture_state())
+}
+
+/// Stop saving clipboard changes, for `minutes` or until `resume_capture`.
+#[tauri::command]
+pub fn pause_capture(
+    minutes: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<CaptureState, String> {
+    Ok(state.clipboard_service.pause_capture(minutes))
+}
+
+#[tauri::command]
+pub fn resume_capture(state: State<'_, AppState>) -> Result<CaptureState, String> {
+    Ok(state.clipboard_service.resume_capture())
+}
+
+#[tauri::command]
+pub fn get_clipboard_text(state: State<'_, AppState>) -> Result<String, String> {
+    state
+        .clipboard_service
+        .get_text()
+        .map_err(|e: anyhow::Error| e.to_string())
+}
+
+/// Quick Paste: copy clip → hide window (OS refocuses previous app) → simulate Ctrl+V/⌘V
+#[tauri::command]
+pub async fn paste_clip(
+    text: String,
+    id: Option<String>,
+    format: Option<PasteFormat>,
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    use tauri::Manager;
+
+    // 1. Copy clip to clipboard in the requested format
+    copy_to_clipboard(text, id, format, state).await?;
+
+    // 2. Hide the overlay window — OS auto-refocuses previous app
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.hide();
+    }
+
+    // 3. Wait for OS to settle the focus change
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    // 4. Simulate paste keystroke (Ctrl+V / ⌘V)
     paste::simulate_paste().map_err(|e| e.to_string())?;
 
     Ok(())
@@ -541,43 +1040,271 @@ pub async fn paste_clip(
 // Window / Shortcut Commands
 // ============================================================================
 
-/// Register (or re-register) the global shortcut that toggles the overlay window.
-/// Called at startup from main.rs AND when user changes shortcut in Settings.
+/// Register (or re-register) every global shortcut in `settings`: the window
+/// toggle, the user's shortcut bindings and the pin slots.
+/// Called when the user changes shortcuts in Settings; returns one status per
+/// shortcut so the UI can show which ones failed and why.
 #[tauri::command]
-pub async fn register_global_shortcut(
+pub async fn register_shortcuts(
     app: tauri::AppHandle,
-    shortcut: String,
-) -> Result<(), String> {
-    setup_global_shortcut(&app, &shortcut)
+    settings: AppSettings,
+    state: State<'_, AppState>,
+) -> Result<Vec<ShortcutStatus>, String> {
+    let statuses = setup_shortcuts(&app, &settings);
+    *state.shortcut_status.lock().map_err(|e| e.to_string())? = statuses.clone();
+    Ok(statuses)
 }
 
-/// Shared helper: parse shortcut string, register with toggle behavior.
-/// Used by both the startup code (main.rs) and the `register_global_shortcut` command.
-pub fn setup_global_shortcut(app: &tauri::AppHandle, shortcut: &str) -> Result<(), String> {
-    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+/// Statuses from the last shortcut registration (including the one at startup).
+#[tauri::command]
+pub fn get_shortcut_status(state: State<'_, AppState>) -> Result<Vec<ShortcutStatus>, String> {
+    Ok(state
+        .shortcut_status
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone())
+}
 
-    // Unregister all existing shortcuts first
-    app.global_shortcut()
-        .unregister_all()
-        .map_err(|e| e.to_string())?;
+/// Shared helper: unregister everything, then register all shortcuts from
+/// `settings`. A shortcut that is malformed, clashes with an earlier one or is
+/// taken by another app is skipped and reported; the others still work.
+/// Used by both the startup code (main.rs) and the `register_shortcuts` command.
+pub fn setup_shortcuts(app: &tauri::AppHandle, settings: &AppSettings) -> Vec<ShortcutStatus> {
+    use tauri_plugin_global_shortcut::GlobalShortcutExt;
+
+    if let Err(e) = app.global_shortcut().unregister_all() {
+        eprintln!("[SHORTCUT] Failed to unregister shortcuts: {}", e);
+    }
+
+    let bindings = shortcuts::collect_bindings(settings);
+    let conflicts = shortcuts::check_conflicts(&bindings);
+
+    bindings
+        .into_iter()
+        .zip(conflicts)
+        .map(|(binding, conflict)| {
+            let result = match conflict {
+                Some(error) => Err(error),
+                None => register_shortcut(app, &binding),
+            };
+            if let Err(e) = &result {
+                eprintln!(
+                    "[SHORTCUT] {} ({}): {}",
+                    binding.accelerator,
+                    binding.action.name(),
+                    e
+                );
+            }
+
+            ShortcutStatus {
+                accelerator: binding.accelerator,
+                action: binding.action,
+                registered: result.is_ok(),
+                error: result.err(),
+            }
+        })
+        .collect()
+}
+
+fn register_shortcut(app: &tauri::AppHandle, binding: &ShortcutBinding) -> Result<(), String> {
+    use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
 
     // Parse shortcut string (e.g. "Ctrl+Shift+V")
-    let shortcut_parsed: Shortcut = shortcut
+    let shortcut: Shortcut = binding
+        .accelerator
         .parse()
         .map_err(|e| format!("Invalid shortcut: {e}"))?;
 
-    // Register shortcut: toggle on key-down only (ignore key-up)
+    // Paste actions run on key-up so the shortcut's key is no longer held down
+    // when the paste keystroke is simulated; everything else on key-down
+    let trigger = match binding.action {
+        ShortcutAction::PastePrevious
+        | ShortcutAction::PastePlainText
+        | ShortcutAction::PastePinSlot { .. }
+        | ShortcutAction::PasteStackNext => ShortcutState::Released,
+        _ => ShortcutState::Pressed,
+    };
+
+    let action = binding.action.clone();
     app.global_shortcut()
-        .on_shortcut(shortcut_parsed, move |app, _shortcut, event| {
-            if event.state() == ShortcutState::Pressed {
-                let _ = toggle_window(app);
+        .on_shortcut(shortcut, move |app, _shortcut, event| {
+            if event.state() == trigger {
+                run_shortcut_action(app, action.clone());
             }
         })
+        .map_err(|e| e.to_string())
+}
+
+fn run_shortcut_action(app: &tauri::AppHandle, action: ShortcutAction) {
+    if action == ShortcutAction::ToggleWindow {
+        let _ = toggle_window(app);
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = match &action {
+            ShortcutAction::ToggleWindow => Ok(()),
+            ShortcutAction::PastePrevious => paste_previous_clip(&app).await,
+            ShortcutAction::PastePlainText => paste_plain_text(&app).await,
+            ShortcutAction::OpenSearch { query, tab } => open_search(&app, query, *tab),
+            ShortcutAction::PauseCapture { minutes } => toggle_capture_paused(&app, *minutes),
+            ShortcutAction::ClearClipboard => clear_clipboard(&app).await,
+            ShortcutAction::CycleHistory => cycle_history(&app).await,
+            ShortcutAction::PastePinSlot { slot } => paste_pin_slot(&app, *slot).await,
+            ShortcutAction::PasteStackNext => paste_stack_next(&app).await,
+        };
+
+        if let Err(e) = result {
+            eprintln!("[SHORTCUT] {} failed: {}", action.name(), e);
+        }
+    });
+}
+
+/// App state for shortcut handlers; shortcuts are registered before the
+/// database is ready, so it may not be managed yet.
+fn app_state(app: &tauri::AppHandle) -> Result<State<'_, AppState>, String> {
+    use tauri::Manager;
+
+    app.try_state::<AppState>()
+        .ok_or_else(|| "App is still starting".to_string())
+}
+
+/// Copies a clip to the clipboard and pastes it into the focused app.
+async fn paste_clip_by_id(app: &tauri::AppHandle, id: String) -> Result<(), String> {
+    copy_to_clipboard(String::new(), Some(id), None, app_state(app)?).await?;
+
+    // Give the user time to release the modifiers, otherwise they'd combine
+    // with the simulated Ctrl+V / ⌘V
+    tokio::time::sleep(std::time::Duration::from_millis(SHORTCUT_PASTE_DELAY_MS)).await;
+
+    paste::simulate_paste().map_err(|e| e.to_string())
+}
+
+/// Pastes the clip in pin slot `slot`.
+async fn paste_pin_slot(app: &tauri::AppHandle, slot: i64) -> Result<(), String> {
+    let clip = app_state(app)?
+        .repository
+        .get_pin_slot(slot)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match clip {
+        Some(clip) => paste_clip_by_id(app, clip.id).await,
+        None => Ok(()),
+    }
+}
+
+/// Pastes the clip copied before the current one. Pasting moves it to the
+/// top, so pressing again swaps back.
+async fn paste_previous_clip(app: &tauri::AppHandle) -> Result<(), String> {
+    let recent = app_state(app)?
+        .repository
+        .query_clips(&ClipQuery::new().sort(ClipSort::Recent).limit(2))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    match recent.items.into_iter().nth(1) {
+        Some(clip) => paste_clip_by_id(app, clip.id).await,
+        None => Ok(()),
+    }
+}
+
+/// Replaces the clipboard with its plain text (dropping HTML/RTF) and pastes.
+async fn paste_plain_text(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app_state(app)?;
+    let text = state
+        .clipboard_service
+        .get_text()
+        .map_err(|e| e.to_string())?;
+    state
+        .clipboard_service
+        .set_text(&text)
+        .await
         .map_err(|e| e.to_string())?;
 
+    tokio::time::sleep(std::time::Duration::from_millis(SHORTCUT_PASTE_DELAY_MS)).await;
+
+    paste::simulate_paste().map_err(|e| e.to_string())
+}
+
+/// Shows the window and asks the frontend to start a search.
+fn open_search(app: &tauri::AppHandle, query: &str, tab: SearchTab) -> Result<(), String> {
+    use tauri::{Emitter, Manager};
+
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_minimized().unwrap_or(false) {
+            let _ = window.unminimize();
+        }
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+
+    app.emit(
+        "open-search",
+        serde_json::json!({ "query": query, "tab": tab }),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Pauses clipboard capture (for `minutes` if set), or resumes it if paused.
+fn toggle_capture_paused(app: &tauri::AppHandle, minutes: Option<u32>) -> Result<(), String> {
+    let clipboard_service = &app_state(app)?.clipboard_service;
+    if clipboard_service.capture_state().paused {
+        clipboard_service.resume_capture();
+    } else {
+        clipboard_service.pause_capture(minutes);
+    }
     Ok(())
 }
 
+async fn clear_clipboard(app: &tauri::AppHandle) -> Result<(), String> {
+    app_state(app)?
+        .clipboard_service
+        .clear()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Copies the next older clip on each press; after `HISTORY_CYCLE_RESET`
+/// without a press it starts again from the previous clip.
+async fn cycle_history(app: &tauri::AppHandle) -> Result<(), String> {
+    let state = app_state(app)?;
+
+    let mut step = {
+        let mut cycle = state.history_cycle.lock().map_err(|e| e.to_string())?;
+        let step = match *cycle {
+            Some((step, at)) if at.elapsed() < HISTORY_CYCLE_RESET => step + 1,
+            _ => 1,
+        };
+        *cycle = Some((step, std::time::Instant::now()));
+        step
+    };
+
+    // Copying moves a clip to the top, so the next older clip is always at
+    // index `step` of the current order
+    loop {
+        let recent = state
+            .repository
+            .query_clips(&ClipQuery::new().sort(ClipSort::Recent).limit(step + 1))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        match recent.items.into_iter().nth(step as usize) {
+            Some(clip) => {
+                return copy_to_clipboard(String::new(), Some(clip.id), None, app_state(app)?).await
+            }
+            // Ran past the oldest clip: start over
+            None if step > 1 => {
+                step = 1;
+                *state.history_cycle.lock().map_err(|e| e.to_string())? =
+                    Some((step, std::time::Instant::now()));
+            }
+            None => return Ok(()),
+        }
+    }
+}
+
 /// Helper to show/focus/hide the main window, used by global shortcut and tray.
 ///
 /// Window state machine:
@@ -644,45 +1371,59 @@ pub fn get_settings_path(state: State<'_, AppState>) -> Result<String, String> {
 // IO / External Commands
 // ============================================================================
 
+/// Opens text in the configured editor (`editor_command`). With `clip_id`,
+/// every save in the editor is written back to the clip as a new revision;
+/// only plain-text clips, since the saved text would replace their HTML/RTF.
 #[tauri::command]
-pub async fn open_text_in_editor(text: String, extension: Option<String>) -> Result<(), String> {
-    // 1. Determine extension (default to .txt)
-    let ext = extension.unwrap_or_else(|| "txt".to_string());
-    // Ensure it starts with a dot if missing, though Builder::suffix handles this usually
-    let suffix = if ext.starts_with('.') {
-        ext
-    } else {
-        format!(".{}", ext)
-    };
+pub async fn open_text_in_editor(
+    text: String,
+    extension: Option<String>,
+    clip_id: Option<String>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), String> {
+    use tauri::Emitter;
+
+    if let Some(clip_id) = &clip_id {
+        let clip = state
+            .repository
+            .get_by_id(clip_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| "Clip not found".to_string())?;
+        if clip.content_type != "text" {
+            return Err(format!(
+                "Only plain-text clips can be edited externally, this one is {}",
+                clip.content_type
+            ));
+        }
+    }
+
+    let settings = state
+        .settings_repository
+        .load()
+        .map_err(|e| e.to_string())?;
+    let extension = extension.unwrap_or_else(|| "txt".to_string());
+    let session = EditorSession::open(&text, &extension, &settings.editor_command)
+        .map_err(|e| e.to_string())?;
 
-    // 2. Create a temporary file with the given extension
-    // We use Builder to set the suffix
-    let mut temp_file = tempfile::Builder::new()
-        .suffix(&suffix)
-        .tempfile()
-        .map_err(|e| format!("Failed to create temp file: {}", e))?;
-
-    // 3. Write content to file
-    use std::io::Write;
-    temp_file
-        .write_all(text.as_bytes())
-        .map_err(|e| format!("Failed to write to temp file: {}", e))?;
-
-    // 4. Persist the file so it outlives the function scope (otherwise it's deleted immediately)
-    // The tempfile crate deletes on drop by default. We want it to persist so the editor can open it.
-    // 'persist' keeps the file. We should rely on OS temp cleanup or implement our own cleanup logic later.
-    // However, usually editors lock the file or read it quickly.
-    // A better approach for "Open With" is to use `.keep()` or similar, BUT `tempfile::NamedTempFile`
-    // deletes on drop. To keep it, we use `.keep()`.
-    let (_file, path) = temp_file
-        .keep()
-        .map_err(|e| format!("Failed to persist temp file: {}", e))?;
-
-    // Close the file handle explicitly before opening to avoid locking issues on Windows
-    drop(_file);
-
-    // 5. Open the file with default application
-    open::that(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let edit_service = state.edit_service.clone();
+    tokio::spawn(session.watch(move |text| {
+        let edit_service = edit_service.clone();
+        let clip_id = clip_id.clone();
+        let app = app.clone();
+        async move {
+            let Some(clip_id) = clip_id else {
+                return;
+            };
+            match edit_service.update_content(&clip_id, text, None).await {
+                Ok(clip) => {
+                    let _ = app.emit("clipboard_changed", &clip);
+                }
+                Err(e) => eprintln!("[ERROR] Failed to save edited clip: {}", e),
+            }
+        }
+    }));
 
     Ok(())
 }
@@ -693,6 +1434,25 @@ pub async fn open_path(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Recognises the text of an image clip now (also done automatically when
+/// one is copied). Errors if tesseract isn't installed.
+#[tauri::command]
+pub async fn ocr_clip(
+    id: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<ClipItem, String> {
+    use tauri::Emitter;
+
+    let clip = state
+        .ocr_service
+        .process(&id)
+        .await
+        .map_err(|e| e.to_string())?;
+    let _ = app.emit("clipboard_changed", &clip);
+    Ok(clip)
+}
+
 // ============================================================================
 // Semantic Search Commands
 // ============================================================================
@@ -709,17 +1469,29 @@ pub async fn init_semantic_search(
 
     state
         .semantic_service
-        .init_model(settings.semantic_model, Some(app_handle))
+        .init_from_settings(&settings, Some(app_handle))
         .await
         .map_err(|e| e.to_string())
 }
 
+/// Switches the local fastembed model. The OpenAI-compatible backend takes
+/// its model from `embedding_endpoint` in the settings instead.
 #[tauri::command]
 pub async fn change_semantic_model(
     model_name: String,
     state: State<'_, AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
+    let settings = state
+        .settings_repository
+        .load()
+        .map_err(|e| e.to_string())?;
+    if settings.embedding_backend == EmbeddingBackendKind::Openai {
+        return Err(
+            "The OpenAI embedding backend takes its model from the endpoint settings".to_string(),
+        );
+    }
+
     // Unload the existing model first to free memory
     state.semantic_service.unload_model();
 
@@ -736,6 +1508,13 @@ pub fn get_semantic_search_status(state: State<'_, AppState>) -> Result<bool, St
     Ok(state.semantic_service.is_ready())
 }
 
+#[tauri::command]
+pub fn get_available_models(
+    state: State<'_, AppState>,
+) -> Result<Vec<crate::services::embedding_backend::EmbeddingModelInfo>, String> {
+    Ok(state.semantic_service.get_available_models())
+}
+
 #[tauri::command]
 pub fn get_downloaded_models(state: State<'_, AppState>) -> Result<Vec<String>, String> {
     Ok(state.semantic_service.get_downloaded_models())
@@ -783,3 +1562,495 @@ pub async fn generate_embedding(id: String, state: State<'_, AppState>) -> Resul
         Err("Clip does not have text content to embed".to_string())
     }
 }
+
+/// "More like this": clips similar to the given one, best first, with `similarityScore` set
+#[tauri::command]
+pub async fn find_similar_clips(
+    id: String,
+    limit: Option<i32>,
+    filters: Option<ClipFilters>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ClipItem>, String> {
+    state
+        .similarity_service
+        .find_similar(
+            &id,
+            limit.unwrap_or(20).max(0) as usize,
+            &filters.unwrap_or_default(),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Clustering Commands
+// ============================================================================
+
+/// Re-cluster all embedded clips into topics. `k` = number of clusters (auto if omitted)
+#[tauri::command]
+pub async fn run_clustering(
+    k: Option<usize>,
+    state: State<'_, AppState>,
+) -> Result<Vec<Cluster>, String> {
+    state
+        .clustering_service
+        .run(k)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Clusters from the last run, largest first. Pass an id as `clusterId` to
+/// `search_clips_paginated` to list a cluster's clips.
+#[tauri::command]
+pub async fn get_clusters(state: State<'_, AppState>) -> Result<Vec<Cluster>, String> {
+    state
+        .repository
+        .get_clusters()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Lineage Commands
+// ============================================================================
+
+/// Clips the given one was derived from, nearest first
+#[tauri::command]
+pub async fn get_clip_ancestors(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ClipItem>, String> {
+    state
+        .repository
+        .get_ancestors(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Clips derived from the given one, generation by generation. Each item's
+/// `parentId` tells which clip it was derived from.
+#[tauri::command]
+pub async fn get_clip_descendants(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ClipItem>, String> {
+    state
+        .repository
+        .get_descendants(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Revision Commands
+// ============================================================================
+
+/// Replaces a clip's text (and HTML, if given), re-running detection and
+/// regenerating its embedding. The previous content is kept as a revision.
+#[tauri::command]
+pub async fn update_clip_content(
+    id: String,
+    text: String,
+    html: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<ClipItem, String> {
+    state
+        .edit_service
+        .update_content(&id, text, html)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Previous versions of a clip, most recent first
+#[tauri::command]
+pub async fn get_clip_revisions(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ClipRevision>, String> {
+    state
+        .edit_service
+        .revisions(&id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Restores a previous version of a clip (the current one becomes a revision)
+#[tauri::command]
+pub async fn revert_clip_revision(
+    id: String,
+    revision_id: i64,
+    state: State<'_, AppState>,
+) -> Result<ClipItem, String> {
+    state
+        .edit_service
+        .revert(&id, revision_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Transform Commands
+// ============================================================================
+
+/// Transforms offered for a clip, based on its detected type
+#[tauri::command]
+pub async fn list_transforms(
+    clip_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<TransformInfo>, String> {
+    state
+        .transform_service
+        .list(&clip_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Runs a transform on a clip's text. With `options.save` the result is also
+/// saved as a new clip linked to the original.
+#[tauri::command]
+pub async fn apply_transform(
+    clip_id: String,
+    transform_id: String,
+    options: Option<TransformOptions>,
+    state: State<'_, AppState>,
+) -> Result<TransformOutput, String> {
+    state
+        .transform_service
+        .apply(&clip_id, &transform_id, &options.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// AI Commands
+// ============================================================================
+
+/// Token of a running `run_ai_prompt`, sent as the "ai-token" event
+#[derive(Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AiToken {
+    request_id: String,
+    token: String,
+}
+
+/// AI prompts offered for a clip, based on its detected type
+#[tauri::command]
+pub async fn list_ai_prompts(
+    clip_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<PromptInfo>, String> {
+    state
+        .ai_service
+        .list(&clip_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Runs an AI prompt on a clip's text with the `ai_endpoint` from settings.
+/// The reply is streamed as "ai-token" events tagged with `request_id` (chosen
+/// by the caller, also used by `cancel_ai_prompt`). With `options.save` the
+/// result is also saved as a new clip linked to the original.
+#[tauri::command]
+pub async fn run_ai_prompt(
+    request_id: String,
+    clip_id: String,
+    prompt_id: String,
+    options: Option<AiOptions>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<AiOutput, String> {
+    use tauri::Emitter;
+
+    let settings = state
+        .settings_repository
+        .load()
+        .map_err(|e| e.to_string())?;
+    let provider = OpenAiChatProvider::new(&settings.ai_endpoint).map_err(|e| e.to_string())?;
+
+    let token_request_id = request_id.clone();
+    state
+        .ai_service
+        .run(
+            &provider,
+            &request_id,
+            &clip_id,
+            &prompt_id,
+            &options.unwrap_or_default(),
+            move |token| {
+                let _ = app.emit(
+                    "ai-token",
+                    AiToken {
+                        request_id: token_request_id.clone(),
+                        token: token.to_string(),
+                    },
+                );
+            },
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Stops a running `run_ai_prompt`; returns false if it already finished
+#[tauri::command]
+pub fn cancel_ai_prompt(request_id: String, state: State<'_, AppState>) -> bool {
+    state.ai_service.cancel(&request_id)
+}
+
+/// User-defined prompt templates, by name
+#[tauri::command]
+pub async fn list_prompt_templates(
+    state: State<'_, AppState>,
+) -> Result<Vec<PromptTemplate>, String> {
+    state
+        .prompt_template_service
+        .list()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn create_prompt_template(
+    template: NewPromptTemplate,
+    state: State<'_, AppState>,
+) -> Result<PromptTemplate, String> {
+    state
+        .prompt_template_service
+        .create(template)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn update_prompt_template(
+    id: i64,
+    template: NewPromptTemplate,
+    state: State<'_, AppState>,
+) -> Result<PromptTemplate, String> {
+    state
+        .prompt_template_service
+        .update(id, template)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_prompt_template(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+    state
+        .prompt_template_service
+        .delete(id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// All prompt templates as JSON, for sharing
+#[tauri::command]
+pub async fn export_prompt_templates(state: State<'_, AppState>) -> Result<String, String> {
+    state
+        .prompt_template_service
+        .export()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Imports templates from `export_prompt_templates` JSON, replacing those
+/// with the same name
+#[tauri::command]
+pub async fn import_prompt_templates(
+    json: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<PromptTemplate>, String> {
+    state
+        .prompt_template_service
+        .import(&json)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Script Commands
+// ============================================================================
+
+/// User scripts that apply to a clip, or all scripts (including ones that
+/// failed to load) without `clip_id`
+#[tauri::command]
+pub async fn list_scripts(
+    clip_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<ScriptInfo>, String> {
+    match clip_id {
+        Some(clip_id) => state
+            .script_service
+            .list(&clip_id)
+            .await
+            .map_err(|e| e.to_string()),
+        None => Ok(state.script_service.list_all()),
+    }
+}
+
+/// Runs a user script on a clip, with the capabilities allowed for it in
+/// `script_grants`. Returns `{ text, clip }` like `apply_transform`.
+#[tauri::command]
+pub async fn run_script(
+    clip_id: String,
+    script_id: String,
+    options: Option<ScriptOptions>,
+    state: State<'_, AppState>,
+) -> Result<ScriptOutput, String> {
+    let settings = state
+        .settings_repository
+        .load()
+        .map_err(|e| e.to_string())?;
+    let granted = settings
+        .script_grants
+        .get(&script_id)
+        .cloned()
+        .unwrap_or_default();
+
+    state
+        .script_service
+        .run(&clip_id, &script_id, &granted, &options.unwrap_or_default())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Directory the user's `.rhai` scripts are loaded from (created if missing)
+#[tauri::command]
+pub fn get_scripts_dir(state: State<'_, AppState>) -> Result<String, String> {
+    state
+        .script_service
+        .dir()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .map_err(|e| e.to_string())
+}
+
+// ============================================================================
+// Detector Commands
+// ============================================================================
+
+/// Custom detectors from `detectors.toml`/`detectors.json`, with the reasons
+/// any were skipped. Their `detectedType`s work as search filters.
+#[tauri::command]
+pub fn list_detectors(state: State<'_, AppState>) -> DetectorList {
+    state.detector_service.list()
+}
+
+/// Path of `detectors.toml`, for opening it in an editor
+#[tauri::command]
+pub fn get_detectors_path(state: State<'_, AppState>) -> String {
+    state.detector_service.path().to_string_lossy().to_string()
+}
+
+// ============================================================================
+// Plugin Commands
+// ============================================================================
+
+/// Hands the detectors, transforms, prompt templates and scripts of the
+/// enabled plugins to the services they extend, replacing the previous ones.
+pub fn apply_plugins(state: &AppState, settings: &AppSettings) {
+    let contributions = state
+        .plugin_manager
+        .contributions(&settings.disabled_plugins);
+    state
+        .detector_service
+        .set_plugin_detectors(contributions.detectors);
+    state
+        .transform_service
+        .set_plugin_transforms(contributions.transforms);
+    state
+        .ai_service
+        .set_plugin_templates(contributions.prompt_templates);
+    state
+        .script_service
+        .set_plugin_scripts(contributions.scripts);
+}
+
+/// Installed plugins, including ones that failed to load
+#[tauri::command]
+pub fn list_plugins(state: State<'_, AppState>) -> Result<Vec<PluginInfo>, String> {
+    let settings = state
+        .settings_repository
+        .load()
+        .map_err(|e| e.to_string())?;
+    Ok(state.plugin_manager.list(&settings.disabled_plugins))
+}
+
+/// Reads the plugin in a `.zip` without installing it, to confirm its
+/// permissions with the user first
+#[tauri::command]
+pub async fn inspect_plugin(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<PluginInfo, String> {
+    let plugin_manager = state.plugin_manager.clone();
+    tokio::task::spawn_blocking(move || plugin_manager.inspect(std::path::Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("{:#}", e))
+}
+
+/// Installs (or updates) the plugin in a `.zip` and loads it
+#[tauri::command]
+pub async fn install_plugin(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<PluginInfo, String> {
+    let plugin_manager = state.plugin_manager.clone();
+    let mut info =
+        tokio::task::spawn_blocking(move || plugin_manager.install(std::path::Path::new(&path)))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("{:#}", e))?;
+
+    let settings = state
+        .settings_repository
+        .load()
+        .map_err(|e| e.to_string())?;
+    info.enabled = !settings.disabled_plugins.contains(&info.id);
+    apply_plugins(&state, &settings);
+    Ok(info)
+}
+
+#[tauri::command]
+pub fn uninstall_plugin(id: String, state: State<'_, AppState>) -> Result<(), String> {
+    state
+        .plugin_manager
+        .uninstall(&id)
+        .map_err(|e| e.to_string())?;
+
+    let mut settings = state
+        .settings_repository
+        .load()
+        .map_err(|e| e.to_string())?;
+    if settings.disabled_plugins.contains(&id) {
+        settings.disabled_plugins.retain(|p| p != &id);
+        state
+            .settings_repository
+            .save(&settings)
+            .map_err(|e| e.to_string())?;
+    }
+    apply_plugins(&state, &settings);
+    Ok(())
+}
+
+/// Turns an installed plugin on or off (kept in `disabled_plugins`)
+#[tauri::command]
+pub fn set_plugin_enabled(
+    id: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let mut settings = state
+        .settings_repository
+        .load()
+        .map_err(|e| e.to_string())?;
+    settings.disabled_plugins.retain(|p| p != &id);
+    if !enabled {
+        settings.disabled_plugins.push(id);
+    }
+    state
+        .settings_repository
+        .save(&settings)
+        .map_err(|e| e.to_string())?;
+    apply_plugins(&state, &settings);
+    Ok(())
+}
@@ -1,4 +1,5 @@
 pub mod commands;
 pub mod models;
+pub mod plugins;
 pub mod repositories;
 pub mod services;
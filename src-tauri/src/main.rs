@@ -2,10 +2,24 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use commands::AppState;
+use plugins::action_pack::PluginManager;
+use models::settings::EmbeddingBackendKind;
 use repositories::{ClipRepository, SettingsRepository};
+use services::ai::AiService;
+use services::backfill;
 use services::clipboard::ClipboardService;
+use services::clustering::ClusteringService;
+use services::detectors::DetectorService;
+use services::edit::EditService;
+use services::file_watch::FileWatcher;
+use services::ocr::OcrService;
+use services::paste_stack::PasteStack;
+use services::prompt_template::PromptTemplateService;
+use services::scripting::ScriptService;
 use services::semantic::SemanticService;
-use std::sync::Arc;
+use services::similarity::SimilarityService;
+use services::transform::TransformService;
+use std::sync::{Arc, Mutex};
 use tauri::{Emitter, Manager};
 #[cfg(target_os = "windows")]
 use tauri_plugin_decorum::WebviewWindowExt;
@@ -40,22 +54,48 @@ fn main() {
 
             std::fs::create_dir_all(&app_dir).expect("Failed to create app directory");
 
+            let config_dir = app
+                .path()
+                .app_config_dir()
+                .expect("Failed to get app config directory");
+            let scripts_dir = config_dir.join("scripts");
+
             let db_path = app_dir.join("clips.db");
             let database_url = format!("sqlite:{}", db_path.display());
 
             let app_handle = app.handle().clone();
 
             // Initialize System Tray
-            use tauri::menu::{Menu, MenuItem};
+            use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
             use tauri::tray::{MouseButton, TrayIconBuilder, TrayIconEvent};
 
             let open_i = MenuItem::with_id(app, "open", "Open Clips", true, None::<&str>)?;
             let settings_i = MenuItem::with_id(app, "settings", "Settings", true, None::<&str>)?;
+            let pause_i = MenuItem::with_id(app, "pause", "Pause Capture", true, None::<&str>)?;
+            let pause_15_i =
+                MenuItem::with_id(app, "pause_15", "Pause for 15 Minutes", true, None::<&str>)?;
+            let pause_60_i =
+                MenuItem::with_id(app, "pause_60", "Pause for 1 Hour", true, None::<&str>)?;
+            let resume_i = MenuItem::with_id(app, "resume", "Resume Capture", true, None::<&str>)?;
             let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&open_i, &settings_i, &quit_i])?;
+            let menu = Menu::with_items(
+                app,
+                &[
+                    &open_i,
+                    &settings_i,
+                    &PredefinedMenuItem::separator(app)?,
+                    &pause_i,
+                    &pause_15_i,
+                    &pause_60_i,
+                    &resume_i,
+                    &PredefinedMenuItem::separator(app)?,
+                    &quit_i,
+                ],
+            )?;
 
-            let _tray = TrayIconBuilder::new()
+            let _tray = TrayIconBuilder::with_id(services::tray::TRAY_ID)
                 .icon(app.default_window_icon().unwrap().clone())
+                .tooltip("Clips Next")
                 .menu(&menu)
                 .show_menu_on_left_click(false)
                 .on_menu_event(|app, event| match event.id().as_ref() {
@@ -69,6 +109,18 @@ fn main() {
                             let _ = app.emit("open-settings", ());
                         }
                     }
+                    "pause" | "pause_15" | "pause_60" | "resume" => {
+                        // Capture state lives in the clipboard service, which
+                        // is only managed once the database is ready
+                        if let Some(state) = app.try_state::<AppState>() {
+                            match event.id().as_ref() {
+                                "pause" => state.clipboard_service.pause_capture(None),
+                                "pause_15" => state.clipboard_service.pause_capture(Some(15)),
+                                "pause_60" => state.clipboard_service.pause_capture(Some(60)),
+                                _ => state.clipboard_service.resume_capture(),
+                            };
+                        }
+                    }
                     "quit" => {
                         app.exit(0);
                     }
@@ -108,26 +160,79 @@ fn main() {
                         .expect("Failed to initialize settings repository"),
                 );
 
+                // Custom detectors are loaded before the first clip is captured
+                let detector_service = Arc::new(DetectorService::new(config_dir));
+                detector_service.reload_if_changed();
+                let mut file_watcher = FileWatcher::default();
+                let detectors_app_handle = app_handle.clone();
+                detector_service.watch(&mut file_watcher, move || {
+                    let _ = detectors_app_handle.emit("detectors-changed", ());
+                });
+
                 let semantic_service = Arc::new(SemanticService::new(app_dir.clone()));
+                let ocr_service = Arc::new(OcrService::new(
+                    repository.clone(),
+                    semantic_service.clone(),
+                ));
 
                 let clipboard_service = Arc::new(ClipboardService::new(
                     repository.clone(),
                     settings_repository.clone(),
                     semantic_service.clone(),
+                    ocr_service.clone(),
                     app_handle.clone(),
                 ));
 
-                // Start clipboard monitoring in background
-                let clipboard_monitor = clipboard_service.clone();
-                tokio::spawn(async move {
-                    clipboard_monitor.start_monitoring().await;
+                let similarity_service = Arc::new(SimilarityService::new(repository.clone()));
+                let clustering_service = Arc::new(ClusteringService::new(repository.clone()));
+                let transform_service = Arc::new(TransformService::new(
+                    repository.clone(),
+                    semantic_service.clone(),
+                ));
+                let edit_service = Arc::new(EditService::new(
+                    repository.clone(),
+                    semantic_service.clone(),
+                ));
+                let ai_service = Arc::new(AiService::new(
+                    repository.clone(),
+                    semantic_service.clone(),
+                ));
+                let prompt_template_service =
+                    Arc::new(PromptTemplateService::new(repository.clone()));
+                let script_service = Arc::new(ScriptService::new(
+                    scripts_dir,
+                    repository.clone(),
+                    semantic_service.clone(),
+                ));
+
+                // Reload user scripts when their directory changes
+                let scripts_app_handle = app_handle.clone();
+                script_service.watch(&mut file_watcher, move || {
+                    let _ = scripts_app_handle.emit("scripts-changed", ());
                 });
+                // One task polls both the detector and the script files
+                file_watcher.spawn();
+
+                let plugin_manager = Arc::new(PluginManager::new(app_dir.join("plugins")));
 
                 let app_state = AppState {
                     repository,
-                    clipboard_service,
+                    clipboard_service: clipboard_service.clone(),
                     settings_repository: settings_repository.clone(),
                     semantic_service: semantic_service.clone(),
+                    similarity_service,
+                    clustering_service,
+                    transform_service,
+                    edit_service,
+                    ocr_service,
+                    ai_service,
+                    prompt_template_service,
+                    script_service,
+                    detector_service,
+                    plugin_manager,
+                    shortcut_status: Arc::new(Mutex::new(Vec::new())),
+                    paste_stack: Arc::new(Mutex::new(PasteStack::default())),
+                    history_cycle: Arc::new(Mutex::new(None)),
                 };
 
                 // Handle first launch
@@ -141,16 +246,34 @@ fn main() {
                     let _ = app_state.settings_repository.save(&settings);
                 }
 
+                // Plugin detectors are loaded before the first clip is captured
+                commands::apply_plugins(&app_state, &settings);
+
+                // Fill in data older clips miss, now that every detector is loaded
+                backfill::spawn(app_state.repository.clone());
+
+                // Start clipboard monitoring in background
+                let clipboard_monitor = clipboard_service.clone();
+                tokio::spawn(async move {
+                    clipboard_monitor.start_monitoring().await;
+                });
+
                 // Robust Startup Check for Semantic Models
                 if settings.semantic_search_enabled {
+                    // Remote endpoints have nothing on disk to check
+                    let uses_local_model =
+                        settings.embedding_backend == EmbeddingBackendKind::Fastembed;
                     let downloaded_models = app_state.semantic_service.get_downloaded_models();
-                    if downloaded_models.contains(&settings.semantic_model) {
-                        // Model is available on disk, load it
+                    if !uses_local_model || downloaded_models.contains(&settings.semantic_model) {
+                        // Backend is available, load it
                         let semantic_service = app_state.semantic_service.clone();
-                        let model_name = settings.semantic_model.clone();
+                        let backend_settings = settings.clone();
                         let app_handle_clone = app_handle.clone();
                         tokio::spawn(async move {
-                            if let Err(e) = semantic_service.init_model(model_name, Some(app_handle_clone)).await {
+                            if let Err(e) = semantic_service
+                                .init_from_settings(&backend_settings, Some(app_handle_clone))
+                                .await
+                            {
                                 eprintln!("Failed to initialize semantic model on startup: {}", e);
                             }
                         });
@@ -165,11 +288,11 @@ fn main() {
                     }
                 }
 
-                // Register global shortcut on startup
-                if let Err(e) =
-                    commands::setup_global_shortcut(&app_handle, &settings.global_shortcut)
-                {
-                    eprintln!("Failed to register global shortcut on startup: {}", e);
+                // Register global shortcuts on startup; failures are kept for the
+                // settings UI (get_shortcut_status)
+                let shortcut_status = commands::setup_shortcuts(&app_handle, &settings);
+                if let Ok(mut status) = app_state.shortcut_status.lock() {
+                    *status = shortcut_status;
                 }
 
                 app_handle.manage(app_state);
@@ -188,18 +311,34 @@ fn main() {
         .invoke_handler(tauri::generate_handler![
             commands::get_recent_clips,
             commands::get_recent_clips_paginated,
-            commands::get_clips_after_timestamp,
+            commands::get_clip_changes,
             commands::get_clip_by_id,
+            commands::get_detected_types,
+            commands::get_clip_entities,
+            commands::search_entities,
+            commands::copy_clip_entities,
             commands::search_clips,
             commands::search_clips_paginated,
+            commands::search_clip_hits,
             commands::delete_clip,
             commands::toggle_favorite,
             commands::toggle_pin,
+            commands::get_pinned_clips,
+            commands::reorder_pins,
+            commands::move_pin,
             commands::clear_all_clips,
             commands::copy_to_clipboard,
             commands::paste_clip,
+            commands::start_paste_stack,
+            commands::push_paste_stack,
+            commands::get_paste_stack,
+            commands::clear_paste_stack,
             commands::get_clipboard_text,
-            commands::register_global_shortcut,
+            commands::get_capture_state,
+            commands::pause_capture,
+            commands::resume_capture,
+            commands::register_shortcuts,
+            commands::get_shortcut_status,
             commands::get_settings,
             commands::update_settings,
             commands::get_settings_path,
@@ -208,12 +347,43 @@ fn main() {
             mac_rounded_corners::reposition_traffic_lights,
             commands::open_text_in_editor,
             commands::open_path,
+            commands::ocr_clip,
             commands::init_semantic_search,
             commands::get_semantic_search_status,
             commands::change_semantic_model,
+            commands::get_available_models,
             commands::get_downloaded_models,
             commands::delete_semantic_model,
             commands::generate_embedding,
+            commands::find_similar_clips,
+            commands::run_clustering,
+            commands::get_clusters,
+            commands::get_clip_ancestors,
+            commands::get_clip_descendants,
+            commands::update_clip_content,
+            commands::get_clip_revisions,
+            commands::revert_clip_revision,
+            commands::list_transforms,
+            commands::apply_transform,
+            commands::list_ai_prompts,
+            commands::run_ai_prompt,
+            commands::cancel_ai_prompt,
+            commands::list_prompt_templates,
+            commands::create_prompt_template,
+            commands::update_prompt_template,
+            commands::delete_prompt_template,
+            commands::export_prompt_templates,
+            commands::import_prompt_templates,
+            commands::list_scripts,
+            commands::run_script,
+            commands::get_scripts_dir,
+            commands::list_detectors,
+            commands::get_detectors_path,
+            commands::list_plugins,
+            commands::inspect_plugin,
+            commands::install_plugin,
+            commands::uninstall_plugin,
+            commands::set_plugin_enabled,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -8,6 +8,8 @@ pub struct ClipItem {
     pub content_text: Option<String>,
     pub content_html: Option<String>,
     pub content_rtf: Option<String>,
+    #[sqlx(default)]
+    pub content_markdown: Option<String>, // Markdown derived from content_html (html clips)
     pub svg_path: Option<String>,         // SVG file path: clipboard_data/svg/{id}.svg
     pub pdf_path: Option<String>,         // PDF file path: clipboard_data/pdf/{id}.pdf
     pub image_path: Option<String>,       // Image file path: clipboard_data/images/{id}.{ext}
@@ -20,6 +22,8 @@ pub struct ClipItem {
     pub updated_at: i64,            // Last access timestamp
     pub app_name: Option<String>,
     pub is_pinned: i32,   // SQLite uses INTEGER for boolean
+    #[sqlx(default)]
+    pub pin_position: Option<i64>, // 1-based order among pinned clips, NULL if not pinned
     pub is_favorite: i32, // SQLite uses INTEGER for boolean
     pub access_count: i32,
     pub content_hash: Option<String>,
@@ -29,6 +33,115 @@ pub struct ClipItem {
 
     #[sqlx(default)]
     pub similarity_score: Option<f32>,
+
+    #[sqlx(default)]
+    pub parent_id: Option<String>, // Clip this one was derived from (clip_relations)
+}
+
+/// Link from a derived clip to the clip it was made from.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipRelation {
+    pub clip_id: String,
+    pub derived_from: String,
+    pub transform_id: Option<String>, // E.g. "json_prettify"
+    pub created_at: i64,
+}
+
+/// Earlier version of an edited clip.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipRevision {
+    pub id: i64,
+    pub clip_id: String,
+    pub content_type: String,
+    pub content_text: Option<String>,
+    pub content_html: Option<String>,
+    pub content_rtf: Option<String>,
+    pub content_markdown: Option<String>,
+    pub detected_type: Option<String>,
+    pub metadata: Option<String>,
+    pub content_hash: Option<String>,
+    pub created_at: i64, // When it was replaced
+}
+
+/// Secondary content type of a clip (`clip_detected_types`); the primary one
+/// is `ClipItem::detected_type`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectedType {
+    pub detected_type: String,
+    pub confidence: f32, // 0..1, lower for types found inside the text
+}
+
+/// URL, email, IP address, ... found in a clip's text (`clip_entities`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipEntity {
+    pub kind: String, // entities::EntityKind
+    pub value: String,
+    pub start: i64, // Byte offsets into content_text
+    pub end: i64,
+}
+
+/// A distinct entity value across clips (`search_entities`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct EntitySummary {
+    pub kind: String,
+    pub value: String,
+    pub clip_count: i64,
+    pub last_seen: i64,         // Latest updated_at of those clips
+    pub latest_clip_id: String, // The clip it was last seen in
+}
+
+/// Common filters accepted by search-style commands.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipFilters {
+    pub filter_types: Option<Vec<String>>, // detected_type values, primary or secondary
+    pub favorites_only: bool,
+    pub pinned_only: bool,
+    pub cluster_id: Option<i64>, // clusters.id
+}
+
+/// One page of a listing plus the opaque cursor of the next page
+/// (`None` after the last page).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+/// Clips changed since a change feed cursor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipChanges {
+    pub upserted: Vec<ClipItem>, // Inserted or updated, in change order
+    pub deleted: Vec<String>,    // Ids of deleted clips
+    pub cursor: String,          // Pass to the next call
+    pub has_more: bool,          // More changes than fit in one response
+    pub reset: bool,             // Deletes since the cursor were pruned: reload all clips
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct Cluster {
+    pub id: i64,
+    pub label: String,
+    pub terms: String, // JSON array of top terms
+    pub size: i64,
+    pub model: String,
+    pub created_at: i64,
+}
+
+/// A cluster computed by the clustering job, before it is stored.
+#[derive(Debug, Clone)]
+pub struct NewCluster {
+    pub label: String,
+    pub terms: Vec<String>,
+    pub members: Vec<(String, f32)>, // (clip_id, similarity to centroid)
 }
 
 #[allow(dead_code)]
@@ -108,6 +221,7 @@ impl ClipItem {
             content_text: Some(content),
             content_html: None,
             content_rtf: None,
+            content_markdown: None,
             svg_path: None,
             pdf_path: None,
             image_path: None,
@@ -120,16 +234,18 @@ impl ClipItem {
             updated_at: now,
             app_name: None,
             is_pinned: 0,
+            pin_position: None,
             is_favorite: 0,
             access_count: 0,
             content_hash: Some(content_hash),
             has_embedding: Some(false),
             similarity_score: None,
+            parent_id: None,
         }
     }
 
     /// Compute SHA-256 hash of content for duplicate detection
-    fn compute_hash(content: &str) -> String {
+    pub fn compute_hash(content: &str) -> String {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
 
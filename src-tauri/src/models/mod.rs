@@ -1,6 +1,13 @@
 // Data models and types
 pub mod clip;
+pub mod prompt;
+pub mod search;
 pub mod settings;
 
-pub use clip::{ClipItem, Collection, Embedding, Tag};
+pub use clip::{
+    ClipChanges, ClipEntity, ClipFilters, ClipItem, ClipRelation, ClipRevision, Cluster,
+    Collection, DetectedType, Embedding, EntitySummary, NewCluster, Page, Tag,
+};
+pub use prompt::{NewPromptTemplate, PromptTemplate};
+pub use search::{SearchHit, SearchMode, Snippet, TextRange};
 pub use settings::AppSettings;
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// User-defined AI prompt.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    pub template: String, // {{text}}, {{language}}, {{app_name}} are filled in
+    #[sqlx(json)]
+    pub content_types: Vec<String>, // detected_type values, empty = any text
+    pub created_at: i64,
+    pub updated_at: i64,
+}
+
+/// Fields of a prompt template when creating, updating or importing one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewPromptTemplate {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub template: String,
+    #[serde(default)]
+    pub content_types: Vec<String>,
+}
+
+impl From<PromptTemplate> for NewPromptTemplate {
+    fn from(template: PromptTemplate) -> Self {
+        Self {
+            name: template.name,
+            description: template.description,
+            template: template.template,
+            content_types: template.content_types,
+        }
+    }
+}
@@ -0,0 +1,196 @@
+use super::clip::ClipItem;
+use serde::{Deserialize, Serialize};
+
+/// Marks the start of a highlighted match in FTS5 `highlight()`/`snippet()` output.
+pub const MARK_START: char = '\u{2}';
+/// Marks the end of a highlighted match.
+pub const MARK_END: char = '\u{3}';
+
+/// Appended to a snippet that was cut off (same as FTS5 `snippet()` ellipsis).
+const ELLIPSIS: &str = "…";
+
+/// Byte range `[start, end)` into a string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Short excerpt of a clip around its matches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub text: String,
+    pub highlights: Vec<TextRange>, // Byte ranges into `text`
+}
+
+/// Which search produced a hit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    Fts,
+    Fuzzy,
+    Semantic,
+}
+
+/// A search result: the clip plus where the query matched.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    #[serde(flatten)]
+    pub clip: ClipItem,
+    pub mode: SearchMode,
+    pub highlights: Vec<TextRange>, // Byte ranges into `clip.content_text`
+    pub snippet: Option<Snippet>,
+}
+
+impl SearchHit {
+    /// Hit with highlights into the clip text and a snippet around the first one.
+    pub fn new(clip: ClipItem, mode: SearchMode, highlights: Vec<TextRange>) -> Self {
+        let snippet = clip
+            .content_text
+            .as_deref()
+            .and_then(|text| Snippet::around(text, &highlights, Snippet::MAX_BYTES));
+
+        Self {
+            clip,
+            mode,
+            highlights,
+            snippet,
+        }
+    }
+}
+
+impl Snippet {
+    /// Target size of snippets built by `around`.
+    pub const MAX_BYTES: usize = 200;
+
+    /// Parses text with `MARK_START`/`MARK_END` around matches (as produced by
+    /// FTS5 `highlight()`/`snippet()`) into plain text plus match ranges.
+    pub fn from_marked(marked: &str) -> Self {
+        let mut text = String::with_capacity(marked.len());
+        let mut highlights = Vec::new();
+        let mut start = None;
+
+        for c in marked.chars() {
+            match c {
+                MARK_START => start = Some(text.len()),
+                MARK_END => {
+                    if let Some(start) = start.take() {
+                        highlights.push(TextRange {
+                            start,
+                            end: text.len(),
+                        });
+                    }
+                }
+                _ => text.push(c),
+            }
+        }
+
+        Self { text, highlights }
+    }
+
+    /// Excerpt of about `max_bytes` of `text` starting shortly before the first
+    /// highlight (or at the beginning), cut at whitespace where possible.
+    /// `None` for empty text.
+    pub fn around(text: &str, highlights: &[TextRange], max_bytes: usize) -> Option<Self> {
+        if text.trim().is_empty() {
+            return None;
+        }
+
+        let anchor = highlights.first().map_or(0, |h| h.start.min(text.len()));
+        let mut start = floor_char_boundary(text, anchor.saturating_sub(max_bytes / 4));
+        if start > 0 {
+            // Don't start in the middle of a word
+            start = text[start..anchor]
+                .find(char::is_whitespace)
+                .map_or(start, |i| start + i + 1);
+        }
+
+        let mut end = floor_char_boundary(text, (start + max_bytes).max(anchor));
+        if end < text.len() {
+            if let Some(i) = text[start..end].rfind(char::is_whitespace) {
+                if start + i > anchor {
+                    end = start + i;
+                }
+            }
+        }
+
+        let mut snippet = String::new();
+        if start > 0 {
+            snippet.push_str(ELLIPSIS);
+        }
+        let shift = snippet.len();
+        snippet.push_str(&text[start..end]);
+        if end < text.len() {
+            snippet.push_str(ELLIPSIS);
+        }
+
+        // Highlights partly outside the excerpt are clipped to it
+        let highlights = highlights
+            .iter()
+            .filter(|h| h.start < end && h.end > start)
+            .map(|h| TextRange {
+                start: h.start.max(start) - start + shift,
+                end: h.end.min(end) - start + shift,
+            })
+            .collect();
+
+        Some(Self {
+            text: snippet,
+            highlights,
+        })
+    }
+}
+
+fn floor_char_boundary(text: &str, mut index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_marked() {
+        let snippet = Snippet::from_marked("…deploy to \u{2}kubernetes\u{3} 東京\u{2}本社\u{3}…");
+        assert_eq!(snippet.text, "…deploy to kubernetes 東京本社…");
+        let matched: Vec<&str> = snippet
+            .highlights
+            .iter()
+            .map(|h| &snippet.text[h.start..h.end])
+            .collect();
+        assert_eq!(matched, vec!["kubernetes", "本社"]);
+    }
+
+    #[test]
+    fn test_around_keeps_highlights_in_window() {
+        let text = format!("{} needle {}", "word ".repeat(100), "tail ".repeat(100));
+        let start = text.find("needle").unwrap();
+        let highlight = TextRange {
+            start,
+            end: start + "needle".len(),
+        };
+
+        let snippet = Snippet::around(&text, &[highlight], 60).unwrap();
+        assert!(snippet.text.starts_with("…word"));
+        assert!(snippet.text.ends_with("…"));
+        assert!(snippet.text.len() <= 60 + 2 * ELLIPSIS.len());
+        let h = snippet.highlights[0];
+        assert_eq!(&snippet.text[h.start..h.end], "needle");
+    }
+
+    #[test]
+    fn test_around_short_text() {
+        let snippet = Snippet::around("会議は明日", &[], 200).unwrap();
+        assert_eq!(snippet.text, "会議は明日");
+        assert!(snippet.highlights.is_empty());
+        assert_eq!(Snippet::around("  ", &[], 200), None);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -41,7 +42,7 @@ impl Default for RetentionPolicy {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PasteFormat {
     Auto,
@@ -56,6 +57,109 @@ impl Default for PasteFormat {
     }
 }
 
+/// History tab shown by an `OpenSearch` shortcut.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchTab {
+    All,
+    Favorites,
+    Pinned,
+}
+
+impl Default for SearchTab {
+    fn default() -> Self {
+        SearchTab::All
+    }
+}
+
+/// What a global shortcut does.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShortcutAction {
+    /// Show/hide the main window
+    ToggleWindow,
+    /// Paste the clip before the current clipboard content
+    PastePrevious,
+    /// Paste the current clipboard content without formatting
+    PastePlainText,
+    /// Show the window with a prefilled search (`query` may contain `/type` filters)
+    OpenSearch {
+        #[serde(default)]
+        query: String,
+        #[serde(default)]
+        tab: SearchTab,
+    },
+    /// Pause or resume clipboard capture; a pause ends by itself after
+    /// `minutes` if set
+    PauseCapture {
+        #[serde(default)]
+        minutes: Option<u32>,
+    },
+    /// Empty the system clipboard
+    ClearClipboard,
+    /// Each press copies the next older clip; starts over after a short pause
+    CycleHistory,
+    /// Paste the clip in pin slot `slot` (1-based)
+    PastePinSlot { slot: i64 },
+    /// Paste the next clip queued in the paste stack
+    PasteStackNext,
+}
+
+impl ShortcutAction {
+    /// Human readable name, used in conflict messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShortcutAction::ToggleWindow => "Toggle window",
+            ShortcutAction::PastePrevious => "Paste previous clip",
+            ShortcutAction::PastePlainText => "Paste as plain text",
+            ShortcutAction::OpenSearch { .. } => "Open search",
+            ShortcutAction::PauseCapture { .. } => "Pause capture",
+            ShortcutAction::ClearClipboard => "Clear clipboard",
+            ShortcutAction::CycleHistory => "Cycle through history",
+            ShortcutAction::PastePinSlot { .. } => "Paste pin slot",
+            ShortcutAction::PasteStackNext => "Paste next from stack",
+        }
+    }
+}
+
+/// A global shortcut: accelerator (e.g. "Ctrl+Shift+P") mapped to an action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShortcutBinding {
+    pub accelerator: String,
+    pub action: ShortcutAction,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmbeddingBackendKind {
+    /// Local ONNX models via fastembed (`semantic_model`)
+    #[default]
+    Fastembed,
+    /// Any OpenAI-compatible `/v1/embeddings` server (`embedding_endpoint`)
+    Openai,
+}
+
+/// Connection details for an OpenAI-compatible API (OpenAI, Ollama, llama.cpp, ...)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenAiEndpoint {
+    pub base_url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+}
+
+impl Default for OpenAiEndpoint {
+    fn default() -> Self {
+        Self {
+            base_url: "http://localhost:11434/v1".to_string(),
+            model: "nomic-embed-text".to_string(),
+            api_key: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppSettings {
     // General
@@ -64,12 +168,23 @@ pub struct AppSettings {
 
     // Shortcuts
     pub global_shortcut: String,
+    /// Modifiers for the pin slot shortcuts (modifiers + 1..9), empty = disabled.
+    /// Off by default: nine system-wide hotkeys would shadow those keys in
+    /// every other app
+    #[serde(default)]
+    pub pin_slot_modifiers: String,
+    /// Extra shortcuts on top of `global_shortcut` and the pin slots
+    #[serde(default)]
+    pub shortcuts: Vec<ShortcutBinding>,
 
     // Clipboard monitoring
     pub enable_images: bool,
     pub enable_files: bool,
     pub enable_rich_text: bool,
     pub enable_office_formats: bool,
+    /// Recognise the text of copied images (needs tesseract installed)
+    #[serde(default = "default_true")]
+    pub enable_ocr: bool,
     pub excluded_apps: Vec<String>,
 
     // Storage & History
@@ -95,6 +210,12 @@ pub struct AppSettings {
     #[serde(default = "default_false")]
     pub always_on_top: bool,
 
+    // External editor
+    /// Command that opens clips for editing, e.g. `code --wait` or `$VISUAL`
+    /// (the file path is appended). Empty = the system default app.
+    #[serde(default)]
+    pub editor_command: String,
+
     // Notifications
     pub show_copy_toast: bool,
 
@@ -107,6 +228,27 @@ pub struct AppSettings {
     pub semantic_search_enabled: bool,
     #[serde(default = "default_semantic_model")]
     pub semantic_model: String,
+    #[serde(default)]
+    pub embedding_backend: EmbeddingBackendKind,
+    #[serde(default)]
+    pub embedding_endpoint: OpenAiEndpoint,
+    /// Chat API used by the AI prompts (summarize, translate, ...)
+    #[serde(default = "default_ai_endpoint")]
+    pub ai_endpoint: OpenAiEndpoint,
+    /// Capabilities the user allowed each script (by script id), out of those
+    /// its header asks for, e.g. `{"fetch_title": ["http"]}`
+    #[serde(default)]
+    pub script_grants: HashMap<String, Vec<String>>,
+    /// Ids of installed plugins that are turned off
+    #[serde(default)]
+    pub disabled_plugins: Vec<String>,
+}
+
+fn default_ai_endpoint() -> OpenAiEndpoint {
+    OpenAiEndpoint {
+        model: "llama3.2".to_string(),
+        ..OpenAiEndpoint::default()
+    }
 }
 
 fn default_semantic_model() -> String {
@@ -123,10 +265,13 @@ impl Default for AppSettings {
             } else {
                 "Ctrl+Shift+V".to_string()
             },
+            pin_slot_modifiers: String::new(),
+            shortcuts: vec![],
             enable_images: true,
             enable_files: true,
             enable_rich_text: true,
             enable_office_formats: true,
+            enable_ocr: true,
             excluded_apps: vec![],
             history_limit: 1000,
             retention_policy: "unlimited".to_string(),
@@ -142,10 +287,16 @@ impl Default for AppSettings {
             paste_on_enter: true,
             hide_on_blur: true,
             always_on_top: false,
+            editor_command: String::new(),
             show_copy_toast: true,
             has_seen_welcome: false,
             semantic_search_enabled: false,
             semantic_model: default_semantic_model(),
+            embedding_backend: EmbeddingBackendKind::default(),
+            embedding_endpoint: OpenAiEndpoint::default(),
+            ai_endpoint: default_ai_endpoint(),
+            script_grants: HashMap::new(),
+            disabled_plugins: Vec::new(),
         }
     }
 }
@@ -0,0 +1,570 @@
+// Action packs
+//
+// A plugin is a `.zip` bundling content detectors, regex transforms, AI prompt
+// templates and Rhai scripts, described by a `manifest.json` at its root (or
+// inside a single top-level folder):
+//
+//     {
+//       "manifestVersion": 1,
+//       "id": "jira",
+//       "name": "Jira",
+//       "version": "1.0.0",
+//       "description": "Ticket ids, links and summaries",
+//       "permissions": ["http"],
+//       "detectors": [
+//         { "detected_type": "ticket", "pattern": "^(?P<project>[A-Z]+)-\\d+$", "priority": 15 }
+//       ],
+//       "transforms": [
+//         { "id": "link", "name": "Ticket Link", "contentTypes": ["ticket"],
+//           "find": "^(.+)$", "replace": "https://jira.example.com/browse/$1" }
+//       ],
+//       "promptTemplates": [
+//         { "name": "Ticket", "template": "Rewrite as a Jira ticket", "contentTypes": ["text"] }
+//       ],
+//       "scripts": ["scripts/fetch_summary.rhai"]
+//     }
+//
+// `detectors` entries are those of `detectors.toml`, `promptTemplates` those of
+// a template export. Scripts may only use the grants listed in `permissions`,
+// which the user accepts by installing the plugin.
+//
+// Installed plugins are unpacked to `{app_data_dir}/plugins/{id}`. Each one is
+// loaded on its own: a plugin whose manifest or contributions are broken is
+// listed with its error and contributes nothing, the others are unaffected.
+
+use crate::models::NewPromptTemplate;
+use crate::services::detectors::{self, DetectorConfig, DetectorInfo};
+use crate::services::intelligence::{self, CustomDetector};
+use crate::services::prompt_template;
+use crate::services::scripting::{self, PluginScript};
+use crate::services::transform::{RegexTransform, RegexTransformConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+/// File describing a plugin.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Newest manifest format this app reads.
+const MANIFEST_VERSION: u32 = 1;
+
+/// Limits on what an archive may unpack to.
+const MAX_FILES: usize = 1000;
+const MAX_UNPACKED_SIZE: u64 = 50 * 1024 * 1024;
+
+/// A plugin's `manifest.json`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default = "default_manifest_version")]
+    pub manifest_version: u32,
+    /// Lowercase letters, digits, `-` and `_`; also the install folder
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    /// Grants its scripts may use (see `scripting::GRANTS`)
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub detectors: Vec<DetectorConfig>,
+    #[serde(default)]
+    pub transforms: Vec<RegexTransformConfig>,
+    #[serde(default)]
+    pub prompt_templates: Vec<NewPromptTemplate>,
+    /// `.rhai` files, relative to the manifest
+    #[serde(default)]
+    pub scripts: Vec<String>,
+}
+
+fn default_manifest_version() -> u32 {
+    1
+}
+
+/// An installed plugin (or, from `inspect`, one about to be installed).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub author: Option<String>,
+    pub permissions: Vec<String>,
+    /// Number of contributions of each kind
+    pub detectors: usize,
+    pub transforms: usize,
+    pub prompt_templates: usize,
+    pub scripts: usize,
+    pub enabled: bool,
+    /// Why the plugin couldn't be loaded; it contributes nothing then
+    pub error: Option<String>,
+}
+
+impl PluginInfo {
+    fn new(manifest: &Manifest, enabled: bool) -> Self {
+        Self {
+            id: manifest.id.clone(),
+            name: manifest.name.clone(),
+            version: manifest.version.clone(),
+            description: manifest.description.clone(),
+            author: manifest.author.clone(),
+            permissions: manifest.permissions.clone(),
+            detectors: manifest.detectors.len(),
+            transforms: manifest.transforms.len(),
+            prompt_templates: manifest.prompt_templates.len(),
+            scripts: manifest.scripts.len(),
+            enabled,
+            error: None,
+        }
+    }
+
+    fn broken(id: &str, enabled: bool, error: &anyhow::Error) -> Self {
+        Self {
+            id: id.to_string(),
+            name: id.to_string(),
+            version: String::new(),
+            description: String::new(),
+            author: None,
+            permissions: Vec::new(),
+            detectors: 0,
+            transforms: 0,
+            prompt_templates: 0,
+            scripts: 0,
+            enabled,
+            error: Some(format!("{:#}", error)),
+        }
+    }
+}
+
+/// What the enabled plugins add to the services, checked and compiled.
+#[derive(Default)]
+pub struct Contributions {
+    pub detectors: Vec<(DetectorInfo, CustomDetector)>,
+    pub transforms: Vec<RegexTransform>,
+    /// `(plugin id, template)`
+    pub prompt_templates: Vec<(String, NewPromptTemplate)>,
+    pub scripts: Vec<PluginScript>,
+}
+
+impl Contributions {
+    fn extend(&mut self, other: Contributions) {
+        self.detectors.extend(other.detectors);
+        self.transforms.extend(other.transforms);
+        self.prompt_templates.extend(other.prompt_templates);
+        self.scripts.extend(other.scripts);
+    }
+}
+
+/// Installs, removes and loads the plugins in the plugins directory.
+pub struct PluginManager {
+    dir: PathBuf,
+}
+
+impl PluginManager {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Reads the plugin in a `.zip` without installing it, to show what it
+    /// contains and which permissions it asks for.
+    pub fn inspect(&self, archive: &Path) -> Result<PluginInfo> {
+        let temp = tempfile::tempdir()?;
+        let root = unpack(archive, temp.path())?;
+        let (manifest, _) = load(&root)?;
+        Ok(PluginInfo::new(&manifest, false))
+    }
+
+    /// Installs the plugin in a `.zip`, replacing an installed version.
+    /// Nothing changes if the plugin doesn't load.
+    pub fn install(&self, archive: &Path) -> Result<PluginInfo> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        // Unpacked next to its destination so it can be moved there
+        let temp = tempfile::Builder::new()
+            .prefix(".install-")
+            .tempdir_in(&self.dir)?;
+        let root = unpack(archive, &temp.path().join("unpacked"))?;
+        let (manifest, _) = load(&root)?;
+
+        let target = self.dir.join(&manifest.id);
+        let previous = temp.path().join("previous");
+        if target.exists() {
+            std::fs::rename(&target, &previous)?;
+        }
+        if let Err(e) = std::fs::rename(&root, &target) {
+            if previous.exists() {
+                let _ = std::fs::rename(&previous, &target);
+            }
+            return Err(e).context("Failed to install plugin");
+        }
+        Ok(PluginInfo::new(&manifest, true))
+    }
+
+    pub fn uninstall(&self, id: &str) -> Result<()> {
+        validate_id(id)?;
+        let path = self.dir.join(id);
+        if !path.is_dir() {
+            bail!("Plugin '{}' is not installed", id);
+        }
+        std::fs::remove_dir_all(&path)
+            .with_context(|| format!("Failed to remove {}", path.display()))
+    }
+
+    /// Installed plugins by id, with load errors.
+    pub fn list(&self, disabled: &[String]) -> Vec<PluginInfo> {
+        self.installed()
+            .into_iter()
+            .map(|(id, path)| {
+                let enabled = !disabled.contains(&id);
+                match load(&path) {
+                    Ok((manifest, _)) => PluginInfo::new(&manifest, enabled),
+                    Err(e) => PluginInfo::broken(&id, enabled, &e),
+                }
+            })
+            .collect()
+    }
+
+    /// Contributions of the enabled plugins that load.
+    pub fn contributions(&self, disabled: &[String]) -> Contributions {
+        let mut contributions = Contributions::default();
+        for (id, path) in self.installed() {
+            if disabled.contains(&id) {
+                continue;
+            }
+            match load(&path) {
+                Ok((_, plugin)) => contributions.extend(plugin),
+                Err(e) => eprintln!("[WARN] Plugin '{}' not loaded: {:#}", id, e),
+            }
+        }
+        contributions
+    }
+
+    /// Plugin folders, sorted by id (unfinished installs are skipped).
+    fn installed(&self) -> Vec<(String, PathBuf)> {
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut plugins: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir())
+            .filter_map(|path| {
+                let id = path.file_name()?.to_str()?.to_string();
+                (!id.starts_with('.')).then_some((id, path))
+            })
+            .collect();
+        plugins.sort();
+        plugins
+    }
+}
+
+/// Unpacks `archive` into `dest` and returns the folder holding the
+/// manifest. Entries escaping `dest` are rejected.
+fn unpack(archive: &Path, dest: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(dest)?;
+    let file =
+        File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    let mut zip = zip::ZipArchive::new(file).map_err(|e| anyhow!("Not a valid .zip: {}", e))?;
+    if zip.len() > MAX_FILES {
+        bail!("Archive has more than {} files", MAX_FILES);
+    }
+
+    let mut unpacked = 0u64;
+    for i in 0..zip.len() {
+        let mut entry = zip.by_index(i)?;
+        let name = entry
+            .enclosed_name()
+            .ok_or_else(|| anyhow!("Archive entry '{}' has an unsafe path", entry.name()))?;
+        let path = dest.join(name);
+        if entry.is_dir() {
+            std::fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Counted as read, the sizes in the archive may lie
+        let mut out = File::create(&path)?;
+        let limit = MAX_UNPACKED_SIZE - unpacked;
+        unpacked += std::io::copy(&mut (&mut entry).take(limit + 1), &mut out)?;
+        if unpacked > MAX_UNPACKED_SIZE {
+            bail!(
+                "Archive unpacks to more than {} MB",
+                MAX_UNPACKED_SIZE / 1024 / 1024
+            );
+        }
+    }
+
+    if dest.join(MANIFEST_FILE).is_file() {
+        return Ok(dest.to_path_buf());
+    }
+    // Zipping a folder puts everything in that folder
+    let entries: Vec<PathBuf> = std::fs::read_dir(dest)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    match entries.as_slice() {
+        [folder] if folder.join(MANIFEST_FILE).is_file() => Ok(folder.clone()),
+        _ => bail!("Archive has no {}", MANIFEST_FILE),
+    }
+}
+
+/// Reads and checks the plugin in `dir`, compiling its contributions.
+fn load(dir: &Path) -> Result<(Manifest, Contributions)> {
+    let json = std::fs::read_to_string(dir.join(MANIFEST_FILE))
+        .with_context(|| format!("Failed to read {}", MANIFEST_FILE))?;
+    let manifest: Manifest =
+        serde_json::from_str(&json).map_err(|e| anyhow!("Invalid {}: {}", MANIFEST_FILE, e))?;
+    let contributions = compile(&manifest, dir)?;
+    Ok((manifest, contributions))
+}
+
+fn compile(manifest: &Manifest, dir: &Path) -> Result<Contributions> {
+    if manifest.manifest_version > MANIFEST_VERSION {
+        bail!(
+            "Manifest version {} needs a newer version of the app",
+            manifest.manifest_version
+        );
+    }
+    validate_id(&manifest.id)?;
+    if manifest.name.trim().is_empty() || manifest.version.trim().is_empty() {
+        bail!("Name and version are required");
+    }
+    if let Some(permission) = manifest
+        .permissions
+        .iter()
+        .find(|p| !scripting::GRANTS.contains(&p.as_str()))
+    {
+        bail!(
+            "Unknown permission '{}' (available: {})",
+            permission,
+            scripting::GRANTS.join(", ")
+        );
+    }
+
+    let id = &manifest.id;
+    let file = format!("plugins/{}/{}", id, MANIFEST_FILE);
+    let mut contributions = Contributions::default();
+
+    for config in &manifest.detectors {
+        let detector = detectors::build(config.clone(), &file)
+            .with_context(|| format!("Detector '{}'", config.detected_type))?;
+        contributions.detectors.push(detector);
+    }
+
+    // Content types may be those of the plugin's own detectors
+    let own_types: Vec<&str> = contributions
+        .detectors
+        .iter()
+        .map(|(info, _)| info.detected_type.as_str())
+        .collect();
+    let check_types = |types: &[String]| -> Result<()> {
+        match types
+            .iter()
+            .find(|t| !own_types.contains(&t.as_str()) && !intelligence::is_known_type(t))
+        {
+            Some(t) => bail!("Unknown content type '{}'", t),
+            None => Ok(()),
+        }
+    };
+
+    for config in &manifest.transforms {
+        let transform = validate_id(&config.id)
+            .and_then(|_| check_types(&config.content_types))
+            .and_then(|_| RegexTransform::new(id, config))
+            .with_context(|| format!("Transform '{}'", config.id))?;
+        if contributions
+            .transforms
+            .iter()
+            .any(|t| t.id() == transform.id())
+        {
+            bail!("Transform '{}' is declared twice", config.id);
+        }
+        contributions.transforms.push(transform);
+    }
+
+    for template in &manifest.prompt_templates {
+        let mut template = template.clone();
+        let name = template.name.clone();
+        let content_types = std::mem::take(&mut template.content_types);
+        let mut template = prompt_template::validate(template)
+            .and_then(|t| check_types(&content_types).map(|_| t))
+            .with_context(|| format!("Prompt template '{}'", name))?;
+        if contributions
+            .prompt_templates
+            .iter()
+            .any(|(_, t)| t.name == template.name)
+        {
+            bail!("Prompt template '{}' is declared twice", template.name);
+        }
+        template.content_types = content_types;
+        contributions.prompt_templates.push((id.clone(), template));
+    }
+
+    for script in &manifest.scripts {
+        let path = script_path(dir, script).with_context(|| format!("Script '{}'", script))?;
+        contributions.scripts.push(PluginScript {
+            plugin_id: id.clone(),
+            path,
+            permissions: manifest.permissions.clone(),
+        });
+    }
+
+    Ok(contributions)
+}
+
+/// Ids are folder names and parts of contribution ids: `[a-z0-9][a-z0-9_-]*`.
+fn validate_id(id: &str) -> Result<()> {
+    let valid = id.len() <= 64
+        && id.starts_with(|c: char| c.is_ascii_lowercase() || c.is_ascii_digit())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    if !valid {
+        bail!(
+            "Invalid id '{}': use lowercase letters, digits, - and _",
+            id
+        );
+    }
+    Ok(())
+}
+
+/// The `.rhai` file `script` names, which must be inside the plugin.
+fn script_path(dir: &Path, script: &str) -> Result<PathBuf> {
+    let relative = Path::new(script);
+    if !relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        bail!("Path must stay inside the plugin");
+    }
+    if relative.extension().and_then(|ext| ext.to_str()) != Some("rhai") {
+        bail!("Not a .rhai file");
+    }
+    let path = dir.join(relative);
+    if !path.is_file() {
+        bail!("File not found");
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    const MANIFEST: &str = r#"{
+        "id": "jira",
+        "name": "Jira",
+        "version": "1.0.0",
+        "permissions": ["http"],
+        "detectors": [
+            { "detected_type": "ticket", "pattern": "^(?P<project>[A-Z]+)-\\d+$", "priority": 15 }
+        ],
+        "transforms": [
+            { "id": "link", "name": "Ticket Link", "contentTypes": ["ticket"],
+              "find": "^(.+)$", "replace": "https://jira.example.com/browse/$1" }
+        ],
+        "promptTemplates": [
+            { "name": "Ticket", "template": "Rewrite as a Jira ticket", "contentTypes": ["ticket"] }
+        ],
+        "scripts": ["scripts/summary.rhai"]
+    }"#;
+
+    fn write_zip(path: &Path, files: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, contents) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_install_list_and_uninstall() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("jira.zip");
+        write_zip(
+            &archive,
+            &[
+                ("jira/manifest.json", MANIFEST),
+                ("jira/scripts/summary.rhai", "fn run(clip) { clip.text }"),
+            ],
+        );
+        let manager = PluginManager::new(dir.path().join("plugins"));
+
+        let info = manager.inspect(&archive).unwrap();
+        assert_eq!((info.id.as_str(), info.enabled), ("jira", false));
+        assert!(manager.list(&[]).is_empty());
+
+        let info = manager.install(&archive).unwrap();
+        assert_eq!(info.permissions, ["http"]);
+        assert_eq!(
+            (
+                info.detectors,
+                info.transforms,
+                info.prompt_templates,
+                info.scripts
+            ),
+            (1, 1, 1, 1)
+        );
+        // Reinstalling replaces the installed version
+        manager.install(&archive).unwrap();
+        assert_eq!(manager.list(&[]), [info]);
+
+        let contributions = manager.contributions(&[]);
+        assert_eq!(contributions.detectors[0].0.detected_type, "ticket");
+        assert_eq!(contributions.transforms[0].id(), "jira/link");
+        assert_eq!(contributions.prompt_templates[0].0, "jira");
+        assert_eq!(contributions.scripts[0].permissions, ["http"]);
+        assert!(contributions.scripts[0]
+            .path
+            .ends_with("jira/scripts/summary.rhai"));
+
+        let disabled = ["jira".to_string()];
+        assert!(!manager.list(&disabled)[0].enabled);
+        assert!(manager.contributions(&disabled).transforms.is_empty());
+
+        manager.uninstall("jira").unwrap();
+        assert!(manager.list(&[]).is_empty());
+        assert!(manager.uninstall("jira").is_err());
+        assert!(manager.uninstall("../x").is_err());
+    }
+
+    #[test]
+    fn test_rejects_broken_plugins() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = PluginManager::new(dir.path().join("plugins"));
+        let install = |files: &[(&str, &str)]| {
+            let archive = dir.path().join("plugin.zip");
+            write_zip(&archive, files);
+            format!("{:#}", manager.install(&archive).unwrap_err())
+        };
+
+        assert!(install(&[("readme.txt", "")]).contains("no manifest.json"));
+        assert!(install(&[("../manifest.json", MANIFEST)]).contains("unsafe path"));
+        assert!(install(&[("manifest.json", MANIFEST)]).contains("summary.rhai"));
+        let manifest = MANIFEST.replace(r#"["http"]"#, r#"["shell"]"#);
+        assert!(install(&[("manifest.json", &manifest)]).contains("Unknown permission"));
+        let manifest = MANIFEST.replace(r#""id": "jira""#, r#""id": "../jira""#);
+        assert!(install(&[("manifest.json", &manifest)]).contains("Invalid id"));
+        let manifest = MANIFEST.replace(r#"["ticket"] }"#, r#"["order"] }"#);
+        assert!(install(&[("manifest.json", &manifest)]).contains("Unknown content type"));
+        assert!(manager.list(&[]).is_empty());
+
+        // A plugin broken after installing is listed with its error
+        let broken = dir.path().join("plugins").join("broken");
+        std::fs::create_dir_all(&broken).unwrap();
+        std::fs::write(broken.join(MANIFEST_FILE), "{").unwrap();
+        let plugins = manager.list(&[]);
+        assert_eq!(plugins[0].id, "broken");
+        assert!(plugins[0].error.is_some());
+        assert!(manager.contributions(&[]).detectors.is_empty());
+    }
+}
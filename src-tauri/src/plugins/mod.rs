@@ -1 +1,2 @@
+pub mod action_pack;
 pub mod mac_rounded_corners;
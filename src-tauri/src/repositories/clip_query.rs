@@ -0,0 +1,401 @@
+use super::ClipRepository;
+use crate::models::{ClipFilters, ClipItem, Page};
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::query::QueryAs;
+use sqlx::sqlite::SqliteArguments;
+use sqlx::Sqlite;
+
+/// Page size when none is given.
+const DEFAULT_LIMIT: i32 = 50;
+
+/// Columns every clip listing selects.
+pub const CLIP_COLUMNS: &str = "clips.*, \
+    EXISTS(SELECT 1 FROM embeddings e WHERE e.clip_id = clips.id) as has_embedding, \
+    (SELECT r.derived_from FROM clip_relations r WHERE r.clip_id = clips.id) as parent_id";
+
+/// Sort key of unpinned clips in `PinnedFirst` order (after every pin).
+const UNPINNED_KEY: i64 = 2_147_483_647;
+
+/// Must match the expression of `idx_clips_pin_order` for the index to be used.
+const PIN_KEY_SQL: &str = "IFNULL(clips.pin_position, 2147483647)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipSort {
+    /// Pinned clips in pin order, then the rest most recently used first
+    PinnedFirst,
+    /// Most recently used first
+    Recent,
+    /// Best FTS5 rank first, then most recent (needs a text query)
+    Relevance,
+}
+
+/// Position in a result list, handed to the frontend as an opaque token.
+///
+/// Keyset cursors remember the last row of the previous page instead of a row
+/// count, so clips inserted or touched while scrolling neither repeat nor get
+/// skipped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "k", rename_all = "lowercase")]
+pub enum Cursor {
+    /// After this row in `(pin position, updated_at, id)` order
+    Pinned {
+        pin_key: i64,
+        updated_at: i64,
+        id: String,
+    },
+    /// After this row in `(updated_at, id)` descending order
+    Recent { updated_at: i64, id: String },
+    /// After this row in `(rank, updated_at, id)` order. The rank is kept as
+    /// `f64::to_bits`: a JSON float does not always parse back to the same
+    /// value, and the keyset comparison needs the exact rank
+    Relevance {
+        rank_bits: u64,
+        updated_at: i64,
+        id: String,
+    },
+    /// Position in a ranking computed in memory (semantic and fuzzy search)
+    Offset { offset: i64 },
+    /// Last seen entry of the change feed
+    Change { seq: i64 },
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_string(self).unwrap_or_default();
+        json.bytes().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    pub fn decode(token: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid cursor");
+        if !token.len().is_multiple_of(2) || !token.is_ascii() {
+            return Err(invalid());
+        }
+        let bytes = (0..token.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&token[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    }
+
+    /// Offset of an optional `Offset` token, 0 for none.
+    pub fn decode_offset(token: Option<&str>) -> Result<i64> {
+        match token.map(Self::decode).transpose()? {
+            None => Ok(0),
+            Some(Cursor::Offset { offset }) => Ok(offset.max(0)),
+            Some(_) => Err(anyhow!("Cursor does not match query")),
+        }
+    }
+}
+
+/// Typed query over the clips table: filters, optional full-text match, sort
+/// order and cursor.
+///
+/// ```ignore
+/// let query = ClipQuery::new()
+///     .filters(filters)
+///     .text("invoice")
+///     .after(cursor.as_deref())?
+///     .limit(50);
+/// let page = repository.query_clips(&query).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClipQuery {
+    filters: ClipFilters,
+    match_expr: Option<String>,
+    sort: Option<ClipSort>,
+    after: Option<Cursor>,
+    limit: i32,
+}
+
+impl Default for ClipQuery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClipQuery {
+    pub fn new() -> Self {
+        Self {
+            filters: ClipFilters::default(),
+            match_expr: None,
+            sort: None,
+            after: None,
+            limit: DEFAULT_LIMIT,
+        }
+    }
+
+    pub fn filters(mut self, filters: ClipFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Full-text search (prefix match on every word); blank text matches all.
+    pub fn text(mut self, query: &str) -> Self {
+        self.match_expr = Some(ClipRepository::escape_fts5_query(query)).filter(|e| e != "\"\"");
+        self
+    }
+
+    /// Defaults to `Relevance` with a text query, `PinnedFirst` otherwise.
+    pub fn sort(mut self, sort: ClipSort) -> Self {
+        self.sort = Some(sort);
+        self
+    }
+
+    /// Continue after a cursor token from a previous page.
+    pub fn after(mut self, token: Option<&str>) -> Result<Self> {
+        self.after = token.map(Cursor::decode).transpose()?;
+        Ok(self)
+    }
+
+    pub fn limit(mut self, limit: i32) -> Self {
+        self.limit = limit.max(0);
+        self
+    }
+
+    pub fn has_text(&self) -> bool {
+        self.match_expr.is_some()
+    }
+
+    pub fn effective_sort(&self) -> ClipSort {
+        match (self.sort, &self.match_expr) {
+            (None | Some(ClipSort::Relevance), None) => ClipSort::PinnedFirst,
+            (None, Some(_)) => ClipSort::Relevance,
+            (Some(sort), _) => sort,
+        }
+    }
+
+    /// SELECT statement for one page. `extra_columns` are added to the select
+    /// list (they may use `clips_fts` when there is a text query); bind their
+    /// placeholders before calling `bind`.
+    ///
+    /// One row more than the page size is fetched to tell whether another
+    /// page follows, see `next_cursor`.
+    pub fn to_sql(&self, extra_columns: &str) -> Result<String> {
+        let sort = self.effective_sort();
+        let mut sql = format!("SELECT {}", CLIP_COLUMNS);
+        if self.match_expr.is_some() {
+            sql.push_str(", clips_fts.rank as fts_rank");
+        }
+        sql.push_str(extra_columns);
+
+        if self.match_expr.is_some() {
+            sql.push_str(
+                " FROM clips INNER JOIN clips_fts ON clips.rowid = clips_fts.rowid WHERE clips_fts MATCH ?",
+            );
+        } else {
+            sql.push_str(" FROM clips WHERE 1=1");
+        }
+
+        push_filters(&mut sql, &self.filters, "clips");
+
+        match (&self.after, sort) {
+            (None, _) => {}
+            (Some(Cursor::Pinned { .. }), ClipSort::PinnedFirst) => sql.push_str(&format!(
+                " AND ({key} > ? OR ({key} = ? AND (clips.updated_at, clips.id) < (?, ?)))",
+                key = PIN_KEY_SQL
+            )),
+            (Some(Cursor::Recent { .. }), ClipSort::Recent) => {
+                sql.push_str(" AND (clips.updated_at, clips.id) < (?, ?)");
+            }
+            (Some(Cursor::Relevance { .. }), ClipSort::Relevance) => sql.push_str(
+                " AND (clips_fts.rank > ? OR (clips_fts.rank = ? AND (clips.updated_at, clips.id) < (?, ?)))",
+            ),
+            _ => return Err(anyhow!("Cursor does not match query")),
+        }
+
+        match sort {
+            ClipSort::PinnedFirst => sql.push_str(&format!(
+                " ORDER BY {}, clips.updated_at DESC, clips.id DESC",
+                PIN_KEY_SQL
+            )),
+            ClipSort::Recent => sql.push_str(" ORDER BY clips.updated_at DESC, clips.id DESC"),
+            ClipSort::Relevance => {
+                sql.push_str(" ORDER BY clips_fts.rank, clips.updated_at DESC, clips.id DESC")
+            }
+        }
+        sql.push_str(" LIMIT ?");
+
+        Ok(sql)
+    }
+
+    /// Binds the placeholders of `to_sql` (after any extra columns).
+    pub fn bind<'q, O>(
+        &'q self,
+        mut query: QueryAs<'q, Sqlite, O, SqliteArguments<'q>>,
+    ) -> QueryAs<'q, Sqlite, O, SqliteArguments<'q>> {
+        if let Some(expr) = &self.match_expr {
+            query = query.bind(expr);
+        }
+        query = bind_filters(query, &self.filters);
+
+        match &self.after {
+            Some(Cursor::Pinned {
+                pin_key,
+                updated_at,
+                id,
+            }) => {
+                query = query
+                    .bind(pin_key)
+                    .bind(pin_key)
+                    .bind(updated_at)
+                    .bind(id);
+            }
+            Some(Cursor::Recent { updated_at, id }) => {
+                query = query.bind(updated_at).bind(id);
+            }
+            Some(Cursor::Relevance {
+                rank_bits,
+                updated_at,
+                id,
+            }) => {
+                let rank = f64::from_bits(*rank_bits);
+                query = query.bind(rank).bind(rank).bind(updated_at).bind(id);
+            }
+            _ => {}
+        }
+
+        query.bind(self.limit + 1)
+    }
+
+    /// Splits the rows fetched for `to_sql` into the page and the cursor of
+    /// the next page (`None` after the last page). `key` gives a row's clip and
+    /// its `fts_rank`.
+    pub fn paginate<R>(
+        &self,
+        mut rows: Vec<R>,
+        key: impl Fn(&R) -> (&ClipItem, Option<f64>),
+    ) -> (Vec<R>, Option<String>) {
+        let page_size = self.limit as usize;
+        if rows.len() <= page_size {
+            return (rows, None);
+        }
+        rows.truncate(page_size);
+
+        let next = rows.last().map(|row| {
+            let (clip, rank) = key(row);
+            match (self.effective_sort(), rank) {
+                (ClipSort::PinnedFirst, _) => Cursor::Pinned {
+                    pin_key: clip.pin_position.unwrap_or(UNPINNED_KEY),
+                    updated_at: clip.updated_at,
+                    id: clip.id.clone(),
+                },
+                (ClipSort::Relevance, Some(rank)) => Cursor::Relevance {
+                    rank_bits: rank.to_bits(),
+                    updated_at: clip.updated_at,
+                    id: clip.id.clone(),
+                },
+                _ => Cursor::Recent {
+                    updated_at: clip.updated_at,
+                    id: clip.id.clone(),
+                },
+            }
+            .encode()
+        });
+        (rows, next)
+    }
+}
+
+/// Page of a ranking computed in memory (semantic and fuzzy search), given the
+/// items from `offset` on with one extra item fetched to detect a next page.
+pub fn offset_page<T>(mut items: Vec<T>, limit: i32, offset: i64) -> Page<T> {
+    let limit = limit.max(0) as usize;
+    let next_cursor = (items.len() > limit).then(|| {
+        Cursor::Offset {
+            offset: offset + limit as i64,
+        }
+        .encode()
+    });
+    items.truncate(limit);
+    Page { items, next_cursor }
+}
+
+/// Append `ClipFilters` conditions for the clips table aliased as `alias`.
+/// Pair with `bind_filters` once every earlier placeholder is bound.
+pub fn push_filters(sql: &mut String, filters: &ClipFilters, alias: &str) {
+    if let Some(types) = &filters.filter_types {
+        if !types.is_empty() {
+            // Primary type, or a secondary one (a URL inside JSON, ...)
+            let placeholders = types.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+            sql.push_str(&format!(
+                " AND ({0}.detected_type IN ({1}) OR {0}.id IN \
+                 (SELECT clip_id FROM clip_detected_types WHERE detected_type IN ({1})))",
+                alias, placeholders
+            ));
+        }
+    }
+    if filters.favorites_only {
+        sql.push_str(&format!(" AND {}.is_favorite = 1", alias));
+    }
+    if filters.pinned_only {
+        sql.push_str(&format!(" AND {}.is_pinned = 1", alias));
+    }
+    if filters.cluster_id.is_some() {
+        sql.push_str(&format!(
+            " AND {}.id IN (SELECT clip_id FROM clip_clusters WHERE cluster_id = ?)",
+            alias
+        ));
+    }
+}
+
+pub fn bind_filters<'q, O>(
+    mut query: QueryAs<'q, Sqlite, O, SqliteArguments<'q>>,
+    filters: &'q ClipFilters,
+) -> QueryAs<'q, Sqlite, O, SqliteArguments<'q>> {
+    if let Some(types) = &filters.filter_types {
+        // Once for the primary type, once for the secondary ones
+        for t in types.iter().chain(types) {
+            query = query.bind(t);
+        }
+    }
+    if let Some(cluster_id) = filters.cluster_id {
+        query = query.bind(cluster_id);
+    }
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trip() {
+        for cursor in [
+            Cursor::Recent {
+                updated_at: 1_700_000_000,
+                id: "17\"00".to_string(),
+            },
+            Cursor::Relevance {
+                rank_bits: (-1.456953642384106e-6f64).to_bits(),
+                updated_at: 1,
+                id: "a".to_string(),
+            },
+            Cursor::Offset { offset: 50 },
+        ] {
+            assert_eq!(Cursor::decode(&cursor.encode()).unwrap(), cursor);
+        }
+        assert!(Cursor::decode("zz").is_err());
+        assert!(Cursor::decode("7b7d").is_err()); // "{}"
+    }
+
+    #[test]
+    fn test_cursor_must_match_sort() {
+        let token = Cursor::Offset { offset: 10 }.encode();
+        let query = ClipQuery::new().after(Some(&token)).unwrap();
+        assert!(query.to_sql("").is_err());
+
+        let token = Cursor::Recent {
+            updated_at: 1,
+            id: "a".to_string(),
+        }
+        .encode();
+        let query = ClipQuery::new()
+            .text("invoice")
+            .after(Some(&token))
+            .unwrap();
+        assert!(query.to_sql("").is_err());
+        let query = query.sort(ClipSort::Recent);
+        assert!(query.to_sql("").is_ok());
+    }
+}
@@ -1,13 +1,57 @@
 #![allow(dead_code)]
-use crate::models::{ClipItem, Collection, Embedding, Tag};
-use anyhow::Result;
-use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
+use crate::models::search::{MARK_END, MARK_START};
+use crate::models::{
+    ClipChanges, ClipEntity, ClipFilters, ClipItem, ClipRelation, ClipRevision, Cluster,
+    Collection, DetectedType, Embedding, EntitySummary, NewCluster, NewPromptTemplate, Page,
+    PromptTemplate, SearchHit, SearchMode, Snippet, Tag,
+};
+use crate::repositories::clip_query::{self, ClipQuery, Cursor};
+use anyhow::{anyhow, Result};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::SqlitePool;
 use std::str::FromStr;
 
 pub struct ClipRepository {
     pool: SqlitePool,
 }
 
+/// Changes returned per `get_changes` call.
+const CHANGE_BATCH_SIZE: i64 = 500;
+
+/// How long the change feed keeps tombstones of deleted clips (30 days).
+const TOMBSTONE_RETENTION_SECS: i64 = 30 * 24 * 60 * 60;
+
+/// Generations walked by `get_ancestors`/`get_descendants`.
+const MAX_LINEAGE_DEPTH: i64 = 100;
+
+/// Clip row of a `ClipQuery` page, with the FTS5 rank for relevance cursors.
+#[derive(sqlx::FromRow)]
+struct ClipPageRow {
+    #[sqlx(flatten)]
+    clip: ClipItem,
+    #[sqlx(default)]
+    fts_rank: Option<f64>,
+}
+
+/// Search row with FTS5 `highlight()`/`snippet()` output next to the clip.
+#[derive(sqlx::FromRow)]
+struct HighlightedClipRow {
+    #[sqlx(flatten)]
+    clip: ClipItem,
+    #[sqlx(default)]
+    fts_rank: Option<f64>,
+    highlighted: Option<String>,
+    snippet: Option<String>,
+}
+
+/// Clip of a backfill batch, with the rowid to resume after.
+#[derive(sqlx::FromRow)]
+struct BackfillRow {
+    #[sqlx(flatten)]
+    clip: ClipItem,
+    backfill_rowid: i64,
+}
+
 impl ClipRepository {
     pub async fn new(database_url: &str) -> Result<Self> {
         let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
@@ -20,16 +64,44 @@ impl ClipRepository {
         Ok(Self { pool })
     }
 
+    /// A repository on a new database in a temporary directory, removed when
+    /// the `TempDir` is dropped.
+    #[cfg(test)]
+    pub async fn open_temp() -> (Self, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}", dir.path().join("test.db").display());
+        let repository = Self::new(&url).await.unwrap();
+        (repository, dir)
+    }
+
     pub async fn insert(&self, clip: &ClipItem) -> Result<()> {
+        Self::insert_query(clip).execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Inserts many clips in one transaction, for seeding large test histories.
+    #[cfg(test)]
+    pub async fn insert_all(&self, clips: &[ClipItem]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for clip in clips {
+            Self::insert_query(clip).execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    fn insert_query(
+        clip: &ClipItem,
+    ) -> sqlx::query::Query<'_, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'_>> {
         sqlx::query(
             r#"
             INSERT INTO clips (
-                id, content_type, content_text, content_html, content_rtf,
+                id, content_type, content_text, content_html, content_rtf, content_markdown,
                 svg_path, pdf_path, image_path, attachment_path, attachment_type,
                 file_paths, detected_type, metadata, created_at, updated_at, app_name,
                 is_pinned, is_favorite, access_count, content_hash
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&clip.id)
@@ -37,6 +109,7 @@ impl ClipRepository {
         .bind(&clip.content_text)
         .bind(&clip.content_html)
         .bind(&clip.content_rtf)
+        .bind(&clip.content_markdown)
         .bind(&clip.svg_path)
         .bind(&clip.pdf_path)
         .bind(&clip.image_path)
@@ -52,61 +125,114 @@ impl ClipRepository {
         .bind(clip.is_favorite)
         .bind(clip.access_count)
         .bind(&clip.content_hash)
-        .execute(&self.pool)
-        .await?;
-
-        Ok(())
     }
 
     pub async fn get_recent(&self, limit: i32) -> Result<Vec<ClipItem>> {
-        let clips = sqlx::query_as::<_, ClipItem>(
-            "SELECT clips.*, EXISTS(SELECT 1 FROM embeddings e WHERE e.clip_id = clips.id) as has_embedding FROM clips ORDER BY updated_at DESC LIMIT ?"
-        )
-        .bind(limit)
-                .fetch_all(&self.pool)
-                .await?;
+        let sql = format!(
+            "SELECT {} FROM clips ORDER BY updated_at DESC LIMIT ?",
+            clip_query::CLIP_COLUMNS
+        );
+        let clips = sqlx::query_as::<_, ClipItem>(&sql)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
 
         Ok(clips)
     }
 
-    pub async fn get_recent_paginated(
-        &self,
-        limit: i32,
-        offset: i32,
-        favorites_only: bool,
-        pinned_only: bool,
-    ) -> Result<Vec<ClipItem>> {
-        let mut sql = String::from(
-            "SELECT clips.*, EXISTS(SELECT 1 FROM embeddings e WHERE e.clip_id = clips.id) as has_embedding FROM clips WHERE 1=1"
-        );
+    /// One page of clips matching `query`, with the cursor of the next page.
+    pub async fn query_clips(&self, query: &ClipQuery) -> Result<Page<ClipItem>> {
+        let sql = query.to_sql("")?;
+        let rows = query
+            .bind(sqlx::query_as::<_, ClipPageRow>(&sql))
+            .fetch_all(&self.pool)
+            .await?;
 
-        if favorites_only {
-            sql.push_str(" AND clips.is_favorite = 1");
-        }
-        if pinned_only {
-            sql.push_str(" AND clips.is_pinned = 1");
+        let (rows, next_cursor) = query.paginate(rows, |row| (&row.clip, row.fts_rank));
+        Ok(Page {
+            items: rows.into_iter().map(|row| row.clip).collect(),
+            next_cursor,
+        })
+    }
+
+    /// Change feed: clips inserted, updated or deleted after `cursor` (a token
+    /// from a previous call), oldest change first. Without a cursor nothing is
+    /// returned, only the cursor of the current position to start from. So is
+    /// a cursor older than pruned tombstones, with `reset` set.
+    pub async fn get_changes(&self, cursor: Option<&str>) -> Result<ClipChanges> {
+        let since = match cursor.map(Cursor::decode).transpose()? {
+            Some(Cursor::Change { seq }) => seq,
+            Some(_) => return Err(anyhow!("Cursor does not match query")),
+            None => return self.change_feed_head(false).await,
+        };
+
+        let pruned_seq: i64 = sqlx::query_scalar("SELECT pruned_seq FROM change_feed")
+            .fetch_one(&self.pool)
+            .await?;
+        if since < pruned_seq {
+            // Deletes after the cursor may be gone
+            return self.change_feed_head(true).await;
         }
 
-        sql.push_str(" ORDER BY updated_at DESC LIMIT ? OFFSET ?");
+        let mut changes: Vec<(i64, String, bool)> = sqlx::query_as(
+            "SELECT seq, clip_id, deleted FROM clip_changes WHERE seq > ? ORDER BY seq LIMIT ?",
+        )
+        .bind(since)
+        .bind(CHANGE_BATCH_SIZE + 1)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let has_more = changes.len() > CHANGE_BATCH_SIZE as usize;
+        changes.truncate(CHANGE_BATCH_SIZE as usize);
+        let last_seq = changes.last().map_or(since, |(seq, _, _)| *seq);
 
-        let clips = sqlx::query_as::<_, ClipItem>(&sql)
-            .bind(limit)
-            .bind(offset)
-            .fetch_all(&self.pool)
-            .await?;
+        let (deleted, upserted): (Vec<_>, Vec<_>) =
+            changes.into_iter().partition(|(_, _, deleted)| *deleted);
+        let upserted_ids: Vec<String> = upserted.into_iter().map(|(_, id, _)| id).collect();
 
-        Ok(clips)
+        Ok(ClipChanges {
+            upserted: self.get_clips_by_ids(&upserted_ids).await?,
+            deleted: deleted.into_iter().map(|(_, id, _)| id).collect(),
+            cursor: Cursor::Change { seq: last_seq }.encode(),
+            has_more,
+            reset: false,
+        })
     }
 
-    pub async fn get_after_timestamp(&self, timestamp: i64) -> Result<Vec<ClipItem>> {
-        let clips = sqlx::query_as::<_, ClipItem>(
-            "SELECT clips.*, EXISTS(SELECT 1 FROM embeddings e WHERE e.clip_id = clips.id) as has_embedding FROM clips WHERE updated_at > ? ORDER BY updated_at DESC",
+    async fn change_feed_head(&self, reset: bool) -> Result<ClipChanges> {
+        // The newest change may be a pruned tombstone
+        let head: i64 = sqlx::query_scalar(
+            "SELECT MAX(IFNULL((SELECT MAX(seq) FROM clip_changes), 0), pruned_seq) FROM change_feed",
         )
-        .bind(timestamp)
-        .fetch_all(&self.pool)
+        .fetch_one(&self.pool)
         .await?;
+        Ok(ClipChanges {
+            upserted: Vec::new(),
+            deleted: Vec::new(),
+            cursor: Cursor::Change { seq: head }.encode(),
+            has_more: false,
+            reset,
+        })
+    }
 
-        Ok(clips)
+    /// Drops tombstones of clips deleted before `before` (Unix seconds) from
+    /// the change feed. Cursors from before them get `reset` from then on.
+    pub async fn prune_tombstones(&self, before: i64) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE change_feed SET pruned_seq = MAX(pruned_seq, IFNULL((SELECT MAX(seq) FROM clip_changes WHERE deleted = 1 AND changed_at < ?), 0))",
+        )
+        .bind(before)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM clip_changes WHERE deleted = 1 AND changed_at < ?")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
     }
 
     pub async fn touch(&self, id: &str) -> Result<()> {
@@ -121,9 +247,11 @@ impl ClipRepository {
     }
 
     pub async fn get_by_id(&self, id: &str) -> Result<Option<ClipItem>> {
-        let clip = sqlx::query_as::<_, ClipItem>(
-            "SELECT clips.*, EXISTS(SELECT 1 FROM embeddings e WHERE e.clip_id = clips.id) as has_embedding FROM clips WHERE id = ?"
-        )
+        let sql = format!(
+            "SELECT {} FROM clips WHERE id = ?",
+            clip_query::CLIP_COLUMNS
+        );
+        let clip = sqlx::query_as::<_, ClipItem>(&sql)
             .bind(id)
             .fetch_optional(&self.pool)
             .await?;
@@ -138,7 +266,11 @@ impl ClipRepository {
         }
 
         let placeholders: String = ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
-        let sql = format!("SELECT clips.*, EXISTS(SELECT 1 FROM embeddings e WHERE e.clip_id = clips.id) as has_embedding FROM clips WHERE id IN ({})", placeholders);
+        let sql = format!(
+            "SELECT {} FROM clips WHERE id IN ({})",
+            clip_query::CLIP_COLUMNS,
+            placeholders
+        );
 
         let mut query = sqlx::query_as::<_, ClipItem>(&sql);
         for id in ids {
@@ -174,7 +306,7 @@ impl ClipRepository {
     /// - `hello world` → `"hello"* AND "world"*` (both prefix match)
     /// - `user@example.com` → `"user@example.com"*` (literal prefix)
     /// - `"quoted"` → `"""quoted"""*` (escaped quotes with prefix)
-    fn escape_fts5_query(query: &str) -> String {
+    pub(crate) fn escape_fts5_query(query: &str) -> String {
         let trimmed = query.trim();
         if trimmed.is_empty() {
             return String::from("\"\"");
@@ -200,137 +332,113 @@ impl ClipRepository {
         escaped_tokens.join(" AND ")
     }
 
-    pub async fn search(
-        &self,
-        query: &str,
-        filter_types: Option<Vec<String>>,
-        limit: i32,
-    ) -> Result<Vec<ClipItem>> {
-        let escaped_query = Self::escape_fts5_query(query);
-
-        // Build base query
-        let mut sql = String::new();
-        let has_text_query = escaped_query != "\"\"";
-
-        if has_text_query {
-            sql.push_str(
-                r#"
-                SELECT clips.* FROM clips
-                INNER JOIN clips_fts ON clips.rowid = clips_fts.rowid
-                WHERE clips_fts MATCH ?
-            "#,
-            );
-        } else {
-            sql.push_str("SELECT clips.* FROM clips WHERE 1=1");
-        }
-
-        // Add filter types if present
-        if let Some(types) = &filter_types {
-            if !types.is_empty() {
-                sql.push_str(" AND clips.detected_type IN (");
-                for (i, _) in types.iter().enumerate() {
-                    if i > 0 {
-                        sql.push_str(", ");
-                    }
-                    sql.push('?');
-                }
-                sql.push(')');
-            }
-        }
-
-        if has_text_query {
-            sql.push_str(" ORDER BY clips_fts.rank, clips.updated_at DESC LIMIT ?");
-        } else {
-            sql.push_str(" ORDER BY clips.updated_at DESC LIMIT ?");
-        }
-
-        // Bind parameters
-        let mut query_builder = sqlx::query_as::<_, ClipItem>(&sql);
-
-        if has_text_query {
-            query_builder = query_builder.bind(escaped_query);
-        }
-
-        if let Some(types) = &filter_types {
-            for t in types {
-                query_builder = query_builder.bind(t);
-            }
+    /// Same as `query_clips`, but each hit carries the byte ranges of the
+    /// matched tokens in `content_text` and an FTS5 snippet around them.
+    pub async fn search_hits(&self, query: &ClipQuery) -> Result<Page<SearchHit>> {
+        if !query.has_text() {
+            // Nothing to highlight without a text query
+            let page = self.query_clips(query).await?;
+            return Ok(Page {
+                items: page
+                    .items
+                    .into_iter()
+                    .map(|clip| SearchHit::new(clip, SearchMode::Fts, Vec::new()))
+                    .collect(),
+                next_cursor: page.next_cursor,
+            });
         }
 
-        let clips = query_builder.bind(limit).fetch_all(&self.pool).await?;
+        // Column 1 of clips_fts is content_text (column 0 is the unindexed id)
+        let sql = query.to_sql(
+            ", highlight(clips_fts, 1, ?, ?) as highlighted, snippet(clips_fts, 1, ?, ?, '…', 24) as snippet",
+        )?;
+
+        let (start, end) = (MARK_START.to_string(), MARK_END.to_string());
+        let query_builder = sqlx::query_as::<_, HighlightedClipRow>(&sql)
+            .bind(&start)
+            .bind(&end)
+            .bind(&start)
+            .bind(&end);
+
+        let rows = query.bind(query_builder).fetch_all(&self.pool).await?;
+        let (rows, next_cursor) = query.paginate(rows, |row| (&row.clip, row.fts_rank));
+
+        let items = rows
+            .into_iter()
+            .map(|row| {
+                let highlighted = row.highlighted.as_deref().map(Snippet::from_marked);
+                // Offsets are only meaningful if the marked text is the clip text
+                // (a clip that itself contains the marker characters is not)
+                let highlights = highlighted
+                    .filter(|h| Some(h.text.as_str()) == row.clip.content_text.as_deref())
+                    .map(|h| h.highlights)
+                    .unwrap_or_default();
+
+                SearchHit {
+                    clip: row.clip,
+                    mode: SearchMode::Fts,
+                    highlights,
+                    snippet: row.snippet.as_deref().map(Snippet::from_marked),
+                }
+            })
+            .collect();
 
-        Ok(clips)
+        Ok(Page { items, next_cursor })
     }
 
-    /// Search clips with FTS and pagination
-    /// NOTE: For future semantic search, replace FTS query with embedding similarity
-    /// TODO: Add semantic_search_paginated() method that uses embeddings table
-    pub async fn search_paginated(
+    /// Candidates for fuzzy search, newest first.
+    ///
+    /// `match_expr` is an FTS5 expression over the trigram index (see
+    /// `services::fuzzy::candidate_query`); `substrings` are terms too short for
+    /// trigrams, matched with LIKE. No ranking happens here: FTS5 streams rowids
+    /// in order, so paging through candidates stays cheap on large histories.
+    pub async fn fuzzy_candidates(
         &self,
-        query: &str,
-        filter_types: Option<Vec<String>>,
+        match_expr: Option<&str>,
+        substrings: &[String],
+        filters: &ClipFilters,
         limit: i32,
         offset: i32,
-        favorites_only: bool,
-        pinned_only: bool,
     ) -> Result<Vec<ClipItem>> {
-        let escaped_query = Self::escape_fts5_query(query);
-
-        let mut sql = String::new();
-        let has_text_query = escaped_query != "\"\"";
+        let patterns: Vec<String> = substrings
+            .iter()
+            .map(|term| {
+                let escaped = term
+                    .replace('\\', "\\\\")
+                    .replace('%', "\\%")
+                    .replace('_', "\\_");
+                format!("%{}%", escaped)
+            })
+            .collect();
 
-        if has_text_query {
+        let mut sql = format!("SELECT {} FROM clips", clip_query::CLIP_COLUMNS);
+        if match_expr.is_some() {
             sql.push_str(
-                r#"
-                SELECT clips.*, EXISTS(SELECT 1 FROM embeddings e WHERE e.clip_id = clips.id) as has_embedding FROM clips
-                INNER JOIN clips_fts ON clips.rowid = clips_fts.rowid
-                WHERE clips_fts MATCH ?
-            "#,
+                " INNER JOIN clips_trigram ON clips.rowid = clips_trigram.rowid WHERE clips_trigram MATCH ?",
             );
         } else {
-            sql.push_str("SELECT clips.*, EXISTS(SELECT 1 FROM embeddings e WHERE e.clip_id = clips.id) as has_embedding FROM clips WHERE 1=1");
+            sql.push_str(" WHERE 1=1");
         }
-
-        if let Some(types) = &filter_types {
-            if !types.is_empty() {
-                sql.push_str(" AND clips.detected_type IN (");
-                for (i, _) in types.iter().enumerate() {
-                    if i > 0 {
-                        sql.push_str(", ");
-                    }
-                    sql.push('?');
-                }
-                sql.push(')');
-            }
-        }
-
-        // Add favorites and pinned filters
-        if favorites_only {
-            sql.push_str(" AND clips.is_favorite = 1");
-        }
-        if pinned_only {
-            sql.push_str(" AND clips.is_pinned = 1");
+        for _ in &patterns {
+            sql.push_str(" AND clips.content_text LIKE ? ESCAPE '\\'");
         }
-
-        if has_text_query {
-            sql.push_str(" ORDER BY clips_fts.rank, clips.updated_at DESC LIMIT ? OFFSET ?");
+        clip_query::push_filters(&mut sql, filters, "clips");
+        // Ordering by the FTS rowid lets FTS5 stream matches instead of sorting them
+        if match_expr.is_some() {
+            sql.push_str(" ORDER BY clips_trigram.rowid DESC LIMIT ? OFFSET ?");
         } else {
-            sql.push_str(" ORDER BY clips.updated_at DESC LIMIT ? OFFSET ?");
+            sql.push_str(" ORDER BY clips.rowid DESC LIMIT ? OFFSET ?");
         }
 
         let mut query_builder = sqlx::query_as::<_, ClipItem>(&sql);
-
-        if has_text_query {
-            query_builder = query_builder.bind(escaped_query);
+        if let Some(expr) = match_expr {
+            query_builder = query_builder.bind(expr);
         }
-
-        if let Some(types) = &filter_types {
-            for t in types {
-                query_builder = query_builder.bind(t);
-            }
+        for pattern in &patterns {
+            query_builder = query_builder.bind(pattern);
         }
 
-        let clips = query_builder
+        let clips = clip_query::bind_filters(query_builder, filters)
             .bind(limit)
             .bind(offset)
             .fetch_all(&self.pool)
@@ -345,13 +453,15 @@ impl ClipRepository {
             .execute(&self.pool)
             .await?;
 
-        Ok(())
+        self.prune_tombstones(chrono::Utc::now().timestamp() - TOMBSTONE_RETENTION_SECS)
+            .await
     }
 
     pub async fn clear_all(&self) -> Result<()> {
         sqlx::query("DELETE FROM clips").execute(&self.pool).await?;
 
-        Ok(())
+        self.prune_tombstones(chrono::Utc::now().timestamp() - TOMBSTONE_RETENTION_SECS)
+            .await
     }
 
     /// Find clip by exact content text match
@@ -376,25 +486,47 @@ impl ClipRepository {
         Ok(clip)
     }
 
-    /// Toggle pin status
+    /// Pins a clip at the end of the pin order, or unpins it and closes the gap.
     pub async fn toggle_pin(&self, id: &str) -> Result<bool> {
-        let current = sqlx::query_scalar::<_, i32>("SELECT is_pinned FROM clips WHERE id = ?")
-            .bind(id)
-            .fetch_one(&self.pool)
-            .await?;
+        let mut tx = self.pool.begin().await?;
 
-        let new_value = if current == 1 { 0 } else { 1 };
+        let (current, position) = sqlx::query_as::<_, (i32, Option<i64>)>(
+            "SELECT is_pinned, pin_position FROM clips WHERE id = ?",
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
 
-        sqlx::query("UPDATE clips SET is_pinned = ? WHERE id = ?")
-            .bind(new_value)
+        if current == 1 {
+            sqlx::query("UPDATE clips SET is_pinned = 0, pin_position = NULL WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            if let Some(position) = position {
+                sqlx::query(
+                    "UPDATE clips SET pin_position = pin_position - 1 WHERE pin_position > ?",
+                )
+                .bind(position)
+                .execute(&mut *tx)
+                .await?;
+            }
+        } else {
+            sqlx::query(
+                r#"
+                UPDATE clips SET is_pinned = 1,
+                    pin_position = (SELECT IFNULL(MAX(pin_position), 0) + 1 FROM clips)
+                WHERE id = ?
+                "#,
+            )
             .bind(id)
-            .execute(&self.pool)
+            .execute(&mut *tx)
             .await?;
+        }
 
-        Ok(new_value == 1)
+        tx.commit().await?;
+        Ok(current != 1)
     }
 
-    /// Toggle favorite status
     pub async fn toggle_favorite(&self, id: &str) -> Result<bool> {
         let current = sqlx::query_scalar::<_, i32>("SELECT is_favorite FROM clips WHERE id = ?")
             .bind(id)
@@ -422,6 +554,174 @@ impl ClipRepository {
         Ok(())
     }
 
+    // ===== PIN OPERATIONS =====
+
+    /// Pinned clips in pin order.
+    pub async fn get_pinned_clips(&self) -> Result<Vec<ClipItem>> {
+        let sql = format!(
+            "SELECT {} FROM clips WHERE clips.pin_position IS NOT NULL ORDER BY clips.pin_position",
+            clip_query::CLIP_COLUMNS
+        );
+        let clips = sqlx::query_as::<_, ClipItem>(&sql)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(clips)
+    }
+
+    /// Clip in quick-paste slot `slot` (1-based), i.e. the pin at that position.
+    pub async fn get_pin_slot(&self, slot: i64) -> Result<Option<ClipItem>> {
+        let sql = format!(
+            "SELECT {} FROM clips WHERE clips.pin_position = ?",
+            clip_query::CLIP_COLUMNS
+        );
+        let clip = sqlx::query_as::<_, ClipItem>(&sql)
+            .bind(slot)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(clip)
+    }
+
+    /// Rewrites the pin order: `ids` first (in that order), then any pinned
+    /// clips not listed, in their previous order. Unpinned ids are ignored.
+    pub async fn reorder_pins(&self, ids: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let current: Vec<String> = sqlx::query_scalar(
+            "SELECT id FROM clips WHERE pin_position IS NOT NULL ORDER BY pin_position",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut order: Vec<&String> = Vec::with_capacity(current.len());
+        for id in ids.iter().filter(|id| current.contains(id)).chain(&current) {
+            if !order.contains(&id) {
+                order.push(id);
+            }
+        }
+
+        for (index, id) in order.iter().enumerate() {
+            sqlx::query("UPDATE clips SET pin_position = ? WHERE id = ? AND pin_position != ?")
+                .bind(index as i64 + 1)
+                .bind(id)
+                .bind(index as i64 + 1)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Moves a pinned clip to `position` (1-based, clamped to the pin count).
+    pub async fn move_pin(&self, id: &str, position: i64) -> Result<()> {
+        let mut order: Vec<String> = self
+            .get_pinned_clips()
+            .await?
+            .into_iter()
+            .map(|clip| clip.id)
+            .collect();
+
+        let from = order
+            .iter()
+            .position(|pinned| pinned == id)
+            .ok_or_else(|| anyhow!("Clip is not pinned"))?;
+        let moved = order.remove(from);
+        let to = (position.max(1) as usize - 1).min(order.len());
+        order.insert(to, moved);
+
+        self.reorder_pins(&order).await
+    }
+
+    // ===== SIMILARITY OPERATIONS =====
+
+    /// FTS search matching ANY of the keywords, best matches first.
+    /// `similarity_score` holds the raw bm25 relevance (higher is better).
+    pub async fn search_by_keywords(
+        &self,
+        keywords: &[String],
+        filters: &ClipFilters,
+        exclude_id: &str,
+        limit: i32,
+    ) -> Result<Vec<ClipItem>> {
+        if keywords.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let match_query = keywords
+            .iter()
+            .map(|k| format!("\"{}\"", k.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+
+        let mut sql = format!(
+            r#"
+            SELECT {}, -bm25(clips_fts) as similarity_score
+            FROM clips
+            INNER JOIN clips_fts ON clips.rowid = clips_fts.rowid
+            WHERE clips_fts MATCH ? AND clips.id != ?
+        "#,
+            clip_query::CLIP_COLUMNS
+        );
+        clip_query::push_filters(&mut sql, filters, "clips");
+        sql.push_str(" ORDER BY clips_fts.rank LIMIT ?");
+
+        let query_builder = sqlx::query_as::<_, ClipItem>(&sql)
+            .bind(match_query)
+            .bind(exclude_id);
+        let clips = clip_query::bind_filters(query_builder, filters)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(clips)
+    }
+
+    /// Clips that have a raster image on disk (images and office PNG previews).
+    pub async fn get_image_clips(&self, filters: &ClipFilters) -> Result<Vec<ClipItem>> {
+        let mut sql = format!(
+            "SELECT {} FROM clips WHERE clips.image_path IS NOT NULL",
+            clip_query::CLIP_COLUMNS
+        );
+        clip_query::push_filters(&mut sql, filters, "clips");
+
+        let query_builder = sqlx::query_as::<_, ClipItem>(&sql);
+        let clips = clip_query::bind_filters(query_builder, filters)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(clips)
+    }
+
+    /// Replace the metadata JSON of a clip
+    pub async fn update_metadata(&self, id: &str, metadata: Option<String>) -> Result<()> {
+        sqlx::query("UPDATE clips SET metadata = ? WHERE id = ?")
+            .bind(metadata)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Saves OCR output: the metadata, and the text unless `content_text` is None.
+    pub async fn set_recognized_text(
+        &self,
+        id: &str,
+        content_text: Option<&str>,
+        metadata: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE clips SET content_text = COALESCE(?, content_text), metadata = ? WHERE id = ?",
+        )
+        .bind(content_text)
+        .bind(metadata)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
     // ===== TAG OPERATIONS =====
 
     /// Create a new tag
@@ -645,46 +945,30 @@ impl ClipRepository {
         Ok(embedding)
     }
 
-    pub async fn get_embeddings_with_filters(
-        &self,
-        filter_types: Option<Vec<String>>,
-        favorites_only: bool,
-        pinned_only: bool,
-    ) -> Result<Vec<Embedding>> {
+    pub async fn get_embeddings_with_filters(&self, filters: &ClipFilters) -> Result<Vec<Embedding>> {
         let mut sql = String::from(
             "SELECT e.* FROM embeddings e INNER JOIN clips c ON e.clip_id = c.id WHERE 1=1",
         );
+        clip_query::push_filters(&mut sql, filters, "c");
 
-        if let Some(types) = &filter_types {
-            if !types.is_empty() {
-                sql.push_str(" AND c.detected_type IN (");
-                for (i, _) in types.iter().enumerate() {
-                    if i > 0 {
-                        sql.push_str(", ");
-                    }
-                    sql.push('?');
-                }
-                sql.push(')');
-            }
-        }
-
-        if favorites_only {
-            sql.push_str(" AND c.is_favorite = 1");
-        }
-        if pinned_only {
-            sql.push_str(" AND c.is_pinned = 1");
-        }
-
-        let mut query_builder = sqlx::query_as::<_, Embedding>(&sql);
+        let query_builder = sqlx::query_as::<_, Embedding>(&sql);
+        let embeddings = clip_query::bind_filters(query_builder, filters)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(embeddings)
+    }
 
-        if let Some(types) = &filter_types {
-            for t in types {
-                query_builder = query_builder.bind(t);
-            }
-        }
+    /// Every stored vector with its clip text: (clip_id, vector, model, content_text)
+    pub async fn get_embeddings_with_text(
+        &self,
+    ) -> Result<Vec<(String, Vec<u8>, String, Option<String>)>> {
+        let rows = sqlx::query_as::<_, (String, Vec<u8>, String, Option<String>)>(
+            "SELECT e.clip_id, e.vector, e.model, c.content_text FROM embeddings e INNER JOIN clips c ON e.clip_id = c.id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
-        let embeddings = query_builder.fetch_all(&self.pool).await?;
-        Ok(embeddings)
+        Ok(rows)
     }
 
     /// Delete embedding for a clip
@@ -696,34 +980,558 @@ impl ClipRepository {
 
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // ===== CLUSTER OPERATIONS =====
 
-    #[test]
-    fn test_escape_fts5_query_simple() {
-        let result = ClipRepository::escape_fts5_query("hello world");
-        assert_eq!(result, "\"hello\"* AND \"world\"*");
-    }
+    /// Replace all clusters and assignments with the result of a new run
+    pub async fn replace_clusters(&self, model: &str, clusters: &[NewCluster]) -> Result<()> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
 
-    #[test]
-    fn test_escape_fts5_query_single_word() {
-        let result = ClipRepository::escape_fts5_query("cli");
-        assert_eq!(result, "\"cli\"*");
-    }
+        sqlx::query("DELETE FROM clip_clusters")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM clusters").execute(&mut *tx).await?;
 
-    #[test]
-    fn test_escape_fts5_query_with_quotes() {
-        let result = ClipRepository::escape_fts5_query("say \"hello\"");
-        assert_eq!(result, "\"say\"* AND \"\"\"hello\"\"\"*");
-    }
+        for cluster in clusters {
+            let cluster_id = sqlx::query_scalar::<_, i64>(
+                "INSERT INTO clusters (label, terms, size, model, created_at) VALUES (?, ?, ?, ?, ?) RETURNING id",
+            )
+            .bind(&cluster.label)
+            .bind(serde_json::to_string(&cluster.terms)?)
+            .bind(cluster.members.len() as i64)
+            .bind(model)
+            .bind(now)
+            .fetch_one(&mut *tx)
+            .await?;
 
-    #[test]
-    fn test_escape_fts5_query_email() {
-        let result = ClipRepository::escape_fts5_query("user@example.com");
-        assert_eq!(result, "\"user@example.com\"*");
+            for (clip_id, score) in &cluster.members {
+                sqlx::query(
+                    "INSERT INTO clip_clusters (clip_id, cluster_id, score) VALUES (?, ?, ?)",
+                )
+                .bind(clip_id)
+                .bind(cluster_id)
+                .bind(score)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// All clusters, largest first
+    pub async fn get_clusters(&self) -> Result<Vec<Cluster>> {
+        let clusters =
+            sqlx::query_as::<_, Cluster>("SELECT * FROM clusters ORDER BY size DESC, id ASC")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(clusters)
+    }
+
+    /// Cluster a clip was assigned to, if any
+    pub async fn get_cluster_for_clip(&self, clip_id: &str) -> Result<Option<Cluster>> {
+        let cluster = sqlx::query_as::<_, Cluster>(
+            "SELECT clusters.* FROM clusters INNER JOIN clip_clusters cc ON cc.cluster_id = clusters.id WHERE cc.clip_id = ?",
+        )
+        .bind(clip_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(cluster)
+    }
+
+    // ===== LINEAGE OPERATIONS =====
+
+    /// Records that `clip_id` was made from `derived_from`, replacing any
+    /// earlier link. Links that would make a clip its own ancestor are refused.
+    pub async fn add_relation(
+        &self,
+        clip_id: &str,
+        derived_from: &str,
+        transform_id: Option<&str>,
+    ) -> Result<()> {
+        if clip_id == derived_from
+            || self
+                .get_descendants(clip_id)
+                .await?
+                .iter()
+                .any(|clip| clip.id == derived_from)
+        {
+            return Err(anyhow!("A clip cannot be derived from itself"));
+        }
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO clip_relations (clip_id, derived_from, transform_id, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(clip_id)
+        .bind(derived_from)
+        .bind(transform_id)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// How `clip_id` was derived, if it was.
+    pub async fn get_relation(&self, clip_id: &str) -> Result<Option<ClipRelation>> {
+        let relation =
+            sqlx::query_as::<_, ClipRelation>("SELECT * FROM clip_relations WHERE clip_id = ?")
+                .bind(clip_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(relation)
+    }
+
+    /// Clips `id` was derived from, nearest first (parent, grandparent, ...).
+    pub async fn get_ancestors(&self, id: &str) -> Result<Vec<ClipItem>> {
+        let sql = format!(
+            r#"
+            WITH RECURSIVE ancestors(id, depth) AS (
+                SELECT derived_from, 1 FROM clip_relations WHERE clip_id = ?
+                UNION ALL
+                SELECT r.derived_from, a.depth + 1
+                FROM clip_relations r INNER JOIN ancestors a ON r.clip_id = a.id
+                WHERE a.depth < ?
+            )
+            SELECT {} FROM clips
+            INNER JOIN ancestors ON ancestors.id = clips.id
+            ORDER BY ancestors.depth
+        "#,
+            clip_query::CLIP_COLUMNS
+        );
+        let clips = sqlx::query_as::<_, ClipItem>(&sql)
+            .bind(id)
+            .bind(MAX_LINEAGE_DEPTH)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(clips)
+    }
+
+    /// Clips derived from `id`, directly or not, generation by generation
+    /// (oldest first within one). `parent_id` tells where each one hangs.
+    pub async fn get_descendants(&self, id: &str) -> Result<Vec<ClipItem>> {
+        let sql = format!(
+            r#"
+            WITH RECURSIVE descendants(id, depth) AS (
+                SELECT clip_id, 1 FROM clip_relations WHERE derived_from = ?
+                UNION ALL
+                SELECT r.clip_id, d.depth + 1
+                FROM clip_relations r INNER JOIN descendants d ON r.derived_from = d.id
+                WHERE d.depth < ?
+            )
+            SELECT {} FROM clips
+            INNER JOIN descendants ON descendants.id = clips.id
+            ORDER BY descendants.depth, clips.created_at, clips.id
+        "#,
+            clip_query::CLIP_COLUMNS
+        );
+        let clips = sqlx::query_as::<_, ClipItem>(&sql)
+            .bind(id)
+            .bind(MAX_LINEAGE_DEPTH)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(clips)
+    }
+
+    // ===== REVISION OPERATIONS =====
+
+    /// Replaces the content of `clip.id` with `clip`'s (content, detection,
+    /// hash, updated_at), keeping the previous content as a revision.
+    pub async fn update_content(&self, clip: &ClipItem) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        let saved = sqlx::query(
+            r#"
+            INSERT INTO clip_revisions (
+                clip_id, content_type, content_text, content_html, content_rtf,
+                content_markdown, detected_type, metadata, content_hash, created_at
+            )
+            SELECT id, content_type, content_text, content_html, content_rtf,
+                content_markdown, detected_type, metadata, content_hash, ?
+            FROM clips WHERE id = ?
+            "#,
+        )
+        .bind(chrono::Utc::now().timestamp())
+        .bind(&clip.id)
+        .execute(&mut *tx)
+        .await?;
+        if saved.rows_affected() == 0 {
+            return Err(anyhow!("Clip not found"));
+        }
+
+        // The FTS and change feed triggers pick up the update
+        sqlx::query(
+            r#"
+            UPDATE clips SET
+                content_type = ?, content_text = ?, content_html = ?, content_rtf = ?,
+                content_markdown = ?, detected_type = ?, metadata = ?, content_hash = ?,
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&clip.content_type)
+        .bind(&clip.content_text)
+        .bind(&clip.content_html)
+        .bind(&clip.content_rtf)
+        .bind(&clip.content_markdown)
+        .bind(&clip.detected_type)
+        .bind(&clip.metadata)
+        .bind(&clip.content_hash)
+        .bind(clip.updated_at)
+        .bind(&clip.id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Previous versions of a clip, most recent first.
+    pub async fn get_revisions(&self, clip_id: &str) -> Result<Vec<ClipRevision>> {
+        let revisions = sqlx::query_as::<_, ClipRevision>(
+            "SELECT * FROM clip_revisions WHERE clip_id = ? ORDER BY id DESC",
+        )
+        .bind(clip_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(revisions)
+    }
+
+    pub async fn get_revision(&self, id: i64) -> Result<Option<ClipRevision>> {
+        let revision =
+            sqlx::query_as::<_, ClipRevision>("SELECT * FROM clip_revisions WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(revision)
+    }
+
+    // ===== DETECTED TYPE OPERATIONS =====
+
+    /// Replaces the secondary content types of a clip.
+    pub async fn set_detected_types(&self, clip_id: &str, types: &[DetectedType]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM clip_detected_types WHERE clip_id = ?")
+            .bind(clip_id)
+            .execute(&mut *tx)
+            .await?;
+        for detected in types {
+            sqlx::query(
+                "INSERT OR REPLACE INTO clip_detected_types (clip_id, detected_type, confidence) VALUES (?, ?, ?)",
+            )
+            .bind(clip_id)
+            .bind(&detected.detected_type)
+            .bind(detected.confidence)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Secondary content types of a clip, most confident first.
+    pub async fn get_detected_types(&self, clip_id: &str) -> Result<Vec<DetectedType>> {
+        let types = sqlx::query_as::<_, DetectedType>(
+            "SELECT detected_type, confidence FROM clip_detected_types WHERE clip_id = ? ORDER BY confidence DESC, rowid",
+        )
+        .bind(clip_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(types)
+    }
+
+    // ===== ENTITY OPERATIONS =====
+
+    /// Replaces the entities extracted from a clip's text.
+    pub async fn set_entities(&self, clip_id: &str, entities: &[ClipEntity]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM clip_entities WHERE clip_id = ?")
+            .bind(clip_id)
+            .execute(&mut *tx)
+            .await?;
+        for entity in entities {
+            sqlx::query(
+                "INSERT INTO clip_entities (clip_id, kind, value, span_start, span_end) VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(clip_id)
+            .bind(&entity.kind)
+            .bind(&entity.value)
+            .bind(entity.start)
+            .bind(entity.end)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Entities of a clip in text order, optionally of one kind.
+    pub async fn get_entities(&self, clip_id: &str, kind: Option<&str>) -> Result<Vec<ClipEntity>> {
+        let entities = sqlx::query_as::<_, ClipEntity>(
+            r#"
+            SELECT kind, value, span_start AS start, span_end AS "end"
+            FROM clip_entities
+            WHERE clip_id = ? AND (? IS NULL OR kind = ?)
+            ORDER BY span_start
+            "#,
+        )
+        .bind(clip_id)
+        .bind(kind)
+        .bind(kind)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entities)
+    }
+
+    /// Distinct entity values across clips used since `since` (Unix seconds),
+    /// most recently seen first.
+    pub async fn search_entities(
+        &self,
+        kind: Option<&str>,
+        since: Option<i64>,
+        limit: i64,
+    ) -> Result<Vec<EntitySummary>> {
+        // SQLite takes the bare `c.id` from the row holding MAX(updated_at)
+        let entities = sqlx::query_as::<_, EntitySummary>(
+            r#"
+            SELECT e.kind, e.value, COUNT(DISTINCT e.clip_id) AS clip_count,
+                   MAX(c.updated_at) AS last_seen, c.id AS latest_clip_id
+            FROM clip_entities e
+            JOIN clips c ON c.id = e.clip_id
+            WHERE (? IS NULL OR e.kind = ?) AND c.updated_at >= ?
+            GROUP BY e.kind, e.value
+            ORDER BY last_seen DESC, e.kind, e.value
+            LIMIT ?
+            "#,
+        )
+        .bind(kind)
+        .bind(kind)
+        .bind(since.unwrap_or(0))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entities)
+    }
+
+    // ===== BACKFILL OPERATIONS =====
+
+    /// The next clips with text (image clips with `images`) a backfill job
+    /// hasn't processed, oldest first, each with its rowid for
+    /// `advance_backfill`. Empty once the job is done.
+    pub async fn next_backfill_batch(
+        &self,
+        name: &str,
+        images: bool,
+        limit: i64,
+    ) -> Result<Vec<(i64, ClipItem)>> {
+        let rows = sqlx::query_as::<_, BackfillRow>(
+            r#"
+            SELECT c.rowid AS backfill_rowid, c.*
+            FROM clips c
+            JOIN backfills b ON b.name = ?
+            WHERE c.rowid > b.last_rowid AND c.rowid <= b.max_rowid
+                AND CASE WHEN ? THEN c.content_type = 'image' ELSE c.content_text IS NOT NULL END
+            ORDER BY c.rowid
+            LIMIT ?
+            "#,
+        )
+        .bind(name)
+        .bind(images)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.backfill_rowid, row.clip))
+            .collect())
+    }
+
+    /// Records that a backfill job has processed the clips up to `rowid`.
+    pub async fn advance_backfill(&self, name: &str, rowid: i64) -> Result<()> {
+        sqlx::query("UPDATE backfills SET last_rowid = MAX(last_rowid, ?) WHERE name = ?")
+            .bind(rowid)
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Makes a backfill job cover every current clip again, as for a database
+    /// migrated with them.
+    #[cfg(test)]
+    pub async fn restart_backfill(&self, name: &str) -> Result<()> {
+        sqlx::query(
+            "UPDATE backfills SET last_rowid = 0, max_rowid = (SELECT IFNULL(MAX(rowid), 0) FROM clips) WHERE name = ?",
+        )
+        .bind(name)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // ===== PROMPT TEMPLATE OPERATIONS =====
+
+    pub async fn create_prompt_template(
+        &self,
+        template: &NewPromptTemplate,
+    ) -> Result<PromptTemplate> {
+        let now = chrono::Utc::now().timestamp();
+        let created = sqlx::query_as::<_, PromptTemplate>(
+            r#"
+            INSERT INTO prompt_templates (name, description, template, content_types, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+        )
+        .bind(&template.name)
+        .bind(&template.description)
+        .bind(&template.template)
+        .bind(sqlx::types::Json(&template.content_types))
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(created)
+    }
+
+    pub async fn update_prompt_template(
+        &self,
+        id: i64,
+        template: &NewPromptTemplate,
+    ) -> Result<PromptTemplate> {
+        let updated = sqlx::query_as::<_, PromptTemplate>(
+            r#"
+            UPDATE prompt_templates
+            SET name = ?, description = ?, template = ?, content_types = ?, updated_at = ?
+            WHERE id = ?
+            RETURNING *
+            "#,
+        )
+        .bind(&template.name)
+        .bind(&template.description)
+        .bind(&template.template)
+        .bind(sqlx::types::Json(&template.content_types))
+        .bind(chrono::Utc::now().timestamp())
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        updated.ok_or_else(|| anyhow!("Prompt template not found"))
+    }
+
+    /// Creates each template, or updates the one with the same name. All or
+    /// none of them are saved.
+    pub async fn upsert_prompt_templates(
+        &self,
+        templates: &[NewPromptTemplate],
+    ) -> Result<Vec<PromptTemplate>> {
+        let now = chrono::Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+
+        let mut saved = Vec::with_capacity(templates.len());
+        for template in templates {
+            let row = sqlx::query_as::<_, PromptTemplate>(
+                r#"
+                INSERT INTO prompt_templates (name, description, template, content_types, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?)
+                ON CONFLICT(name) DO UPDATE SET
+                    description = excluded.description,
+                    template = excluded.template,
+                    content_types = excluded.content_types,
+                    updated_at = excluded.updated_at
+                RETURNING *
+                "#,
+            )
+            .bind(&template.name)
+            .bind(&template.description)
+            .bind(&template.template)
+            .bind(sqlx::types::Json(&template.content_types))
+            .bind(now)
+            .bind(now)
+            .fetch_one(&mut *tx)
+            .await?;
+            saved.push(row);
+        }
+
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    pub async fn delete_prompt_template(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM prompt_templates WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_prompt_template(&self, id: i64) -> Result<Option<PromptTemplate>> {
+        let template =
+            sqlx::query_as::<_, PromptTemplate>("SELECT * FROM prompt_templates WHERE id = ?")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(template)
+    }
+
+    /// All prompt templates, by name
+    pub async fn get_prompt_templates(&self) -> Result<Vec<PromptTemplate>> {
+        let templates = sqlx::query_as::<_, PromptTemplate>(
+            "SELECT * FROM prompt_templates ORDER BY name COLLATE NOCASE",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(templates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_fts5_query_simple() {
+        let result = ClipRepository::escape_fts5_query("hello world");
+        assert_eq!(result, "\"hello\"* AND \"world\"*");
+    }
+
+    #[test]
+    fn test_escape_fts5_query_single_word() {
+        let result = ClipRepository::escape_fts5_query("cli");
+        assert_eq!(result, "\"cli\"*");
+    }
+
+    #[test]
+    fn test_escape_fts5_query_with_quotes() {
+        let result = ClipRepository::escape_fts5_query("say \"hello\"");
+        assert_eq!(result, "\"say\"* AND \"\"\"hello\"\"\"*");
+    }
+
+    #[test]
+    fn test_escape_fts5_query_email() {
+        let result = ClipRepository::escape_fts5_query("user@example.com");
+        assert_eq!(result, "\"user@example.com\"*");
     }
 
     #[test]
@@ -748,9 +1556,613 @@ mod tests {
         assert_eq!(result, "\"\"");
     }
 
+    #[tokio::test]
+    async fn test_fuzzy_candidates() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        for (id, text) in [
+            ("1", "kubectl get pods in the kubernetes cluster"),
+            ("2", "東京本社の会議"),
+            ("3", "grocery list"),
+        ] {
+            let mut clip = ClipItem::from_text(text.to_string(), "text".to_string(), None);
+            clip.id = id.to_string();
+            repo.insert(&clip).await.unwrap();
+        }
+
+        let filters = ClipFilters::default();
+        let trigram = repo
+            .fuzzy_candidates(Some("\"kube\" OR \"ren\""), &[], &filters, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(trigram.len(), 1);
+        assert_eq!(trigram[0].id, "1");
+
+        let cjk = repo
+            .fuzzy_candidates(None, &["東京".to_string()], &filters, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(cjk.len(), 1);
+        assert_eq!(cjk[0].id, "2");
+
+        // Trigram index follows deletes
+        repo.delete("1").await.unwrap();
+        assert!(repo
+            .fuzzy_candidates(Some("\"kube\""), &[], &filters, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    async fn insert_text(repo: &ClipRepository, id: &str, text: &str, updated_at: i64) {
+        let mut clip = ClipItem::from_text(text.to_string(), "text".to_string(), None);
+        clip.id = id.to_string();
+        clip.updated_at = updated_at;
+        repo.insert(&clip).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_query_clips_keyset_pagination() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        // "b" and "c" share a timestamp, the id breaks the tie
+        for (id, updated_at) in [("a", 1), ("b", 2), ("c", 2), ("d", 3)] {
+            insert_text(&repo, id, &format!("note {}", id), updated_at).await;
+        }
+
+        let first = repo
+            .query_clips(&ClipQuery::new().limit(2))
+            .await
+            .unwrap();
+        let ids: Vec<&str> = first.items.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["d", "c"]);
+
+        // A clip arriving while scrolling doesn't shift the next page
+        insert_text(&repo, "e", "note e", 4).await;
+
+        let second = repo
+            .query_clips(
+                &ClipQuery::new()
+                    .after(first.next_cursor.as_deref())
+                    .unwrap()
+                    .limit(2),
+            )
+            .await
+            .unwrap();
+        let ids: Vec<&str> = second.items.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "a"]);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_query_clips_relevance_cursor() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        insert_text(&repo, "1", "invoice invoice invoice", 1).await;
+        insert_text(&repo, "2", "invoice for march and april payments", 2).await;
+        insert_text(&repo, "3", "unrelated", 3).await;
+        insert_text(&repo, "4", "invoice", 4).await;
+
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let query = ClipQuery::new()
+                .text("invoice")
+                .after(cursor.as_deref())
+                .unwrap()
+                .limit(1);
+            let page = repo.query_clips(&query).await.unwrap();
+            seen.extend(page.items.into_iter().map(|c| c.id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        let mut all = repo
+            .query_clips(&ClipQuery::new().text("invoice"))
+            .await
+            .unwrap()
+            .items
+            .into_iter()
+            .map(|c| c.id)
+            .collect::<Vec<_>>();
+        assert_eq!(seen, all);
+        all.sort();
+        assert_eq!(all, vec!["1", "2", "4"]);
+    }
+
+    /// Clips whose bm25 ranks are arbitrary floats, several of them tied.
+    async fn insert_ranked(repo: &ClipRepository) {
+        let mut clips = Vec::new();
+        for i in 0..90 {
+            // Every third text repeats the one before, so its rank ties
+            let n = if i % 3 == 2 { i - 1 } else { i };
+            let text = format!(
+                "{} {}",
+                "invoice ".repeat(n % 4 + 1),
+                "filler ".repeat(n * 7 % 41 + 1)
+            );
+            let mut clip = ClipItem::from_text(text, "text".to_string(), None);
+            clip.id = format!("{:02}", i);
+            clip.updated_at = i as i64 % 4;
+            clips.push(clip);
+        }
+        repo.insert_all(&clips).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_query_clips_relevance_pages_with_ties() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+        insert_ranked(&repo).await;
+
+        let all = repo
+            .query_clips(&ClipQuery::new().text("invoice").limit(100))
+            .await
+            .unwrap();
+        let expected: Vec<String> = all.items.into_iter().map(|c| c.id).collect();
+        assert_eq!(expected.len(), 90);
+
+        // A cursor that drifts off its row repeats or skips rows, so stop
+        // after one page per row instead of looping on a repeated page
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..expected.len() {
+            let query = ClipQuery::new()
+                .text("invoice")
+                .after(cursor.as_deref())
+                .unwrap()
+                .limit(2);
+            let page = repo.query_clips(&query).await.unwrap();
+            seen.extend(page.items.into_iter().map(|c| c.id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(seen, expected);
+    }
+
+    #[tokio::test]
+    async fn test_change_feed() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        insert_text(&repo, "old", "before the cursor", 1).await;
+        let start = repo.get_changes(None).await.unwrap();
+        assert!(start.upserted.is_empty());
+
+        insert_text(&repo, "a", "first", 2).await;
+        insert_text(&repo, "b", "second", 2).await;
+        repo.toggle_pin("a").await.unwrap();
+        repo.delete("old").await.unwrap();
+
+        let changes = repo.get_changes(Some(&start.cursor)).await.unwrap();
+        let ids: Vec<&str> = changes.upserted.iter().map(|c| c.id.as_str()).collect();
+        // "a" moved behind "b" when it was pinned
+        assert_eq!(ids, vec!["b", "a"]);
+        assert_eq!(changes.upserted[1].is_pinned, 1);
+        assert_eq!(changes.deleted, vec!["old"]);
+        assert!(!changes.has_more);
+
+        let none = repo.get_changes(Some(&changes.cursor)).await.unwrap();
+        assert!(none.upserted.is_empty() && none.deleted.is_empty());
+        assert_eq!(none.cursor, changes.cursor);
+        assert!(!none.reset);
+    }
+
+    #[tokio::test]
+    async fn test_change_feed_prunes_tombstones() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+        insert_text(&repo, "a", "first", 1).await;
+        insert_text(&repo, "b", "second", 1).await;
+        let before = repo.get_changes(None).await.unwrap();
+
+        repo.delete("a").await.unwrap();
+        let after = repo.get_changes(None).await.unwrap();
+        // Within the retention window the tombstone is kept
+        let changes = repo.get_changes(Some(&before.cursor)).await.unwrap();
+        assert_eq!(changes.deleted, vec!["a"]);
+
+        repo.prune_tombstones(chrono::Utc::now().timestamp() + 1)
+            .await
+            .unwrap();
+        let tombstones: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM clip_changes WHERE deleted = 1")
+                .fetch_one(&repo.pool)
+                .await
+                .unwrap();
+        assert_eq!(tombstones, 0);
+
+        // The old cursor could miss the delete, so the client has to reload
+        let stale = repo.get_changes(Some(&before.cursor)).await.unwrap();
+        assert!(stale.reset);
+        assert!(stale.deleted.is_empty());
+        assert_eq!(stale.cursor, after.cursor);
+
+        insert_text(&repo, "c", "third", 2).await;
+        let changes = repo.get_changes(Some(&after.cursor)).await.unwrap();
+        assert!(!changes.reset);
+        assert_eq!(changes.upserted[0].id, "c");
+    }
+
+    #[tokio::test]
+    async fn test_search_hits_highlight_matches() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        let text = format!(
+            "{} Deploy the cluster with kubectl apply {}",
+            "intro ".repeat(50),
+            "outro ".repeat(50)
+        );
+        let mut clip = ClipItem::from_text(text.clone(), "text".to_string(), None);
+        clip.id = "1".to_string();
+        repo.insert(&clip).await.unwrap();
+
+        let hits = repo
+            .search_hits(&ClipQuery::new().text("kubectl deploy"))
+            .await
+            .unwrap()
+            .items;
+        assert_eq!(hits.len(), 1);
+
+        let matched: Vec<&str> = hits[0]
+            .highlights
+            .iter()
+            .map(|h| &text[h.start..h.end])
+            .collect();
+        assert_eq!(matched, vec!["Deploy", "kubectl"]);
+
+        let snippet = hits[0].snippet.as_ref().unwrap();
+        assert!(snippet.text.len() < text.len());
+        assert!(snippet.text.starts_with('…'));
+        let h = snippet.highlights[0];
+        assert_eq!(&snippet.text[h.start..h.end], "Deploy");
+    }
+
+    #[tokio::test]
+    async fn test_search_hits_pages_with_ties() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+        insert_ranked(&repo).await;
+
+        let all = repo
+            .search_hits(&ClipQuery::new().text("invoice").limit(100))
+            .await
+            .unwrap();
+        let expected: Vec<String> = all.items.into_iter().map(|h| h.clip.id).collect();
+        assert_eq!(expected.len(), 90);
+
+        let mut seen = Vec::new();
+        let mut cursor: Option<String> = None;
+        for _ in 0..expected.len() {
+            let query = ClipQuery::new()
+                .text("invoice")
+                .after(cursor.as_deref())
+                .unwrap()
+                .limit(2);
+            let page = repo.search_hits(&query).await.unwrap();
+            assert!(page.items.iter().all(|h| !h.highlights.is_empty()));
+            seen.extend(page.items.into_iter().map(|h| h.clip.id));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(seen, expected);
+    }
+
     #[test]
     fn test_escape_fts5_query_whitespace() {
         let result = ClipRepository::escape_fts5_query("   ");
         assert_eq!(result, "\"\"");
     }
+
+    #[tokio::test]
+    async fn test_pin_order_and_slots() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        for (id, updated_at) in [("a", 1), ("b", 2), ("c", 3), ("d", 4), ("e", 5)] {
+            insert_text(&repo, id, &format!("note {}", id), updated_at).await;
+        }
+        for id in ["a", "b", "c"] {
+            assert!(repo.toggle_pin(id).await.unwrap());
+        }
+
+        let pinned_ids = |clips: Vec<ClipItem>| -> Vec<String> {
+            clips.into_iter().map(|c| c.id).collect()
+        };
+        assert_eq!(pinned_ids(repo.get_pinned_clips().await.unwrap()), ["a", "b", "c"]);
+
+        repo.move_pin("c", 1).await.unwrap();
+        assert_eq!(pinned_ids(repo.get_pinned_clips().await.unwrap()), ["c", "a", "b"]);
+
+        // Unlisted pins keep their order after the listed ones
+        repo.reorder_pins(&["b".to_string(), "d".to_string()]).await.unwrap();
+        assert_eq!(pinned_ids(repo.get_pinned_clips().await.unwrap()), ["b", "c", "a"]);
+        assert_eq!(repo.get_pin_slot(2).await.unwrap().unwrap().id, "c");
+
+        // Deleting or unpinning closes the gap
+        repo.delete("b").await.unwrap();
+        assert!(!repo.toggle_pin("a").await.unwrap());
+        let pinned = repo.get_pinned_clips().await.unwrap();
+        assert_eq!(pinned[0].id, "c");
+        assert_eq!(pinned[0].pin_position, Some(1));
+        assert!(repo.get_pin_slot(2).await.unwrap().is_none());
+
+        // History lists pins first, then the rest newest first
+        assert!(repo.toggle_pin("a").await.unwrap());
+        let first = repo.query_clips(&ClipQuery::new().limit(3)).await.unwrap();
+        assert_eq!(pinned_ids(first.items), ["c", "a", "e"]);
+        let second = repo
+            .query_clips(
+                &ClipQuery::new()
+                    .after(first.next_cursor.as_deref())
+                    .unwrap()
+                    .limit(3),
+            )
+            .await
+            .unwrap();
+        assert_eq!(pinned_ids(second.items), ["d"]);
+        assert_eq!(second.next_cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_lineage() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        // a → b → c, a → d
+        for (id, created_at) in [("a", 1), ("b", 2), ("c", 3), ("d", 4)] {
+            insert_text(&repo, id, &format!("note {}", id), created_at).await;
+        }
+        repo.add_relation("b", "a", Some("json_prettify"))
+            .await
+            .unwrap();
+        repo.add_relation("c", "b", None).await.unwrap();
+        repo.add_relation("d", "a", None).await.unwrap();
+        assert!(repo.add_relation("a", "c", None).await.is_err());
+
+        let ids = |clips: Vec<ClipItem>| clips.into_iter().map(|c| c.id).collect::<Vec<_>>();
+        assert_eq!(ids(repo.get_ancestors("c").await.unwrap()), ["b", "a"]);
+        assert_eq!(ids(repo.get_descendants("a").await.unwrap()), ["b", "d", "c"]);
+
+        let c = repo.get_by_id("c").await.unwrap().unwrap();
+        assert_eq!(c.parent_id.as_deref(), Some("b"));
+        let relation = repo.get_relation("b").await.unwrap().unwrap();
+        assert_eq!(relation.transform_id.as_deref(), Some("json_prettify"));
+
+        // Deleting a parent leaves its children as roots
+        repo.delete("b").await.unwrap();
+        let c = repo.get_by_id("c").await.unwrap().unwrap();
+        assert_eq!(c.parent_id, None);
+        assert_eq!(ids(repo.get_descendants("a").await.unwrap()), ["d"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_content_keeps_revisions() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+        insert_text(&repo, "a", "first draft", 1).await;
+
+        let mut clip = repo.get_by_id("a").await.unwrap().unwrap();
+        clip.content_text = Some("second version".to_string());
+        clip.content_hash = Some(ClipItem::compute_hash("second version"));
+        clip.updated_at = 2;
+        repo.update_content(&clip).await.unwrap();
+
+        let updated = repo.get_by_id("a").await.unwrap().unwrap();
+        assert_eq!(updated.content_text.as_deref(), Some("second version"));
+        assert_eq!(updated.updated_at, 2);
+
+        // FTS follows the new text
+        let found = |query: &'static str| {
+            let repo = &repo;
+            async move {
+                repo.query_clips(&ClipQuery::new().text(query))
+                    .await
+                    .unwrap()
+                    .items
+                    .len()
+            }
+        };
+        assert_eq!(found("second").await, 1);
+        assert_eq!(found("draft").await, 0);
+
+        let revisions = repo.get_revisions("a").await.unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].content_text.as_deref(), Some("first draft"));
+        let revision = repo.get_revision(revisions[0].id).await.unwrap().unwrap();
+        assert_eq!(revision.clip_id, "a");
+
+        clip.id = "missing".to_string();
+        assert!(repo.update_content(&clip).await.is_err());
+
+        repo.delete("a").await.unwrap();
+        assert!(repo.get_revisions("a").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_filter_by_secondary_type() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        insert_text(&repo, "a", "see https://example.com", 1).await;
+        insert_text(&repo, "b", "plain note", 2).await;
+        let types = |detected_type: &str, confidence| DetectedType {
+            detected_type: detected_type.to_string(),
+            confidence,
+        };
+        repo.set_detected_types("a", &[types("email", 0.4), types("url", 0.5)])
+            .await
+            .unwrap();
+        let stored = repo.get_detected_types("a").await.unwrap();
+        assert_eq!(stored, [types("url", 0.5), types("email", 0.4)]);
+
+        let filtered = |filter: &[&str]| {
+            let filters = ClipFilters {
+                filter_types: Some(filter.iter().map(|t| t.to_string()).collect()),
+                ..ClipFilters::default()
+            };
+            let repo = &repo;
+            async move {
+                let page = repo
+                    .query_clips(&ClipQuery::new().filters(filters))
+                    .await
+                    .unwrap();
+                page.items.into_iter().map(|c| c.id).collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(filtered(&["url"]).await, ["a"]);
+        assert_eq!(filtered(&["text"]).await, ["b", "a"]);
+        assert!(filtered(&["json"]).await.is_empty());
+
+        repo.set_detected_types("a", &[]).await.unwrap();
+        assert!(filtered(&["url"]).await.is_empty());
+        repo.set_detected_types("a", &[types("url", 0.5)])
+            .await
+            .unwrap();
+        repo.delete("a").await.unwrap();
+        assert!(repo.get_detected_types("a").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_entities() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        let entity = |kind: &str, value: &str, start| ClipEntity {
+            kind: kind.to_string(),
+            value: value.to_string(),
+            start,
+            end: start + value.len() as i64,
+        };
+        insert_text(&repo, "a", "old", 10).await;
+        insert_text(&repo, "b", "new", 20).await;
+        repo.set_entities("a", &[entity("url", "https://a.io", 0)])
+            .await
+            .unwrap();
+        repo.set_entities(
+            "b",
+            &[
+                entity("email", "x@a.io", 0),
+                entity("url", "https://a.io", 10),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let emails = repo.get_entities("b", Some("email")).await.unwrap();
+        assert_eq!(emails, [entity("email", "x@a.io", 0)]);
+        assert_eq!(repo.get_entities("b", None).await.unwrap().len(), 2);
+
+        let urls = repo.search_entities(Some("url"), None, 10).await.unwrap();
+        assert_eq!(urls.len(), 1);
+        assert_eq!(urls[0].value, "https://a.io");
+        assert_eq!(urls[0].clip_count, 2);
+        assert_eq!(
+            (urls[0].last_seen, urls[0].latest_clip_id.as_str()),
+            (20, "b")
+        );
+
+        // Only clips used since then count
+        repo.delete("b").await.unwrap();
+        assert!(repo
+            .search_entities(None, Some(15), 10)
+            .await
+            .unwrap()
+            .is_empty());
+        let all = repo.search_entities(None, None, 10).await.unwrap();
+        assert_eq!((all.len(), all[0].clip_count), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_upsert_prompt_templates_saves_all_or_none() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+        let template = |name: &str, text: &str| NewPromptTemplate {
+            name: name.to_string(),
+            description: None,
+            template: text.to_string(),
+            content_types: Vec::new(),
+        };
+        repo.upsert_prompt_templates(&[template("Jira", "old")])
+            .await
+            .unwrap();
+
+        sqlx::query(
+            "CREATE TRIGGER reject_bad BEFORE INSERT ON prompt_templates WHEN NEW.name = 'Bad'
+             BEGIN SELECT RAISE(ABORT, 'rejected'); END",
+        )
+        .execute(&repo.pool)
+        .await
+        .unwrap();
+        let batch = [template("Jira", "new"), template("Bad", "x")];
+        assert!(repo.upsert_prompt_templates(&batch).await.is_err());
+        let templates = repo.get_prompt_templates().await.unwrap();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].template, "old");
+
+        let saved = repo
+            .upsert_prompt_templates(&[template("Jira", "new"), template("Commit", "c")])
+            .await
+            .unwrap();
+        assert_eq!(saved.len(), 2);
+        assert_eq!(repo.get_prompt_templates().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_backfill_batches() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        // A new database has nothing to backfill
+        insert_text(&repo, "a", "one", 1).await;
+        assert!(repo
+            .next_backfill_batch("detected_types", false, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let mut image = ClipItem::from_text(String::new(), "image".to_string(), None);
+        image.content_type = "image".to_string();
+        image.content_text = None;
+        repo.insert(&image).await.unwrap();
+        insert_text(&repo, "b", "two", 2).await;
+        repo.restart_backfill("detected_types").await.unwrap();
+        // Captured after the migration
+        insert_text(&repo, "c", "three", 3).await;
+
+        let batch = repo
+            .next_backfill_batch("detected_types", false, 1)
+            .await
+            .unwrap();
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].1.id, "a");
+        repo.advance_backfill("detected_types", batch[0].0)
+            .await
+            .unwrap();
+
+        // Clips without text are skipped
+        let batch = repo
+            .next_backfill_batch("detected_types", false, 10)
+            .await
+            .unwrap();
+        let ids: Vec<_> = batch.iter().map(|(_, clip)| clip.id.as_str()).collect();
+        assert_eq!(ids, ["b"]);
+        repo.advance_backfill("detected_types", batch[0].0)
+            .await
+            .unwrap();
+        assert!(repo
+            .next_backfill_batch("detected_types", false, 10)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(repo
+            .next_backfill_batch("unknown", false, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Image jobs cover only image clips
+        repo.restart_backfill("image_hashes").await.unwrap();
+        let batch = repo
+            .next_backfill_batch("image_hashes", true, 10)
+            .await
+            .unwrap();
+        let ids: Vec<_> = batch.iter().map(|(_, clip)| clip.id.as_str()).collect();
+        assert_eq!(ids, [image.id.as_str()]);
+    }
 }
@@ -1,6 +1,8 @@
 // Data access layer
+pub mod clip_query;
 pub mod clip_repository;
 pub mod settings_repository;
 
+pub use clip_query::{ClipQuery, ClipSort, Cursor};
 pub use clip_repository::ClipRepository;
 pub use settings_repository::SettingsRepository;
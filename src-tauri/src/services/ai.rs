@@ -1,13 +1,959 @@
 // AI integration service (Privacy Mode - Direct API calls)
+//
+// Generative transforms over any OpenAI-compatible chat API, so a local
+// llama.cpp or Ollama server works as well as a hosted one.
 
-#[allow(dead_code)]
-pub struct AiService;
+use crate::models::settings::OpenAiEndpoint;
+use crate::models::{ClipItem, NewPromptTemplate};
+use crate::repositories::ClipRepository;
+use crate::services::intelligence::ContentType;
+use crate::services::prompt_template::{self, TemplateVars};
+use crate::services::semantic::SemanticService;
+use crate::services::transform::{clip_text, clip_types, save_derived};
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Longest a whole generation may take.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Longest wait for the server to accept the request or send the next token.
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Prefix of the `clip_relations.transform_id` of saved results.
+const TRANSFORM_PREFIX: &str = "ai:";
+
+/// Prefix of the prompt ids of user templates (`template:{id}`).
+const TEMPLATE_PREFIX: &str = "template:";
+
+/// Prefix of the prompt ids of plugin templates (`plugin:{plugin}/{name}`).
+const PLUGIN_PREFIX: &str = "plugin:";
+
+// ============================================================================
+// Providers
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChatMessage {
+    pub role: &'static str,
+    pub content: String,
+}
+
+/// A chat completion API.
+pub trait ChatProvider: Send + Sync {
+    /// Generates the reply to `messages`, calling `on_token` with each piece
+    /// as it arrives. Dropping the future stops the generation.
+    fn complete(
+        &self,
+        messages: &[ChatMessage],
+        on_token: &mut (dyn FnMut(&str) + Send),
+    ) -> impl Future<Output = Result<String>> + Send;
+}
+
+/// Any server implementing `POST {base_url}/chat/completions`, streamed.
+pub struct OpenAiChatProvider {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiChatProvider {
+    pub fn new(endpoint: &OpenAiEndpoint) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(READ_TIMEOUT)
+            .read_timeout(READ_TIMEOUT)
+            .build()?;
+
+        Ok(Self {
+            client,
+            url: format!(
+                "{}/chat/completions",
+                endpoint.base_url.trim_end_matches('/')
+            ),
+            model: endpoint.model.clone(),
+            api_key: endpoint.api_key.clone().filter(|k| !k.is_empty()),
+        })
+    }
+}
+
+impl ChatProvider for OpenAiChatProvider {
+    async fn complete(
+        &self,
+        messages: &[ChatMessage],
+        on_token: &mut (dyn FnMut(&str) + Send),
+    ) -> Result<String> {
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": true,
+        }));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| anyhow!("AI request failed: {}", e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            bail!("AI endpoint returned {}: {}", status, error_message(&body));
+        }
+
+        let mut reader = ReplyReader::default();
+        let mut pending = Vec::new();
+        while !reader.done {
+            let Some(bytes) = response
+                .chunk()
+                .await
+                .map_err(|e| anyhow!("Failed to read AI response: {}", e))?
+            else {
+                break;
+            };
+            pending.extend_from_slice(&bytes);
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                reader.line(&String::from_utf8_lossy(&line), on_token)?;
+            }
+        }
+        // The last line may have no newline
+        reader.line(&String::from_utf8_lossy(&pending), on_token)?;
+        reader.finish(on_token)
+    }
+}
+
+/// Assembles a reply from the lines of the response body: server-sent events,
+/// one `data: {chunk}` line per token. Servers that ignore `stream` answer
+/// with a single JSON object instead.
+#[derive(Default)]
+struct ReplyReader {
+    reply: String,
+    plain_body: String,
+    streamed: bool,
+    /// `[DONE]` was received
+    done: bool,
+}
+
+impl ReplyReader {
+    fn line(&mut self, line: &str, on_token: &mut (dyn FnMut(&str) + Send)) -> Result<()> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if self.done {
+            return Ok(());
+        }
+        let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+            if !self.streamed {
+                self.plain_body.push_str(line);
+            }
+            return Ok(());
+        };
+        self.streamed = true;
+        if data == "[DONE]" {
+            self.done = true;
+            return Ok(());
+        }
+
+        let chunk: Value =
+            serde_json::from_str(data).map_err(|e| anyhow!("Invalid AI stream chunk: {}", e))?;
+        if chunk.get("error").is_some() {
+            bail!("AI endpoint error: {}", error_message(data));
+        }
+        if let Some(token) = chunk["choices"][0]["delta"]["content"].as_str() {
+            self.reply.push_str(token);
+            on_token(token);
+        }
+        Ok(())
+    }
+
+    fn finish(self, on_token: &mut (dyn FnMut(&str) + Send)) -> Result<String> {
+        if self.streamed {
+            return Ok(self.reply);
+        }
+        let body: Value = serde_json::from_str(&self.plain_body)
+            .map_err(|e| anyhow!("Invalid AI response: {}", e))?;
+        if body.get("error").is_some() {
+            bail!("AI endpoint error: {}", error_message(&self.plain_body));
+        }
+        let reply = body["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow!("AI response has no content"))?;
+        on_token(reply);
+        Ok(reply.to_string())
+    }
+}
+
+/// `error.message` of an OpenAI-style error body, or the body itself.
+fn error_message(body: &str) -> String {
+    serde_json::from_str::<Value>(body)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.trim().to_string())
+}
+
+/// Error of a generation stopped by `AiService::cancel`.
+#[derive(Debug, Clone, Copy, PartialEq, thiserror::Error)]
+#[error("Cancelled")]
+pub struct Cancelled;
+
+// ============================================================================
+// Prompts
+// ============================================================================
+
+/// A prompt offered for a clip, built-in or a user template.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Set for user templates
+    pub template_id: Option<i64>,
+}
+
+/// Options of `run_ai_prompt`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AiOptions {
+    /// Target language of `translate` and `{{language}}` (default English)
+    pub language: Option<String>,
+    /// Save the result as a new clip derived from the source
+    pub save: bool,
+}
+
+/// Result of `run_ai_prompt`; `clip` is set when the result was saved.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiOutput {
+    pub text: String,
+    pub clip: Option<ClipItem>,
+}
+
+struct Prompt {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    /// Detected types the prompt applies to (empty = any text)
+    content_types: &'static [ContentType],
+    /// System message; `{language}` is replaced by `AiOptions::language`
+    system: &'static str,
+    /// The reply is code: a surrounding Markdown fence is removed
+    code_reply: bool,
+}
+
+const fn prompt(
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    content_types: &'static [ContentType],
+    system: &'static str,
+    code_reply: bool,
+) -> Prompt {
+    Prompt {
+        id,
+        name,
+        description,
+        content_types,
+        system,
+        code_reply,
+    }
+}
+
+/// Built-in prompts, in the order they are offered.
+const PROMPTS: &[Prompt] = &[
+    prompt(
+        "summarize",
+        "Summarize",
+        "Summarize the text in a few sentences",
+        &[],
+        "Summarize the text the user sends in a few sentences. Reply with the summary only.",
+        false,
+    ),
+    prompt(
+        "fix_grammar",
+        "Fix Grammar",
+        "Correct spelling and grammar, keeping the tone",
+        &[],
+        "Fix the spelling and grammar of the text the user sends. Keep its meaning, tone and \
+         formatting. Reply with the corrected text only.",
+        false,
+    ),
+    prompt(
+        "translate",
+        "Translate",
+        "Translate the text (to English unless another language is given)",
+        &[],
+        "Translate the text the user sends to {language}. Reply with the translation only.",
+        false,
+    ),
+    prompt(
+        "explain_code",
+        "Explain Code",
+        "Explain what the code does, step by step",
+        &[ContentType::Code],
+        "Explain what the code the user sends does, step by step, in plain language.",
+        false,
+    ),
+    prompt(
+        "json_to_typescript",
+        "JSON → TypeScript",
+        "Write TypeScript types describing the JSON",
+        &[ContentType::Json],
+        "Write TypeScript type definitions describing the JSON the user sends. Use `type` \
+         aliases and name the root type `Root`. Reply with the TypeScript code only.",
+        true,
+    ),
+    prompt(
+        "extract_emails",
+        "Extract Emails",
+        "List the email addresses found in the text",
+        &[],
+        "List every email address in the text the user sends, one per line, without \
+         duplicates. Reply with the list only, or an empty reply if there are none.",
+        false,
+    ),
+];
+
+impl Prompt {
+    fn info(&self) -> PromptInfo {
+        PromptInfo {
+            id: self.id.to_string(),
+            name: self.name.to_string(),
+            description: self.description.to_string(),
+            template_id: None,
+        }
+    }
+}
+
+fn prompts_for(types: &[String]) -> impl Iterator<Item = &'static Prompt> + '_ {
+    PROMPTS.iter().filter(move |p| {
+        p.content_types.is_empty()
+            || p.content_types
+                .iter()
+                .any(|ct| types.iter().any(|ty| ct.as_str() == ty))
+    })
+}
+
+/// Removes a Markdown code fence around the whole reply, if there is one.
+fn strip_code_fence(reply: &str) -> &str {
+    let trimmed = reply.trim();
+    let Some(rest) = trimmed.strip_prefix("```") else {
+        return trimmed;
+    };
+    match (rest.find('\n'), rest.strip_suffix("```")) {
+        (Some(start), Some(body)) if start <= body.len() => body[start..].trim(),
+        _ => trimmed,
+    }
+}
+
+// ============================================================================
+// Service
+// ============================================================================
+
+/// Runs the built-in prompts and the user's prompt templates on clip text,
+/// streaming the reply.
+pub struct AiService {
+    repository: Arc<ClipRepository>,
+    semantic_service: Arc<SemanticService>,
+    /// Templates contributed by plugins, by prompt id
+    plugin_templates: RwLock<Vec<(String, NewPromptTemplate)>>,
+    /// Cancel signals of running generations, by request id
+    running: Mutex<HashMap<String, Arc<Notify>>>,
+    timeout: Duration,
+}
 
-#[allow(dead_code)]
 impl AiService {
-    pub fn new() -> Self {
-        Self
+    pub fn new(repository: Arc<ClipRepository>, semantic_service: Arc<SemanticService>) -> Self {
+        Self {
+            repository,
+            semantic_service,
+            plugin_templates: RwLock::new(Vec::new()),
+            running: Mutex::new(HashMap::new()),
+            timeout: REQUEST_TIMEOUT,
+        }
+    }
+
+    /// Replaces the templates contributed by plugins, given as
+    /// `(plugin id, template)`. Their prompt ids are
+    /// `plugin:{plugin id}/{name}`.
+    pub fn set_plugin_templates(&self, templates: Vec<(String, NewPromptTemplate)>) {
+        *self
+            .plugin_templates
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = templates
+            .into_iter()
+            .map(|(plugin_id, t)| (format!("{}{}/{}", PLUGIN_PREFIX, plugin_id, t.name), t))
+            .collect();
+    }
+
+    /// Prompts that apply to any of the clip's types: built-in ones, the
+    /// user's templates, then those of plugins. Empty for clips without text.
+    pub async fn list(&self, clip_id: &str) -> Result<Vec<PromptInfo>> {
+        let clip = self.get_clip(clip_id).await?;
+        if clip_text(&clip).is_none() {
+            return Ok(Vec::new());
+        }
+        let types = clip_types(&self.repository, &clip).await?;
+        let applies = |content_types: &[String]| {
+            types
+                .iter()
+                .any(|ty| prompt_template::applies_to(content_types, ty))
+        };
+
+        let mut prompts: Vec<_> = prompts_for(&types).map(Prompt::info).collect();
+        let templates = self.repository.get_prompt_templates().await?;
+        prompts.extend(
+            templates
+                .into_iter()
+                .filter(|t| applies(&t.content_types))
+                .map(|t| PromptInfo {
+                    id: format!("{}{}", TEMPLATE_PREFIX, t.id),
+                    name: t.name,
+                    description: t.description.unwrap_or_default(),
+                    template_id: Some(t.id),
+                }),
+        );
+        let plugin_templates = self
+            .plugin_templates
+            .read()
+            .unwrap_or_else(|e| e.into_inner());
+        prompts.extend(
+            plugin_templates
+                .iter()
+                .filter(|(_, t)| applies(&t.content_types))
+                .map(|(id, t)| PromptInfo {
+                    id: id.clone(),
+                    name: t.name.clone(),
+                    description: t.description.clone().unwrap_or_default(),
+                    template_id: None,
+                }),
+        );
+        Ok(prompts)
+    }
+
+    /// Runs a prompt on the clip's text with `provider`, passing each token
+    /// to `on_token`. `request_id` identifies the run for `cancel`. With
+    /// `options.save` the reply is also saved as a new clip.
+    pub async fn run<F>(
+        &self,
+        provider: &impl ChatProvider,
+        request_id: &str,
+        clip_id: &str,
+        prompt_id: &str,
+        options: &AiOptions,
+        mut on_token: F,
+    ) -> Result<AiOutput>
+    where
+        F: FnMut(&str) + Send,
+    {
+        let clip = self.get_clip(clip_id).await?;
+        let text = clip_text(&clip).ok_or_else(|| anyhow!("Clip has no text"))?;
+        let language = options.language.as_deref().unwrap_or("English");
+
+        let template = if let Some(template_id) = prompt_id.strip_prefix(TEMPLATE_PREFIX) {
+            let template = match template_id.parse() {
+                Ok(id) => self.repository.get_prompt_template(id).await?,
+                Err(_) => None,
+            };
+            Some(template.map(|t| t.template))
+        } else if prompt_id.starts_with(PLUGIN_PREFIX) {
+            let plugin_templates = self
+                .plugin_templates
+                .read()
+                .unwrap_or_else(|e| e.into_inner());
+            Some(
+                plugin_templates
+                    .iter()
+                    .find(|(id, _)| id == prompt_id)
+                    .map(|(_, t)| t.template.clone()),
+            )
+        } else {
+            None
+        };
+
+        let (messages, code_reply) = match template {
+            Some(template) => {
+                let template = template.ok_or_else(|| anyhow!("Unknown prompt: {}", prompt_id))?;
+                let vars = TemplateVars {
+                    text,
+                    language,
+                    app_name: clip.app_name.as_deref().unwrap_or_default(),
+                };
+                let messages = vec![ChatMessage {
+                    role: "user",
+                    content: prompt_template::render(&template, &vars),
+                }];
+                (messages, false)
+            }
+            None => {
+                let prompt = PROMPTS
+                    .iter()
+                    .find(|p| p.id == prompt_id)
+                    .ok_or_else(|| anyhow!("Unknown prompt: {}", prompt_id))?;
+                let messages = vec![
+                    ChatMessage {
+                        role: "system",
+                        content: prompt.system.replace("{language}", language),
+                    },
+                    ChatMessage {
+                        role: "user",
+                        content: text.to_string(),
+                    },
+                ];
+                (messages, prompt.code_reply)
+            }
+        };
+
+        let cancel = Arc::new(Notify::new());
+        {
+            let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+            if running.contains_key(request_id) {
+                bail!("Request {} is already running", request_id);
+            }
+            running.insert(request_id.to_string(), cancel.clone());
+        }
+
+        // Losing the race drops the generation, which closes the connection
+        // even while the server is stalled
+        let generation = provider.complete(&messages, &mut on_token);
+        let result = tokio::select! {
+            result = tokio::time::timeout(self.timeout, generation) => Some(result),
+            _ = cancel.notified() => None,
+        };
+
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(request_id);
+
+        let reply = match result {
+            Some(Ok(reply)) => reply?,
+            Some(Err(_)) => bail!("AI request timed out after {}s", self.timeout.as_secs()),
+            None => bail!(Cancelled),
+        };
+
+        let text = if code_reply {
+            strip_code_fence(&reply)
+        } else {
+            reply.trim()
+        }
+        .to_string();
+
+        let saved = if options.save && !text.is_empty() {
+            let transform_id = format!("{}{}", TRANSFORM_PREFIX, prompt_id);
+            Some(
+                save_derived(
+                    &self.repository,
+                    &self.semantic_service,
+                    &clip,
+                    &transform_id,
+                    &text,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+
+        Ok(AiOutput { text, clip: saved })
+    }
+
+    /// Stops a running generation. Returns false if it isn't running.
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(request_id)
+        {
+            Some(cancel) => {
+                // Kept until `run` waits for it, if that's not yet the case
+                cancel.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn get_clip(&self, clip_id: &str) -> Result<ClipItem> {
+        self.repository
+            .get_by_id(clip_id)
+            .await?
+            .ok_or_else(|| anyhow!("Clip not found"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DetectedType, NewPromptTemplate};
+    use crate::services::embedding_backend::tests::mock_server;
+    use crate::services::intelligence::IntelligenceService;
+
+    /// Replies with canned tokens, `delay` apart.
+    struct ScriptedProvider {
+        tokens: Vec<&'static str>,
+        delay: Duration,
+    }
+
+    impl ChatProvider for ScriptedProvider {
+        async fn complete(
+            &self,
+            _messages: &[ChatMessage],
+            on_token: &mut (dyn FnMut(&str) + Send),
+        ) -> Result<String> {
+            let mut reply = String::new();
+            for token in &self.tokens {
+                tokio::time::sleep(self.delay).await;
+                on_token(token);
+                reply.push_str(token);
+            }
+            Ok(reply)
+        }
+    }
+
+    fn scripted(tokens: Vec<&'static str>, delay_ms: u64) -> ScriptedProvider {
+        ScriptedProvider {
+            tokens,
+            delay: Duration::from_millis(delay_ms),
+        }
+    }
+
+    async fn service_with_clip(text: &str) -> (Arc<AiService>, String, tempfile::TempDir) {
+        let (repository, dir) = ClipRepository::open_temp().await;
+        let detection = IntelligenceService::detect(text);
+        let clip = ClipItem::from_text(
+            text.to_string(),
+            detection.detected_type_str().to_string(),
+            detection.metadata_json(),
+        );
+        repository.insert(&clip).await.unwrap();
+
+        let semantic_service = Arc::new(SemanticService::new(dir.path().to_path_buf()));
+        let service = Arc::new(AiService::new(Arc::new(repository), semantic_service));
+        (service, clip.id, dir)
+    }
+
+    fn endpoint(base_url: String) -> OpenAiEndpoint {
+        OpenAiEndpoint {
+            base_url,
+            model: "llama3.2".to_string(),
+            api_key: Some("secret".to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_openai_provider_streams_tokens() {
+        let (base_url, server) = mock_server(vec![[
+            r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#,
+            "",
+            r#"data: {"choices":[{"delta":{"content":"Hello"}}]}"#,
+            "",
+            r#"data: {"choices":[{"delta":{"content":" world"}}]}"#,
+            "",
+            "data: [DONE]",
+            "",
+        ]
+        .join("\n")]);
+
+        let provider = OpenAiChatProvider::new(&endpoint(base_url)).unwrap();
+        let messages = [ChatMessage {
+            role: "user",
+            content: "hi".to_string(),
+        }];
+        let mut tokens = Vec::new();
+        let reply = provider
+            .complete(&messages, &mut |token| tokens.push(token.to_string()))
+            .await
+            .unwrap();
+
+        assert_eq!(reply, "Hello world");
+        assert_eq!(tokens, ["Hello", " world"]);
+        let request = server.join().unwrap().remove(0);
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request
+            .to_lowercase()
+            .contains("authorization: bearer secret"));
+        assert!(request.contains(r#""stream":true"#));
+        assert!(request.contains(r#""model":"llama3.2""#));
     }
 
-    // TODO: Implement direct OpenAI/Claude API calls
+    #[tokio::test]
+    async fn test_openai_provider_accepts_unstreamed_reply() {
+        let (base_url, server) = mock_server(vec![
+            r#"{"choices":[{"message":{"role":"assistant","content":"Done."}}]}"#.to_string(),
+            r#"{"error":{"message":"model not found"}}"#.to_string(),
+        ]);
+        let provider = OpenAiChatProvider::new(&endpoint(base_url)).unwrap();
+
+        let reply = provider.complete(&[], &mut |_| {}).await.unwrap();
+        assert_eq!(reply, "Done.");
+        let error = provider.complete(&[], &mut |_| {}).await.unwrap_err();
+        assert!(error.to_string().contains("model not found"));
+        server.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_run_streams_and_saves() {
+        let (service, clip_id, _dir) = service_with_clip(r#"{"id": 1, "tags": ["a"]}"#).await;
+
+        let ids: Vec<_> = service
+            .list(&clip_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        assert!(ids.contains(&"json_to_typescript".to_string()));
+        assert!(!ids.contains(&"explain_code".to_string()));
+
+        let tokens = Arc::new(Mutex::new(Vec::new()));
+        let sink = tokens.clone();
+        let output = service
+            .run(
+                &scripted(vec!["```ts\ntype Root", " = { id: number }\n```"], 0),
+                "req-1",
+                &clip_id,
+                "json_to_typescript",
+                &AiOptions {
+                    save: true,
+                    ..AiOptions::default()
+                },
+                move |token| sink.lock().unwrap().push(token.to_string()),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(output.text, "type Root = { id: number }");
+        assert_eq!(tokens.lock().unwrap().len(), 2);
+        let saved = output.clip.unwrap();
+        assert_eq!(saved.parent_id.as_deref(), Some(clip_id.as_str()));
+        let relation = service.repository.get_relation(&saved.id).await.unwrap();
+        assert_eq!(
+            relation.unwrap().transform_id.as_deref(),
+            Some("ai:json_to_typescript")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_includes_secondary_types() {
+        let (service, clip_id, _dir) = service_with_clip("Some notes about the order").await;
+        let ids = |prompts: Vec<PromptInfo>| -> Vec<String> {
+            prompts.into_iter().map(|p| p.id).collect()
+        };
+        assert!(
+            !ids(service.list(&clip_id).await.unwrap()).contains(&"json_to_typescript".to_string())
+        );
+
+        service
+            .repository
+            .set_detected_types(
+                &clip_id,
+                &[DetectedType {
+                    detected_type: "json".to_string(),
+                    confidence: 0.5,
+                }],
+            )
+            .await
+            .unwrap();
+        let prompts = ids(service.list(&clip_id).await.unwrap());
+        assert!(prompts.contains(&"json_to_typescript".to_string()));
+        assert!(prompts.contains(&"summarize".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_and_timeout() {
+        let (service, clip_id, _dir) = service_with_clip("Some text to summarize").await;
+
+        let running = service.clone();
+        let id = clip_id.clone();
+        let run = tokio::spawn(async move {
+            running
+                .run(
+                    &scripted(vec!["a"; 100], 10),
+                    "req-1",
+                    &id,
+                    "summarize",
+                    &AiOptions::default(),
+                    |_| {},
+                )
+                .await
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(service.cancel("req-1"));
+        let error = run.await.unwrap().unwrap_err();
+        assert!(error.is::<Cancelled>());
+        assert!(!service.cancel("req-1"));
+
+        let mut service = Arc::try_unwrap(service).ok().unwrap();
+        service.timeout = Duration::from_millis(50);
+        let error = service
+            .run(
+                &scripted(vec!["a"; 100], 10),
+                "req-2",
+                &clip_id,
+                "summarize",
+                &AiOptions::default(),
+                |_| {},
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stalled_stream() {
+        use std::io::{Read, Write};
+
+        // Streams a first token, then holds the connection open until `stop`
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let (stop, stopped) = std::sync::mpsc::channel::<()>();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.read(&mut [0; 4096]).unwrap();
+            let event = "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n";
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n",
+                event.len(),
+                event
+            )
+            .unwrap();
+            let _ = stopped.recv();
+        });
+
+        let (service, clip_id, _dir) = service_with_clip("Some text to summarize").await;
+        let (first_token, got_token) = tokio::sync::oneshot::channel();
+        let mut first_token = Some(first_token);
+        let running = service.clone();
+        let run = tokio::spawn(async move {
+            let provider = OpenAiChatProvider::new(&endpoint(base_url)).unwrap();
+            running
+                .run(
+                    &provider,
+                    "req-1",
+                    &clip_id,
+                    "summarize",
+                    &AiOptions::default(),
+                    move |_| {
+                        if let Some(sender) = first_token.take() {
+                            let _ = sender.send(());
+                        }
+                    },
+                )
+                .await
+        });
+
+        got_token.await.unwrap();
+        let started = std::time::Instant::now();
+        assert!(service.cancel("req-1"));
+        let error = run.await.unwrap().unwrap_err();
+        assert!(error.is::<Cancelled>());
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        drop(stop);
+        server.join().unwrap();
+    }
+
+    /// Replies with the messages it was sent.
+    struct EchoProvider;
+
+    impl ChatProvider for EchoProvider {
+        async fn complete(
+            &self,
+            messages: &[ChatMessage],
+            _on_token: &mut (dyn FnMut(&str) + Send),
+        ) -> Result<String> {
+            Ok(serde_json::to_string(messages)?)
+        }
+    }
+
+    const CODE: &str = "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}";
+
+    #[tokio::test]
+    async fn test_run_user_template() {
+        let (service, clip_id, _dir) = service_with_clip(CODE).await;
+        let new_template = |name: &str, content_types: Vec<String>| NewPromptTemplate {
+            name: name.to_string(),
+            description: None,
+            template: "Commit message in {{language}} for: {{text}}".to_string(),
+            content_types,
+        };
+        let commit = service
+            .repository
+            .create_prompt_template(&new_template("Commit", vec!["code".to_string()]))
+            .await
+            .unwrap();
+        service
+            .repository
+            .create_prompt_template(&new_template("Ticket", vec!["json".to_string()]))
+            .await
+            .unwrap();
+
+        let prompts = service.list(&clip_id).await.unwrap();
+        let templates: Vec<_> = prompts.iter().filter(|p| p.template_id.is_some()).collect();
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].id, format!("template:{}", commit.id));
+
+        let output = service
+            .run(
+                &EchoProvider,
+                "req-1",
+                &clip_id,
+                &templates[0].id,
+                &AiOptions {
+                    language: Some("French".to_string()),
+                    save: true,
+                },
+                |_| {},
+            )
+            .await
+            .unwrap();
+        let messages: Value = serde_json::from_str(&output.text).unwrap();
+        assert_eq!(
+            messages,
+            serde_json::json!([{
+                "role": "user",
+                "content": format!("Commit message in French for: {}", CODE),
+            }])
+        );
+        let relation = service
+            .repository
+            .get_relation(&output.clip.unwrap().id)
+            .await
+            .unwrap();
+        assert_eq!(
+            relation.unwrap().transform_id,
+            Some(format!("ai:template:{}", commit.id))
+        );
+
+        for unknown in ["template:999", "template:x"] {
+            let error = service
+                .run(
+                    &EchoProvider,
+                    "req-2",
+                    &clip_id,
+                    unknown,
+                    &AiOptions::default(),
+                    |_| {},
+                )
+                .await
+                .unwrap_err();
+            assert!(error.to_string().contains("Unknown prompt"));
+        }
+    }
+
+    #[test]
+    fn test_strip_code_fence() {
+        assert_eq!(strip_code_fence("```ts\ntype A = 1\n```\n"), "type A = 1");
+        assert_eq!(strip_code_fence("```\nx\n```"), "x");
+        assert_eq!(strip_code_fence(" type A = 1 "), "type A = 1");
+        assert_eq!(strip_code_fence("```"), "```");
+    }
 }
@@ -0,0 +1,199 @@
+// Backfill service: one-time jobs deriving data for clips saved before a
+// feature existed.
+//
+// The migration adding a job records the newest clip at that time (see
+// migrations/012_backfills.sql); newer clips get the data when they are saved.
+// Jobs run in the background in small batches, so a long history holds up
+// neither startup nor clipboard captures, and save their progress after each
+// batch to resume where they stopped.
+
+use crate::models::ClipItem;
+use crate::repositories::ClipRepository;
+use crate::services::entities;
+use crate::services::intelligence::{self, IntelligenceService};
+use crate::services::similarity;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Clips processed between two progress saves.
+const BATCH_SIZE: i64 = 200;
+
+/// Pause between batches, leaving the database to captures.
+const BATCH_PAUSE: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backfill {
+    /// Secondary types (`clip_detected_types`) from `detect_all`
+    DetectedTypes,
+    /// URLs, emails, ... (`clip_entities`)
+    Entities,
+    /// Perceptual hashes of images, for "more like this"
+    ImageHashes,
+}
+
+impl Backfill {
+    pub const ALL: &[Backfill] = &[
+        Backfill::DetectedTypes,
+        Backfill::Entities,
+        Backfill::ImageHashes,
+    ];
+
+    /// Row of the job in `backfills`.
+    fn name(self) -> &'static str {
+        match self {
+            Backfill::DetectedTypes => "detected_types",
+            Backfill::Entities => "entities",
+            Backfill::ImageHashes => "image_hashes",
+        }
+    }
+
+    /// Whether the job covers image clips rather than clips with text.
+    fn images(self) -> bool {
+        self == Backfill::ImageHashes
+    }
+
+    /// Runs the job to the end. Returns the number of clips processed.
+    pub async fn run(self, repository: &ClipRepository) -> Result<usize> {
+        let mut processed = 0;
+        loop {
+            let batch = repository
+                .next_backfill_batch(self.name(), self.images(), BATCH_SIZE)
+                .await?;
+            let Some(&(last_rowid, _)) = batch.last() else {
+                return Ok(processed);
+            };
+            for (_, clip) in &batch {
+                self.process(repository, clip).await?;
+            }
+            repository.advance_backfill(self.name(), last_rowid).await?;
+            processed += batch.len();
+            tokio::time::sleep(BATCH_PAUSE).await;
+        }
+    }
+
+    async fn process(self, repository: &ClipRepository, clip: &ClipItem) -> Result<()> {
+        match self {
+            Backfill::DetectedTypes => {
+                let detections =
+                    IntelligenceService::detect_all(clip.content_text.as_deref().unwrap_or(""));
+                repository
+                    .set_detected_types(
+                        &clip.id,
+                        &intelligence::secondary_types(&detections, &clip.detected_type),
+                    )
+                    .await
+            }
+            Backfill::Entities => {
+                repository
+                    .set_entities(&clip.id, &entities::extract_clip(clip))
+                    .await
+            }
+            Backfill::ImageHashes => {
+                // Missing or unreadable files are left without a hash
+                similarity::perceptual_hash(repository, clip).await;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Runs the unfinished jobs one after the other in the background.
+pub fn spawn(repository: Arc<ClipRepository>) {
+    tokio::spawn(async move {
+        for job in Backfill::ALL {
+            match job.run(&repository).await {
+                Ok(0) => {}
+                Ok(count) => eprintln!("[BACKFILL] {:?}: {} clips processed", job, count),
+                Err(e) => eprintln!("[BACKFILL] {:?} failed: {:#}", job, e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::image_hash;
+
+    #[tokio::test]
+    async fn test_detected_types_backfill() {
+        let (repository, _dir) = ClipRepository::open_temp().await;
+
+        let text = "Contact: ann@example.com";
+        let mut clip = ClipItem::from_text(text.to_string(), "text".to_string(), None);
+        clip.detected_type = IntelligenceService::detect(text)
+            .detected_type_str()
+            .to_string();
+        repository.insert(&clip).await.unwrap();
+        repository.restart_backfill("detected_types").await.unwrap();
+
+        let job = Backfill::DetectedTypes;
+        assert_eq!(job.run(&repository).await.unwrap(), 1);
+        let types = repository.get_detected_types(&clip.id).await.unwrap();
+        assert!(types.iter().any(|t| t.detected_type == "email"));
+
+        // Done: the next start has nothing left
+        assert_eq!(job.run(&repository).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_entities_backfill() {
+        let (repository, _dir) = ClipRepository::open_temp().await;
+
+        let clip = ClipItem::from_text(
+            "See https://example.com".to_string(),
+            "text".to_string(),
+            None,
+        );
+        repository.insert(&clip).await.unwrap();
+        let mut secret = ClipItem::from_text(
+            "https://user:pw@example.com".to_string(),
+            "text".to_string(),
+            None,
+        );
+        secret.detected_type = "secret".to_string();
+        repository.insert(&secret).await.unwrap();
+        repository.restart_backfill("entities").await.unwrap();
+
+        assert_eq!(Backfill::Entities.run(&repository).await.unwrap(), 2);
+        let urls = repository
+            .get_entities(&clip.id, Some("url"))
+            .await
+            .unwrap();
+        assert_eq!(urls[0].value, "https://example.com");
+        // Secrets stay out of listings
+        assert!(repository
+            .get_entities(&secret.id, None)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_image_hashes_backfill() {
+        let (repository, dir) = ClipRepository::open_temp().await;
+
+        let path = dir.path().join("1.png");
+        image::RgbImage::from_fn(32, 32, |x, y| image::Rgb([(x * 8) as u8, (y * 8) as u8, 0]))
+            .save(&path)
+            .unwrap();
+        let mut clip = ClipItem::from_text(String::new(), "image".to_string(), None);
+        clip.content_type = "image".to_string();
+        clip.content_text = None;
+        clip.image_path = Some(path.to_string_lossy().to_string());
+        repository.insert(&clip).await.unwrap();
+        let text = ClipItem::from_text("no image".to_string(), "text".to_string(), None);
+        repository.insert(&text).await.unwrap();
+        repository.restart_backfill("image_hashes").await.unwrap();
+
+        // Only the image is covered
+        assert_eq!(Backfill::ImageHashes.run(&repository).await.unwrap(), 1);
+        let clip = repository.get_by_id(&clip.id).await.unwrap().unwrap();
+        let hash = image_hash::from_metadata(clip.metadata.as_deref());
+        assert_eq!(
+            hash,
+            Some(image_hash::dhash(&std::fs::read(&path).unwrap()).unwrap())
+        );
+    }
+}
@@ -2,9 +2,15 @@ use crate::models::ClipItem;
 use crate::repositories::{ClipRepository, SettingsRepository};
 use crate::services::clipboard_monitor::{self, ClipboardCheckResult, ClipboardMonitor};
 use crate::services::clipboard_platform::{self, ClipboardContent};
+use crate::services::entities;
+use crate::services::image_hash;
+use crate::services::intelligence::{self, IntelligenceService};
+use crate::services::markdown::html_to_markdown;
+use crate::services::ocr::OcrService;
 use crate::services::semantic::SemanticService;
 use anyhow::Result;
 use arboard::Clipboard;
+use serde::Serialize;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tauri::{AppHandle, Emitter, Manager};
@@ -21,8 +27,9 @@ use tokio::time::{sleep, Duration};
 /// }
 pub struct ClipboardService {
     repository: Arc<ClipRepository>,
-    _settings_repository: Arc<SettingsRepository>,
+    settings_repository: Arc<SettingsRepository>,
     semantic_service: Arc<SemanticService>,
+    ocr_service: Arc<OcrService>,
     // NOTE: `Arc<Mutex<T>>` is like a thread-safe shared reference
     // Arc = Atomic Reference Counted (like shared_ptr in C++)
     // Mutex = Mutual exclusion lock (prevents concurrent access)
@@ -30,6 +37,17 @@ pub struct ClipboardService {
     monitor: Arc<Mutex<Box<dyn ClipboardMonitor>>>,
     app_handle: AppHandle,
     storage_dir: PathBuf,
+    // While paused, clipboard changes are tracked but not saved
+    capture: std::sync::Mutex<CaptureState>,
+}
+
+/// Whether clipboard capture is paused (e.g. while screen-sharing).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureState {
+    pub paused: bool,
+    /// Unix seconds at which capture resumes by itself; `None` = until resumed
+    pub resume_at: Option<i64>,
 }
 
 impl ClipboardService {
@@ -37,6 +55,7 @@ impl ClipboardService {
         repository: Arc<ClipRepository>,
         settings_repository: Arc<SettingsRepository>,
         semantic_service: Arc<SemanticService>,
+        ocr_service: Arc<OcrService>,
         app_handle: AppHandle,
     ) -> Self {
         // Base directory for all clipboard data
@@ -54,14 +73,62 @@ impl ClipboardService {
 
         Self {
             repository,
-            _settings_repository: settings_repository,
+            settings_repository,
             semantic_service,
+            ocr_service,
             // NOTE: Create platform-specific monitor (macOS vs Windows/Linux)
             monitor: Arc::new(Mutex::new(clipboard_monitor::create_monitor(
                 app_handle.clone(),
             ))),
             app_handle,
             storage_dir,
+            capture: std::sync::Mutex::new(CaptureState::default()),
+        }
+    }
+
+    pub fn capture_state(&self) -> CaptureState {
+        *self.capture.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stop saving clipboard changes, for `minutes` or until resumed (`None`/0).
+    pub fn pause_capture(&self, minutes: Option<u32>) -> CaptureState {
+        let resume_at = minutes
+            .filter(|m| *m > 0)
+            .map(|m| chrono::Utc::now().timestamp() + i64::from(m) * 60);
+        self.set_capture_state(CaptureState {
+            paused: true,
+            resume_at,
+        })
+    }
+
+    pub fn resume_capture(&self) -> CaptureState {
+        self.set_capture_state(CaptureState::default())
+    }
+
+    /// Stores the new state and shows it in the frontend and the tray icon.
+    fn set_capture_state(&self, state: CaptureState) -> CaptureState {
+        *self.capture.lock().unwrap_or_else(|e| e.into_inner()) = state;
+
+        eprintln!(
+            "[CAPTURE] paused: {}, resume_at: {:?}",
+            state.paused, state.resume_at
+        );
+        if let Err(e) = self.app_handle.emit("capture-state", state) {
+            eprintln!("[ERROR] Failed to emit capture state: {}", e);
+        }
+        crate::services::tray::show_capture_state(&self.app_handle, &state);
+        state
+    }
+
+    /// Whether capture is paused; resumes a timed pause once it has run out.
+    fn is_paused(&self) -> bool {
+        let state = self.capture_state();
+        match state.resume_at {
+            Some(at) if state.paused && chrono::Utc::now().timestamp() >= at => {
+                self.resume_capture();
+                false
+            }
+            _ => state.paused,
         }
     }
 
@@ -100,6 +167,12 @@ impl ClipboardService {
         let platform = monitor.platform_name();
         drop(monitor);
 
+        // The monitor has still seen the change, so content copied while
+        // paused isn't picked up after resuming
+        if self.is_paused() {
+            return Ok(());
+        }
+
         let (content, content_hash, source_app) = match result {
             ClipboardCheckResult::Unchanged => return Ok(()),
             ClipboardCheckResult::Changed {
@@ -115,11 +188,15 @@ impl ClipboardService {
             &content_hash[..8]
         );
 
+        // Types beyond the primary one (stored in `clip_detected_types`)
+        let mut secondary_types = Vec::new();
         let clip = match content {
             ClipboardContent::Text { content } => {
                 // Intelligence: detect semantic type from text content
-                let detection =
-                    crate::services::intelligence::IntelligenceService::detect(&content);
+                let detections = IntelligenceService::detect_all(&content);
+                let detection = &detections[0];
+                secondary_types =
+                    intelligence::secondary_types(&detections, detection.detected_type_str());
 
                 let mut clip = ClipItem::from_text(
                     content,
@@ -132,13 +209,19 @@ impl ClipboardService {
             }
             ClipboardContent::Html { html, plain } => {
                 // Intelligence: analyze the plain text extracted from HTML
-                let detection = crate::services::intelligence::IntelligenceService::detect(&plain);
-                Self::create_html_clip(html, plain, &content_hash, &detection, source_app.clone())
+                let detections = IntelligenceService::detect_all(&plain);
+                let detection = &detections[0];
+                secondary_types =
+                    intelligence::secondary_types(&detections, detection.detected_type_str());
+                Self::create_html_clip(html, plain, &content_hash, detection, source_app.clone())
             }
             ClipboardContent::Rtf { rtf, plain } => {
                 // Intelligence: analyze the plain text extracted from RTF
-                let detection = crate::services::intelligence::IntelligenceService::detect(&plain);
-                Self::create_rtf_clip(rtf, plain, &content_hash, &detection, source_app.clone())
+                let detections = IntelligenceService::detect_all(&plain);
+                let detection = &detections[0];
+                secondary_types =
+                    intelligence::secondary_types(&detections, detection.detected_type_str());
+                Self::create_rtf_clip(rtf, plain, &content_hash, detection, source_app.clone())
             }
             ClipboardContent::Image { data, format } => {
                 self.create_image_clip(data, format, &content_hash, source_app.clone())
@@ -186,6 +269,15 @@ impl ClipboardService {
                     platform, clip.content_type
                 );
                 self.repository.insert(&clip).await?;
+                if !secondary_types.is_empty() {
+                    self.repository
+                        .set_detected_types(&clip.id, &secondary_types)
+                        .await?;
+                }
+                let entities = entities::extract_clip(&clip);
+                if !entities.is_empty() {
+                    self.repository.set_entities(&clip.id, &entities).await?;
+                }
 
                 // Trigger background embedding generation if a model is loaded
                 if let Some(text) = &clip.content_text {
@@ -215,6 +307,8 @@ impl ClipboardService {
                         });
                     }
                 }
+
+                self.spawn_ocr(&clip);
             }
         }
 
@@ -231,6 +325,36 @@ impl ClipboardService {
         Ok(())
     }
 
+    /// Recognises the text of a new image clip in the background (when
+    /// enabled and tesseract is installed), then sends the updated clip.
+    fn spawn_ocr(&self, clip: &ClipItem) {
+        if !OcrService::applies_to(clip) || !self.ocr_service.is_available() {
+            return;
+        }
+        let enabled = self
+            .settings_repository
+            .load()
+            .map(|settings| settings.enable_ocr)
+            .unwrap_or(true);
+        if !enabled {
+            return;
+        }
+
+        let ocr = self.ocr_service.clone();
+        let app_handle = self.app_handle.clone();
+        let clip_id = clip.id.clone();
+        tokio::spawn(async move {
+            match ocr.process(&clip_id).await {
+                Ok(clip) => {
+                    if let Err(e) = app_handle.emit("clipboard_changed", &clip) {
+                        eprintln!("[ERROR] Failed to emit event: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("[ERROR] OCR failed: {}", e),
+            }
+        });
+    }
+
     fn create_html_clip(
         html: String,
         plain: String,
@@ -245,6 +369,7 @@ impl ClipboardService {
             id,
             content_type: "html".to_string(),
             content_text: Some(plain),
+            content_markdown: Some(html_to_markdown(&html)),
             content_html: Some(html),
             content_rtf: None,
             svg_path: None,
@@ -259,11 +384,13 @@ impl ClipboardService {
             updated_at: now,
             app_name,
             is_pinned: 0,
+            pin_position: None,
             is_favorite: 0,
             access_count: 0,
             content_hash: Some(hash.to_string()),
             has_embedding: Some(false),
             similarity_score: None,
+            parent_id: None,
         }
     }
 
@@ -283,6 +410,7 @@ impl ClipboardService {
             content_text: Some(plain),
             content_html: None,
             content_rtf: Some(rtf),
+            content_markdown: None,
             svg_path: None,
             pdf_path: None,
             image_path: None,
@@ -295,11 +423,13 @@ impl ClipboardService {
             updated_at: now,
             app_name,
             is_pinned: 0,
+            pin_position: None,
             is_favorite: 0,
             access_count: 0,
             content_hash: Some(hash.to_string()),
             has_embedding: Some(false),
             similarity_score: None,
+            parent_id: None,
         }
     }
 
@@ -316,14 +446,27 @@ impl ClipboardService {
         let filename = format!("{}.{}", id, format.extension());
         let image_path = self.storage_dir.join("images").join(&filename);
 
+        // Perceptual hash for "find similar" (decoding is CPU-bound)
+        let (data, perceptual_hash) = tokio::task::spawn_blocking(move || {
+            let hash = image_hash::dhash(&data).ok();
+            (data, hash)
+        })
+        .await?;
+
         tokio::fs::write(&image_path, data).await?;
 
+        let mut metadata = serde_json::json!({ "format": format.mime_type() });
+        if let Some(hash) = perceptual_hash {
+            metadata[image_hash::METADATA_KEY] = serde_json::json!(image_hash::to_hex(hash));
+        }
+
         Ok(ClipItem {
             id,
             content_type: "image".to_string(),
             content_text: Some(format!("[Image: {}]", filename)),
             content_html: None,
             content_rtf: None,
+            content_markdown: None,
             svg_path: None,
             pdf_path: None,
             image_path: Some(image_path.to_string_lossy().to_string()),
@@ -331,16 +474,18 @@ impl ClipboardService {
             attachment_type: None,
             file_paths: None,
             detected_type: "image".to_string(),
-            metadata: Some(format!(r#"{{"format":"{}"}}"#, format.mime_type())),
+            metadata: Some(metadata.to_string()),
             created_at: now,
             updated_at: now,
             app_name,
             is_pinned: 0,
+            pin_position: None,
             is_favorite: 0,
             access_count: 0,
             content_hash: Some(hash.to_string()),
             has_embedding: Some(false),
             similarity_score: None,
+            parent_id: None,
         })
     }
 
@@ -404,6 +549,7 @@ impl ClipboardService {
             content_text: Some(preview),
             content_html: None,
             content_rtf: None,
+            content_markdown: None,
             svg_path: None,
             pdf_path: None,
             image_path: None,
@@ -416,11 +562,13 @@ impl ClipboardService {
             updated_at: now,
             app_name,
             is_pinned: 0,
+            pin_position: None,
             is_favorite: 0,
             access_count: 0,
             content_hash: Some(hash.to_string()),
             has_embedding: Some(false),
             similarity_score: None,
+            parent_id: None,
         }
     }
 
@@ -486,6 +634,7 @@ impl ClipboardService {
             content_text: Some(extracted_text), // Text from pasteboard/SVG/PDF → searchable via FTS5
             content_html: html_data,
             content_rtf: rtf_data,
+            content_markdown: None,
             svg_path,                  // SVG file: clipboard_data/svg/{id}.svg
             pdf_path,                  // PDF file: clipboard_data/pdf/{id}.pdf
             image_path,                // PNG file: clipboard_data/images/{id}.png
@@ -498,11 +647,13 @@ impl ClipboardService {
             updated_at: now,
             app_name,
             is_pinned: 0,
+            pin_position: None,
             is_favorite: 0,
             access_count: 0,
             content_hash: Some(hash.to_string()),
             has_embedding: Some(false),
             similarity_score: None,
+            parent_id: None,
         })
     }
 
@@ -520,6 +671,18 @@ impl ClipboardService {
         Ok(())
     }
 
+    /// Empty the system clipboard
+    pub async fn clear(&self) -> Result<()> {
+        let mut clipboard = Clipboard::new()?;
+        clipboard.clear()?;
+        let mut monitor = self.monitor.lock().await;
+        let content = crate::services::clipboard_platform::ClipboardContent::Text {
+            content: String::new(),
+        };
+        monitor.notify_wrote(&content);
+        Ok(())
+    }
+
     /// Get current clipboard text
     pub fn get_text(&self) -> Result<String> {
         let mut clipboard = Clipboard::new()?;
@@ -48,7 +48,7 @@ use windows::Win32::{
     },
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum ClipboardContent {
     Text {
         content: String,
@@ -82,7 +82,7 @@ pub enum ClipboardContent {
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ImageFormat {
     Png,
     Jpeg,
@@ -366,56 +366,6 @@ unsafe fn read_text(pasteboard: id) -> Option<String> {
     Some(text)
 }
 
-/// Strip HTML tags to extract plain text (basic implementation)
-fn strip_html(html: &str) -> String {
-    html.replace("<br>", "\n")
-        .replace("<BR>", "\n")
-        .replace("</p>", "\n")
-        .replace("</P>", "\n")
-        .chars()
-        .fold((String::new(), false), |(mut acc, mut in_tag), c| match c {
-            '<' => {
-                in_tag = true;
-                (acc, in_tag)
-            }
-            '>' => {
-                in_tag = false;
-                (acc, in_tag)
-            }
-            _ if !in_tag => {
-                acc.push(c);
-                (acc, in_tag)
-            }
-            _ => (acc, in_tag),
-        })
-        .0
-        .trim()
-        .to_string()
-}
-
-/// Extract plain text from RTF (basic implementation)
-fn extract_rtf_text(rtf: &str) -> String {
-    // Very basic RTF text extraction
-    // RTF format: {\rtf1...text...}
-    // Remove control words and braces
-    let mut result = String::new();
-    let mut in_control_word = false;
-    let mut depth = 0;
-
-    for c in rtf.chars() {
-        match c {
-            '{' => depth += 1,
-            '}' => depth -= 1,
-            '\\' => in_control_word = true,
-            ' ' | '\n' | '\r' if in_control_word => in_control_word = false,
-            _ if !in_control_word && depth > 0 => result.push(c),
-            _ => {}
-        }
-    }
-
-    result.trim().to_string()
-}
-
 #[cfg(target_os = "macos")]
 unsafe fn read_html(pasteboard: id) -> Option<(String, String)> {
     let ns_html: id = msg_send![pasteboard, stringForType: NSPasteboardTypeHTML];
@@ -431,7 +381,8 @@ unsafe fn read_html(pasteboard: id) -> Option<(String, String)> {
     let html = std::ffi::CStr::from_ptr(c_str)
         .to_string_lossy()
         .into_owned();
-    let plain = read_text(pasteboard).unwrap_or_else(|| strip_html(&html));
+    let plain = read_text(pasteboard)
+        .unwrap_or_else(|| crate::services::markdown::html_to_markdown(&html));
 
     Some((html, plain))
 }
@@ -452,7 +403,7 @@ unsafe fn read_rtf(pasteboard: id) -> Option<(String, String)> {
 
     let data = std::slice::from_raw_parts(bytes, length);
     let rtf = String::from_utf8_lossy(data).into_owned();
-    let plain = read_text(pasteboard).unwrap_or_else(|| extract_rtf_text(&rtf));
+    let plain = read_text(pasteboard).unwrap_or_else(|| crate::services::rtf::rtf_to_text(&rtf));
 
     Some((rtf, plain))
 }
@@ -993,7 +944,11 @@ pub fn read_clipboard(_app: &tauri::AppHandle) -> Result<Option<ClipboardContent
             .unwrap_or(html_data.len());
         let html_string = String::from_utf8_lossy(&html_data[..html_len]).to_string();
         if !html_string.trim().is_empty() {
-            let plain = clipboard.get_text().unwrap_or_default();
+            let plain = clipboard
+                .get_text()
+                .ok()
+                .filter(|text| !text.trim().is_empty())
+                .unwrap_or_else(|| crate::services::markdown::html_to_markdown(&html_string));
             return Ok(Some(ClipboardContent::Html {
                 html: html_string,
                 plain,
@@ -1010,7 +965,11 @@ pub fn read_clipboard(_app: &tauri::AppHandle) -> Result<Option<ClipboardContent
             .unwrap_or(rtf_data.len());
         let rtf_string = String::from_utf8_lossy(&rtf_data[..rtf_len]).to_string();
         if !rtf_string.trim().is_empty() {
-            let plain = clipboard.get_text().unwrap_or_default();
+            let plain = clipboard
+                .get_text()
+                .ok()
+                .filter(|text| !text.trim().is_empty())
+                .unwrap_or_else(|| crate::services::rtf::rtf_to_text(&rtf_string));
             return Ok(Some(ClipboardContent::Rtf {
                 rtf: rtf_string,
                 plain,
@@ -0,0 +1,386 @@
+use crate::models::{Cluster, NewCluster};
+use crate::repositories::ClipRepository;
+use crate::services::semantic::SemanticService;
+use crate::services::similarity;
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::task;
+
+/// Smaller groups are treated as noise and left unclustered.
+const MIN_CLUSTER_SIZE: usize = 3;
+
+/// Clips less similar than this to their centroid are treated as noise.
+const MIN_MEMBER_SIMILARITY: f32 = 0.3;
+
+const MAX_ITERATIONS: usize = 50;
+const MAX_AUTO_CLUSTERS: usize = 24;
+const LABEL_TERMS: usize = 3;
+const STORED_TERMS: usize = 8;
+
+/// A clip as seen by the clustering job.
+pub struct Document {
+    pub clip_id: String,
+    pub vector: Vec<f32>,
+    pub text: String,
+}
+
+/// Groups clip history into topics by clustering stored embeddings
+/// (spherical k-means) and labelling each cluster with its top TF-IDF terms.
+pub struct ClusteringService {
+    repository: Arc<ClipRepository>,
+}
+
+impl ClusteringService {
+    pub fn new(repository: Arc<ClipRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Re-clusters every embedded clip and replaces the `clusters` table.
+    /// `k` = number of clusters, picked from the history size when `None`.
+    pub async fn run(&self, k: Option<usize>) -> Result<Vec<Cluster>> {
+        let rows = self.repository.get_embeddings_with_text().await?;
+
+        // Only vectors from one model are comparable: use the one most clips have
+        let mut model_counts: HashMap<&str, usize> = HashMap::new();
+        for (_, _, model, _) in &rows {
+            *model_counts.entry(model.as_str()).or_insert(0) += 1;
+        }
+        let model = model_counts
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(model, _)| model.to_string())
+            .ok_or_else(|| anyhow!("No embeddings to cluster. Enable semantic search first."))?;
+
+        let documents: Vec<Document> = rows
+            .into_iter()
+            .filter(|(_, _, m, _)| *m == model)
+            .map(|(clip_id, vector, _, text)| Document {
+                clip_id,
+                vector: SemanticService::bytes_to_vector(&vector),
+                text: text.unwrap_or_default(),
+            })
+            .collect();
+
+        let clusters = task::spawn_blocking(move || cluster_documents(&documents, k)).await?;
+
+        self.repository.replace_clusters(&model, &clusters).await?;
+        self.repository.get_clusters().await
+    }
+}
+
+/// Clusters documents and labels each cluster. Pure and deterministic.
+pub fn cluster_documents(documents: &[Document], k: Option<usize>) -> Vec<NewCluster> {
+    let n = documents.len();
+    if n < MIN_CLUSTER_SIZE * 2 {
+        return Vec::new();
+    }
+
+    let k = k
+        .unwrap_or_else(|| ((n as f64 / 2.0).sqrt().round() as usize).min(MAX_AUTO_CLUSTERS))
+        .clamp(2, n / MIN_CLUSTER_SIZE);
+
+    let vectors: Vec<Vec<f32>> = documents.iter().map(|d| normalize(&d.vector)).collect();
+    let (assignments, centroids) = kmeans(&vectors, k);
+
+    let tokens: Vec<Vec<String>> = documents
+        .iter()
+        .map(|d| similarity::tokenize(&d.text))
+        .collect();
+
+    // Document frequency of each term across the whole history (the IDF part)
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for doc_tokens in &tokens {
+        let unique: HashSet<&str> = doc_tokens.iter().map(String::as_str).collect();
+        for term in unique {
+            *document_frequency.entry(term).or_insert(0) += 1;
+        }
+    }
+
+    let mut clusters = Vec::new();
+    for (cluster, centroid) in centroids.iter().enumerate() {
+        let mut members: Vec<(usize, f32)> = assignments
+            .iter()
+            .enumerate()
+            .filter(|(_, assigned)| **assigned == cluster)
+            .map(|(i, _)| (i, dot(&vectors[i], centroid)))
+            .filter(|(_, score)| *score >= MIN_MEMBER_SIMILARITY)
+            .collect();
+
+        if members.len() < MIN_CLUSTER_SIZE {
+            continue;
+        }
+        members.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+        let terms = top_terms(
+            members.iter().map(|(i, _)| &tokens[*i]),
+            &document_frequency,
+            n,
+        );
+        let label = if terms.is_empty() {
+            format!("Topic {}", clusters.len() + 1)
+        } else {
+            terms
+                .iter()
+                .take(LABEL_TERMS)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        clusters.push(NewCluster {
+            label,
+            terms,
+            members: members
+                .into_iter()
+                .map(|(i, score)| (documents[i].clip_id.clone(), score))
+                .collect(),
+        });
+    }
+
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.members.len()));
+    clusters
+}
+
+/// Terms of a cluster ranked by TF-IDF. Terms must occur in at least two of the
+/// cluster's clips so that one long clip cannot name the whole cluster.
+fn top_terms<'a>(
+    members: impl Iterator<Item = &'a Vec<String>>,
+    document_frequency: &HashMap<&str, usize>,
+    total_documents: usize,
+) -> Vec<String> {
+    let mut term_counts: HashMap<&str, usize> = HashMap::new();
+    let mut member_frequency: HashMap<&str, usize> = HashMap::new();
+    let mut total_terms = 0;
+
+    for doc_tokens in members {
+        total_terms += doc_tokens.len();
+        for term in doc_tokens {
+            *term_counts.entry(term).or_insert(0) += 1;
+        }
+        let unique: HashSet<&str> = doc_tokens.iter().map(String::as_str).collect();
+        for term in unique {
+            *member_frequency.entry(term).or_insert(0) += 1;
+        }
+    }
+
+    let mut scored: Vec<(&str, f64)> = term_counts
+        .into_iter()
+        .filter(|(term, _)| member_frequency[term] >= 2)
+        .map(|(term, count)| {
+            let tf = count as f64 / total_terms.max(1) as f64;
+            let df = document_frequency.get(term).copied().unwrap_or(1) as f64;
+            let idf = (1.0 + total_documents as f64 / df).ln();
+            (term, tf * idf)
+        })
+        .collect();
+
+    scored.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.0.cmp(b.0))
+    });
+
+    scored
+        .into_iter()
+        .take(STORED_TERMS)
+        .map(|(term, _)| term.to_string())
+        .collect()
+}
+
+/// Spherical k-means (cosine distance) with k-means++ seeding.
+/// Expects unit vectors. Returns the assignment of each vector and the centroids.
+fn kmeans(vectors: &[Vec<f32>], k: usize) -> (Vec<usize>, Vec<Vec<f32>>) {
+    let mut rng = SplitMix64(0x5eed_c105 ^ vectors.len() as u64);
+
+    // k-means++: spread initial centroids, each new one picked with probability
+    // proportional to its squared distance from the nearest existing centroid
+    let mut centroids = vec![vectors[(rng.next() % vectors.len() as u64) as usize].clone()];
+    while centroids.len() < k {
+        let weights: Vec<f32> = vectors
+            .iter()
+            .map(|v| {
+                let nearest = centroids
+                    .iter()
+                    .map(|c| dot(v, c))
+                    .fold(f32::MIN, f32::max);
+                (1.0 - nearest).max(0.0).powi(2)
+            })
+            .collect();
+
+        let total: f32 = weights.iter().sum();
+        if total <= f32::EPSILON {
+            break; // Fewer distinct points than k
+        }
+
+        let mut target = rng.next_f32() * total;
+        let mut chosen = weights.len() - 1;
+        for (i, w) in weights.iter().enumerate() {
+            if target < *w {
+                chosen = i;
+                break;
+            }
+            target -= w;
+        }
+        centroids.push(vectors[chosen].clone());
+    }
+
+    let mut assignments = vec![usize::MAX; vectors.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (i, v) in vectors.iter().enumerate() {
+            let best = centroids
+                .iter()
+                .enumerate()
+                .map(|(c, centroid)| (c, dot(v, centroid)))
+                .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+                .map(|(c, _)| c)
+                .unwrap_or(0);
+            if assignments[i] != best {
+                assignments[i] = best;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+
+        for (c, centroid) in centroids.iter_mut().enumerate() {
+            let mut sum = vec![0.0f32; centroid.len()];
+            let mut count = 0;
+            for (v, _) in vectors.iter().zip(&assignments).filter(|(_, a)| **a == c) {
+                sum.iter_mut().zip(v).for_each(|(s, x)| *s += x);
+                count += 1;
+            }
+            // Empty clusters keep their previous centroid
+            if count > 0 {
+                *centroid = normalize(&sum);
+            }
+        }
+    }
+
+    (assignments, centroids)
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalize(v: &[f32]) -> Vec<f32> {
+    let norm = dot(v, v).sqrt();
+    if norm == 0.0 {
+        v.to_vec()
+    } else {
+        v.iter().map(|x| x / norm).collect()
+    }
+}
+
+/// Tiny seeded PRNG so clustering is reproducible without a `rand` dependency.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ClipFilters, ClipItem};
+    use crate::repositories::ClipQuery;
+    use crate::services::embedding_backend::{EmbeddingBackend, HashEmbeddingBackend};
+
+    const KUBERNETES: &[&str] = &[
+        "kubernetes deployment manifest replicas",
+        "apply the kubernetes deployment manifest",
+        "kubernetes service manifest deployment yaml",
+        "kubernetes deployment rollout manifest",
+    ];
+    const INVOICES: &[&str] = &[
+        "customer invoice payment overdue",
+        "invoice payment received from customer",
+        "customer invoice payment reminder email",
+    ];
+
+    fn documents() -> Vec<Document> {
+        let backend = HashEmbeddingBackend::new(128);
+        KUBERNETES
+            .iter()
+            .chain(INVOICES)
+            .enumerate()
+            .map(|(i, text)| Document {
+                clip_id: i.to_string(),
+                vector: backend.embed(&[text.to_string()]).unwrap().remove(0),
+                text: text.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_clusters_separate_topics_with_tfidf_labels() {
+        let clusters = cluster_documents(&documents(), Some(2));
+
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].members.len(), KUBERNETES.len());
+        assert_eq!(clusters[1].members.len(), INVOICES.len());
+        assert!(clusters[0].label.contains("kubernetes"));
+        assert!(clusters[1].label.contains("invoice"));
+    }
+
+    #[test]
+    fn test_too_few_documents_yield_no_clusters() {
+        let docs: Vec<Document> = documents().into_iter().take(4).collect();
+        assert!(cluster_documents(&docs, None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_run_stores_clusters_usable_as_filter() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+        let repo = Arc::new(repo);
+
+        for doc in documents() {
+            let mut clip = ClipItem::from_text(doc.text.clone(), "text".to_string(), None);
+            clip.id = doc.clip_id.clone();
+            repo.insert(&clip).await.unwrap();
+            repo.create_embedding(
+                &doc.clip_id,
+                SemanticService::vector_to_bytes(&doc.vector),
+                "test-hash",
+                128,
+            )
+            .await
+            .unwrap();
+        }
+
+        let clusters = ClusteringService::new(repo.clone())
+            .run(Some(2))
+            .await
+            .unwrap();
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters[0].model, "test-hash");
+
+        let invoices = clusters.iter().find(|c| c.label.contains("invoice")).unwrap();
+        let filters = ClipFilters {
+            cluster_id: Some(invoices.id),
+            ..ClipFilters::default()
+        };
+        let clips = repo
+            .query_clips(&ClipQuery::new().filters(filters))
+            .await
+            .unwrap()
+            .items;
+        assert_eq!(clips.len(), INVOICES.len());
+
+        let assigned = repo.get_cluster_for_clip("0").await.unwrap().unwrap();
+        assert!(assigned.label.contains("kubernetes"));
+    }
+}
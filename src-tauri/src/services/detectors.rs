@@ -0,0 +1,347 @@
+// User-declared content detectors
+//
+// Teams teach detection their own formats (ticket ids, internal hostnames,
+// order numbers) in `{app_config_dir}/detectors.toml` or `detectors.json`:
+//
+//     [[detector]]
+//     name = "Jira ticket"
+//     detected_type = "ticket"
+//     pattern = '^(?P<project>[A-Z][A-Z0-9]+)-(?P<number>\d+)$'
+//     priority = 15  # after jwt (10), before url (20)
+//
+// The JSON file has the same shape: `{ "detector": [{ ... }] }`. Named groups
+// become metadata fields. The files are reloaded when they change.
+
+use crate::services::file_watch::{FileWatcher, FilesVersion};
+use crate::services::intelligence::{self, ContentType, CustomDetector};
+use anyhow::{anyhow, bail, Result};
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Detector files in the config directory, read in this order.
+pub const FILE_NAMES: &[&str] = &["detectors.toml", "detectors.json"];
+
+/// Compiled size limit of a pattern, against pathological regexes.
+const MAX_REGEX_SIZE: usize = 1 << 20;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DetectorFile {
+    #[serde(default)]
+    detector: Vec<DetectorConfig>,
+}
+
+/// A `[[detector]]` entry, also used in plugin manifests.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DetectorConfig {
+    /// Label, defaults to `detected_type`
+    pub name: Option<String>,
+    pub detected_type: String,
+    pub pattern: String,
+    /// See `BUILTIN_DETECTORS`; 0 (the default) runs before every built-in
+    /// except secrets
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+/// A loaded detector.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DetectorInfo {
+    pub name: String,
+    pub detected_type: String,
+    pub pattern: String,
+    pub priority: i32,
+    /// File it was declared in, `plugins/{id}/manifest.json` for plugins
+    pub file: String,
+}
+
+/// Result of `list_detectors`: the detectors in use, and why the others
+/// (or whole files) were skipped.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DetectorList {
+    pub detectors: Vec<DetectorInfo>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Default)]
+struct LoadedDetectors {
+    version: Option<FilesVersion>,
+    list: DetectorList,
+    detectors: Vec<CustomDetector>,
+    plugin_detectors: Vec<(DetectorInfo, CustomDetector)>,
+}
+
+impl LoadedDetectors {
+    fn publish(&self) {
+        let plugin_detectors = self.plugin_detectors.iter().map(|(_, d)| d.clone());
+        intelligence::set_custom_detectors(
+            self.detectors
+                .iter()
+                .cloned()
+                .chain(plugin_detectors)
+                .collect(),
+        );
+    }
+}
+
+/// Loads the detector files, plus the detectors of plugins, into
+/// `intelligence::set_custom_detectors`.
+pub struct DetectorService {
+    dir: PathBuf,
+    loaded: Mutex<LoadedDetectors>,
+}
+
+impl DetectorService {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            loaded: Mutex::new(LoadedDetectors::default()),
+        }
+    }
+
+    /// Where new detectors go (`detectors.toml`).
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(FILE_NAMES[0])
+    }
+
+    pub fn list(&self) -> DetectorList {
+        self.reload_if_changed();
+        let loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        let mut list = loaded.list.clone();
+        list.detectors
+            .extend(loaded.plugin_detectors.iter().map(|(info, _)| info.clone()));
+        list
+    }
+
+    /// Replaces the detectors contributed by plugins (see `build`).
+    pub fn set_plugin_detectors(&self, detectors: Vec<(DetectorInfo, CustomDetector)>) {
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        loaded.plugin_detectors = detectors;
+        loaded.publish();
+    }
+
+    /// Reloads the detectors if a file was added, removed or changed.
+    pub fn reload_if_changed(&self) -> bool {
+        let version = FilesVersion::of_files(FILE_NAMES.iter().map(|name| self.dir.join(name)));
+
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        if loaded.version.as_ref() == Some(&version) {
+            return false;
+        }
+
+        let mut list = DetectorList::default();
+        let mut detectors = Vec::new();
+        for path in version.existing() {
+            let file = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let configs = match read_file(path) {
+                Ok(configs) => configs,
+                Err(e) => {
+                    // Keep what the file declared until it's fixed
+                    list.errors.push(format!("{}: {}", file, e));
+                    let previous = loaded.list.detectors.iter().zip(&loaded.detectors);
+                    for (info, detector) in previous.filter(|(info, _)| info.file == file) {
+                        list.detectors.push(info.clone());
+                        detectors.push(detector.clone());
+                    }
+                    continue;
+                }
+            };
+            for config in configs {
+                let label = config
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| config.detected_type.clone());
+                match build(config, &file) {
+                    Ok((info, detector)) => {
+                        list.detectors.push(info);
+                        detectors.push(detector);
+                    }
+                    Err(e) => list
+                        .errors
+                        .push(format!("{}: detector '{}': {}", file, label, e)),
+                }
+            }
+        }
+        for error in &list.errors {
+            eprintln!("[WARN] Custom detectors: {}", error);
+        }
+
+        loaded.list = list;
+        loaded.detectors = detectors;
+        loaded.publish();
+        loaded.version = Some(version);
+        true
+    }
+
+    /// Has `watcher` reload the detectors whenever a file changes, calling
+    /// `on_change` afterwards.
+    pub fn watch<F>(self: &Arc<Self>, watcher: &mut FileWatcher, on_change: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let service = self.clone();
+        watcher.watch(move || service.reload_if_changed(), on_change);
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<DetectorConfig>> {
+    let contents = std::fs::read_to_string(path)?;
+    let file: DetectorFile = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&contents).map_err(|e| anyhow!("{}", e))?,
+        _ => serde_json::from_str(&contents)?,
+    };
+    Ok(file.detector)
+}
+
+/// Checks and compiles a detector declared in `file`.
+pub fn build(config: DetectorConfig, file: &str) -> Result<(DetectorInfo, CustomDetector)> {
+    let detected_type = config.detected_type.trim().to_string();
+    let valid_name = detected_type.starts_with(|c: char| c.is_ascii_lowercase())
+        && detected_type
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid_name {
+        bail!(
+            "detected_type '{}' must be lowercase letters, digits and _",
+            detected_type
+        );
+    }
+    if ContentType::ALL
+        .iter()
+        .any(|ct| ct.as_str() == detected_type)
+    {
+        bail!("detected_type '{}' is a built-in type", detected_type);
+    }
+
+    let regex = RegexBuilder::new(&config.pattern)
+        .case_insensitive(config.case_insensitive)
+        .size_limit(MAX_REGEX_SIZE)
+        .build()
+        .map_err(|e| anyhow!("invalid pattern: {}", e))?;
+    let name = config
+        .name
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| detected_type.clone());
+
+    let info = DetectorInfo {
+        name: name.clone(),
+        detected_type: detected_type.clone(),
+        pattern: config.pattern,
+        priority: config.priority,
+        file: file.to_string(),
+    };
+    let detector = CustomDetector {
+        name,
+        detected_type,
+        regex,
+        priority: config.priority,
+    };
+    Ok((info, detector))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_toml_and_json() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("detectors.toml"),
+            r#"
+[[detector]]
+name = "Jira ticket"
+detected_type = "ticket"
+pattern = '^(?P<project>[A-Z][A-Z0-9]+)-(?P<number>\d+)$'
+priority = 15
+
+[[detector]]
+detected_type = "Bad Type"
+pattern = 'x'
+
+[[detector]]
+detected_type = "url"
+pattern = 'x'
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            dir.path().join("detectors.json"),
+            r#"{ "detector": [
+                { "detected_type": "host", "pattern": "\\.corp$", "case_insensitive": true },
+                { "detected_type": "broken", "pattern": "(" }
+            ] }"#,
+        )
+        .unwrap();
+
+        let toml = read_file(&dir.path().join("detectors.toml")).unwrap();
+        assert_eq!(toml.len(), 3);
+        let (info, detector) = build(toml.into_iter().next().unwrap(), "detectors.toml").unwrap();
+        assert_eq!(info.name, "Jira ticket");
+        assert_eq!(info.priority, 15);
+        assert!(detector.regex.is_match("PROJ-1234"));
+
+        let errors: Vec<String> = read_file(&dir.path().join("detectors.toml"))
+            .unwrap()
+            .into_iter()
+            .skip(1)
+            .map(|config| build(config, "detectors.toml").unwrap_err().to_string())
+            .collect();
+        assert!(errors[0].contains("lowercase"));
+        assert!(errors[1].contains("built-in"));
+
+        let json = read_file(&dir.path().join("detectors.json")).unwrap();
+        let mut json = json.into_iter();
+        let (_, host) = build(json.next().unwrap(), "detectors.json").unwrap();
+        assert!(host.regex.is_match("BUILD.CORP"));
+        assert!(build(json.next().unwrap(), "detectors.json").is_err());
+
+        std::fs::write(dir.path().join("bad.toml"), "[[detector]]\nregex = 'x'").unwrap();
+        assert!(read_file(&dir.path().join("bad.toml")).is_err());
+    }
+
+    #[test]
+    fn test_reload_if_changed() {
+        let _lock = intelligence::CUSTOM_DETECTORS_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let service = DetectorService::new(dir.path().to_path_buf());
+        let detected = |text: &str| intelligence::detect(text).detected_type_str().to_string();
+        let write = |contents: &str| std::fs::write(service.path(), contents).unwrap();
+
+        write("[[detector]]\ndetected_type = 'zz_reload'\npattern = '^zz-reload-\\d+$'\n");
+        assert!(service.reload_if_changed());
+        assert!(!service.reload_if_changed());
+        assert_eq!(service.list().detectors.len(), 1);
+        assert_eq!(detected("zz-reload-1"), "zz_reload");
+
+        // Sizes differ, so a change within the same mtime tick is noticed too
+        write("[[detector]]\ndetected_type = 'zz_reload'\npattern = '^zz-reloaded-\\d+$'\n");
+        assert!(service.reload_if_changed());
+        assert_eq!(detected("zz-reload-1"), "text");
+        assert_eq!(detected("zz-reloaded-1"), "zz_reload");
+
+        // A file that doesn't parse keeps its previous detectors
+        write("[[detector]]\nbroken");
+        assert!(service.reload_if_changed());
+        let list = service.list();
+        assert_eq!(list.errors.len(), 1);
+        assert_eq!(list.detectors.len(), 1);
+        assert_eq!(detected("zz-reloaded-1"), "zz_reload");
+
+        std::fs::remove_file(service.path()).unwrap();
+        assert!(service.reload_if_changed());
+        assert_eq!(service.list(), DetectorList::default());
+        assert_eq!(detected("zz-reloaded-1"), "text");
+    }
+}
@@ -0,0 +1,180 @@
+use crate::models::{ClipItem, ClipRevision};
+use crate::repositories::ClipRepository;
+use crate::services::entities;
+use crate::services::intelligence::{self, IntelligenceService};
+use crate::services::markdown::html_to_markdown;
+use crate::services::semantic::SemanticService;
+use anyhow::{anyhow, bail, Result};
+use std::sync::Arc;
+
+/// Content types whose text can be edited. The others are backed by files
+/// (images, file lists, Office attachments) the text only describes.
+const EDITABLE_TYPES: &[&str] = &["text", "html", "rtf"];
+
+/// In-place editing of clip text, keeping earlier versions in `clip_revisions`.
+pub struct EditService {
+    repository: Arc<ClipRepository>,
+    semantic_service: Arc<SemanticService>,
+}
+
+impl EditService {
+    pub fn new(repository: Arc<ClipRepository>, semantic_service: Arc<SemanticService>) -> Self {
+        Self {
+            repository,
+            semantic_service,
+        }
+    }
+
+    /// Replaces the clip's text, and its HTML when `html` is given, then
+    /// re-runs detection. Without `html` the clip becomes plain text, since
+    /// the old HTML/RTF no longer matches it.
+    pub async fn update_content(
+        &self,
+        clip_id: &str,
+        text: String,
+        html: Option<String>,
+    ) -> Result<ClipItem> {
+        let mut clip = self.get_clip(clip_id).await?;
+        if !EDITABLE_TYPES.contains(&clip.content_type.as_str()) {
+            bail!("Clips of type '{}' can't be edited", clip.content_type);
+        }
+        if clip.content_text.as_deref() == Some(text.as_str()) && clip.content_html == html {
+            return Ok(clip);
+        }
+
+        let detection = IntelligenceService::detect(&text);
+        clip.content_type = if html.is_some() { "html" } else { "text" }.to_string();
+        clip.content_markdown = html.as_deref().map(html_to_markdown);
+        clip.content_html = html;
+        clip.content_rtf = None;
+        clip.detected_type = detection.detected_type_str().to_string();
+        clip.metadata = detection.metadata_json();
+        clip.content_hash = Some(ClipItem::compute_hash(&text));
+        clip.content_text = Some(text);
+
+        self.save(clip).await
+    }
+
+    /// Earlier versions of the clip, most recent first.
+    pub async fn revisions(&self, clip_id: &str) -> Result<Vec<ClipRevision>> {
+        self.repository.get_revisions(clip_id).await
+    }
+
+    /// Restores an earlier version. The content it replaces is kept as a
+    /// revision too, so reverting can be undone.
+    pub async fn revert(&self, clip_id: &str, revision_id: i64) -> Result<ClipItem> {
+        let revision = self
+            .repository
+            .get_revision(revision_id)
+            .await?
+            .filter(|revision| revision.clip_id == clip_id)
+            .ok_or_else(|| anyhow!("Revision not found"))?;
+
+        let mut clip = self.get_clip(clip_id).await?;
+        clip.content_type = revision.content_type;
+        clip.content_text = revision.content_text;
+        clip.content_html = revision.content_html;
+        clip.content_rtf = revision.content_rtf;
+        clip.content_markdown = revision.content_markdown;
+        clip.detected_type = revision.detected_type.unwrap_or_else(|| "text".to_string());
+        clip.metadata = revision.metadata;
+        clip.content_hash = revision.content_hash;
+
+        self.save(clip).await
+    }
+
+    async fn get_clip(&self, clip_id: &str) -> Result<ClipItem> {
+        self.repository
+            .get_by_id(clip_id)
+            .await?
+            .ok_or_else(|| anyhow!("Clip not found"))
+    }
+
+    async fn save(&self, mut clip: ClipItem) -> Result<ClipItem> {
+        clip.updated_at = chrono::Utc::now().timestamp();
+        self.repository.update_content(&clip).await?;
+
+        // Secondary types and entities follow the text (a revert restores the primary one)
+        let detections =
+            IntelligenceService::detect_all(clip.content_text.as_deref().unwrap_or(""));
+        self.repository
+            .set_detected_types(
+                &clip.id,
+                &intelligence::secondary_types(&detections, &clip.detected_type),
+            )
+            .await?;
+        self.repository
+            .set_entities(&clip.id, &entities::extract_clip(&clip))
+            .await?;
+
+        // The stored vector describes the old text
+        self.repository.delete_embedding(&clip.id).await?;
+        self.semantic_service
+            .spawn_clip_embedding(self.repository.clone(), &clip);
+
+        self.get_clip(&clip.id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn service() -> (EditService, tempfile::TempDir) {
+        let (repository, dir) = ClipRepository::open_temp().await;
+        let semantic_service = Arc::new(SemanticService::new(dir.path().to_path_buf()));
+        let service = EditService::new(Arc::new(repository), semantic_service);
+        (service, dir)
+    }
+
+    #[tokio::test]
+    async fn test_edit_and_revert() {
+        let (service, _dir) = service().await;
+        let mut clip = ClipItem::from_text("hello".to_string(), "text".to_string(), None);
+        clip.content_type = "html".to_string();
+        clip.content_html = Some("<b>hello</b>".to_string());
+        service.repository.insert(&clip).await.unwrap();
+
+        let edited = service
+            .update_content(&clip.id, "https://example.com".to_string(), None)
+            .await
+            .unwrap();
+        assert_eq!(edited.content_type, "text");
+        assert_eq!(edited.content_html, None);
+        assert_eq!(edited.detected_type, "url");
+        assert_eq!(
+            edited.content_hash,
+            Some(ClipItem::compute_hash("https://example.com"))
+        );
+
+        // Saving the same content again doesn't add a revision
+        service
+            .update_content(&clip.id, "https://example.com".to_string(), None)
+            .await
+            .unwrap();
+        let revisions = service.revisions(&clip.id).await.unwrap();
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].content_html.as_deref(), Some("<b>hello</b>"));
+
+        let reverted = service.revert(&clip.id, revisions[0].id).await.unwrap();
+        assert_eq!(reverted.content_type, "html");
+        assert_eq!(reverted.content_text.as_deref(), Some("hello"));
+        assert_eq!(reverted.detected_type, "text");
+        assert_eq!(service.revisions(&clip.id).await.unwrap().len(), 2);
+
+        assert!(service.revert("other", revisions[0].id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_files_are_not_editable() {
+        let (service, _dir) = service().await;
+        let mut clip = ClipItem::from_text("a.txt".to_string(), "file_list".to_string(), None);
+        clip.content_type = "files".to_string();
+        service.repository.insert(&clip).await.unwrap();
+
+        assert!(service
+            .update_content(&clip.id, "b.txt".to_string(), None)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,434 @@
+use crate::models::settings::OpenAiEndpoint;
+use anyhow::{anyhow, bail, Result};
+use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A source of text embeddings.
+///
+/// Implementations are blocking (ONNX inference, blocking HTTP) and are always
+/// driven from `task::spawn_blocking` by `SemanticService`, possibly from
+/// several threads at once.
+pub trait EmbeddingBackend: Send + Sync {
+    /// Identifier stored in the `embeddings.model` column.
+    fn model_id(&self) -> &str;
+
+    /// Size of the vectors returned by `embed`, as reported by the model itself.
+    fn dimensions(&self) -> usize;
+
+    /// Embed a batch of texts. The output has one vector per input, in order.
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Describes an embedding model that can be selected in Settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingModelInfo {
+    pub id: String,
+    pub description: String,
+    pub dimensions: usize,
+    pub downloaded: bool,
+}
+
+// ============================================================================
+// fastembed (local ONNX models)
+// ============================================================================
+
+/// Model ids persisted in settings before every fastembed model was exposed.
+/// Other models use their `EmbeddingModel` variant name (e.g. "BGESmallENV15").
+const LEGACY_MODEL_IDS: &[(&str, EmbeddingModel)] = &[
+    ("all-MiniLM-L6-v2", EmbeddingModel::AllMiniLML6V2),
+    (
+        "paraphrase-multilingual-MiniLM-L12-v2",
+        EmbeddingModel::ParaphraseMLMiniLML12V2,
+    ),
+];
+
+/// Local embeddings through fastembed. Supports every model fastembed ships.
+pub struct FastEmbedBackend {
+    /// Inference needs `&mut`; the lock serializes it for this backend only
+    model: Mutex<TextEmbedding>,
+    id: String,
+    dimensions: usize,
+}
+
+impl FastEmbedBackend {
+    /// Downloads (if necessary) and loads the model. Blocking.
+    pub fn load(model_id: &str, cache_dir: PathBuf) -> Result<Self> {
+        let model_enum = Self::parse_model_id(model_id)
+            .ok_or_else(|| anyhow!("Unknown embedding model: {}", model_id))?;
+        let dimensions = TextEmbedding::get_model_info(&model_enum)
+            .map_err(|e| anyhow!("Failed to read model info: {}", e))?
+            .dim;
+
+        let mut options = InitOptions::new(model_enum.clone());
+        options.cache_dir = cache_dir;
+
+        let model = TextEmbedding::try_new(options)
+            .map_err(|e| anyhow!("Failed to load embedding model: {}", e))?;
+
+        Ok(Self {
+            model: Mutex::new(model),
+            id: Self::model_id_of(&model_enum),
+            dimensions,
+        })
+    }
+
+    /// Stable id for a fastembed model (legacy alias if one exists).
+    pub fn model_id_of(model: &EmbeddingModel) -> String {
+        LEGACY_MODEL_IDS
+            .iter()
+            .find(|(_, m)| m == model)
+            .map(|(id, _)| id.to_string())
+            .unwrap_or_else(|| format!("{:?}", model))
+    }
+
+    /// Resolve a model id (legacy alias or variant name, case-insensitive).
+    pub fn parse_model_id(model_id: &str) -> Option<EmbeddingModel> {
+        LEGACY_MODEL_IDS
+            .iter()
+            .find(|(id, _)| *id == model_id)
+            .map(|(_, m)| m.clone())
+            .or_else(|| EmbeddingModel::from_str(model_id).ok())
+    }
+
+    /// hf-hub stores each repository as `models--{org}--{name}` inside the cache dir.
+    pub fn cache_dir_name(model_code: &str) -> String {
+        format!("models--{}", model_code.replace('/', "--"))
+    }
+
+    /// Directory holding the downloaded files of a model, if the id is known.
+    pub fn model_cache_path(cache_dir: &Path, model_id: &str) -> Option<PathBuf> {
+        let model = Self::parse_model_id(model_id)?;
+        let info = TextEmbedding::get_model_info(&model).ok()?;
+        Some(cache_dir.join(Self::cache_dir_name(&info.model_code)))
+    }
+
+    /// Every model fastembed ships, flagged with whether it is already on disk.
+    pub fn supported_models(cache_dir: &Path) -> Vec<EmbeddingModelInfo> {
+        TextEmbedding::list_supported_models()
+            .into_iter()
+            .map(|info| EmbeddingModelInfo {
+                id: Self::model_id_of(&info.model),
+                downloaded: cache_dir
+                    .join(Self::cache_dir_name(&info.model_code))
+                    .is_dir(),
+                description: info.description,
+                dimensions: info.dim,
+            })
+            .collect()
+    }
+}
+
+impl EmbeddingBackend for FastEmbedBackend {
+    fn model_id(&self) -> &str {
+        &self.id
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.model
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .embed(texts, None)
+            .map_err(|e| anyhow!("Failed to generate embedding: {}", e))
+    }
+}
+
+// ============================================================================
+// OpenAI-compatible HTTP endpoint (OpenAI, Ollama, llama.cpp, LM Studio, ...)
+// ============================================================================
+
+/// Embeddings from any server implementing `POST {base_url}/embeddings`.
+pub struct OpenAiEmbeddingBackend {
+    client: reqwest::blocking::Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    id: String,
+    dimensions: usize,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingsItem>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingsItem {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+impl OpenAiEmbeddingBackend {
+    /// Connects to the endpoint and embeds a probe string to learn the vector size.
+    /// Blocking.
+    pub fn connect(endpoint: &OpenAiEndpoint) -> Result<Self> {
+        let client = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()?;
+
+        let mut backend = Self {
+            client,
+            url: format!("{}/embeddings", endpoint.base_url.trim_end_matches('/')),
+            model: endpoint.model.clone(),
+            api_key: endpoint.api_key.clone().filter(|k| !k.is_empty()),
+            id: format!("openai:{}", endpoint.model),
+            dimensions: 0,
+        };
+
+        let probe = backend.request(&["dimension probe".to_string()])?;
+        backend.dimensions = probe
+            .first()
+            .map(|v| v.len())
+            .filter(|len| *len > 0)
+            .ok_or_else(|| anyhow!("Embedding endpoint returned an empty vector"))?;
+
+        Ok(backend)
+    }
+
+    fn request(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut request = self.client.post(&self.url).json(&serde_json::json!({
+            "model": self.model,
+            "input": texts,
+        }));
+        if let Some(key) = &self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request
+            .send()
+            .map_err(|e| anyhow!("Embedding endpoint unreachable: {}", e))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            bail!("Embedding endpoint returned {}: {}", status, body);
+        }
+
+        let mut parsed: EmbeddingsResponse = response
+            .json()
+            .map_err(|e| anyhow!("Invalid embeddings response: {}", e))?;
+
+        if parsed.data.len() != texts.len() {
+            bail!(
+                "Embedding endpoint returned {} vectors for {} inputs",
+                parsed.data.len(),
+                texts.len()
+            );
+        }
+
+        parsed.data.sort_by_key(|item| item.index);
+        Ok(parsed.data.into_iter().map(|item| item.embedding).collect())
+    }
+}
+
+impl EmbeddingBackend for OpenAiEmbeddingBackend {
+    fn model_id(&self) -> &str {
+        &self.id
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        self.request(texts)
+    }
+}
+
+// ============================================================================
+// Deterministic backend for tests
+// ============================================================================
+
+/// Feature-hashing bag of words: texts sharing words get similar vectors.
+/// No model download, stable across runs.
+#[cfg(test)]
+pub struct HashEmbeddingBackend {
+    dimensions: usize,
+}
+
+#[cfg(test)]
+impl HashEmbeddingBackend {
+    pub fn new(dimensions: usize) -> Self {
+        Self { dimensions }
+    }
+}
+
+#[cfg(test)]
+impl EmbeddingBackend for HashEmbeddingBackend {
+    fn model_id(&self) -> &str {
+        "test-hash"
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let mut vector = vec![0.0f32; self.dimensions];
+                for word in text
+                    .split(|c: char| !c.is_alphanumeric())
+                    .filter(|w| !w.is_empty())
+                {
+                    let mut hasher = DefaultHasher::new();
+                    word.to_lowercase().hash(&mut hasher);
+                    vector[(hasher.finish() % self.dimensions as u64) as usize] += 1.0;
+                }
+                let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+                if norm > 0.0 {
+                    vector.iter_mut().for_each(|v| *v /= norm);
+                }
+                vector
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::services::semantic::SemanticService;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    /// Minimal HTTP server: answers each incoming request with the next canned
+    /// JSON body and returns the raw requests it received.
+    pub(crate) fn mock_server(bodies: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut head = String::new();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    head.push_str(&line);
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+                let mut payload = vec![0; content_length];
+                reader.read_exact(&mut payload).unwrap();
+                requests.push(head + &*String::from_utf8_lossy(&payload));
+
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        (base_url, handle)
+    }
+
+    #[test]
+    fn openai_backend_probes_dimensions_and_orders_by_index() {
+        let (base_url, server) = mock_server(vec![
+            r#"{"data":[{"embedding":[0.1,0.2,0.3],"index":0}]}"#.to_string(),
+            r#"{"data":[{"embedding":[2.0,2.0,2.0],"index":1},{"embedding":[1.0,1.0,1.0],"index":0}]}"#
+                .to_string(),
+        ]);
+
+        let endpoint = OpenAiEndpoint {
+            base_url: format!("{}/", base_url),
+            model: "nomic-embed-text".to_string(),
+            api_key: Some("secret".to_string()),
+        };
+
+        let backend = OpenAiEmbeddingBackend::connect(&endpoint).unwrap();
+        assert_eq!(backend.dimensions(), 3);
+        assert_eq!(backend.model_id(), "openai:nomic-embed-text");
+
+        let vectors = backend
+            .embed(&["first".to_string(), "second".to_string()])
+            .unwrap();
+        assert_eq!(vectors, vec![vec![1.0, 1.0, 1.0], vec![2.0, 2.0, 2.0]]);
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /v1/embeddings "));
+        assert!(requests[0].to_lowercase().contains("authorization: bearer secret"));
+        assert!(requests[1].contains(r#""model":"nomic-embed-text""#));
+        assert!(requests[1].contains(r#""input":["first","second"]"#));
+    }
+
+    #[test]
+    fn openai_backend_rejects_mismatched_batch() {
+        let (base_url, _server) = mock_server(vec![
+            r#"{"data":[{"embedding":[0.5,0.5],"index":0}]}"#.to_string(),
+            r#"{"data":[{"embedding":[0.5,0.5],"index":0}]}"#.to_string(),
+        ]);
+
+        let endpoint = OpenAiEndpoint {
+            base_url,
+            ..OpenAiEndpoint::default()
+        };
+        let backend = OpenAiEmbeddingBackend::connect(&endpoint).unwrap();
+        assert!(backend.embed(&["a".to_string(), "b".to_string()]).is_err());
+    }
+
+    #[test]
+    fn hash_backend_is_deterministic_and_similarity_aware() {
+        let backend = HashEmbeddingBackend::new(64);
+        let texts = vec![
+            "kubernetes deployment manifest".to_string(),
+            "kubernetes deployment manifest".to_string(),
+            "deployment manifest for kubernetes pods".to_string(),
+            "grocery list: milk, eggs".to_string(),
+        ];
+        let vectors = backend.embed(&texts).unwrap();
+
+        assert_eq!(vectors[0].len(), 64);
+        assert_eq!(vectors[0], vectors[1]);
+
+        let close = SemanticService::cosine_similarity(&vectors[0], &vectors[2]);
+        let far = SemanticService::cosine_similarity(&vectors[0], &vectors[3]);
+        assert!(close > far);
+    }
+
+    #[test]
+    fn legacy_model_ids_resolve() {
+        assert_eq!(
+            FastEmbedBackend::parse_model_id("all-MiniLM-L6-v2"),
+            Some(EmbeddingModel::AllMiniLML6V2)
+        );
+        assert_eq!(
+            FastEmbedBackend::model_id_of(&EmbeddingModel::ParaphraseMLMiniLML12V2),
+            "paraphrase-multilingual-MiniLM-L12-v2"
+        );
+    }
+
+    #[test]
+    fn cache_dir_name_matches_hf_hub_layout() {
+        assert_eq!(
+            FastEmbedBackend::cache_dir_name("Qdrant/all-MiniLM-L6-v2-onnx"),
+            "models--Qdrant--all-MiniLM-L6-v2-onnx"
+        );
+    }
+}
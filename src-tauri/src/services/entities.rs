@@ -0,0 +1,315 @@
+// Entity extraction
+//
+// The detectors in `intelligence` classify a clip as a whole. This pulls every
+// URL, email, phone number, IP address, path, hex color, UUID, hash and ticket
+// id out of free text (an email thread, a log) with its byte span, for
+// `clip_entities`.
+
+use crate::models::{ClipEntity, ClipItem};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::ops::Range;
+
+/// Only the start of longer text is scanned.
+const MAX_SCAN_BYTES: usize = 512 * 1024;
+
+/// Most entities kept per clip.
+pub const MAX_ENTITIES: usize = 500;
+
+/// Dashed prefixes that are standards, not ticket ids ("UTF-8", "SHA-256").
+const NOT_TICKETS: &[&str] = &[
+    "AES", "CVE", "COVID", "HTTP", "IEEE", "IPV", "ISO", "MD", "RFC", "SHA", "SSL", "TLS", "UTF",
+];
+
+/// Kind of an extracted entity, stored in `clip_entities.kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Url,
+    Email,
+    Uuid,
+    Hash,
+    Ipv6,
+    Ipv4,
+    Color,
+    Ticket,
+    Phone,
+    Path,
+}
+
+impl EntityKind {
+    /// Every kind. Where matches overlap, the earlier kind wins (the path
+    /// inside a URL, the digits of an IP address).
+    pub const ALL: &'static [EntityKind] = &[
+        EntityKind::Url,
+        EntityKind::Email,
+        EntityKind::Uuid,
+        EntityKind::Hash,
+        EntityKind::Ipv6,
+        EntityKind::Ipv4,
+        EntityKind::Color,
+        EntityKind::Ticket,
+        EntityKind::Phone,
+        EntityKind::Path,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EntityKind::Url => "url",
+            EntityKind::Email => "email",
+            EntityKind::Uuid => "uuid",
+            EntityKind::Hash => "hash",
+            EntityKind::Ipv6 => "ipv6",
+            EntityKind::Ipv4 => "ipv4",
+            EntityKind::Color => "color",
+            EntityKind::Ticket => "ticket",
+            EntityKind::Phone => "phone",
+            EntityKind::Path => "path",
+        }
+    }
+}
+
+/// Every entity in `text`, in order of appearance, with byte offsets into it.
+/// Overlapping matches are resolved by `EntityKind::ALL` order.
+pub fn extract(text: &str) -> Vec<ClipEntity> {
+    let mut end = text.len().min(MAX_SCAN_BYTES);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let text = &text[..end];
+
+    // start -> (end, kind)
+    let mut found: BTreeMap<usize, (usize, EntityKind)> = BTreeMap::new();
+    for &kind in EntityKind::ALL {
+        for span in find(kind, text) {
+            let overlaps = found
+                .range(..span.end)
+                .next_back()
+                .is_some_and(|(_, (end, _))| *end > span.start);
+            if !overlaps {
+                found.insert(span.start, (span.end, kind));
+            }
+        }
+    }
+
+    found
+        .into_iter()
+        .take(MAX_ENTITIES)
+        .map(|(start, (end, kind))| ClipEntity {
+            kind: kind.as_str().to_string(),
+            value: text[start..end].to_string(),
+            start: start as i64,
+            end: end as i64,
+        })
+        .collect()
+}
+
+/// Entities of a clip's text. None for secrets, which stay out of listings.
+pub fn extract_clip(clip: &ClipItem) -> Vec<ClipEntity> {
+    match &clip.content_text {
+        Some(text) if clip.detected_type != "secret" => extract(text),
+        _ => Vec::new(),
+    }
+}
+
+/// Spans of `kind` in `text`, possibly overlapping other kinds.
+fn find(kind: EntityKind, text: &str) -> Vec<Range<usize>> {
+    lazy_static! {
+        static ref URL: Regex = Regex::new(r#"\b(?:(?:https?|ftp)://|www\.)[^\s<>"'`]+"#).unwrap();
+        static ref EMAIL: Regex =
+            Regex::new(r"\b[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}\b").unwrap();
+        static ref UUID: Regex = Regex::new(
+            r"\b[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}\b"
+        )
+        .unwrap();
+        // MD5, SHA-1 (git commits), SHA-256, SHA-512
+        static ref HASH: Regex = Regex::new(r"\b[0-9a-fA-F]{32,128}\b").unwrap();
+        static ref IPV6: Regex =
+            Regex::new(r"(?:[0-9a-fA-F]{1,4})?(?::[0-9a-fA-F]{0,4}){2,7}").unwrap();
+        static ref IPV4: Regex = Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").unwrap();
+        static ref COLOR: Regex =
+            Regex::new(r"#(?:[0-9a-fA-F]{8}|[0-9a-fA-F]{6}|[0-9a-fA-F]{3,4})\b").unwrap();
+        static ref TICKET: Regex = Regex::new(r"\b([A-Z][A-Z0-9]{1,9})-[1-9]\d{0,6}\b").unwrap();
+        // The `detect_phone` formats, unanchored
+        static ref PHONE: Regex = Regex::new(
+            r"\+\d{1,3}[\s-]?\(?\d{1,4}\)?[\s-]?\d{3,4}[\s-]?\d{3,4}|\(?\d{3}\)?[\s.-]?\d{3}[\s.-]?\d{4}"
+        )
+        .unwrap();
+        static ref UNIX_PATH: Regex = Regex::new(r"(?:~|\.{1,2})?(?:/[\w.-]+)+/?").unwrap();
+        static ref WIN_PATH: Regex =
+            Regex::new(r#"\b[A-Za-z]:\\(?:[^\\/:*?"<>|\s]+\\?)*"#).unwrap();
+    }
+
+    let spans = |regex: &Regex| regex.find_iter(text).map(|m| m.range()).collect::<Vec<_>>();
+    let mut spans = match kind {
+        EntityKind::Url => spans(&URL)
+            .into_iter()
+            .map(|span| trim_url(text, span))
+            .collect(),
+        EntityKind::Email => spans(&EMAIL),
+        EntityKind::Uuid => spans(&UUID),
+        EntityKind::Hash => spans(&HASH)
+            .into_iter()
+            .filter(|span| {
+                let hash = &text[span.clone()];
+                [32, 40, 64, 128].contains(&hash.len())
+                    && hash.bytes().any(|b| b.is_ascii_digit())
+                    && hash.bytes().any(|b| b.is_ascii_alphabetic())
+            })
+            .collect(),
+        EntityKind::Ipv6 => spans(&IPV6)
+            .into_iter()
+            .filter(|span| {
+                let ip = &text[span.clone()];
+                // A digit keeps out `Ab::Cd` paths in code
+                ip.bytes().any(|b| b.is_ascii_digit())
+                    && ip.parse::<Ipv6Addr>().is_ok()
+                    && bounded(text, span, |c| c.is_alphanumeric() || c == ':')
+            })
+            .collect(),
+        EntityKind::Ipv4 => spans(&IPV4)
+            .into_iter()
+            .filter(|span| {
+                text[span.clone()].parse::<Ipv4Addr>().is_ok()
+                    // Not part of a longer dotted number (a version)
+                    && !dotted(text[..span.start].chars().rev())
+                    && !dotted(text[span.end..].chars())
+            })
+            .collect(),
+        EntityKind::Color => spans(&COLOR)
+            .into_iter()
+            .filter(|span| {
+                let hex = &text[span.start + 1..span.end];
+                // `#123` is more likely an issue number
+                (hex.len() >= 6 || hex.bytes().any(|b| b.is_ascii_alphabetic()))
+                    && bounded(text, span, |c| c.is_alphanumeric() || c == '&')
+            })
+            .collect(),
+        EntityKind::Ticket => TICKET
+            .captures_iter(text)
+            .filter(|caps| !NOT_TICKETS.contains(&&caps[1]))
+            .map(|caps| caps.get(0).unwrap().range())
+            .filter(|span| bounded(text, span, |c| c == '-'))
+            .collect(),
+        EntityKind::Phone => spans(&PHONE)
+            .into_iter()
+            .filter(|span| {
+                // A bare 10-digit run is more likely a timestamp
+                !text[span.clone()].bytes().all(|b| b.is_ascii_digit())
+                    && bounded(text, span, |c| c.is_alphanumeric())
+            })
+            .collect(),
+        EntityKind::Path => spans(&UNIX_PATH)
+            .into_iter()
+            .filter(|span| {
+                let path = &text[span.clone()];
+                // `/etc` alone is too ambiguous; `~/notes` and `./x` are not
+                let relative = path.starts_with(['~', '.']);
+                (relative || path.matches('/').count() >= 2)
+                    && bounded(text, span, |c| {
+                        !c.is_whitespace() && !"\"'`([{<=:,".contains(c)
+                    })
+            })
+            .chain(spans(&WIN_PATH))
+            .map(|span| trim_end(text, span, &['.', ',', ';', ':', '!', '?']))
+            .collect(),
+    };
+    spans.retain(|span| !span.is_empty());
+    spans
+}
+
+/// Whether the characters just outside `span` are not `joins` ones.
+fn bounded(text: &str, span: &Range<usize>, joins: impl Fn(char) -> bool) -> bool {
+    !text[..span.start].chars().next_back().is_some_and(&joins)
+        && !text[span.end..].chars().next().is_some_and(&joins)
+}
+
+/// Whether `chars` (walking away from a match) start with a dot and a digit.
+fn dotted(mut chars: impl Iterator<Item = char>) -> bool {
+    chars.next() == Some('.') && chars.next().is_some_and(|c| c.is_ascii_digit())
+}
+
+/// Drops trailing characters in `chars` from `span`.
+fn trim_end(text: &str, span: Range<usize>, chars: &[char]) -> Range<usize> {
+    span.start..span.start + text[span].trim_end_matches(chars).len()
+}
+
+/// Drops sentence punctuation and unbalanced closing brackets after a URL
+/// ("(see https://example.com).").
+fn trim_url(text: &str, span: Range<usize>) -> Range<usize> {
+    let mut span = span;
+    loop {
+        span = trim_end(text, span, &['.', ',', ';', ':', '!', '?']);
+        let url = &text[span.clone()];
+        let unbalanced = [('(', ')'), ('[', ']'), ('{', '}')]
+            .iter()
+            .any(|&(open, close)| {
+                url.ends_with(close) && url.matches(close).count() > url.matches(open).count()
+            });
+        if !unbalanced {
+            return span;
+        }
+        span.end -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(text: &str) -> Vec<(String, String)> {
+        extract(text)
+            .into_iter()
+            .map(|e| (e.kind, e.value))
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn extracts_entities_from_free_text() {
+        let text = "From: Ana <ana@example.com>\n\
+                    Logs are in /var/log/app/server.log (see https://example.com/runbook_(v2)).\n\
+                    Call +1 555 555 5555 about PROJ-1234, request 550e8400-e29b-41d4-a716-446655440000.\n\
+                    Hosts fe80::1 and 10.0.0.12. Use #1e90ff; fixed in 3f786850e387550fdab836ed7e6dc881de23001b.\n\
+                    C:\\Users\\ana\\notes.txt";
+        assert_eq!(
+            kinds(text),
+            pairs(&[
+                ("email", "ana@example.com"),
+                ("path", "/var/log/app/server.log"),
+                ("url", "https://example.com/runbook_(v2)"),
+                ("phone", "+1 555 555 5555"),
+                ("ticket", "PROJ-1234"),
+                ("uuid", "550e8400-e29b-41d4-a716-446655440000"),
+                ("ipv6", "fe80::1"),
+                ("ipv4", "10.0.0.12"),
+                ("color", "#1e90ff"),
+                ("hash", "3f786850e387550fdab836ed7e6dc881de23001b"),
+                ("path", "C:\\Users\\ana\\notes.txt"),
+            ])
+        );
+
+        // Spans are byte offsets into the text
+        let text = "é https://a.io";
+        let url = &extract(text)[0];
+        assert_eq!((url.start, url.end), (3, 15));
+        assert_eq!(&text[url.start as usize..url.end as usize], url.value);
+    }
+
+    #[test]
+    fn skips_lookalikes() {
+        let text = "Version 1.2.3.4.5 of UTF-8 and SHA-256, issue #123, \
+                    and/or 1/2/2024, at 12:30:45 in Foo::bar, ts 1700000000 \
+                    on 2024-01-15, std::vector, /etc";
+        assert_eq!(kinds(text), []);
+    }
+}
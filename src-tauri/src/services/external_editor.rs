@@ -0,0 +1,295 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::process::{Child, Command};
+
+/// How often the temp file is checked for saves.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Without an editor process to wait for (system default app), the session
+/// ends this long after the last save.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Text opened in an external editor through a temp file.
+pub struct EditorSession {
+    path: PathBuf,
+    original: String,
+    /// The editor process, when `editor_command` is set. Its exit ends the session.
+    child: Option<Child>,
+}
+
+impl EditorSession {
+    /// Writes `text` to a temp file and opens it with `command` (e.g.
+    /// `code --wait`, `$VISUAL`; the file path is appended), or with the
+    /// system default app when `command` is empty.
+    pub fn open(text: &str, extension: &str, command: &str) -> Result<Self> {
+        let suffix = format!(".{}", extension.trim_start_matches('.'));
+        let mut temp_file = tempfile::Builder::new()
+            .prefix("clipsx-")
+            .suffix(&suffix)
+            .tempfile()
+            .context("Failed to create temp file")?;
+        temp_file
+            .write_all(text.as_bytes())
+            .context("Failed to write to temp file")?;
+
+        // Removed by `watch` once the session ends
+        let (file, path) = temp_file
+            .keep()
+            .map_err(|e| anyhow!("Failed to persist temp file: {}", e))?;
+        // Close the handle before opening to avoid locking issues on Windows
+        drop(file);
+
+        let child = if command.trim().is_empty() {
+            open::that(&path).context("Failed to open file")?;
+            None
+        } else {
+            let args = parse_command(command)?;
+            let child = Command::new(&args[0])
+                .args(&args[1..])
+                .arg(&path)
+                .spawn()
+                .with_context(|| format!("Failed to start editor '{}'", args[0]));
+            match child {
+                Ok(child) => Some(child),
+                Err(e) => {
+                    let _ = std::fs::remove_file(&path);
+                    return Err(e);
+                }
+            }
+        };
+
+        Ok(Self {
+            path,
+            original: text.to_string(),
+            child,
+        })
+    }
+
+    /// Calls `on_save` with the file's text after every save, until the editor
+    /// exits (or the idle timeout passes), then deletes the file.
+    pub async fn watch<F, Fut>(self, on_save: F)
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        self.watch_with(POLL_INTERVAL, IDLE_TIMEOUT, on_save).await
+    }
+
+    async fn watch_with<F, Fut>(mut self, poll: Duration, idle_timeout: Duration, mut on_save: F)
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut last_version = file_version(&self.path);
+        let mut last_save = Instant::now();
+
+        loop {
+            // Checked before reading, so a save right before exiting is synced
+            let exited = match self.child.as_mut() {
+                Some(child) => !matches!(child.try_wait(), Ok(None)),
+                None => false,
+            };
+
+            let version = file_version(&self.path);
+            if version.is_some() && version != last_version {
+                last_version = version;
+                last_save = Instant::now();
+                match std::fs::read_to_string(&self.path) {
+                    Ok(contents) => on_save(saved_text(contents, &self.original)).await,
+                    Err(e) => eprintln!("[ERROR] Failed to read edited file: {}", e),
+                }
+            }
+
+            let timed_out = self.child.is_none() && last_save.elapsed() >= idle_timeout;
+            if exited || timed_out {
+                break;
+            }
+            tokio::time::sleep(poll).await;
+        }
+
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            eprintln!("[WARN] Failed to remove {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+/// Modification time and size; either changes on a save.
+fn file_version(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Drops the final newline most editors add on save, unless the text had one.
+fn saved_text(mut contents: String, original: &str) -> String {
+    if !original.ends_with('\n') {
+        if contents.ends_with("\r\n") {
+            contents.truncate(contents.len() - 2);
+        } else if contents.ends_with('\n') {
+            contents.pop();
+        }
+    }
+    contents
+}
+
+/// Splits an editor command into program and arguments, like a shell would:
+/// whitespace separates arguments except inside single or double quotes, and
+/// `$VAR`/`${VAR}` is expanded outside single quotes (unquoted, its value is
+/// split too, so `$VISUAL` may hold arguments).
+fn parse_command(command: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quote: Option<char> = None;
+    let mut chars = command.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('\''), c) => current.push(c),
+            (_, '$') => {
+                let Some(name) = env_var_name(&mut chars) else {
+                    current.push('$');
+                    in_arg = true;
+                    continue;
+                };
+                let value = std::env::var(name).unwrap_or_default();
+                if quote.is_some() {
+                    current.push_str(&value);
+                    continue;
+                }
+                let mut words = value.split_whitespace().peekable();
+                while let Some(word) = words.next() {
+                    current.push_str(word);
+                    in_arg = true;
+                    if words.peek().is_some() {
+                        args.push(std::mem::take(&mut current));
+                    }
+                }
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        bail!("Unterminated quote in editor command");
+    }
+    if in_arg {
+        args.push(current);
+    }
+    if args.is_empty() {
+        bail!("Editor command '{}' is empty", command);
+    }
+    Ok(args)
+}
+
+/// Reads the name after a `$`: `NAME` or `{NAME}`. None (nothing consumed)
+/// if no name follows.
+fn env_var_name(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let is_name_char = |c: &char| c.is_ascii_alphanumeric() || *c == '_';
+
+    if chars.peek() == Some(&'{') {
+        let mut lookahead = chars.clone();
+        lookahead.next();
+        let mut name = String::new();
+        let mut closed = false;
+        for c in lookahead.by_ref() {
+            if c == '}' {
+                closed = true;
+                break;
+            }
+            name.push(c);
+        }
+        if !closed || name.is_empty() || !name.chars().all(|c| is_name_char(&c)) {
+            return None;
+        }
+        *chars = lookahead;
+        return Some(name);
+    }
+
+    let mut name = String::new();
+    while let Some(c) = chars.next_if(is_name_char) {
+        name.push(c);
+    }
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("code --wait").unwrap(), ["code", "--wait"]);
+        assert_eq!(
+            parse_command(r#""/Applications/Sublime Text.app/bin/subl" -w '' x"#).unwrap(),
+            ["/Applications/Sublime Text.app/bin/subl", "-w", "", "x"]
+        );
+
+        std::env::set_var("CLIPSX_TEST_EDITOR", "gvim -f");
+        assert_eq!(
+            parse_command("$CLIPSX_TEST_EDITOR --nofork").unwrap(),
+            ["gvim", "-f", "--nofork"]
+        );
+        assert_eq!(
+            parse_command("${CLIPSX_TEST_EDITOR}x").unwrap(),
+            ["gvim", "-fx"]
+        );
+        assert_eq!(
+            parse_command(r#"ed "$CLIPSX_TEST_EDITOR" '$CLIPSX_TEST_EDITOR' $"#).unwrap(),
+            ["ed", "gvim -f", "$CLIPSX_TEST_EDITOR", "$"]
+        );
+
+        assert_eq!(parse_command("ed ${CLIPSX").unwrap(), ["ed", "${CLIPSX"]);
+        assert!(parse_command("$CLIPSX_TEST_UNSET").is_err());
+        assert!(parse_command("code 'unterminated").is_err());
+    }
+
+    #[test]
+    fn test_saved_text() {
+        assert_eq!(saved_text("a\n".to_string(), "a"), "a");
+        assert_eq!(saved_text("a\r\n".to_string(), "b"), "a");
+        assert_eq!(saved_text("a\n\n".to_string(), "a"), "a\n");
+        assert_eq!(saved_text("a\n".to_string(), "a\n"), "a\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_round_trip() {
+        // The "editor" saves once and exits; the file path is its $0
+        let session = EditorSession::open(
+            "draft",
+            "txt",
+            r#"sh -c 'sleep 0.2; printf "edited\n" > "$0"'"#,
+        )
+        .unwrap();
+        let path = session.path.clone();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "draft");
+
+        let mut saves = Vec::new();
+        session
+            .watch_with(Duration::from_millis(20), IDLE_TIMEOUT, |text| {
+                saves.push(text);
+                async {}
+            })
+            .await;
+
+        assert_eq!(saves, ["edited"]);
+        assert!(!path.exists());
+    }
+}
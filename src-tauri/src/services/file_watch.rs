@@ -0,0 +1,143 @@
+// Change detection for user-editable files (scripts, detectors)
+//
+// Polls each file's modification time and size. There are only a few small
+// files, and polling behaves the same on every platform and file system. One
+// `FileWatcher` task polls the files of every service.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// How often watched files are checked for changes.
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Watched files with their modification time and size (`None` while a file
+/// is missing). Two versions differ when a file was added, removed or changed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilesVersion(Vec<(PathBuf, Option<(SystemTime, u64)>)>);
+
+impl FilesVersion {
+    /// Version of the given files, in the given order.
+    pub fn of_files(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        Self(
+            paths
+                .into_iter()
+                .map(|path| {
+                    let stamp = file_stamp(&path);
+                    (path, stamp)
+                })
+                .collect(),
+        )
+    }
+
+    /// Version of the files in `dir` with the given extension, sorted by
+    /// path. Empty if the directory can't be read.
+    pub fn of_dir(dir: &Path, extension: &str) -> Self {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Self::default();
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+            .collect();
+        paths.sort();
+        Self::of_files(paths)
+    }
+
+    /// The files that exist, in order.
+    pub fn existing(&self) -> impl Iterator<Item = &Path> {
+        self.0
+            .iter()
+            .filter(|(_, stamp)| stamp.is_some())
+            .map(|(path, _)| path.as_path())
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+type Reload = Arc<dyn Fn() -> bool + Send + Sync>;
+
+/// Polls the files of several services from one task.
+#[derive(Default)]
+pub struct FileWatcher {
+    watches: Vec<(Reload, Box<dyn Fn() + Send + Sync>)>,
+}
+
+impl FileWatcher {
+    /// Adds a service: `reload_if_changed` is called every `WATCH_INTERVAL`,
+    /// and `on_change` after each call that reloaded something.
+    pub fn watch<R, F>(&mut self, reload_if_changed: R, on_change: F)
+    where
+        R: Fn() -> bool + Send + Sync + 'static,
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.watches
+            .push((Arc::new(reload_if_changed), Box::new(on_change)));
+    }
+
+    /// Starts polling. Reloads read and parse files, so they run on the
+    /// blocking pool.
+    pub fn spawn(self) {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(WATCH_INTERVAL).await;
+                for (reload_if_changed, on_change) in &self.watches {
+                    let reload_if_changed = reload_if_changed.clone();
+                    match tokio::task::spawn_blocking(move || reload_if_changed()).await {
+                        Ok(true) => on_change(),
+                        Ok(false) => {}
+                        Err(e) => eprintln!("[WATCH] Reload failed: {}", e),
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_files_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("b.rhai");
+        let empty = FilesVersion::of_dir(dir.path(), "rhai");
+        assert_eq!(empty.existing().count(), 0);
+
+        std::fs::write(&script, "fn run(clip) { clip.text }").unwrap();
+        std::fs::write(dir.path().join("a.rhai"), "").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        let version = FilesVersion::of_dir(dir.path(), "rhai");
+        assert_ne!(version, empty);
+        let names: Vec<_> = version.existing().map(|p| p.file_name().unwrap()).collect();
+        assert_eq!(names, vec!["a.rhai", "b.rhai"]);
+        assert_eq!(FilesVersion::of_dir(dir.path(), "rhai"), version);
+
+        // Missing files are part of the version, so creating one is a change
+        let missing = dir.path().join("detectors.json");
+        let files = FilesVersion::of_files([script.clone(), missing.clone()]);
+        assert_eq!(files.existing().collect::<Vec<_>>(), vec![script.as_path()]);
+        std::fs::write(&missing, "{}").unwrap();
+        assert_ne!(FilesVersion::of_files([script, missing]), files);
+    }
+
+    #[tokio::test]
+    async fn test_file_watcher_polls_every_service() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut watcher = FileWatcher::default();
+        for name in ["detectors", "scripts"] {
+            let tx = tx.clone();
+            watcher.watch(|| true, move || tx.send(name).unwrap());
+        }
+        watcher.spawn();
+
+        let timeout = WATCH_INTERVAL * 2;
+        let first = tokio::time::timeout(timeout, rx.recv()).await.unwrap();
+        let second = tokio::time::timeout(timeout, rx.recv()).await.unwrap();
+        assert_eq!((first, second), (Some("detectors"), Some("scripts")));
+    }
+}
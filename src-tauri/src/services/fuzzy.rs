@@ -0,0 +1,565 @@
+use crate::models::{ClipFilters, ClipItem, TextRange};
+use crate::repositories::ClipRepository;
+use anyhow::Result;
+use std::collections::HashSet;
+
+/// Upper bound on each kind of candidate (exact and trigram, see
+/// `search`), keeps latency flat on large histories when the query's trigrams
+/// are very common.
+const MAX_CANDIDATES: i32 = 1_000;
+
+/// Only the beginning of very long clips is scanned for matches.
+const MAX_SCAN_CHARS: usize = 20_000;
+
+/// Typo-tolerant search: trigram-index candidates, re-ranked by edit distance.
+///
+/// Every page re-ranks the same candidates: the newest `MAX_CANDIDATES` clips
+/// that contain the query terms verbatim, plus the newest `MAX_CANDIDATES`
+/// that share a trigram with each term. Exact matches are fetched on their
+/// own so an old clip that matches exactly is not crowded out by newer
+/// near-misses.
+pub async fn search(
+    repository: &ClipRepository,
+    query: &str,
+    filters: &ClipFilters,
+    limit: i32,
+    offset: i32,
+) -> Result<Vec<ClipItem>> {
+    search_in_window(repository, query, filters, limit, offset, MAX_CANDIDATES).await
+}
+
+async fn search_in_window(
+    repository: &ClipRepository,
+    query: &str,
+    filters: &ClipFilters,
+    limit: i32,
+    offset: i32,
+    max_candidates: i32,
+) -> Result<Vec<ClipItem>> {
+    let candidates = candidate_query(query);
+    let mut match_exprs = vec![candidates.exact_expr.as_deref()];
+    if candidates.match_expr != candidates.exact_expr {
+        match_exprs.push(candidates.match_expr.as_deref());
+    }
+
+    let mut seen = HashSet::new();
+    let mut window = Vec::new();
+    for match_expr in match_exprs {
+        let candidates_batch = repository
+            .fuzzy_candidates(
+                match_expr,
+                &candidates.substrings,
+                filters,
+                max_candidates,
+                0,
+            )
+            .await?;
+        window.extend(
+            candidates_batch
+                .into_iter()
+                .filter(|clip| seen.insert(clip.id.clone())),
+        );
+    }
+
+    Ok(rerank(query, window)
+        .into_iter()
+        .skip(offset.max(0) as usize)
+        .take(limit.max(0) as usize)
+        .collect())
+}
+
+/// Trigram-index lookup for a fuzzy query.
+#[derive(Debug, PartialEq)]
+pub struct CandidateQuery {
+    /// FTS5 expression for `clips_trigram`, `None` if every term is too short
+    pub match_expr: Option<String>,
+    /// FTS5 expression matching clips that contain every term verbatim
+    pub exact_expr: Option<String>,
+    /// Terms shorter than a trigram, matched as plain substrings
+    pub substrings: Vec<String>,
+}
+
+/// ORs the trigrams of each term and ANDs the terms.
+///
+/// A typo only touches the trigrams around it, so a misspelled word still
+/// shares some trigram with the intended one; the edit-distance re-ranking
+/// then drops the false positives. The exact expression ANDs the whole terms.
+///
+/// Example: `recieve` → `("rec" OR "eci" OR "cie" OR "iev" OR "eve")`, exact
+/// `"recieve"`
+pub fn candidate_query(query: &str) -> CandidateQuery {
+    let mut groups = Vec::new();
+    let mut phrases = Vec::new();
+    let mut substrings = Vec::new();
+
+    for term in query_terms(query) {
+        if term.len() < 3 {
+            substrings.push(term.iter().collect());
+            continue;
+        }
+
+        phrases.push(quote(&term));
+        let mut alternatives: Vec<String> = Vec::new();
+        for trigram in term.windows(3) {
+            let quoted = quote(trigram);
+            if !alternatives.contains(&quoted) {
+                alternatives.push(quoted);
+            }
+        }
+
+        groups.push(if alternatives.len() == 1 {
+            alternatives.remove(0)
+        } else {
+            format!("({})", alternatives.join(" OR "))
+        });
+    }
+
+    CandidateQuery {
+        match_expr: (!groups.is_empty()).then(|| groups.join(" AND ")),
+        exact_expr: (!phrases.is_empty()).then(|| phrases.join(" AND ")),
+        substrings,
+    }
+}
+
+/// FTS5 string literal; in a trigram table it matches as a substring.
+fn quote(chars: &[char]) -> String {
+    format!(
+        "\"{}\"",
+        chars.iter().collect::<String>().replace('"', "\"\"")
+    )
+}
+
+/// Typo-tolerant re-ranking of trigram search candidates.
+///
+/// Every query term must match some word of the clip within an edit budget
+/// that grows with the term length ("kuberentes" → "kubernetes" is one
+/// transposition). Terms written in CJK scripts have no word boundaries, so
+/// they are compared against same-length windows of the text instead.
+/// Clips that fail any term are dropped; the rest are sorted by score
+/// (`similarity_score`, 0.0–1.0), then recency.
+pub fn rerank(query: &str, candidates: Vec<ClipItem>) -> Vec<ClipItem> {
+    let terms = query_terms(query);
+    if terms.is_empty() {
+        return candidates;
+    }
+
+    let mut ranked: Vec<ClipItem> = candidates
+        .into_iter()
+        .filter_map(|mut clip| {
+            let score = score(&terms, clip.content_text.as_deref().unwrap_or(""))?;
+            clip.similarity_score = Some(score);
+            Some(clip)
+        })
+        .collect();
+
+    sort_ranked(&mut ranked);
+    ranked
+}
+
+fn sort_ranked(clips: &mut [ClipItem]) {
+    clips.sort_by(|a, b| {
+        b.similarity_score
+            .partial_cmp(&a.similarity_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| b.updated_at.cmp(&a.updated_at))
+    });
+}
+
+/// Lowercased whitespace-separated terms of a query.
+pub fn query_terms(query: &str) -> Vec<Vec<char>> {
+    query
+        .split_whitespace()
+        .map(|term| term.to_lowercase().chars().collect())
+        .collect()
+}
+
+/// Average similarity of the terms to their best matches in `text`, or `None`
+/// if some term has no match within its edit budget.
+pub fn score(terms: &[Vec<char>], text: &str) -> Option<f32> {
+    let text: Vec<char> = text.to_lowercase().chars().take(MAX_SCAN_CHARS).collect();
+    let words: HashSet<&[char]> = text
+        .split(|c| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let mut total = 0.0;
+    for term in terms {
+        let best = if term.iter().any(|c| is_cjk(*c)) {
+            best_window_similarity(term, &text)
+        } else {
+            words
+                .iter()
+                .filter_map(|word| word_similarity(term, word))
+                .fold(None, |best: Option<f32>, s| Some(best.map_or(s, |b| b.max(s))))
+        }?;
+        total += best;
+    }
+
+    Some(total / terms.len() as f32)
+}
+
+/// Byte ranges of `text` that `score` accepted as matches for the query terms:
+/// whole words for regular terms, the best same-length window for CJK terms.
+/// Sorted by position, overlapping ranges merged.
+pub fn match_ranges(query: &str, text: &str) -> Vec<TextRange> {
+    let (cjk_terms, word_terms): (Vec<Vec<char>>, Vec<Vec<char>>) = query_terms(query)
+        .into_iter()
+        .partition(|term| term.iter().any(|c| is_cjk(*c)));
+
+    // Lowercased per character so positions still point into `text`
+    let chars: Vec<(usize, char)> = text
+        .char_indices()
+        .take(MAX_SCAN_CHARS)
+        .map(|(i, c)| (i, c.to_lowercase().next().unwrap_or(c)))
+        .collect();
+    let byte_end = |index: usize| chars.get(index).map_or(text.len(), |(i, _)| *i);
+    let lowered: Vec<char> = chars.iter().map(|(_, c)| *c).collect();
+
+    let mut ranges = Vec::new();
+
+    let mut word_start = None;
+    for index in 0..=lowered.len() {
+        if lowered.get(index).is_some_and(|c| c.is_alphanumeric()) {
+            word_start.get_or_insert(index);
+        } else if let Some(start) = word_start.take() {
+            let word = &lowered[start..index];
+            if word_terms.iter().any(|t| word_similarity(t, word).is_some()) {
+                ranges.push(TextRange {
+                    start: chars[start].0,
+                    end: byte_end(index),
+                });
+            }
+        }
+    }
+
+    for term in &cjk_terms {
+        let budget = max_typos(term.len());
+        let best = lowered
+            .windows(term.len())
+            .enumerate()
+            .map(|(start, window)| (osa_distance(term, window), start))
+            .filter(|(distance, _)| *distance <= budget)
+            .min();
+        if let Some((_, start)) = best {
+            ranges.push(TextRange {
+                start: chars[start].0,
+                end: byte_end(start + term.len()),
+            });
+        }
+    }
+
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<TextRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Similarity of a term to a word (or to the word's prefix, for partially typed
+/// words), if within the term's edit budget.
+fn word_similarity(term: &[char], word: &[char]) -> Option<f32> {
+    let budget = max_typos(term.len());
+    if word.len() + budget < term.len() {
+        return None;
+    }
+    if word.starts_with(term) {
+        return Some(1.0);
+    }
+
+    let mut distance = if word.len() <= term.len() + budget {
+        osa_distance(term, word)
+    } else {
+        usize::MAX
+    };
+    if word.len() > term.len() {
+        distance = distance.min(osa_distance(term, &word[..term.len()]) + 1);
+    }
+
+    (distance <= budget).then(|| 1.0 - distance as f32 / term.len().max(word.len()) as f32)
+}
+
+fn best_window_similarity(term: &[char], text: &[char]) -> Option<f32> {
+    if text.windows(term.len()).any(|w| w == term) {
+        return Some(1.0);
+    }
+
+    let budget = max_typos(term.len());
+    text.windows(term.len())
+        .map(|window| osa_distance(term, window))
+        .filter(|d| *d <= budget)
+        .min()
+        .map(|d| 1.0 - d as f32 / term.len() as f32)
+}
+
+/// Allowed edits for a term of `len` characters.
+fn max_typos(len: usize) -> usize {
+    match len {
+        0..=2 => 0,
+        3..=5 => 1,
+        6..=9 => 2,
+        _ => 3,
+    }
+}
+
+/// Optimal string alignment distance: Levenshtein plus adjacent transpositions.
+pub fn osa_distance(a: &[char], b: &[char]) -> usize {
+    let (n, m) = (a.len(), b.len());
+    if n == 0 || m == 0 {
+        return n.max(m);
+    }
+
+    // Three rolling rows: i-2, i-1, i
+    let mut two_back = vec![0; m + 1];
+    let mut previous: Vec<usize> = (0..=m).collect();
+    let mut current = vec![0; m + 1];
+
+    for i in 1..=n {
+        current[0] = i;
+        for j in 1..=m {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(two_back[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut two_back, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[m]
+}
+
+/// Han, Hiragana/Katakana and Hangul: scripts written without spaces.
+pub fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF      // Hiragana, Katakana
+        | 0x3400..=0x4DBF    // CJK Extension A
+        | 0x4E00..=0x9FFF    // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF    // Hangul syllables
+        | 0xF900..=0xFAFF    // CJK Compatibility Ideographs
+        | 0x20000..=0x2FFFF) // CJK Extensions B+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn clip(id: &str, text: &str, updated_at: i64) -> ClipItem {
+        let mut clip = ClipItem::from_text(text.to_string(), "text".to_string(), None);
+        clip.id = id.to_string();
+        clip.updated_at = updated_at;
+        clip
+    }
+
+    #[test]
+    fn test_osa_distance() {
+        assert_eq!(osa_distance(&chars("kuberentes"), &chars("kubernetes")), 1);
+        assert_eq!(osa_distance(&chars("recieve"), &chars("receive")), 1);
+        assert_eq!(osa_distance(&chars("kitten"), &chars("sitting")), 3);
+        assert_eq!(osa_distance(&chars(""), &chars("abc")), 3);
+        assert_eq!(osa_distance(&chars("same"), &chars("same")), 0);
+    }
+
+    #[test]
+    fn test_score_tolerates_typos_and_prefixes() {
+        let text = "kubectl apply -f the Kubernetes deployment";
+        assert!(score(&query_terms("kuberentes"), text).is_some());
+        assert_eq!(score(&query_terms("deploy"), text), Some(1.0));
+        assert!(score(&query_terms("kubrenetes deplyment"), text).is_some());
+        assert!(score(&query_terms("kubernetes grocery"), text).is_none());
+        // Short terms must match exactly
+        assert!(score(&query_terms("fx"), text).is_none());
+    }
+
+    #[test]
+    fn test_score_cjk_without_word_boundaries() {
+        let text = "明日の会議は東京本社で行います";
+        assert_eq!(score(&query_terms("東京本社"), text), Some(1.0));
+        // One wrong character in a 4-character term
+        assert!(score(&query_terms("東京支社"), text).is_some());
+        assert!(score(&query_terms("大阪"), text).is_none());
+    }
+
+    #[test]
+    fn test_match_ranges() {
+        let text = "Please RECEIVE the Kubernetes manifest at 東京本社";
+        let matched: Vec<&str> = match_ranges("recieve kuberentes 東京支社", text)
+            .iter()
+            .map(|r| &text[r.start..r.end])
+            .collect();
+        assert_eq!(matched, vec!["RECEIVE", "Kubernetes", "東京本社"]);
+        assert!(match_ranges("xyzzy", text).is_empty());
+    }
+
+    #[test]
+    fn test_candidate_query() {
+        assert_eq!(
+            candidate_query("Recieve ab"),
+            CandidateQuery {
+                match_expr: Some(r#"("rec" OR "eci" OR "cie" OR "iev" OR "eve")"#.to_string()),
+                exact_expr: Some(r#""recieve""#.to_string()),
+                substrings: vec!["ab".to_string()],
+            }
+        );
+        assert_eq!(
+            candidate_query("東京本社 mississippi").match_expr,
+            Some(
+                r#"("東京本" OR "京本社") AND ("mis" OR "iss" OR "ssi" OR "sis" OR "sip" OR "ipp" OR "ppi")"#
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            candidate_query(r#"東京 a"b"#).match_expr,
+            Some(r#""a""b""#.to_string())
+        );
+        assert_eq!(
+            candidate_query("東京本社 mississippi").exact_expr,
+            Some(r#""東京本社" AND "mississippi""#.to_string())
+        );
+        assert_eq!(candidate_query("ab").match_expr, None);
+        assert_eq!(candidate_query("ab").exact_expr, None);
+    }
+
+    #[tokio::test]
+    async fn test_search_finds_typos_and_paginates() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        for (id, text) in [
+            ("1", "kubectl get pods in the kubernetes cluster"),
+            ("2", "kubernetes deployment"),
+            ("3", "kube-proxy logs"),
+            ("4", "grocery list"),
+            ("5", "did you receive the payment?"),
+        ] {
+            repo.insert(&clip(id, text, id.parse().unwrap())).await.unwrap();
+        }
+
+        let filters = ClipFilters::default();
+        let all = search(&repo, "kuberentes", &filters, 10, 0).await.unwrap();
+        let ids: Vec<&str> = all.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["2", "1"]);
+
+        let second_page = search(&repo, "kuberentes", &filters, 1, 1).await.unwrap();
+        assert_eq!(second_page[0].id, "1");
+
+        let typos = search(&repo, "recieve paymnet", &filters, 10, 0).await.unwrap();
+        assert_eq!(typos.len(), 1);
+        assert_eq!(typos[0].id, "5");
+    }
+
+    #[tokio::test]
+    async fn test_pages_rank_the_same_window() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        // Exact, typo and prefix matches mixed across ages, so a page-sized
+        // prefix of the newest candidates ranks differently from all of them
+        let texts = ["kubernetes", "kuberentes", "kubernetes cluster", "kubernet"];
+        let clips: Vec<ClipItem> = (0..12)
+            .map(|i| clip(&i.to_string(), texts[i % texts.len()], i as i64))
+            .collect();
+        repo.insert_all(&clips).await.unwrap();
+
+        let filters = ClipFilters::default();
+        let page = |offset| search_in_window(&repo, "kubernetes", &filters, 4, offset, 100);
+        let mut paged = Vec::new();
+        for offset in [0, 4, 8] {
+            paged.extend(page(offset).await.unwrap().into_iter().map(|c| c.id));
+        }
+
+        let all: Vec<String> = search_in_window(&repo, "kubernetes", &filters, 100, 0, 100)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.id)
+            .collect();
+        assert_eq!(all.len(), 12);
+        assert_eq!(paged, all);
+    }
+
+    #[tokio::test]
+    async fn test_old_exact_match_beyond_candidate_cap() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        let mut clips = vec![clip("old", "receive", 1)];
+        clips.extend((2..20).map(|i| clip(&i.to_string(), "recipe", i)));
+        repo.insert_all(&clips).await.unwrap();
+
+        // The 18 newer clips share trigrams with "receive" and fill the cap
+        let found = search_in_window(&repo, "receive", &ClipFilters::default(), 10, 0, 5)
+            .await
+            .unwrap();
+        assert_eq!(found[0].id, "old");
+    }
+
+    /// Latency budget on a large history, run with
+    /// `cargo test --release -- --ignored test_search_latency`.
+    #[tokio::test]
+    #[ignore]
+    async fn test_search_latency_100k_clips() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+
+        let words = [
+            "kubernetes",
+            "deployment",
+            "invoice",
+            "meeting",
+            "recipe",
+            "password",
+            "cluster",
+            "payment",
+            "receive",
+            "schedule",
+            "東京本社",
+            "会議",
+        ];
+        let clips: Vec<ClipItem> = (0..100_000)
+            .map(|i| {
+                let text = format!(
+                    "{} {} {} note {}",
+                    words[i % words.len()],
+                    words[(i / 7) % words.len()],
+                    words[(i / 13) % words.len()],
+                    i
+                );
+                clip(&i.to_string(), &text, i as i64)
+            })
+            .collect();
+        repo.insert_all(&clips).await.unwrap();
+
+        let filters = ClipFilters::default();
+        for query in ["kuberentes", "recieve paymnet", "東京本社", "metting"] {
+            let started = std::time::Instant::now();
+            let hits = search(&repo, query, &filters, 50, 0).await.unwrap();
+            let elapsed = started.elapsed();
+            assert!(!hits.is_empty(), "{}", query);
+            assert!(elapsed.as_millis() < 50, "{}: {:?}", query, elapsed);
+        }
+    }
+
+    #[test]
+    fn test_rerank_orders_by_score_then_recency() {
+        let ranked = rerank(
+            "recieve",
+            vec![
+                clip("typo-old", "please recieve the parcel", 1),
+                clip("exact", "you will receive it", 2),
+                clip("typo-new", "recieve", 3),
+                clip("miss", "nothing relevant", 4),
+            ],
+        );
+
+        let ids: Vec<&str> = ranked.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["typo-new", "typo-old", "exact"]);
+        assert_eq!(ranked[0].similarity_score, Some(1.0));
+    }
+}
@@ -0,0 +1,93 @@
+use anyhow::Result;
+use image::imageops::FilterType;
+use image::DynamicImage;
+
+/// Metadata key under which image clips store their perceptual hash.
+pub const METADATA_KEY: &str = "perceptual_hash";
+
+/// Difference hash (dHash) of an encoded image (PNG, JPEG, TIFF, ...).
+///
+/// The image is shrunk to 9x8 grayscale and each bit records whether a pixel is
+/// brighter than its right neighbour. Resizes, re-encodes and small edits keep
+/// most bits, so near-duplicate screenshots end up a few bits apart.
+pub fn dhash(data: &[u8]) -> Result<u64> {
+    let img = image::load_from_memory(data)?;
+    Ok(dhash_image(&img))
+}
+
+pub fn dhash_image(img: &DynamicImage) -> u64 {
+    let small = img.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | (left > right) as u64;
+        }
+    }
+    hash
+}
+
+/// 1.0 for identical hashes, 0.0 when every bit differs.
+pub fn similarity(a: u64, b: u64) -> f32 {
+    1.0 - (a ^ b).count_ones() as f32 / 64.0
+}
+
+pub fn to_hex(hash: u64) -> String {
+    format!("{:016x}", hash)
+}
+
+pub fn from_hex(hex: &str) -> Option<u64> {
+    u64::from_str_radix(hex, 16).ok()
+}
+
+/// Reads the hash stored in a clip's metadata JSON, if any.
+pub fn from_metadata(metadata: Option<&str>) -> Option<u64> {
+    let value: serde_json::Value = serde_json::from_str(metadata?).ok()?;
+    from_hex(value.get(METADATA_KEY)?.as_str()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, ImageFormat, Rgb};
+    use std::io::Cursor;
+
+    fn gradient(width: u32, height: u32, invert: bool) -> DynamicImage {
+        DynamicImage::ImageRgb8(ImageBuffer::from_fn(width, height, |x, y| {
+            let v = ((x * 255 / width + y * 40 / height) % 256) as u8;
+            let v = if invert { 255 - v } else { v };
+            Rgb([v, v / 2, 255 - v])
+        }))
+    }
+
+    #[test]
+    fn test_resized_copy_hashes_close() {
+        let original = dhash_image(&gradient(320, 200, false));
+        let resized = dhash_image(&gradient(160, 100, false));
+        let inverted = dhash_image(&gradient(320, 200, true));
+
+        assert!(similarity(original, resized) > 0.9);
+        assert!(similarity(original, inverted) < 0.5);
+    }
+
+    #[test]
+    fn test_dhash_of_encoded_png() {
+        let img = gradient(64, 64, false);
+        let mut png = Cursor::new(Vec::new());
+        img.write_to(&mut png, ImageFormat::Png).unwrap();
+
+        assert_eq!(dhash(png.get_ref()).unwrap(), dhash_image(&img));
+        assert!(dhash(b"not an image").is_err());
+    }
+
+    #[test]
+    fn test_metadata_roundtrip() {
+        let hash = 0x0123_4567_89ab_cdef;
+        let metadata = serde_json::json!({ "format": "image/png", METADATA_KEY: to_hex(hash) });
+        assert_eq!(from_metadata(Some(&metadata.to_string())), Some(hash));
+        assert_eq!(from_metadata(Some(r#"{"format":"image/png"}"#)), None);
+        assert_eq!(from_metadata(None), None);
+    }
+}
@@ -0,0 +1,1001 @@
+//! HTML → Markdown (GitHub flavoured) for pasting and exporting rich clips,
+//! and HTML → plain text for clips captured without a plain text flavor.
+//!
+//! Clipboard HTML is rarely well formed (Office fragments, unclosed `<p>`/`<li>`,
+//! `<o:p>` tags), so it is parsed into a forgiving node tree first and then
+//! rendered block by block.
+
+/// Elements that never have children.
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+/// Elements whose content is never rendered.
+const SKIPPED_ELEMENTS: &[&str] = &["head", "script", "style", "template", "title"];
+
+/// Elements that start a new block in Markdown output.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "center",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "ul",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Element {
+        name: String,
+        attrs: Vec<(String, String)>,
+        children: Vec<Node>,
+    },
+    Text(String),
+}
+
+impl Node {
+    fn attr(&self, key: &str) -> Option<&str> {
+        match self {
+            Node::Element { attrs, .. } => attrs
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str()),
+            Node::Text(_) => None,
+        }
+    }
+}
+
+/// Converts an HTML document or fragment to Markdown.
+pub fn html_to_markdown(html: &str) -> String {
+    let nodes = parse(strip_cf_html_header(html));
+    blocks(&nodes).join("\n\n")
+}
+
+/// Converts an HTML document or fragment to plain text: blocks are separated
+/// by blank lines, list items and table rows start a new line and table cells
+/// are separated by tabs.
+pub fn html_to_text(html: &str) -> String {
+    let nodes = parse(strip_cf_html_header(html));
+    let mut out = String::new();
+    plain(&nodes, &mut out);
+    trim_trailing_spaces(&mut out);
+    out.trim_end_matches('\n').to_string()
+}
+
+/// Windows CF_HTML starts with a "Version:0.9\r\nStartHTML:..." header.
+fn strip_cf_html_header(html: &str) -> &str {
+    match html.strip_prefix("Version:") {
+        Some(_) => html.find('<').map_or("", |start| &html[start..]),
+        None => html,
+    }
+}
+
+// ============================================================================
+// Parsing
+// ============================================================================
+
+/// Open element while parsing: name, attributes, children so far.
+type OpenElement = (String, Vec<(String, String)>, Vec<Node>);
+
+fn parse(html: &str) -> Vec<Node> {
+    let mut stack: Vec<OpenElement> = vec![(String::new(), Vec::new(), Vec::new())];
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            push_text(&mut stack, rest);
+            break;
+        };
+        push_text(&mut stack, &rest[..lt]);
+        rest = &rest[lt..];
+
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+
+        let Some(gt) = rest.find('>') else {
+            push_text(&mut stack, rest);
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        if let Some(name) = tag.strip_prefix('/') {
+            close_element(&mut stack, &name.trim().to_ascii_lowercase());
+            continue;
+        }
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue; // Doctype, processing instructions
+        }
+
+        let (name, attrs) = parse_tag(tag);
+        if name.is_empty() {
+            push_text(&mut stack, "<");
+            continue;
+        }
+
+        if SKIPPED_ELEMENTS.contains(&name.as_str()) {
+            // Raw text content: skip to the matching end tag
+            let end = format!("</{}", name);
+            rest = find_ignore_case(rest, &end)
+                .and_then(|i| rest[i..].find('>').map(|j| &rest[i + j + 1..]))
+                .unwrap_or("");
+            continue;
+        }
+
+        close_implied(&mut stack, &name);
+
+        if VOID_ELEMENTS.contains(&name.as_str()) || tag.ends_with('/') {
+            push_node(
+                &mut stack,
+                Node::Element {
+                    name,
+                    attrs,
+                    children: Vec::new(),
+                },
+            );
+        } else {
+            stack.push((name, attrs, Vec::new()));
+        }
+    }
+
+    while stack.len() > 1 {
+        pop_element(&mut stack);
+    }
+    stack
+        .pop()
+        .map(|(_, _, children)| children)
+        .unwrap_or_default()
+}
+
+fn parse_tag(tag: &str) -> (String, Vec<(String, String)>) {
+    let tag = tag.trim_end_matches('/');
+    let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+    let name = tag[..name_end].to_ascii_lowercase();
+
+    let mut attrs = Vec::new();
+    let mut rest = tag[name_end..].trim_start();
+    while !rest.is_empty() {
+        let key_end = rest
+            .find(|c: char| c == '=' || c.is_whitespace())
+            .unwrap_or(rest.len());
+        let key = rest[..key_end].to_ascii_lowercase();
+        rest = rest[key_end..].trim_start();
+
+        let mut value = String::new();
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let body = &after[1..];
+                    let end = body.find(quote).unwrap_or(body.len());
+                    (&body[..end], body.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after
+                        .find(|c: char| c.is_whitespace())
+                        .unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = decode_entities(raw);
+            rest = remaining.trim_start();
+        }
+
+        if !key.is_empty() {
+            attrs.push((key, value));
+        }
+    }
+
+    (name, attrs)
+}
+
+fn push_text(stack: &mut [OpenElement], text: &str) {
+    if !text.is_empty() {
+        push_node(stack, Node::Text(decode_entities(text)));
+    }
+}
+
+fn push_node(stack: &mut [OpenElement], node: Node) {
+    if let Some((_, _, children)) = stack.last_mut() {
+        children.push(node);
+    }
+}
+
+fn pop_element(stack: &mut Vec<OpenElement>) {
+    if let Some((name, attrs, children)) = stack.pop() {
+        push_node(
+            stack,
+            Node::Element {
+                name,
+                attrs,
+                children,
+            },
+        );
+    }
+}
+
+/// Closes `name` and everything opened inside it; stray end tags are ignored.
+fn close_element(stack: &mut Vec<OpenElement>, name: &str) {
+    if let Some(index) = stack.iter().rposition(|(open, _, _)| open == name) {
+        while stack.len() > index.max(1) {
+            pop_element(stack);
+        }
+    }
+}
+
+/// HTML's optional end tags: a new `<li>` closes the previous item, a new
+/// cell or row the previous one, and a block closes an open `<p>`.
+fn close_implied(stack: &mut Vec<OpenElement>, name: &str) {
+    let (closes, boundaries): (&[&str], &[&str]) = match name {
+        "li" => (&["li"], &["ul", "ol"]),
+        "dt" | "dd" => (&["dt", "dd"], &["dl"]),
+        "td" | "th" => (&["td", "th"], &["tr", "table"]),
+        "tr" => (&["tr"], &["table"]),
+        "thead" | "tbody" | "tfoot" => (&["thead", "tbody", "tfoot"], &["table"]),
+        _ if BLOCK_ELEMENTS.contains(&name) => (&["p"], &[]),
+        _ => return,
+    };
+
+    let found = stack
+        .iter()
+        .enumerate()
+        .rev()
+        .take_while(|(_, (open, _, _))| !boundaries.contains(&open.as_str()))
+        .find(|(_, (open, _, _))| closes.contains(&open.as_str()))
+        .map(|(index, _)| index);
+
+    if let Some(index) = found {
+        // Only close a <p> that is directly open, not one further up
+        if closes == ["p"] && index != stack.len() - 1 {
+            return;
+        }
+        while stack.len() > index {
+            pop_element(stack);
+        }
+    }
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .to_ascii_lowercase()
+        .find(&needle.to_ascii_lowercase())
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest
+            .find(';')
+            .filter(|end| *end <= 10)
+            .and_then(|end| decode_entity(&rest[1..end]).map(|c| (c, end)));
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    if let Some(number) = entity.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+
+    Some(match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "bull" => '•',
+        "middot" => '·',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "euro" => '€',
+        _ => return None,
+    })
+}
+
+// ============================================================================
+// Rendering
+// ============================================================================
+
+/// Renders nodes as a list of Markdown blocks (to be joined by blank lines).
+fn blocks(nodes: &[Node]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut run: Vec<&Node> = Vec::new();
+
+    for node in nodes {
+        let name = match node {
+            Node::Element { name, .. } if BLOCK_ELEMENTS.contains(&name.as_str()) => name,
+            _ => {
+                run.push(node);
+                continue;
+            }
+        };
+
+        flush_paragraph(&mut out, &mut run);
+        let Node::Element { children, .. } = node else {
+            continue;
+        };
+
+        let block = match name.as_str() {
+            "p" | "dt" | "dd" | "figcaption" => paragraph(children),
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                paragraph(children)
+                    .map(|text| format!("{} {}", "#".repeat(level), text.replace("  \n", " ")))
+            }
+            "ul" | "ol" => list(node),
+            "li" => list_item("- ", children),
+            "pre" => Some(code_block(node, None)),
+            "blockquote" => {
+                let inner = blocks(children).join("\n\n");
+                (!inner.is_empty()).then(|| quote(&inner))
+            }
+            "hr" => Some("---".to_string()),
+            "table" => table(node),
+            _ => match class_language(node) {
+                // GitHub puts the language on a wrapper: <div class="highlight-source-rust"><pre>
+                Some(language) => {
+                    for child in children {
+                        match child {
+                            Node::Element { name, .. } if name == "pre" => {
+                                out.push(code_block(child, Some(&language)))
+                            }
+                            _ => out.extend(blocks(std::slice::from_ref(child))),
+                        }
+                    }
+                    None
+                }
+                None => {
+                    out.extend(blocks(children));
+                    None
+                }
+            },
+        };
+        out.extend(block);
+    }
+
+    flush_paragraph(&mut out, &mut run);
+    out
+}
+
+fn flush_paragraph(out: &mut Vec<String>, run: &mut Vec<&Node>) {
+    if run.is_empty() {
+        return;
+    }
+    let nodes: Vec<Node> = run.drain(..).cloned().collect();
+    out.extend(paragraph(&nodes));
+}
+
+/// Inline content as one block; `None` if it is only whitespace.
+fn paragraph(nodes: &[Node]) -> Option<String> {
+    let text = inline(nodes);
+    let text = text
+        .split('\n')
+        .map(str::trim_start)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim_matches(|c: char| c == ' ' || c == '\n');
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn inline(nodes: &[Node]) -> String {
+    let mut out = String::new();
+
+    for node in nodes {
+        match node {
+            Node::Text(text) => push_collapsed(&mut out, &escape_markdown(text)),
+            Node::Element { name, children, .. } => match name.as_str() {
+                "br" => {
+                    trim_trailing_spaces(&mut out);
+                    out.push_str("  \n");
+                }
+                "strong" | "b" => wrap_inline(&mut out, "**", &inline(children)),
+                "em" | "i" => wrap_inline(&mut out, "*", &inline(children)),
+                "del" | "s" | "strike" => wrap_inline(&mut out, "~~", &inline(children)),
+                "code" | "kbd" | "samp" | "tt" => {
+                    let code = text_content(children);
+                    if !code.trim().is_empty() {
+                        let fence = if code.contains('`') { "``" } else { "`" };
+                        out.push_str(&format!("{fence}{}{fence}", code.trim()));
+                    }
+                }
+                "a" => link(&mut out, node, children),
+                "img" => image(&mut out, node),
+                _ if BLOCK_ELEMENTS.contains(&name.as_str()) => {
+                    // Blocks nested in inline context (e.g. <p> in a table cell)
+                    let inner = blocks(std::slice::from_ref(node)).join("\n");
+                    if !inner.is_empty() {
+                        if !out.is_empty() && !out.ends_with('\n') {
+                            out.push('\n');
+                        }
+                        out.push_str(&inner);
+                        out.push('\n');
+                    }
+                }
+                _ => out.push_str(&inline(children)),
+            },
+        }
+    }
+
+    out
+}
+
+fn link(out: &mut String, node: &Node, children: &[Node]) {
+    let text = inline(children);
+    let href = node.attr("href").unwrap_or("").trim();
+
+    if href.is_empty() || href.starts_with('#') || href.starts_with("javascript:") {
+        out.push_str(&text);
+    } else if text.trim().is_empty() || text.trim() == escape_markdown(href) {
+        out.push_str(&format!("<{}>", href));
+    } else {
+        let (leading, core, trailing) = split_spaces(&text);
+        out.push_str(&format!(
+            "{leading}[{}]({}){trailing}",
+            core,
+            href.replace(' ', "%20").replace(')', "%29")
+        ));
+    }
+}
+
+/// `![alt](src "title")`. Inline `data:` images are too large to be useful in
+/// text and become their alt text.
+fn image(out: &mut String, node: &Node) {
+    let alt = escape_markdown(node.attr("alt").unwrap_or("").trim());
+    let src = node.attr("src").unwrap_or("").trim();
+
+    if src.is_empty() || src.starts_with("data:") {
+        out.push_str(&alt);
+        return;
+    }
+
+    let src = src.replace(' ', "%20").replace(')', "%29");
+    match node.attr("title").map(str::trim).filter(|t| !t.is_empty()) {
+        Some(title) => out.push_str(&format!(
+            "![{}]({} \"{}\")",
+            alt,
+            src,
+            title.replace('"', "\\\"")
+        )),
+        None => out.push_str(&format!("![{}]({})", alt, src)),
+    }
+}
+
+/// `**text**` with surrounding spaces kept outside the markers (`** a**` is
+/// not bold in Markdown).
+fn wrap_inline(out: &mut String, marker: &str, text: &str) {
+    let (leading, core, trailing) = split_spaces(text);
+    if core.is_empty() {
+        out.push_str(text);
+    } else {
+        out.push_str(&format!("{leading}{marker}{core}{marker}{trailing}"));
+    }
+}
+
+fn split_spaces(text: &str) -> (&str, &str, &str) {
+    let core = text.trim_matches(|c: char| c == ' ' || c == '\n');
+    let start = text.find(core).unwrap_or(0);
+    (&text[..start], core, &text[start + core.len()..])
+}
+
+/// Appends text with HTML whitespace rules: any run of whitespace is one space.
+fn push_collapsed(out: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() {
+            if !out.is_empty() && !out.ends_with([' ', '\n', '\t']) {
+                out.push(' ');
+            }
+        } else {
+            out.push(c);
+        }
+    }
+}
+
+fn trim_trailing_spaces(out: &mut String) {
+    while out.ends_with(' ') {
+        out.pop();
+    }
+}
+
+/// Escapes characters that would otherwise start Markdown formatting.
+/// Underscores inside words are left alone (GFM ignores them).
+fn escape_markdown(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        let escape = match c {
+            '\\' | '*' | '`' | '[' | ']' => true,
+            '_' => {
+                let before = i.checked_sub(1).map(|j| chars[j]);
+                let after = chars.get(i + 1).copied();
+                !(before.is_some_and(char::is_alphanumeric)
+                    && after.is_some_and(char::is_alphanumeric))
+            }
+            _ => false,
+        };
+        if escape {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+
+    out
+}
+
+/// Raw text of nodes (whitespace kept), for code.
+fn text_content(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Element { name, .. } if name == "br" => out.push('\n'),
+            Node::Element { children, .. } => out.push_str(&text_content(children)),
+        }
+    }
+    out
+}
+
+/// Fenced code block for a `<pre>`, tagged with its language if the markup
+/// names one (on the `<pre>`, its `<code>`, or passed in from a wrapper).
+fn code_block(pre: &Node, wrapper_language: Option<&str>) -> String {
+    let Node::Element { children, .. } = pre else {
+        return String::new();
+    };
+
+    let code_child = children
+        .iter()
+        .find(|child| matches!(child, Node::Element { name, .. } if name == "code"));
+    let language = code_language(pre)
+        .or_else(|| code_child.and_then(code_language))
+        .or_else(|| wrapper_language.map(str::to_string))
+        .unwrap_or_default();
+
+    let code = text_content(children);
+    let code = code.strip_prefix('\n').unwrap_or(&code).trim_end();
+    let fence = if code.contains("```") { "~~~" } else { "```" };
+    format!("{fence}{language}\n{code}\n{fence}")
+}
+
+fn code_language(node: &Node) -> Option<String> {
+    class_language(node).or_else(|| node.attr("data-lang").and_then(language_name))
+}
+
+/// Language from the class names highlighters use: `language-rust` (Prism,
+/// highlight.js, CommonMark renderers), `lang-rust`, `highlight-source-rust`
+/// (GitHub).
+fn class_language(node: &Node) -> Option<String> {
+    node.attr("class")?.split_whitespace().find_map(|class| {
+        ["language-", "lang-", "highlight-source-"]
+            .iter()
+            .find_map(|prefix| class.strip_prefix(prefix))
+            .and_then(language_name)
+    })
+}
+
+fn language_name(name: &str) -> Option<String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '#' | '-' | '_' | '.'));
+    valid.then(|| name.to_ascii_lowercase())
+}
+
+fn quote(text: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                ">".to_string()
+            } else {
+                format!("> {}", line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn list(node: &Node) -> Option<String> {
+    let Node::Element { name, children, .. } = node else {
+        return None;
+    };
+    let ordered = name == "ol";
+    let mut number: usize = node
+        .attr("start")
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(1);
+
+    let items: Vec<String> = children
+        .iter()
+        .filter_map(|child| match child {
+            Node::Element { name, children, .. } if name == "li" => {
+                let marker = if ordered {
+                    format!("{}. ", number)
+                } else {
+                    "- ".to_string()
+                };
+                number += 1;
+                list_item(&marker, children)
+            }
+            // Lists directly inside lists (invalid but common)
+            Node::Element { name, .. } if name == "ul" || name == "ol" => {
+                list(child).map(|nested| indent(&nested, 2))
+            }
+            _ => None,
+        })
+        .collect();
+
+    (!items.is_empty()).then(|| items.join("\n"))
+}
+
+/// One list item: marker on the first line, continuation lines indented to
+/// line up with the text (which is how nested lists attach to their parent).
+fn list_item(marker: &str, children: &[Node]) -> Option<String> {
+    let body = blocks(children).join("\n");
+    let indented = indent(&body, marker.len());
+    Some(format!("{}{}", marker, indented.trim_start()))
+}
+
+fn indent(text: &str, width: usize) -> String {
+    let pad = " ".repeat(width);
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", pad, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Column alignment from `align="center"` or `style="text-align: center"`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+struct Cell {
+    text: String,
+    span: usize,
+    align: Option<Align>,
+}
+
+/// GFM table; the first row becomes the header row and sets the column
+/// alignment. Cells spanning several columns are padded with empty cells.
+fn table(node: &Node) -> Option<String> {
+    let mut rows: Vec<Vec<Cell>> = Vec::new();
+    collect_rows(node, &mut rows);
+
+    let columns = rows
+        .iter()
+        .map(|row| row.iter().map(|cell| cell.span).sum::<usize>())
+        .max()
+        .unwrap_or(0);
+    if columns == 0 {
+        return None;
+    }
+
+    let render_row = |cells: &[Cell]| {
+        let mut texts: Vec<&str> = Vec::with_capacity(columns);
+        for cell in cells {
+            texts.push(&cell.text);
+            texts.extend(std::iter::repeat_n("", cell.span - 1));
+        }
+        texts.resize(columns, "");
+        format!("| {} |", texts.join(" | "))
+    };
+
+    let mut aligns: Vec<Option<Align>> = Vec::with_capacity(columns);
+    for cell in &rows[0] {
+        aligns.extend(std::iter::repeat_n(cell.align, cell.span));
+    }
+    aligns.resize(columns, None);
+    let separator: String = aligns
+        .iter()
+        .map(|align| match align {
+            None => " --- |",
+            Some(Align::Left) => " :--- |",
+            Some(Align::Center) => " :---: |",
+            Some(Align::Right) => " ---: |",
+        })
+        .collect();
+
+    let mut lines = vec![render_row(&rows[0]), format!("|{}", separator)];
+    lines.extend(rows[1..].iter().map(|row| render_row(row)));
+    Some(lines.join("\n"))
+}
+
+fn collect_rows(node: &Node, rows: &mut Vec<Vec<Cell>>) {
+    let Node::Element { children, .. } = node else {
+        return;
+    };
+
+    for child in children {
+        match child {
+            Node::Element { name, children, .. } if name == "tr" => {
+                let cells = children
+                    .iter()
+                    .filter_map(|cell| match cell {
+                        Node::Element { name, children, .. } if name == "td" || name == "th" => {
+                            Some(Cell {
+                                text: table_cell(children),
+                                span: cell
+                                    .attr("colspan")
+                                    .and_then(|span| span.trim().parse().ok())
+                                    .unwrap_or(1usize)
+                                    .clamp(1, 100),
+                                align: cell_align(cell),
+                            })
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                rows.push(cells);
+            }
+            // Nested tables are flattened into their cell's text, not rows
+            Node::Element { name, .. } if name != "table" => collect_rows(child, rows),
+            _ => {}
+        }
+    }
+}
+
+fn cell_align(cell: &Node) -> Option<Align> {
+    let style_align = cell.attr("style").and_then(|style| {
+        style.split(';').find_map(|declaration| {
+            let (property, value) = declaration.split_once(':')?;
+            (property.trim().eq_ignore_ascii_case("text-align")).then(|| value.trim())
+        })
+    });
+
+    match style_align
+        .or(cell.attr("align"))?
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "left" | "start" => Some(Align::Left),
+        "center" => Some(Align::Center),
+        "right" | "end" => Some(Align::Right),
+        _ => None,
+    }
+}
+
+fn table_cell(children: &[Node]) -> String {
+    paragraph(children)
+        .unwrap_or_default()
+        .replace("  \n", " ")
+        .replace('\n', " ")
+        .replace('|', "\\|")
+}
+
+// ============================================================================
+// Plain text
+// ============================================================================
+
+/// Renders nodes as plain text, roughly like a browser's `innerText`:
+/// paragraphs, headings, code blocks, quotes and tables are separated by a
+/// blank line, other blocks (list items, divs, table rows) by a line break.
+fn plain(nodes: &[Node], out: &mut String) {
+    for node in nodes {
+        let (name, children) = match node {
+            Node::Text(text) => {
+                push_collapsed(out, text);
+                continue;
+            }
+            Node::Element { name, children, .. } => (name.as_str(), children),
+        };
+        let newlines = match name {
+            "br" => {
+                push_newlines(out, 1);
+                continue;
+            }
+            "td" | "th" => {
+                trim_trailing_spaces(out);
+                if !out.is_empty() && !out.ends_with('\n') {
+                    out.push('\t');
+                }
+                plain(children, out);
+                trim_trailing_spaces(out);
+                continue;
+            }
+            "pre" => {
+                push_newlines(out, 2);
+                out.push_str(text_content(children).trim_matches('\n'));
+                push_newlines(out, 2);
+                continue;
+            }
+            "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" | "table" | "hr" => 2,
+            "tr" => 1,
+            _ if BLOCK_ELEMENTS.contains(&name) => 1,
+            _ => 0,
+        };
+        push_newlines(out, newlines);
+        plain(children, out);
+        push_newlines(out, newlines);
+    }
+}
+
+/// Ends the text with (at least) `count` line breaks, except at the start.
+fn push_newlines(out: &mut String, count: usize) {
+    if count == 0 {
+        return;
+    }
+    trim_trailing_spaces(out);
+    if out.is_empty() {
+        return;
+    }
+    let have = out.len() - out.trim_end_matches('\n').len();
+    for _ in have..count {
+        out.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inline_formatting_and_links() {
+        let html = r#"<p>Read <b>the</b> <a href="https://example.com/docs?a=1&amp;b=2">docs</a>,
+            then run <code>cargo test</code>.<br>Done <em>soon </em>&amp; snake_case *ok*</p>"#;
+        assert_eq!(
+            html_to_markdown(html),
+            "Read **the** [docs](https://example.com/docs?a=1&b=2), then run `cargo test`.  \n\
+             Done *soon* & snake_case \\*ok\\*"
+        );
+    }
+
+    #[test]
+    fn test_office_fragment() {
+        let html = "Version:0.9\r\nStartHTML:0000000105\r\nEndHTML:0000000300\r\n\
+            <html><head><style>p { margin: 0 }</style></head><body>\
+            <!--StartFragment--><h2>Q3 Report</h2><p class=MsoNormal>Revenue grew<o:p></o:p>\
+            <p>Costs fell<!--EndFragment--></body></html>";
+        assert_eq!(
+            html_to_markdown(html),
+            "## Q3 Report\n\nRevenue grew\n\nCosts fell"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let html = "<ol start=3><li>First<li>Second<ul><li>Nested</li></ul></li></ol>\
+            <ul><li><p>Para item</p></li></ul>";
+        assert_eq!(
+            html_to_markdown(html),
+            "3. First\n4. Second\n   - Nested\n\n- Para item"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let html = "<table><thead><tr><th>Name<th>Link</tr></thead>\
+            <tbody><tr><td>Alpha | Beta<td><a href='https://a.example'>site</a>\
+            <tr><td>Gamma</table>";
+        assert_eq!(
+            html_to_markdown(html),
+            "| Name | Link |\n| --- | --- |\n| Alpha \\| Beta | [site](https://a.example) |\n| Gamma |  |"
+        );
+    }
+
+    #[test]
+    fn test_code_block_and_quote() {
+        let html = "<pre><code>fn main() {\n    println!(\"&lt;hi&gt;\");\n}</code></pre>\
+            <blockquote><p>Quoted</p><p>Twice</p></blockquote>";
+        assert_eq!(
+            html_to_markdown(html),
+            "```\nfn main() {\n    println!(\"<hi>\");\n}\n```\n\n> Quoted\n>\n> Twice"
+        );
+    }
+
+    #[test]
+    fn test_code_languages() {
+        let html = r#"<pre><code class="hljs language-Rust">let x = 1;</code></pre>
+            <div class="highlight highlight-source-python notranslate"><pre>print("hi")</pre>
+            <div class="zeroclipboard-container"></div></div>
+            <pre data-lang="sql">SELECT 1;</pre>"#;
+        assert_eq!(
+            html_to_markdown(html),
+            "```rust\nlet x = 1;\n```\n\n```python\nprint(\"hi\")\n```\n\n```sql\nSELECT 1;\n```"
+        );
+    }
+
+    #[test]
+    fn test_images() {
+        let html = r#"<p><a href="https://example.com"><img src="https://example.com/logo 1.png" alt="Logo"></a>
+            <img src="diagram.svg" alt="[Flow]" title="The &quot;flow&quot;">
+            <img src="data:image/png;base64,AAAA" alt="inline"></p>"#;
+        assert_eq!(
+            html_to_markdown(html),
+            "[![Logo](https://example.com/logo%201.png)](https://example.com) \
+             ![\\[Flow\\]](diagram.svg \"The \\\"flow\\\"\") inline"
+        );
+    }
+
+    #[test]
+    fn test_table_alignment_and_colspan() {
+        let html = r#"<table><tr><th align="left">Item<th style="text-align: right">Qty<th>Note
+            <tr><td colspan="2">Total<td>ok</table>"#;
+        assert_eq!(
+            html_to_markdown(html),
+            "| Item | Qty | Note |\n| :--- | ---: | --- |\n| Total |  | ok |"
+        );
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "Version:0.9\r\nStartHTML:0000000105\r\n<html><body>\
+            <h2>Q3 Report</h2><p>Revenue <b>grew</b> by *5%*<br>see <a href='https://x.example'>site</a>\
+            <ul><li>First<li>Second</ul>\
+            <table><tr><th>Name<th>Value<tr><td>a &amp; b<td> 1 </table>\
+            <pre>fn main() {\n    run();\n}</pre></body></html>";
+        assert_eq!(
+            html_to_text(html),
+            "Q3 Report\n\nRevenue grew by *5%*\nsee site\n\nFirst\nSecond\n\n\
+             Name\tValue\na & b\t1\n\nfn main() {\n    run();\n}"
+        );
+    }
+}
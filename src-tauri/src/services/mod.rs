@@ -1,11 +1,31 @@
 // Business logic services
 pub mod ai;
+pub mod backfill;
 pub mod clipboard;
 pub mod clipboard_monitor;
 pub mod clipboard_platform;
 pub mod clipboard_platform_linux;
 pub mod clipboard_provider_trait;
+pub mod clustering;
+pub mod detectors;
+pub mod edit;
+pub mod embedding_backend;
+pub mod entities;
+pub mod external_editor;
+pub mod file_watch;
+pub mod fuzzy;
+pub mod image_hash;
 pub mod intelligence;
+pub mod markdown;
 pub mod ocr;
 pub mod paste;
+pub mod paste_format;
+pub mod paste_stack;
+pub mod prompt_template;
+pub mod rtf;
+pub mod scripting;
 pub mod semantic;
+pub mod shortcuts;
+pub mod similarity;
+pub mod transform;
+pub mod tray;
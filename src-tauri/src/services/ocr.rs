@@ -1,13 +1,382 @@
 // OCR service for image text extraction
+//
+// Runs a locally installed Tesseract (https://github.com/tesseract-ocr/tesseract)
+// and parses its TSV output, which gives a bounding box per recognised word.
 
-#[allow(dead_code)]
-pub struct OcrService;
+use crate::models::ClipItem;
+use crate::repositories::ClipRepository;
+use crate::services::entities;
+use crate::services::intelligence::{self, IntelligenceService};
+use crate::services::semantic::SemanticService;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Metadata key holding the recognised lines of an image clip.
+pub const METADATA_KEY: &str = "ocr";
+
+/// Where installers put tesseract, for GUI launches that don't get the shell's
+/// PATH (macOS apps started from Finder, for instance).
+const TESSERACT_LOCATIONS: &[&str] = &[
+    "/opt/homebrew/bin/tesseract",
+    "/usr/local/bin/tesseract",
+    "/usr/bin/tesseract",
+    r"C:\Program Files\Tesseract-OCR\tesseract.exe",
+];
+
+/// How long tesseract may take on one image before it is killed.
+const TESSERACT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// TSV row level of a single word.
+const WORD_LEVEL: &str = "5";
+
+/// A recognised line of text and its bounding box, in image pixels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrLine {
+    pub text: String,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Mean word confidence, 0-100
+    pub confidence: f32,
+}
+
+/// Text of an image: lines joined by newlines, paragraphs by blank lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OcrResult {
+    pub text: String,
+    pub lines: Vec<OcrLine>,
+}
+
+/// Recognises the text of image clips (and the PNG rendering of Office clips).
+pub struct OcrService {
+    repository: Arc<ClipRepository>,
+    semantic_service: Arc<SemanticService>,
+}
 
-#[allow(dead_code)]
 impl OcrService {
-    pub fn new() -> Self {
-        Self
+    pub fn new(repository: Arc<ClipRepository>, semantic_service: Arc<SemanticService>) -> Self {
+        Self {
+            repository,
+            semantic_service,
+        }
+    }
+
+    /// Whether a tesseract binary was found.
+    pub fn is_available(&self) -> bool {
+        find_tesseract().is_some()
+    }
+
+    /// Whether the clip has an image OCR can read.
+    pub fn applies_to(clip: &ClipItem) -> bool {
+        ocr_image(clip).is_some()
+    }
+
+    /// Recognises the clip's image and saves the lines in its metadata. The
+    /// text becomes the clip's `content_text` (searchable, embedded) for image
+    /// clips, and for Office clips without text of their own.
+    pub async fn process(&self, clip_id: &str) -> Result<ClipItem> {
+        let clip = self.get_clip(clip_id).await?;
+        let image_path = ocr_image(&clip).ok_or_else(|| anyhow!("Clip has no image to read"))?;
+        let result = self.recognize(Path::new(image_path)).await?;
+        self.save_result(clip, result).await
+    }
+
+    /// Stores what `recognize` read from the clip's image.
+    async fn save_result(&self, clip: ClipItem, result: OcrResult) -> Result<ClipItem> {
+        let mut metadata = clip
+            .metadata
+            .as_deref()
+            .and_then(|m| serde_json::from_str::<Value>(m).ok())
+            .filter(Value::is_object)
+            .unwrap_or_else(|| json!({}));
+        metadata[METADATA_KEY] = json!({ "engine": "tesseract", "lines": result.lines });
+
+        let has_own_text = clip.content_type != "image"
+            && clip
+                .content_text
+                .as_deref()
+                .is_some_and(|text| !text.trim().is_empty());
+        let content_text = Some(result.text).filter(|text| !text.is_empty() && !has_own_text);
+
+        self.repository
+            .set_recognized_text(&clip.id, content_text.as_deref(), &metadata.to_string())
+            .await?;
+        let clip = self.get_clip(&clip.id).await?;
+
+        if let Some(text) = &content_text {
+            // Secondary types and entities follow the recognised text, as for
+            // edits. A secret in a screenshot keeps its entities out of listings.
+            let detections = IntelligenceService::detect_all(text);
+            self.repository
+                .set_detected_types(
+                    &clip.id,
+                    &intelligence::secondary_types(&detections, &clip.detected_type),
+                )
+                .await?;
+            let entities = if detections[0].detected_type_str() == "secret" {
+                Vec::new()
+            } else {
+                entities::extract_clip(&clip)
+            };
+            self.repository.set_entities(&clip.id, &entities).await?;
+
+            self.semantic_service
+                .spawn_clip_embedding(self.repository.clone(), &clip);
+        }
+        Ok(clip)
+    }
+
+    /// Runs tesseract on an image file.
+    pub async fn recognize(&self, image_path: &Path) -> Result<OcrResult> {
+        let tesseract = find_tesseract().ok_or_else(|| {
+            anyhow!("Tesseract is not installed (https://tesseract-ocr.github.io)")
+        })?;
+
+        run_tesseract(&tesseract, image_path, TESSERACT_TIMEOUT).await
+    }
+
+    async fn get_clip(&self, clip_id: &str) -> Result<ClipItem> {
+        self.repository
+            .get_by_id(clip_id)
+            .await?
+            .ok_or_else(|| anyhow!("Clip not found"))
+    }
+}
+
+/// The image file OCR reads for a clip.
+fn ocr_image(clip: &ClipItem) -> Option<&str> {
+    let path = clip.image_path.as_deref()?;
+    match clip.content_type.as_str() {
+        "image" => Some(path),
+        "office" if path.to_ascii_lowercase().ends_with(".png") => Some(path),
+        _ => None,
+    }
+}
+
+/// Runs `tesseract` on an image, killing it if it hasn't finished in `timeout`.
+async fn run_tesseract(
+    tesseract: &Path,
+    image_path: &Path,
+    timeout: Duration,
+) -> Result<OcrResult> {
+    let mut command = tokio::process::Command::new(tesseract);
+    command
+        .arg(image_path)
+        .args(["stdout", "tsv"])
+        .kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    {
+        // CREATE_NO_WINDOW: no console flashing up for each image
+        command.creation_flags(0x0800_0000);
     }
 
-    // TODO: Implement OCR functionality
+    // Dropping the timed-out `output()` future drops the child, which kills it
+    let output = tokio::time::timeout(timeout, command.output())
+        .await
+        .map_err(|_| anyhow!("Tesseract took longer than {}s", timeout.as_secs()))?
+        .context("Failed to run tesseract")?;
+    if !output.status.success() {
+        bail!(
+            "Tesseract failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(parse_tsv(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn find_tesseract() -> Option<PathBuf> {
+    let binary = format!("tesseract{}", std::env::consts::EXE_SUFFIX);
+    std::env::var_os("PATH")
+        .into_iter()
+        .flat_map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .map(|dir| dir.join(&binary))
+        .chain(TESSERACT_LOCATIONS.iter().map(PathBuf::from))
+        .find(|path| path.is_file())
+}
+
+/// Groups the words of tesseract's TSV output into lines.
+///
+/// Columns: level, page_num, block_num, par_num, line_num, word_num, left,
+/// top, width, height, conf, text.
+fn parse_tsv(tsv: &str) -> OcrResult {
+    struct Line {
+        paragraph: (u32, u32, u32),
+        words: Vec<String>,
+        confidences: Vec<f32>,
+        bounds: (u32, u32, u32, u32), // left, top, right, bottom
+    }
+
+    let mut lines: Vec<Line> = Vec::new();
+    let mut current_key = None;
+
+    for row in tsv.lines().skip(1) {
+        let cols: Vec<&str> = row.splitn(12, '\t').collect();
+        if cols.len() < 12 || cols[0] != WORD_LEVEL || cols[11].trim().is_empty() {
+            continue;
+        }
+        let num = |i: usize| cols[i].parse::<u32>().unwrap_or(0);
+        let (left, top) = (num(6), num(7));
+        let (right, bottom) = (left + num(8), top + num(9));
+        let key = (num(1), num(2), num(3), num(4));
+
+        if current_key != Some(key) {
+            current_key = Some(key);
+            lines.push(Line {
+                paragraph: (key.0, key.1, key.2),
+                words: Vec::new(),
+                confidences: Vec::new(),
+                bounds: (left, top, right, bottom),
+            });
+        }
+        let line = lines.last_mut().expect("line pushed above");
+        line.words.push(cols[11].trim().to_string());
+        if let Ok(conf) = cols[10].parse::<f32>() {
+            if conf >= 0.0 {
+                line.confidences.push(conf);
+            }
+        }
+        let b = &mut line.bounds;
+        *b = (b.0.min(left), b.1.min(top), b.2.max(right), b.3.max(bottom));
+    }
+
+    let mut result = OcrResult::default();
+    let mut last_paragraph = None;
+    for line in lines {
+        let text = line.words.join(" ");
+        if let Some(paragraph) = last_paragraph {
+            result.text.push_str(if paragraph == line.paragraph {
+                "\n"
+            } else {
+                "\n\n"
+            });
+        }
+        result.text.push_str(&text);
+        last_paragraph = Some(line.paragraph);
+
+        let (left, top, right, bottom) = line.bounds;
+        let confidence = if line.confidences.is_empty() {
+            0.0
+        } else {
+            line.confidences.iter().sum::<f32>() / line.confidences.len() as f32
+        };
+        result.lines.push(OcrLine {
+            text,
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+            confidence,
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TSV: &str = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t640\t480\t-1\t
+2\t1\t1\t0\t0\t0\t36\t92\t582\t120\t-1\t
+3\t1\t1\t1\t0\t0\t36\t92\t582\t48\t-1\t
+4\t1\t1\t1\t1\t0\t36\t92\t210\t22\t-1\t
+5\t1\t1\t1\t1\t1\t36\t92\t90\t20\t96\tMeeting
+5\t1\t1\t1\t1\t2\t136\t94\t110\t20\t90\tnotes:
+5\t1\t1\t1\t1\t3\t250\t94\t4\t20\t95\t
+4\t1\t1\t1\t2\t0\t36\t120\t300\t20\t-1\t
+5\t1\t1\t1\t2\t1\t36\t120\t300\t20\t80\tQ3
+3\t1\t1\t2\t0\t0\t36\t180\t582\t32\t-1\t
+5\t1\t1\t2\t1\t1\t40\t180\t60\t32\t70\tDone
+";
+
+    #[test]
+    fn test_parse_tsv() {
+        let result = parse_tsv(TSV);
+        assert_eq!(result.text, "Meeting notes:\nQ3\n\nDone");
+        assert_eq!(
+            result.lines[0],
+            OcrLine {
+                text: "Meeting notes:".to_string(),
+                x: 36,
+                y: 92,
+                width: 210,
+                height: 22,
+                confidence: 93.0,
+            }
+        );
+        assert_eq!(result.lines.len(), 3);
+        assert_eq!(result.lines[2].confidence, 70.0);
+
+        assert_eq!(parse_tsv(""), OcrResult::default());
+    }
+
+    #[test]
+    fn test_ocr_image() {
+        let mut clip = ClipItem::from_text(String::new(), "image".to_string(), None);
+        clip.content_type = "image".to_string();
+        clip.image_path = Some("/data/images/1.jpg".to_string());
+        assert!(OcrService::applies_to(&clip));
+
+        clip.content_type = "office".to_string();
+        assert!(!OcrService::applies_to(&clip));
+        clip.image_path = Some("/data/images/1.PNG".to_string());
+        assert!(OcrService::applies_to(&clip));
+
+        clip.content_type = "text".to_string();
+        assert!(!OcrService::applies_to(&clip));
+    }
+
+    #[tokio::test]
+    async fn test_save_result_detects_text() {
+        let (repository, dir) = ClipRepository::open_temp().await;
+        let repository = Arc::new(repository);
+        let semantic_service = Arc::new(SemanticService::new(dir.path().to_path_buf()));
+        let service = OcrService::new(repository.clone(), semantic_service);
+
+        let mut clip = ClipItem::from_text(String::new(), "image".to_string(), None);
+        clip.content_type = "image".to_string();
+        clip.content_text = None;
+        clip.image_path = Some("/data/images/1.png".to_string());
+        repository.insert(&clip).await.unwrap();
+
+        let result = OcrResult {
+            text: "Mail ann@example.com".to_string(),
+            lines: Vec::new(),
+        };
+        let clip = service.save_result(clip, result).await.unwrap();
+        assert_eq!(clip.content_text.as_deref(), Some("Mail ann@example.com"));
+
+        let types = repository.get_detected_types(&clip.id).await.unwrap();
+        assert!(types.iter().any(|t| t.detected_type == "email"));
+        let emails = repository
+            .get_entities(&clip.id, Some("email"))
+            .await
+            .unwrap();
+        assert_eq!(emails.len(), 1);
+        assert_eq!(emails[0].value, "ann@example.com");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_tesseract_timeout() {
+        // `sh` runs the "image" as a script. Executing a freshly written file
+        // instead can fail with ETXTBSY while a test on another thread forks.
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("slow.sh");
+        std::fs::write(&script, "sleep 30\n").unwrap();
+
+        let started = std::time::Instant::now();
+        let err = run_tesseract(Path::new("/bin/sh"), &script, Duration::from_millis(200))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("took longer"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
 }
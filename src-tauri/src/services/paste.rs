@@ -10,7 +10,8 @@
 #[cfg(target_os = "windows")]
 mod platform {
     use windows::Win32::UI::Input::KeyboardAndMouse::{
-        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_KEYUP, VK_CONTROL, VK_V,
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        VIRTUAL_KEY, VK_CONTROL, VK_TAB, VK_V,
     };
 
     /// Simulate Ctrl+V keystroke
@@ -78,6 +79,37 @@ mod platform {
 
         Ok(())
     }
+
+    /// Simulate a Tab keystroke
+    pub fn simulate_tab() -> anyhow::Result<()> {
+        let inputs = [
+            key_input(VK_TAB, Default::default()),
+            key_input(VK_TAB, KEYEVENTF_KEYUP),
+        ];
+
+        let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+
+        if sent != 2 {
+            anyhow::bail!("SendInput failed: only {sent}/2 inputs sent");
+        }
+
+        Ok(())
+    }
+
+    fn key_input(key: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS) -> INPUT {
+        INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: key,
+                    wScan: 0,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        }
+    }
 }
 
 // =============================================================================
@@ -88,8 +120,9 @@ mod platform {
     use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation, CGKeyCode};
     use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
 
-    // Virtual key code for 'V' on macOS
+    // Virtual key codes on macOS
     const KV_V: CGKeyCode = 9;
+    const KV_TAB: CGKeyCode = 48;
 
     /// Simulate ⌘V keystroke
     pub fn simulate_paste() -> anyhow::Result<()> {
@@ -110,6 +143,20 @@ mod platform {
 
         Ok(())
     }
+
+    /// Simulate a Tab keystroke
+    pub fn simulate_tab() -> anyhow::Result<()> {
+        let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+            .map_err(|_| anyhow::anyhow!("Failed to create CGEventSource"))?;
+
+        for key_down in [true, false] {
+            let event = CGEvent::new_keyboard_event(source.clone(), KV_TAB, key_down)
+                .map_err(|_| anyhow::anyhow!("Failed to create key event"))?;
+            event.post(CGEventTapLocation::HID);
+        }
+
+        Ok(())
+    }
 }
 
 // =============================================================================
@@ -120,6 +167,10 @@ mod platform {
     pub fn simulate_paste() -> anyhow::Result<()> {
         anyhow::bail!("Quick paste not supported on this platform")
     }
+
+    pub fn simulate_tab() -> anyhow::Result<()> {
+        anyhow::bail!("Key simulation not supported on this platform")
+    }
 }
 
 // =============================================================================
@@ -132,3 +183,8 @@ mod platform {
 pub fn simulate_paste() -> anyhow::Result<()> {
     platform::simulate_paste()
 }
+
+/// Simulate a Tab keystroke, e.g. to move to the next form field.
+pub fn simulate_tab() -> anyhow::Result<()> {
+    platform::simulate_tab()
+}
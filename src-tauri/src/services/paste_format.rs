@@ -0,0 +1,167 @@
+use crate::models::clip::ClipItem;
+use crate::models::settings::PasteFormat;
+use crate::services::clipboard_platform::ClipboardContent;
+use crate::services::markdown::{html_to_markdown, html_to_text};
+use crate::services::rtf::rtf_to_html;
+
+/// Converts a clip to the clipboard content for `format`.
+///
+/// Returns `None` when the clip should be written as captured: `Auto`, or a
+/// clip with no text form (images, files).
+pub fn render(clip: &ClipItem, format: PasteFormat) -> Option<ClipboardContent> {
+    if !matches!(
+        clip.content_type.as_str(),
+        "text" | "html" | "rtf" | "office"
+    ) {
+        return None;
+    }
+
+    let text = clip.content_text.clone().unwrap_or_default();
+    let html = || {
+        clip.content_html
+            .clone()
+            .or_else(|| clip.content_rtf.as_deref().map(rtf_to_html))
+    };
+
+    match format {
+        PasteFormat::Auto => None,
+        PasteFormat::Plain => Some(ClipboardContent::Text {
+            content: plain_text(clip, text),
+        }),
+        PasteFormat::Html => Some(ClipboardContent::Html {
+            html: html().unwrap_or_else(|| text_to_html(&text)),
+            plain: plain_text(clip, text),
+        }),
+        PasteFormat::Markdown => Some(ClipboardContent::Text {
+            content: clip
+                .content_markdown
+                .clone()
+                .or_else(|| html().map(|html| html_to_markdown(&html)))
+                .filter(|markdown| !markdown.is_empty())
+                .unwrap_or(text),
+        }),
+    }
+}
+
+/// The clip's text without Markdown syntax. HTML captured without a plain
+/// text flavor stores its Markdown as text, so that is rendered from the HTML
+/// again as plain text.
+fn plain_text(clip: &ClipItem, text: String) -> String {
+    match clip.content_html.as_deref() {
+        Some(html) if html_to_markdown(html) == text => html_to_text(html),
+        _ => text,
+    }
+}
+
+/// Wraps plain text as HTML: blank lines separate paragraphs, single line
+/// breaks become `<br>`.
+pub fn text_to_html(text: &str) -> String {
+    let escaped = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace("\r\n", "\n");
+
+    escaped
+        .split("\n\n")
+        .filter(|paragraph| !paragraph.trim().is_empty())
+        .map(|paragraph| {
+            format!(
+                "<p>{}</p>",
+                paragraph.trim_matches('\n').replace('\n', "<br>")
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clip(content_type: &str, text: &str, html: Option<&str>, rtf: Option<&str>) -> ClipItem {
+        let mut clip = ClipItem::from_text(text.to_string(), "text".to_string(), None);
+        clip.content_type = content_type.to_string();
+        clip.content_html = html.map(str::to_string);
+        clip.content_rtf = rtf.map(str::to_string);
+        clip
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let html = clip(
+            "html",
+            "Docs",
+            Some(r#"<p><b>Docs</b> at <a href="https://example.com">example</a></p>"#),
+            None,
+        );
+        assert_eq!(render(&html, PasteFormat::Auto), None);
+        assert_eq!(
+            render(&html, PasteFormat::Plain),
+            Some(ClipboardContent::Text {
+                content: "Docs".to_string()
+            })
+        );
+        assert_eq!(
+            render(&html, PasteFormat::Markdown),
+            Some(ClipboardContent::Text {
+                content: "**Docs** at [example](https://example.com)".to_string()
+            })
+        );
+
+        let rtf = clip("rtf", "Bold", None, Some(r"{\rtf1 {\b Bold}\par}"));
+        assert_eq!(
+            render(&rtf, PasteFormat::Html),
+            Some(ClipboardContent::Html {
+                html: "<p><b>Bold</b></p>".to_string(),
+                plain: "Bold".to_string()
+            })
+        );
+
+        let text = clip("text", "a < b\nc\n\nd", None, None);
+        assert_eq!(
+            render(&text, PasteFormat::Html),
+            Some(ClipboardContent::Html {
+                html: "<p>a &lt; b<br>c</p><p>d</p>".to_string(),
+                plain: "a < b\nc\n\nd".to_string()
+            })
+        );
+        assert_eq!(
+            render(&text, PasteFormat::Markdown),
+            Some(ClipboardContent::Text {
+                content: "a < b\nc\n\nd".to_string()
+            })
+        );
+
+        assert_eq!(
+            render(&clip("image", "", None, None), PasteFormat::Plain),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render_html_without_plain_text() {
+        // Captured without a plain text flavor, the text is the Markdown
+        let html = "<h1>Notes</h1><ul><li><b>Ship</b> it</li><li>See <a href='https://x.example'>docs</a></li></ul>";
+        let markdown = html_to_markdown(html);
+        let clip = clip("html", &markdown, Some(html), None);
+
+        let plain = "Notes\n\nShip it\nSee docs".to_string();
+        assert_eq!(
+            render(&clip, PasteFormat::Plain),
+            Some(ClipboardContent::Text {
+                content: plain.clone()
+            })
+        );
+        assert_eq!(
+            render(&clip, PasteFormat::Html),
+            Some(ClipboardContent::Html {
+                html: html.to_string(),
+                plain
+            })
+        );
+        assert_eq!(
+            render(&clip, PasteFormat::Markdown),
+            Some(ClipboardContent::Text { content: markdown })
+        );
+    }
+}
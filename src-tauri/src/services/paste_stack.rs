@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Order in which queued clips are pasted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StackOrder {
+    /// In the order they were queued
+    #[default]
+    Fifo,
+    /// Last queued first
+    Lifo,
+}
+
+/// Clips queued for pasting one after another, e.g. into the fields of a form.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasteStack {
+    /// Clip ids in queueing order
+    clip_ids: VecDeque<String>,
+    order: StackOrder,
+    /// Press Tab after each paste that isn't the last
+    send_tab: bool,
+}
+
+impl PasteStack {
+    /// Replaces the queue.
+    pub fn start(&mut self, clip_ids: Vec<String>, order: StackOrder, send_tab: bool) {
+        self.clip_ids = clip_ids.into();
+        self.order = order;
+        self.send_tab = send_tab;
+    }
+
+    /// Queues one more clip, keeping the current order and options.
+    pub fn push(&mut self, clip_id: String) {
+        self.clip_ids.push_back(clip_id);
+    }
+
+    /// Removes and returns the clip to paste next.
+    pub fn pop_next(&mut self) -> Option<String> {
+        match self.order {
+            StackOrder::Fifo => self.clip_ids.pop_front(),
+            StackOrder::Lifo => self.clip_ids.pop_back(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.clip_ids.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clip_ids.is_empty()
+    }
+
+    pub fn send_tab(&self) -> bool {
+        self.send_tab
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_fifo_and_lifo() {
+        let mut stack = PasteStack::default();
+        stack.start(ids(&["name", "email"]), StackOrder::Fifo, true);
+        stack.push("phone".to_string());
+        assert_eq!(stack.pop_next().as_deref(), Some("name"));
+        assert_eq!(stack.pop_next().as_deref(), Some("email"));
+        assert_eq!(stack.pop_next().as_deref(), Some("phone"));
+        assert_eq!(stack.pop_next(), None);
+        assert!(stack.send_tab());
+
+        stack.start(ids(&["name", "email", "phone"]), StackOrder::Lifo, false);
+        assert_eq!(stack.pop_next().as_deref(), Some("phone"));
+        assert!(!stack.is_empty());
+        stack.clear();
+        assert_eq!(stack.pop_next(), None);
+    }
+}
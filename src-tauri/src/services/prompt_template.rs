@@ -0,0 +1,299 @@
+// User-defined AI prompts
+//
+// Templates are stored in `prompt_templates` and offered next to the built-in
+// prompts of `AiService`. They can be exported to and imported from a JSON
+// file to share them across a team.
+
+use crate::models::{NewPromptTemplate, PromptTemplate};
+use crate::repositories::ClipRepository;
+use crate::services::intelligence;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Variables a template may use, as `{{name}}`.
+pub const VARIABLES: &[&str] = &["text", "language", "app_name"];
+
+/// Version of the export format.
+const EXPORT_VERSION: u32 = 1;
+
+/// Values of the template variables for one run.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVars<'a> {
+    pub text: &'a str,
+    pub language: &'a str,
+    pub app_name: &'a str,
+}
+
+/// File written by `export` and read by `import`.
+#[derive(Debug, Serialize, Deserialize)]
+struct TemplateExport {
+    version: u32,
+    templates: Vec<NewPromptTemplate>,
+}
+
+pub struct PromptTemplateService {
+    repository: Arc<ClipRepository>,
+}
+
+impl PromptTemplateService {
+    pub fn new(repository: Arc<ClipRepository>) -> Self {
+        Self { repository }
+    }
+
+    pub async fn list(&self) -> Result<Vec<PromptTemplate>> {
+        self.repository.get_prompt_templates().await
+    }
+
+    pub async fn create(&self, template: NewPromptTemplate) -> Result<PromptTemplate> {
+        let template = validate(template)?;
+        self.ensure_name_free(&template.name, None).await?;
+        self.repository.create_prompt_template(&template).await
+    }
+
+    pub async fn update(&self, id: i64, template: NewPromptTemplate) -> Result<PromptTemplate> {
+        let template = validate(template)?;
+        self.ensure_name_free(&template.name, Some(id)).await?;
+        self.repository.update_prompt_template(id, &template).await
+    }
+
+    pub async fn delete(&self, id: i64) -> Result<()> {
+        self.repository.delete_prompt_template(id).await
+    }
+
+    /// All templates as pretty-printed JSON.
+    pub async fn export(&self) -> Result<String> {
+        let export = TemplateExport {
+            version: EXPORT_VERSION,
+            templates: self
+                .list()
+                .await?
+                .into_iter()
+                .map(NewPromptTemplate::from)
+                .collect(),
+        };
+        Ok(serde_json::to_string_pretty(&export)?)
+    }
+
+    /// Imports templates exported by `export`. A template replaces the one
+    /// with the same name. Nothing is imported if any template is invalid or
+    /// fails to save.
+    pub async fn import(&self, json: &str) -> Result<Vec<PromptTemplate>> {
+        let export: TemplateExport =
+            serde_json::from_str(json).map_err(|e| anyhow!("Invalid template file: {}", e))?;
+        if export.version > EXPORT_VERSION {
+            bail!(
+                "Template file version {} is newer than this app supports",
+                export.version
+            );
+        }
+
+        let templates = export
+            .templates
+            .into_iter()
+            .map(|template| {
+                let name = template.name.clone();
+                validate(template).map_err(|e| anyhow!("Template '{}': {}", name, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.repository.upsert_prompt_templates(&templates).await
+    }
+
+    async fn ensure_name_free(&self, name: &str, id: Option<i64>) -> Result<()> {
+        let taken = self
+            .list()
+            .await?
+            .iter()
+            .any(|t| t.name == name && Some(t.id) != id);
+        if taken {
+            bail!("A prompt template named '{}' already exists", name);
+        }
+        Ok(())
+    }
+}
+
+/// Whether a template with `content_types` is offered for clips of
+/// `detected_type`.
+pub fn applies_to(content_types: &[String], detected_type: &str) -> bool {
+    content_types.is_empty() || content_types.iter().any(|t| t == detected_type)
+}
+
+/// Fills in the template's variables. Templates without `{{text}}` get the
+/// text appended, so "Rewrite as a Jira ticket" works on its own.
+pub fn render(template: &str, vars: &TemplateVars) -> String {
+    let mut rendered = String::with_capacity(template.len() + vars.text.len());
+    let mut has_text = false;
+    let mut rest = template;
+
+    while let Some((before, name, after)) = next_variable(rest) {
+        rendered.push_str(before);
+        match name {
+            "text" => {
+                has_text = true;
+                rendered.push_str(vars.text);
+            }
+            "language" => rendered.push_str(vars.language),
+            "app_name" => rendered.push_str(vars.app_name),
+            // Unknown names are rejected on save; keep them as written
+            _ => rendered.push_str(&rest[before.len()..rest.len() - after.len()]),
+        }
+        rest = after;
+    }
+    rendered.push_str(rest);
+
+    if !has_text {
+        rendered.push_str("\n\n");
+        rendered.push_str(vars.text);
+    }
+    rendered
+}
+
+/// Splits at the next `{{name}}`: (text before it, trimmed name, text after).
+fn next_variable(s: &str) -> Option<(&str, &str, &str)> {
+    let start = s.find("{{")?;
+    let end = start + 2 + s[start + 2..].find("}}")?;
+    Some((&s[..start], s[start + 2..end].trim(), &s[end + 2..]))
+}
+
+/// Trims the fields and checks the name, variables and content types.
+pub(crate) fn validate(mut template: NewPromptTemplate) -> Result<NewPromptTemplate> {
+    template.name = template.name.trim().to_string();
+    template.description = template
+        .description
+        .map(|d| d.trim().to_string())
+        .filter(|d| !d.is_empty());
+    if template.name.is_empty() {
+        bail!("Name is required");
+    }
+    if template.template.trim().is_empty() {
+        bail!("Template is empty");
+    }
+
+    let mut rest = template.template.as_str();
+    while let Some((_, name, after)) = next_variable(rest) {
+        if !VARIABLES.contains(&name) {
+            bail!(
+                "Unknown variable {{{{{}}}}} (available: {})",
+                name,
+                VARIABLES.join(", ")
+            );
+        }
+        rest = after;
+    }
+
+    for content_type in &template.content_types {
+        if !intelligence::is_known_type(content_type) {
+            bail!("Unknown content type '{}'", content_type);
+        }
+    }
+    template.content_types.sort();
+    template.content_types.dedup();
+    Ok(template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_template(name: &str, template: &str, content_types: &[&str]) -> NewPromptTemplate {
+        NewPromptTemplate {
+            name: name.to_string(),
+            description: None,
+            template: template.to_string(),
+            content_types: content_types.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let vars = TemplateVars {
+            text: "fix bug",
+            language: "German",
+            app_name: "Slack",
+        };
+        assert_eq!(
+            render("From {{ app_name }} to {{language}}: {{text}}!", &vars),
+            "From Slack to German: fix bug!"
+        );
+        assert_eq!(
+            render("Rewrite as a Jira ticket.", &vars),
+            "Rewrite as a Jira ticket.\n\nfix bug"
+        );
+        assert_eq!(render("{{text}} {{x}} {{", &vars), "fix bug {{x}} {{");
+    }
+
+    #[test]
+    fn test_validate() {
+        let template = validate(NewPromptTemplate {
+            description: Some("  ".to_string()),
+            ..new_template(" Commit ", "Write a commit message for {{text}}", &["code"])
+        })
+        .unwrap();
+        assert_eq!(template.name, "Commit");
+        assert_eq!(template.description, None);
+
+        assert!(validate(new_template("", "{{text}}", &[])).is_err());
+        assert!(validate(new_template("a", " ", &[])).is_err());
+        let error = validate(new_template("a", "{{clip}}", &[])).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown variable {{clip}} (available: text, language, app_name)"
+        );
+        assert!(validate(new_template("a", "{{text}}", &["image"])).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_crud_and_import_export() {
+        let (repository, _dir) = ClipRepository::open_temp().await;
+        let service = PromptTemplateService::new(Arc::new(repository));
+
+        let jira = service
+            .create(new_template("Jira", "Rewrite as a Jira ticket", &[]))
+            .await
+            .unwrap();
+        let commit = service
+            .create(new_template(
+                "Commit",
+                "Commit message for {{text}}",
+                &["code"],
+            ))
+            .await
+            .unwrap();
+        assert!(service
+            .create(new_template("Jira", "{{text}}", &[]))
+            .await
+            .is_err());
+        assert!(service
+            .update(commit.id, new_template("Jira", "{{text}}", &[]))
+            .await
+            .is_err());
+        let names: Vec<_> = service
+            .list()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        assert_eq!(names, ["Commit", "Jira"]);
+
+        let exported = service.export().await.unwrap();
+        service.delete(jira.id).await.unwrap();
+        service
+            .update(commit.id, new_template("Commit", "Changed {{text}}", &[]))
+            .await
+            .unwrap();
+
+        let imported = service.import(&exported).await.unwrap();
+        assert_eq!(imported.len(), 2);
+        let templates = service.list().await.unwrap();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates[0].id, commit.id);
+        assert_eq!(templates[0].template, "Commit message for {{text}}");
+        assert_eq!(templates[0].content_types, ["code"]);
+
+        let invalid = r#"{"version":1,"templates":[{"name":"x","template":"{{y}}"}]}"#;
+        assert!(service.import(invalid).await.is_err());
+        assert!(service.import("[]").await.is_err());
+    }
+}
@@ -0,0 +1,905 @@
+//! RTF reader: a tokenizer and an interpreter that builds a small document
+//! model (paragraphs, lists, tables, character formatting), rendered as plain
+//! text for `content_text` or as HTML for pasting and Markdown conversion.
+//!
+//! Handles groups and destinations (font/color tables, `\*` and other
+//! metadata are skipped), `\'hh` bytes in the document or font code page,
+//! `\uN` escapes with their `\ucN` fallbacks and HYPERLINK fields.
+
+use encoding_rs::Encoding;
+use std::collections::HashMap;
+
+/// Destinations whose content is metadata, not document text.
+const SKIPPED_DESTINATIONS: &[&str] = &[
+    "annotation",
+    "colortbl",
+    "filetbl",
+    "footer",
+    "footerf",
+    "footerl",
+    "footerr",
+    "footnote",
+    "header",
+    "headerf",
+    "headerl",
+    "headerr",
+    "info",
+    "listoverridetable",
+    "listtable",
+    "nonshppict",
+    "object",
+    "pict",
+    "revtbl",
+    "stylesheet",
+];
+
+// ============================================================================
+// Tokenizer
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    GroupStart,
+    GroupEnd,
+    /// `\name` or `\nameN`
+    Word {
+        name: String,
+        param: Option<i32>,
+    },
+    /// `\` followed by a non-letter, e.g. `\~` or `\{`
+    Symbol(char),
+    /// `\'hh`, a byte in the current code page
+    Byte(u8),
+    Text(char),
+}
+
+fn tokenize(rtf: &str) -> Vec<Token> {
+    let chars: Vec<char> = rtf.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+
+        match c {
+            '{' => tokens.push(Token::GroupStart),
+            '}' => tokens.push(Token::GroupEnd),
+            // Line breaks in the source are formatting only
+            '\r' | '\n' => {}
+            '\\' => {
+                let Some(&next) = chars.get(i) else {
+                    break;
+                };
+
+                if next.is_ascii_alphabetic() {
+                    let start = i;
+                    while chars.get(i).is_some_and(char::is_ascii_alphabetic) {
+                        i += 1;
+                    }
+                    let name: String = chars[start..i].iter().collect();
+
+                    let param_start = i;
+                    if chars.get(i) == Some(&'-') {
+                        i += 1;
+                    }
+                    while chars.get(i).is_some_and(char::is_ascii_digit) {
+                        i += 1;
+                    }
+                    let param = chars[param_start..i]
+                        .iter()
+                        .collect::<String>()
+                        .parse()
+                        .ok();
+                    // A space delimiting the control word is part of it
+                    if chars.get(i) == Some(&' ') {
+                        i += 1;
+                    }
+
+                    if name == "bin" {
+                        // Raw binary data (only seen in pictures)
+                        let len = param.unwrap_or(0).max(0) as usize;
+                        i = i.saturating_add(len).min(chars.len());
+                        continue;
+                    }
+                    tokens.push(Token::Word { name, param });
+                } else if next == '\'' {
+                    let hex: String = chars.iter().skip(i + 1).take(2).collect();
+                    i += 1 + hex.len();
+                    if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                        tokens.push(Token::Byte(byte));
+                    }
+                } else {
+                    i += 1;
+                    tokens.push(Token::Symbol(next));
+                }
+            }
+            _ => tokens.push(Token::Text(c)),
+        }
+    }
+
+    tokens
+}
+
+// ============================================================================
+// Document model
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Format {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    strike: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    text: String,
+    format: Format,
+    link: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ListItem {
+    level: usize,
+    /// Bullet or number as written in `\listtext`, e.g. "•" or "2."
+    marker: String,
+}
+
+impl ListItem {
+    fn ordered(&self) -> bool {
+        self.marker
+            .strip_suffix(['.', ')'])
+            .is_some_and(|n| !n.is_empty() && n.chars().all(char::is_alphanumeric))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Paragraph {
+    runs: Vec<Run>,
+    list: Option<ListItem>,
+}
+
+/// Cells are lists of paragraphs.
+type Row = Vec<Vec<Paragraph>>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Block {
+    Paragraph(Paragraph),
+    Table(Vec<Row>),
+}
+
+// ============================================================================
+// Interpreter
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Destination {
+    Text,
+    Skip,
+    FontTable,
+    /// Bullet/number text of a list item (`\listtext`, `\pntext`)
+    ListText,
+    /// `\fldinst`, e.g. `HYPERLINK "https://…"`
+    FieldInstruction,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Charset {
+    Encoding(&'static Encoding),
+    /// Symbol/Wingdings fonts, only used for list bullets
+    Symbol,
+}
+
+#[derive(Debug, Clone)]
+struct GroupState {
+    destination: Destination,
+    format: Format,
+    font: Option<i32>,
+    /// Fallback characters following each `\uN` (`\ucN`)
+    unicode_fallback: usize,
+    link: Option<String>,
+}
+
+impl Default for GroupState {
+    fn default() -> Self {
+        Self {
+            destination: Destination::Text,
+            format: Format::default(),
+            font: None,
+            unicode_fallback: 1,
+            link: None,
+        }
+    }
+}
+
+struct Reader {
+    blocks: Vec<Block>,
+    paragraph: Paragraph,
+
+    // Paragraph properties, reset by \pard
+    in_table: bool,
+    list_id: Option<i32>,
+    list_level: usize,
+    /// Marker read from \listtext for the current paragraph
+    list_marker: Option<String>,
+
+    cell: Vec<Paragraph>,
+    row: Row,
+    rows: Vec<Row>,
+
+    document_encoding: &'static Encoding,
+    default_font: Option<i32>,
+    fonts: HashMap<i32, Charset>,
+    /// Font being defined inside \fonttbl
+    defining_font: Option<i32>,
+
+    /// Consecutive \'hh bytes, decoded together (multi-byte code pages)
+    bytes: Vec<u8>,
+    /// Fallback characters still to skip after a \uN
+    skip: usize,
+    high_surrogate: Option<u16>,
+    field_instruction: String,
+}
+
+fn read(rtf: &str) -> Vec<Block> {
+    let tokens = tokenize(rtf);
+    let mut reader = Reader {
+        blocks: Vec::new(),
+        paragraph: Paragraph::default(),
+        in_table: false,
+        list_id: None,
+        list_level: 0,
+        list_marker: None,
+        cell: Vec::new(),
+        row: Vec::new(),
+        rows: Vec::new(),
+        document_encoding: encoding_rs::WINDOWS_1252,
+        default_font: None,
+        fonts: HashMap::new(),
+        defining_font: None,
+        bytes: Vec::new(),
+        skip: 0,
+        high_surrogate: None,
+        field_instruction: String::new(),
+    };
+
+    let mut stack: Vec<GroupState> = Vec::new();
+    let mut group = GroupState::default();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if !matches!(token, Token::Byte(_)) {
+            reader.flush_bytes(&group);
+        }
+
+        match token {
+            Token::GroupStart => {
+                stack.push(group.clone());
+                reader.skip = 0;
+            }
+            Token::GroupEnd => {
+                if group.destination == Destination::FontTable {
+                    reader.defining_font = None;
+                }
+                group = stack.pop().unwrap_or_default();
+                reader.skip = 0;
+            }
+            Token::Symbol('*') => {
+                // Optional destination: skipped unless it is one we read
+                let known = matches!(
+                    tokens.get(i + 1),
+                    Some(Token::Word { name, .. }) if name == "fldinst"
+                );
+                if !known {
+                    group.destination = Destination::Skip;
+                }
+            }
+            Token::Symbol(c) => {
+                if reader.skip > 0 {
+                    reader.skip -= 1;
+                    continue;
+                }
+                match c {
+                    '\\' | '{' | '}' => reader.push_char(&group, *c),
+                    '~' => reader.push_char(&group, '\u{a0}'),
+                    '_' => reader.push_char(&group, '\u{2011}'),
+                    // Escaped line break (TextEdit) means \par
+                    '\n' | '\r' if group.destination == Destination::Text => reader.end_paragraph(),
+                    _ => {}
+                }
+            }
+            Token::Byte(byte) => {
+                if reader.skip > 0 {
+                    reader.skip -= 1;
+                } else {
+                    reader.bytes.push(*byte);
+                }
+            }
+            Token::Text(c) => {
+                if reader.skip > 0 {
+                    reader.skip -= 1;
+                } else if !stack.is_empty() {
+                    // Text outside the {\rtf1 ...} group is ignored
+                    reader.push_char(&group, *c);
+                }
+            }
+            Token::Word { name, param } => reader.control_word(&mut group, name, *param),
+        }
+    }
+
+    reader.flush_bytes(&group);
+    reader.finish()
+}
+
+impl Reader {
+    fn control_word(&mut self, group: &mut GroupState, name: &str, param: Option<i32>) {
+        // Destinations
+        match name {
+            "fonttbl" => {
+                group.destination = Destination::FontTable;
+                return;
+            }
+            "listtext" | "pntext" => {
+                group.destination = Destination::ListText;
+                self.list_marker = Some(String::new());
+                return;
+            }
+            "field" => self.field_instruction.clear(),
+            "fldinst" => {
+                group.destination = Destination::FieldInstruction;
+                return;
+            }
+            "fldrslt" => {
+                group.link = hyperlink_target(&self.field_instruction);
+                return;
+            }
+            _ if SKIPPED_DESTINATIONS.contains(&name) => {
+                group.destination = Destination::Skip;
+                return;
+            }
+            _ => {}
+        }
+
+        match group.destination {
+            Destination::Skip => return,
+            Destination::FontTable => {
+                match name {
+                    "f" => self.defining_font = param,
+                    "fcharset" => {
+                        if let (Some(font), Some(charset)) = (self.defining_font, param) {
+                            if let Some(charset) = charset_encoding(charset) {
+                                self.fonts.insert(font, charset);
+                            }
+                        }
+                    }
+                    "cpg" => {
+                        if let (Some(font), Some(code_page)) = (self.defining_font, param) {
+                            if let Some(encoding) = code_page_encoding(code_page) {
+                                self.fonts.insert(font, Charset::Encoding(encoding));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                return;
+            }
+            _ => {}
+        }
+
+        let on = param != Some(0);
+        match name {
+            // Document
+            "ansicpg" => {
+                if let Some(encoding) = param.and_then(code_page_encoding) {
+                    self.document_encoding = encoding;
+                }
+            }
+            "mac" => self.document_encoding = encoding_rs::MACINTOSH,
+            "deff" => self.default_font = param,
+
+            // Characters
+            "u" => {
+                if let Some(code) = param {
+                    // Negative values are UTF-16 units above 32767
+                    let unit = if code < 0 { code + 65536 } else { code } as u16;
+                    self.push_unicode(group, unit);
+                    self.skip = group.unicode_fallback;
+                }
+            }
+            "uc" => group.unicode_fallback = param.unwrap_or(1).max(0) as usize,
+            "f" => group.font = param,
+            "b" => group.format.bold = on,
+            "i" => group.format.italic = on,
+            "ul" | "uld" | "uldash" | "uldb" | "ulth" | "ulw" | "ulwave" => {
+                group.format.underline = on
+            }
+            "ulnone" => group.format.underline = false,
+            "strike" | "striked" => group.format.strike = on,
+            "plain" => group.format = Format::default(),
+            "line" => self.push_char(group, '\n'),
+            "tab" => self.push_char(group, '\t'),
+            "emdash" => self.push_char(group, '—'),
+            "endash" => self.push_char(group, '–'),
+            "bullet" => self.push_char(group, '•'),
+            "lquote" => self.push_char(group, '‘'),
+            "rquote" => self.push_char(group, '’'),
+            "ldblquote" => self.push_char(group, '“'),
+            "rdblquote" => self.push_char(group, '”'),
+            "emspace" | "enspace" | "qmspace" => self.push_char(group, ' '),
+
+            // Paragraphs (the bullet text group of a list item may reset
+            // paragraph properties that belong to the item itself)
+            "pard" if group.destination != Destination::ListText => {
+                self.in_table = false;
+                self.list_id = None;
+                self.list_level = 0;
+            }
+            "par" | "sect" if group.destination == Destination::Text => self.end_paragraph(),
+            "intbl" => self.in_table = true,
+            "ls" => self.list_id = param,
+            "ilvl" => self.list_level = param.unwrap_or(0).clamp(0, 8) as usize,
+            "cell" | "nestcell" => self.end_cell(),
+            "row" | "nestrow" => self.end_row(),
+            _ => {}
+        }
+    }
+
+    fn push_unicode(&mut self, group: &GroupState, unit: u16) {
+        match unit {
+            0xD800..=0xDBFF => self.high_surrogate = Some(unit),
+            0xDC00..=0xDFFF => {
+                if let Some(high) = self.high_surrogate.take() {
+                    for c in char::decode_utf16([high, unit]).flatten() {
+                        self.push_char(group, c);
+                    }
+                }
+            }
+            _ => {
+                self.high_surrogate = None;
+                if let Some(c) = char::from_u32(unit as u32) {
+                    self.push_char(group, c);
+                }
+            }
+        }
+    }
+
+    fn push_char(&mut self, group: &GroupState, c: char) {
+        match group.destination {
+            Destination::Text => {
+                let runs = &mut self.paragraph.runs;
+                match runs.last_mut() {
+                    Some(run) if run.format == group.format && run.link == group.link => {
+                        run.text.push(c)
+                    }
+                    _ => runs.push(Run {
+                        text: c.to_string(),
+                        format: group.format,
+                        link: group.link.clone(),
+                    }),
+                }
+            }
+            Destination::ListText => self.list_marker.get_or_insert_default().push(c),
+            Destination::FieldInstruction => self.field_instruction.push(c),
+            Destination::Skip | Destination::FontTable => {}
+        }
+    }
+
+    /// Decodes pending `\'hh` bytes in the code page of the current font.
+    fn flush_bytes(&mut self, group: &GroupState) {
+        if self.bytes.is_empty() {
+            return;
+        }
+        let bytes = std::mem::take(&mut self.bytes);
+
+        let charset = group
+            .font
+            .or(self.default_font)
+            .and_then(|font| self.fonts.get(&font).copied())
+            .unwrap_or(Charset::Encoding(self.document_encoding));
+
+        match charset {
+            Charset::Encoding(encoding) => {
+                let (text, _) = encoding.decode_without_bom_handling(&bytes);
+                for c in text.chars() {
+                    self.push_char(group, c);
+                }
+            }
+            Charset::Symbol => {
+                for byte in bytes {
+                    let c = match byte {
+                        0xA7 => '▪',
+                        0x80.. => '•',
+                        _ => byte as char,
+                    };
+                    self.push_char(group, c);
+                }
+            }
+        }
+    }
+
+    fn end_paragraph(&mut self) {
+        let mut paragraph = std::mem::take(&mut self.paragraph);
+        let marker = self.list_marker.take();
+
+        if marker.is_some() || self.list_id.is_some() {
+            let marker = marker
+                .map(|m| m.trim().to_string())
+                .filter(|m| !m.is_empty())
+                .unwrap_or_else(|| "•".to_string());
+            paragraph.list = Some(ListItem {
+                level: self.list_level,
+                marker,
+            });
+        }
+
+        if self.in_table {
+            self.cell.push(paragraph);
+        } else {
+            self.flush_table();
+            self.blocks.push(Block::Paragraph(paragraph));
+        }
+    }
+
+    fn end_cell(&mut self) {
+        let paragraph = std::mem::take(&mut self.paragraph);
+        self.list_marker = None;
+        if !paragraph.runs.is_empty() || self.cell.is_empty() {
+            self.cell.push(paragraph);
+        }
+        self.row.push(std::mem::take(&mut self.cell));
+    }
+
+    fn end_row(&mut self) {
+        if !self.row.is_empty() {
+            self.rows.push(std::mem::take(&mut self.row));
+        }
+    }
+
+    fn flush_table(&mut self) {
+        self.end_row();
+        if !self.rows.is_empty() {
+            self.blocks
+                .push(Block::Table(std::mem::take(&mut self.rows)));
+        }
+    }
+
+    fn finish(mut self) -> Vec<Block> {
+        // Text after the last \par (TextEdit ends without one)
+        if !self.paragraph.runs.is_empty() {
+            self.in_table = false;
+            self.end_paragraph();
+        }
+        self.flush_table();
+        self.blocks
+    }
+}
+
+/// Target of a `HYPERLINK "url"` field instruction.
+fn hyperlink_target(instruction: &str) -> Option<String> {
+    let instruction = instruction.trim();
+    let rest = instruction
+        .get(..9)
+        .filter(|keyword| keyword.eq_ignore_ascii_case("HYPERLINK"))
+        .map(|_| instruction[9..].trim_start())?;
+
+    let target = match rest.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap_or(""),
+        None => rest.split_whitespace().next().unwrap_or(""),
+    };
+    (!target.is_empty() && !target.starts_with('\\')).then(|| target.to_string())
+}
+
+/// `\fcharsetN` → code page. `None` for ANSI/default, which use the
+/// document code page.
+fn charset_encoding(charset: i32) -> Option<Charset> {
+    let code_page = match charset {
+        2 => return Some(Charset::Symbol),
+        77 => 10000,
+        128 => 932,
+        129 => 949,
+        134 => 936,
+        136 => 950,
+        161 => 1253,
+        162 => 1254,
+        163 => 1258,
+        177 => 1255,
+        178 => 1256,
+        186 => 1257,
+        204 => 1251,
+        222 => 874,
+        238 => 1250,
+        _ => return None,
+    };
+    code_page_encoding(code_page).map(Charset::Encoding)
+}
+
+fn code_page_encoding(code_page: i32) -> Option<&'static Encoding> {
+    let label = match code_page {
+        874 | 1250..=1258 => format!("windows-{}", code_page),
+        932 => "shift_jis".to_string(),
+        936 => "gbk".to_string(),
+        949 => "euc-kr".to_string(),
+        950 => "big5".to_string(),
+        866 => "ibm866".to_string(),
+        10000 => "macintosh".to_string(),
+        20866 => "koi8-r".to_string(),
+        65001 => "utf-8".to_string(),
+        _ => return None,
+    };
+    Encoding::for_label(label.as_bytes())
+}
+
+// ============================================================================
+// Rendering
+// ============================================================================
+
+/// Plain text of an RTF document: one line per paragraph, list items with
+/// their bullet or number, table cells separated by tabs.
+pub fn rtf_to_text(rtf: &str) -> String {
+    let lines: Vec<String> = read(rtf)
+        .iter()
+        .map(|block| match block {
+            Block::Paragraph(paragraph) => {
+                let text = plain_text(paragraph);
+                match &paragraph.list {
+                    Some(item) => format!("{}{} {}", "  ".repeat(item.level), item.marker, text),
+                    None => text,
+                }
+            }
+            Block::Table(rows) => rows
+                .iter()
+                .map(|cells| {
+                    cells
+                        .iter()
+                        .map(|cell| cell.iter().map(plain_text).collect::<Vec<_>>().join(" "))
+                        .collect::<Vec<_>>()
+                        .join("\t")
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        })
+        .collect();
+
+    lines.join("\n").trim().to_string()
+}
+
+fn plain_text(paragraph: &Paragraph) -> String {
+    let text: String = paragraph.runs.iter().map(|run| run.text.as_str()).collect();
+    text.trim().to_string()
+}
+
+/// Converts an RTF document to an HTML fragment: `<p>` paragraphs, nested
+/// `<ul>`/`<ol>` lists and `<table>`s.
+pub fn rtf_to_html(rtf: &str) -> String {
+    let blocks = read(rtf);
+    let mut html = String::new();
+    let mut list_items: Vec<(&ListItem, String)> = Vec::new();
+
+    for block in &blocks {
+        if let Block::Paragraph(Paragraph {
+            runs,
+            list: Some(item),
+        }) = block
+        {
+            list_items.push((item, runs_html(runs)));
+            continue;
+        }
+
+        html.push_str(&list_html(&list_items));
+        list_items.clear();
+
+        match block {
+            Block::Paragraph(paragraph) => {
+                let inner = runs_html(&paragraph.runs);
+                if !inner.is_empty() {
+                    html.push_str(&format!("<p>{}</p>", inner));
+                }
+            }
+            Block::Table(rows) => html.push_str(&table_html(rows)),
+        }
+    }
+    html.push_str(&list_html(&list_items));
+
+    html
+}
+
+fn list_html(items: &[(&ListItem, String)]) -> String {
+    let tag = |ordered: bool| if ordered { "ol" } else { "ul" };
+    let mut html = String::new();
+    // Kind (ordered?) of each open list, outermost first
+    let mut open: Vec<bool> = Vec::new();
+
+    for (item, inner) in items {
+        let ordered = item.ordered();
+        // A level can only be one deeper than the current one
+        let level = item.level.min(open.len());
+
+        while open.len() > level + 1 {
+            let closed = open.pop().unwrap_or_default();
+            html.push_str(&format!("</li></{}>", tag(closed)));
+        }
+        if open.len() == level + 1 {
+            if open.last() == Some(&ordered) {
+                html.push_str("</li>");
+            } else {
+                let closed = open.pop().unwrap_or_default();
+                html.push_str(&format!("</li></{}>", tag(closed)));
+            }
+        }
+        while open.len() < level + 1 {
+            html.push_str(&format!("<{}>", tag(ordered)));
+            open.push(ordered);
+        }
+        html.push_str(&format!("<li>{}", inner));
+    }
+
+    while let Some(closed) = open.pop() {
+        html.push_str(&format!("</li></{}>", tag(closed)));
+    }
+    html
+}
+
+fn table_html(rows: &[Row]) -> String {
+    let mut html = String::from("<table>");
+    for cells in rows {
+        html.push_str("<tr>");
+        for cell in cells {
+            let inner: Vec<String> = cell
+                .iter()
+                .map(|paragraph| runs_html(&paragraph.runs))
+                .filter(|inner| !inner.is_empty())
+                .collect();
+            html.push_str(&format!("<td>{}</td>", inner.join("<br>")));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+    html
+}
+
+fn runs_html(runs: &[Run]) -> String {
+    let mut html = String::new();
+
+    let last = runs.len().saturating_sub(1);
+    for (i, run) in runs.iter().enumerate() {
+        let mut text = run.text.as_str();
+        if i == 0 {
+            text = text.trim_start();
+        }
+        if i == last {
+            text = text.trim_end();
+        }
+        if text.is_empty() {
+            continue;
+        }
+
+        let tags: Vec<&str> = [
+            (run.format.bold, "b"),
+            (run.format.italic, "i"),
+            (run.format.underline, "u"),
+            (run.format.strike, "s"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|(_, tag)| *tag)
+        .collect();
+
+        if let Some(link) = &run.link {
+            html.push_str(&format!("<a href=\"{}\">", escape_html(link)));
+        }
+        for tag in &tags {
+            html.push_str(&format!("<{}>", tag));
+        }
+        html.push_str(&escape_html(text).replace('\n', "<br>"));
+        for tag in tags.iter().rev() {
+            html.push_str(&format!("</{}>", tag));
+        }
+        if run.link.is_some() {
+            html.push_str("</a>");
+        }
+    }
+
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORD: &str = include_str!("../../tests/fixtures/rtf/word.rtf");
+    const LIBREOFFICE: &str = include_str!("../../tests/fixtures/rtf/libreoffice.rtf");
+    const TEXTEDIT: &str = include_str!("../../tests/fixtures/rtf/textedit.rtf");
+
+    #[test]
+    fn test_rtf_to_html() {
+        let rtf = r"{\rtf1\ansi\deff0{\fonttbl{\f0 Helvetica;}}{\colortbl;\red0\green0\blue0;}
+{\*\generator Riched20;}\f0\fs24 Hello \b bold\b0  and {\i italic <tags>}\par
+Caf\'e9 \u8364? 5\line next\par}";
+        assert_eq!(
+            rtf_to_html(rtf),
+            "<p>Hello <b>bold</b> and <i>italic &lt;tags&gt;</i></p><p>Café € 5<br>next</p>"
+        );
+    }
+
+    #[test]
+    fn test_unicode_and_code_pages() {
+        // Surrogate pair with \uc0, Shift-JIS font, \bin data skipped
+        let rtf = r"{\rtf1\ansi\ansicpg1252{\fonttbl{\f1\fcharset128 MS Gothic;}}
+\uc0 \u-10179\u-8704 {\f1\'93\'fa\'96\'7b}{\*\blipuid 00}{\pict\bin4 {}\}x}\'e9\par}";
+        assert_eq!(rtf_to_text(rtf), "😀日本é");
+    }
+
+    #[test]
+    fn test_word_fixture() {
+        assert_eq!(
+            rtf_to_text(WORD),
+            "Quarterly report for the café – draft\n\
+             • Revenue up 12%\n\
+             • Costs ≥ plan\n\
+             Region\tSales\n\
+             North\t1,200\n\
+             Привет, Мир"
+        );
+        assert_eq!(
+            rtf_to_html(WORD),
+            "<p><b>Quarterly report</b> for the café – <i>draft</i></p>\
+             <ul><li>Revenue up 12%</li><li>Costs ≥ plan</li></ul>\
+             <table><tr><td><b>Region</b></td><td><b>Sales</b></td></tr>\
+             <tr><td>North</td><td>1,200</td></tr></table>\
+             <p>Привет, Мир</p>"
+        );
+    }
+
+    #[test]
+    fn test_libreoffice_fixture() {
+        assert_eq!(
+            rtf_to_text(LIBREOFFICE),
+            "Meeting notes\n\
+             Agreed on three items, see the plan:\n\
+             1. Ship the beta\n  \
+             • Fix naïve bugs\n\
+             2. Write docs\n\
+             Done — thanks!"
+        );
+        assert_eq!(
+            rtf_to_html(LIBREOFFICE),
+            "<p><b>Meeting notes</b></p>\
+             <p>Agreed on <b>three</b> items, see \
+             <a href=\"https://example.org/plan?a=1&amp;b=2\"><u>the plan</u></a>:</p>\
+             <ol><li>Ship the <i>beta</i><ul><li>Fix naïve bugs</li></ul></li>\
+             <li>Write docs</li></ol>\
+             <p>Done — thanks!</p>"
+        );
+    }
+
+    #[test]
+    fn test_textedit_fixture() {
+        assert_eq!(
+            rtf_to_text(TEXTEDIT),
+            "Shopping list for “Sunday”\n\
+             • Crème fraîche\n\
+             • Café au lait ☕\n\
+             \n\
+             Item\tQty\n\
+             Eggs\t12\n\
+             \n\
+             Total: € 9,50"
+        );
+        assert_eq!(
+            rtf_to_html(TEXTEDIT),
+            "<p>Shopping list for “Sunday”</p>\
+             <ul><li><b>Crème</b> fraîche</li><li><i>Café</i> au lait ☕</li></ul>\
+             <table><tr><td><b>Item</b></td><td>Qty</td></tr>\
+             <tr><td>Eggs</td><td>12</td></tr></table>\
+             <p>Total: € 9,50</p>"
+        );
+    }
+}
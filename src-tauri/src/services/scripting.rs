@@ -0,0 +1,792 @@
+// User scripts
+//
+// Custom clip actions written in Rhai (https://rhai.rs), an embedded scripting
+// language with no filesystem or network access of its own. Scripts live in
+// `{app_config_dir}/scripts/*.rhai`, are reloaded when that directory changes,
+// and run with an operation budget and a time limit. Capabilities beyond pure
+// computation (`read_file`, `http_get`) are only registered for scripts that
+// ask for them in their header and that the user allowed in settings.
+//
+// A script defines `fn run(clip)`; `clip` is a map with `id`, `text`, `html`,
+// `content_type`, `detected_type`, `app_name` and `metadata`. It returns the
+// new text, or `#{ text: "...", new_clip: true }` to always save the text as a
+// new clip:
+//
+//     // @name Jira ticket
+//     // @description Turn the first line into a ticket title
+//     // @types text, code
+//     fn run(clip) {
+//         let lines = clip.text.split("\n");
+//         "h1. " + lines[0] + "\n\n" + clip.text
+//     }
+
+use crate::models::ClipItem;
+use crate::repositories::ClipRepository;
+use crate::services::file_watch::{FileWatcher, FilesVersion};
+use crate::services::intelligence;
+use crate::services::semantic::SemanticService;
+use crate::services::transform::{clip_text, clip_types, save_derived};
+use anyhow::{anyhow, bail, Context, Result};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, AST};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// File extension of scripts.
+const EXTENSION: &str = "rhai";
+
+/// Longest a script may run.
+const TIME_LIMIT: Duration = Duration::from_secs(2);
+
+/// Rhai operations a run may take (roughly a second of CPU).
+const MAX_OPERATIONS: u64 = 10_000_000;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_STRING_SIZE: usize = 10 * 1024 * 1024;
+const MAX_COLLECTION_SIZE: usize = 100_000;
+
+/// Largest file `read_file` returns.
+const MAX_READ_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Prefix of the `clip_relations.transform_id` of saved results.
+const TRANSFORM_PREFIX: &str = "script:";
+
+/// Capabilities a script can ask for with `// @grant`:
+/// - `read_file`: `read_file(path)` returns a file's text
+/// - `http`: `http_get(url)` returns the body of an http(s) URL
+pub const GRANTS: &[&str] = &["read_file", "http"];
+
+/// A script in the scripts directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptInfo {
+    /// File name without the extension
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    /// Detected types the script applies to (empty = any text)
+    pub content_types: Vec<String>,
+    /// Capabilities the script asks for (see `GRANTS`)
+    pub grants: Vec<String>,
+    /// Why the script couldn't be loaded; it isn't offered for clips then
+    pub error: Option<String>,
+}
+
+/// Options of `run_script`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScriptOptions {
+    /// Save the result as a new clip derived from the source
+    pub save: bool,
+}
+
+/// Result of `run_script`; `clip` is set when the result was saved.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptOutput {
+    pub text: String,
+    pub clip: Option<ClipItem>,
+}
+
+/// Value returned by a script's `run`.
+#[derive(Debug, Clone, PartialEq)]
+struct ScriptResult {
+    text: String,
+    new_clip: bool,
+}
+
+/// The `// @key value` lines at the top of a script.
+#[derive(Debug, Default, PartialEq)]
+struct Header {
+    name: Option<String>,
+    description: String,
+    content_types: Vec<String>,
+    grants: Vec<String>,
+}
+
+struct Script {
+    info: ScriptInfo,
+    ast: Option<AST>,
+    /// Grants approved without `script_grants`: a plugin's permissions,
+    /// accepted when it was installed
+    approved: Vec<String>,
+}
+
+impl Script {
+    /// The script's info, with an error for `@types` no detector gives (any
+    /// more): custom detectors change while scripts stay loaded.
+    fn info(&self) -> ScriptInfo {
+        let mut info = self.info.clone();
+        if info.error.is_none() {
+            info.error = info
+                .content_types
+                .iter()
+                .find(|t| !intelligence::is_known_type(t))
+                .map(|t| format!("Unknown content type '{}' in @types", t));
+        }
+        info
+    }
+}
+
+/// A script shipped by a plugin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginScript {
+    pub plugin_id: String,
+    pub path: PathBuf,
+    /// The plugin's permissions; the script may not ask for others
+    pub permissions: Vec<String>,
+}
+
+#[derive(Default)]
+struct LoadedScripts {
+    version: Option<FilesVersion>,
+    scripts: Vec<Arc<Script>>,
+    plugin_scripts: Vec<Arc<Script>>,
+}
+
+/// Loads the user's scripts and runs them on clips.
+pub struct ScriptService {
+    dir: PathBuf,
+    repository: Arc<ClipRepository>,
+    semantic_service: Arc<SemanticService>,
+    loaded: Mutex<LoadedScripts>,
+}
+
+impl ScriptService {
+    pub fn new(
+        dir: PathBuf,
+        repository: Arc<ClipRepository>,
+        semantic_service: Arc<SemanticService>,
+    ) -> Self {
+        Self {
+            dir,
+            repository,
+            semantic_service,
+            loaded: Mutex::new(LoadedScripts::default()),
+        }
+    }
+
+    /// The scripts directory, created if missing.
+    pub fn dir(&self) -> Result<&Path> {
+        std::fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        Ok(&self.dir)
+    }
+
+    /// Replaces the scripts contributed by plugins. Their ids are
+    /// `{plugin id}/{file stem}`.
+    pub fn set_plugin_scripts(&self, scripts: Vec<PluginScript>) {
+        let scripts = scripts
+            .iter()
+            .map(|script| Arc::new(load_plugin_script(script)))
+            .collect();
+        self.loaded
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .plugin_scripts = scripts;
+    }
+
+    /// All scripts, including those that failed to load.
+    pub fn list_all(&self) -> Vec<ScriptInfo> {
+        self.scripts().iter().map(|s| s.info()).collect()
+    }
+
+    /// Scripts that apply to any of the clip's types, empty for clips without
+    /// text.
+    pub async fn list(&self, clip_id: &str) -> Result<Vec<ScriptInfo>> {
+        let clip = self.get_clip(clip_id).await?;
+        if clip_text(&clip).is_none() {
+            return Ok(Vec::new());
+        }
+        let types = clip_types(&self.repository, &clip).await?;
+        Ok(self
+            .scripts()
+            .iter()
+            .filter(|s| s.ast.is_some())
+            .map(|s| s.info())
+            .filter(|info| info.error.is_none() && types.iter().any(|ty| applies_to(info, ty)))
+            .collect())
+    }
+
+    /// Runs a script on the clip. `granted` are the capabilities the user
+    /// allowed this script; only those it also asks for are available to it.
+    pub async fn run(
+        &self,
+        clip_id: &str,
+        script_id: &str,
+        granted: &[String],
+        options: &ScriptOptions,
+    ) -> Result<ScriptOutput> {
+        let clip = self.get_clip(clip_id).await?;
+        if clip_text(&clip).is_none() {
+            bail!("Clip has no text");
+        }
+        let script = self
+            .scripts()
+            .into_iter()
+            .find(|s| s.info.id == script_id)
+            .ok_or_else(|| anyhow!("Unknown script: {}", script_id))?;
+        let ast = match (&script.ast, script.info().error) {
+            (Some(ast), None) => ast.clone(),
+            (_, error) => bail!(
+                "Script '{}' failed to load: {}",
+                script_id,
+                error.as_deref().unwrap_or_default()
+            ),
+        };
+        let grants: Vec<String> = script
+            .info
+            .grants
+            .iter()
+            .filter(|g| granted.contains(g) || script.approved.contains(g))
+            .cloned()
+            .collect();
+
+        let input = clip_input(&clip);
+        let result =
+            tokio::task::spawn_blocking(move || run_script(&ast, &grants, &input, TIME_LIMIT))
+                .await??;
+
+        let saved = if options.save || result.new_clip {
+            let transform_id = format!("{}{}", TRANSFORM_PREFIX, script_id);
+            Some(
+                save_derived(
+                    &self.repository,
+                    &self.semantic_service,
+                    &clip,
+                    &transform_id,
+                    &result.text,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+
+        Ok(ScriptOutput {
+            text: result.text,
+            clip: saved,
+        })
+    }
+
+    /// Has `watcher` reload the scripts whenever the directory changes,
+    /// calling `on_change` afterwards.
+    pub fn watch<F>(self: &Arc<Self>, watcher: &mut FileWatcher, on_change: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let service = self.clone();
+        watcher.watch(move || service.reload_if_changed(), on_change);
+    }
+
+    fn scripts(&self) -> Vec<Arc<Script>> {
+        self.reload_if_changed();
+        let loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        loaded
+            .scripts
+            .iter()
+            .chain(&loaded.plugin_scripts)
+            .cloned()
+            .collect()
+    }
+
+    /// Reloads the scripts if a file was added, removed or changed.
+    fn reload_if_changed(&self) -> bool {
+        let version = FilesVersion::of_dir(&self.dir, EXTENSION);
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        if loaded.version.as_ref() == Some(&version) {
+            return false;
+        }
+        loaded.scripts = version
+            .existing()
+            .map(|path| Arc::new(load_script(path)))
+            .collect();
+        loaded.version = Some(version);
+        true
+    }
+
+    async fn get_clip(&self, clip_id: &str) -> Result<ClipItem> {
+        self.repository
+            .get_by_id(clip_id)
+            .await?
+            .ok_or_else(|| anyhow!("Clip not found"))
+    }
+}
+
+fn applies_to(info: &ScriptInfo, detected_type: &str) -> bool {
+    info.content_types.is_empty() || info.content_types.iter().any(|t| t == detected_type)
+}
+
+fn load_script(path: &Path) -> Script {
+    let id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut info = ScriptInfo {
+        id: id.clone(),
+        name: id,
+        description: String::new(),
+        content_types: Vec::new(),
+        grants: Vec::new(),
+        error: None,
+    };
+
+    let compiled = std::fs::read_to_string(path)
+        .context("Failed to read script")
+        .and_then(|source| Ok((parse_header(&source)?, compile(&source)?)));
+    match compiled {
+        Ok((header, ast)) => {
+            if let Some(name) = header.name {
+                info.name = name;
+            }
+            info.description = header.description;
+            info.content_types = header.content_types;
+            info.grants = header.grants;
+            Script {
+                info,
+                ast: Some(ast),
+                approved: Vec::new(),
+            }
+        }
+        Err(e) => {
+            info.error = Some(e.to_string());
+            Script {
+                info,
+                ast: None,
+                approved: Vec::new(),
+            }
+        }
+    }
+}
+
+fn load_plugin_script(plugin_script: &PluginScript) -> Script {
+    let mut script = load_script(&plugin_script.path);
+    script.info.id = format!("{}/{}", plugin_script.plugin_id, script.info.id);
+    let undeclared = script
+        .info
+        .grants
+        .iter()
+        .find(|g| !plugin_script.permissions.contains(g));
+    if let Some(grant) = undeclared {
+        script.info.error = Some(format!(
+            "Asks for '{}', which the plugin doesn't declare in its permissions",
+            grant
+        ));
+        script.ast = None;
+    }
+    script.approved = plugin_script.permissions.clone();
+    script
+}
+
+/// Reads the leading comment lines of a script:
+///
+///     // @name Jira ticket
+///     // @description Rewrite as a Jira ticket
+///     // @types text, code
+///     // @grant http
+fn parse_header(source: &str) -> Result<Header> {
+    let mut header = Header::default();
+    let list = |value: &str| -> Vec<String> {
+        value
+            .split([',', ' '])
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+
+    for line in source.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let Some(comment) = line.strip_prefix("//") else {
+            break;
+        };
+        let Some(field) = comment.trim().strip_prefix('@') else {
+            continue;
+        };
+        let (key, value) = field.split_once(char::is_whitespace).unwrap_or((field, ""));
+        let value = value.trim();
+
+        match key {
+            "name" => header.name = Some(value.to_string()).filter(|v| !v.is_empty()),
+            "description" => header.description = value.to_string(),
+            // Checked on use (`Script::info`), against the detectors of the time
+            "types" => header.content_types.extend(list(value)),
+            "grant" => {
+                for grant in list(value) {
+                    if !GRANTS.contains(&grant.as_str()) {
+                        bail!(
+                            "Unknown grant '{}' (available: {})",
+                            grant,
+                            GRANTS.join(", ")
+                        );
+                    }
+                    if !header.grants.contains(&grant) {
+                        header.grants.push(grant);
+                    }
+                }
+            }
+            _ => bail!("Unknown header field @{}", key),
+        }
+    }
+    Ok(header)
+}
+
+fn compile(source: &str) -> Result<AST> {
+    let ast = base_engine()
+        .compile(source)
+        .map_err(|e| anyhow!("Syntax error: {}", e))?;
+    if !ast
+        .iter_functions()
+        .any(|f| f.name == "run" && f.params.len() == 1)
+    {
+        bail!("Script has no `fn run(clip)`");
+    }
+    Ok(ast)
+}
+
+/// The `clip` map passed to `run`.
+fn clip_input(clip: &ClipItem) -> Value {
+    let metadata = clip
+        .metadata
+        .as_deref()
+        .and_then(|m| serde_json::from_str(m).ok())
+        .unwrap_or(Value::Null);
+    json!({
+        "id": clip.id,
+        "text": clip.content_text,
+        "html": clip.content_html,
+        "content_type": clip.content_type,
+        "detected_type": clip.detected_type,
+        "app_name": clip.app_name,
+        "metadata": metadata,
+    })
+}
+
+/// Calls the script's `run` with `input`. Blocking.
+fn run_script(
+    ast: &AST,
+    grants: &[String],
+    input: &Value,
+    time_limit: Duration,
+) -> Result<ScriptResult> {
+    let engine = sandboxed_engine(grants, Instant::now() + time_limit);
+    let input = rhai::serde::to_dynamic(input).map_err(|e| anyhow!("{}", e))?;
+
+    let value = engine
+        .call_fn::<Dynamic>(&mut rhai::Scope::new(), ast, "run", (input,))
+        .map_err(|e| match *e {
+            EvalAltResult::ErrorTerminated(..) => {
+                anyhow!("Script ran longer than {:?}", time_limit)
+            }
+            EvalAltResult::ErrorTooManyOperations(..) => {
+                anyhow!("Script exceeded its operation limit")
+            }
+            e => anyhow!("Script error: {}", e),
+        })?;
+
+    let type_name = value.type_name();
+    if value.is_string() {
+        let text = value.into_string().map_err(|e| anyhow!(e))?;
+        return Ok(ScriptResult {
+            text,
+            new_clip: false,
+        });
+    }
+    if let Some(map) = value.try_cast::<rhai::Map>() {
+        let text = map
+            .get("text")
+            .and_then(|text| text.clone().into_string().ok())
+            .ok_or_else(|| anyhow!("The map returned by run() needs a `text` string"))?;
+        let new_clip = map
+            .get("new_clip")
+            .and_then(|new_clip| new_clip.as_bool().ok())
+            .unwrap_or(false);
+        return Ok(ScriptResult { text, new_clip });
+    }
+    bail!(
+        "run() must return a string or a map with `text`, not {}",
+        type_name
+    )
+}
+
+/// An engine without `import` and `eval`, with the run limits. Scripts are
+/// compiled with it too, since `eval` is rejected while parsing.
+fn base_engine() -> Engine {
+    let mut engine = Engine::new();
+    // `import` would read other files
+    engine.set_module_resolver(DummyModuleResolver::new());
+    engine.disable_symbol("eval");
+
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE);
+    engine
+}
+
+/// The base engine with a deadline, script output going to the log, and the
+/// functions of `grants`.
+fn sandboxed_engine(grants: &[String], deadline: Instant) -> Engine {
+    let mut engine = base_engine();
+    // Checked every 1024 operations; stops the script once it's past the deadline
+    engine.on_progress(move |operations| {
+        (operations.is_multiple_of(1024) && Instant::now() >= deadline).then_some(Dynamic::UNIT)
+    });
+    engine.on_print(|text| eprintln!("[script] {}", text));
+    engine.on_debug(|text, _, position| eprintln!("[script] {} {}", position, text));
+
+    if grants.iter().any(|g| g == "read_file") {
+        engine.register_fn("read_file", read_file);
+    }
+    if grants.iter().any(|g| g == "http") {
+        engine.register_fn("http_get", move |url: &str| http_get(url, deadline));
+    }
+    engine
+}
+
+fn read_file(path: &str) -> Result<String, Box<EvalAltResult>> {
+    let size = std::fs::metadata(path)
+        .map_err(|e| format!("read_file({}): {}", path, e))?
+        .len();
+    if size > MAX_READ_SIZE {
+        return Err(format!("read_file({}): file is larger than 10 MB", path).into());
+    }
+    std::fs::read_to_string(path).map_err(|e| format!("read_file({}): {}", path, e).into())
+}
+
+/// Fetches `url`, giving up at the script's deadline: `on_progress` can't stop
+/// a script while it waits on the network.
+fn http_get(url: &str, deadline: Instant) -> Result<String, Box<EvalAltResult>> {
+    if !(url.starts_with("http://") || url.starts_with("https://")) {
+        return Err(format!("http_get({}): only http(s) URLs are allowed", url).into());
+    }
+    let timeout = deadline.saturating_duration_since(Instant::now());
+    if timeout.is_zero() {
+        return Err(format!("http_get({}): the script ran out of time", url).into());
+    }
+    reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build()
+        .and_then(|client| client.get(url).send())
+        .and_then(|response| response.error_for_status())
+        .and_then(|response| response.text())
+        .map_err(|e| format!("http_get({}): {}", url, e).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::intelligence::IntelligenceService;
+
+    fn run_source(source: &str, grants: &[&str], input: Value) -> Result<ScriptResult> {
+        let grants: Vec<String> = grants.iter().map(|g| g.to_string()).collect();
+        run_script(
+            &compile(source)?,
+            &grants,
+            &input,
+            Duration::from_millis(200),
+        )
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let header = parse_header(
+            "\n// @name  Jira ticket\n// Some notes\n//@types text,code\n// @grant http http\nfn run(clip) {}\n// @bogus",
+        )
+        .unwrap();
+        assert_eq!(
+            header,
+            Header {
+                name: Some("Jira ticket".to_string()),
+                description: String::new(),
+                content_types: vec!["text".to_string(), "code".to_string()],
+                grants: vec!["http".to_string()],
+            }
+        );
+
+        assert!(parse_header("// @grant shell").is_err());
+        assert!(parse_header("// @author me").is_err());
+        assert_eq!(parse_header("fn run(clip) {}").unwrap(), Header::default());
+    }
+
+    #[test]
+    fn test_run_script() {
+        let input = json!({ "text": "hello", "detected_type": "text", "metadata": { "n": 2 } });
+
+        let result = run_source("fn run(clip) { clip.text.to_upper() }", &[], input.clone());
+        assert_eq!(result.unwrap().text, "HELLO");
+
+        let result = run_source(
+            r#"fn run(clip) { #{ text: `${clip.text} x${clip.metadata.n}`, new_clip: true } }"#,
+            &[],
+            input.clone(),
+        );
+        assert_eq!(
+            result.unwrap(),
+            ScriptResult {
+                text: "hello x2".to_string(),
+                new_clip: true,
+            }
+        );
+
+        let error = run_source("fn run(clip) { 42 }", &[], input.clone()).unwrap_err();
+        assert!(error.to_string().contains("must return a string"));
+        assert!(compile("fn main() {}").is_err());
+    }
+
+    #[test]
+    fn test_run_script_is_sandboxed() {
+        let input = json!({ "text": "hello" });
+
+        let error = run_source("fn run(clip) { loop {} }", &[], input.clone()).unwrap_err();
+        let message = error.to_string();
+        assert!(message.contains("longer than") || message.contains("operation limit"));
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "secret").unwrap();
+        let source = format!(
+            "fn run(clip) {{ read_file(\"{}\") }}",
+            file.path().display().to_string().replace('\\', "/")
+        );
+        assert!(run_source(&source, &[], input.clone()).is_err());
+        assert_eq!(
+            run_source(&source, &["read_file"], input.clone())
+                .unwrap()
+                .text,
+            "secret"
+        );
+
+        assert!(run_source(
+            r#"fn run(clip) { http_get("http://localhost") }"#,
+            &[],
+            input.clone()
+        )
+        .is_err());
+        assert!(run_source(
+            r#"fn run(clip) { import "other" as o; "" }"#,
+            &[],
+            input.clone()
+        )
+        .is_err());
+        assert!(run_source(r#"fn run(clip) { eval("1") }"#, &[], input).is_err());
+    }
+
+    #[test]
+    fn test_script_types_checked_on_use() {
+        let _lock = intelligence::CUSTOM_DETECTORS_TEST_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("roster.rhai");
+        std::fs::write(&path, "// @types zz_roster\nfn run(clip) { clip.text }").unwrap();
+
+        let script = load_script(&path);
+        assert!(script.ast.is_some());
+        assert_eq!(
+            script.info().error.as_deref(),
+            Some("Unknown content type 'zz_roster' in @types")
+        );
+
+        // A detector added later makes the type known, and removing it unknown again
+        intelligence::set_custom_detectors(vec![intelligence::CustomDetector {
+            name: "Roster".to_string(),
+            detected_type: "zz_roster".to_string(),
+            regex: regex::Regex::new(r"^zz-roster-\d+$").unwrap(),
+            priority: 0,
+        }]);
+        assert_eq!(script.info().error, None);
+        intelligence::set_custom_detectors(Vec::new());
+        assert!(script.info().error.is_some());
+    }
+
+    #[test]
+    fn test_http_get_stops_at_deadline() {
+        // Accepts the connection but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+
+        let started = Instant::now();
+        let err = http_get(&url, started + Duration::from_millis(200)).unwrap_err();
+        assert!(err.to_string().contains("http_get"));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        let err = http_get(&url, Instant::now()).unwrap_err();
+        assert!(err.to_string().contains("ran out of time"));
+    }
+
+    #[tokio::test]
+    async fn test_service_lists_and_runs_scripts() {
+        let (repository, dir) = ClipRepository::open_temp().await;
+        let repository = Arc::new(repository);
+        let semantic_service = Arc::new(SemanticService::new(dir.path().to_path_buf()));
+        let scripts_dir = dir.path().join("scripts");
+        let service = ScriptService::new(scripts_dir.clone(), repository.clone(), semantic_service);
+
+        let text = "See https://example.com";
+        let detection = IntelligenceService::detect(text);
+        let clip = ClipItem::from_text(
+            text.to_string(),
+            detection.detected_type_str().to_string(),
+            detection.metadata_json(),
+        );
+        repository.insert(&clip).await.unwrap();
+        assert!(service.list(&clip.id).await.unwrap().is_empty());
+
+        let dir_path = service.dir().unwrap();
+        std::fs::write(
+            dir_path.join("shout.rhai"),
+            "// @name Shout\nfn run(clip) { clip.text.to_upper() }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir_path.join("json_only.rhai"),
+            "// @types json\nfn run(clip) { clip.text }",
+        )
+        .unwrap();
+        std::fs::write(dir_path.join("broken.rhai"), "fn run(clip) {").unwrap();
+        std::fs::write(dir_path.join("notes.txt"), "not a script").unwrap();
+
+        let all = service.list_all();
+        assert_eq!(all.len(), 3);
+        assert!(all
+            .iter()
+            .find(|s| s.id == "broken")
+            .unwrap()
+            .error
+            .is_some());
+        let offered: Vec<_> = service
+            .list(&clip.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(offered, ["Shout"]);
+
+        let output = service
+            .run(&clip.id, "shout", &[], &ScriptOptions { save: true })
+            .await
+            .unwrap();
+        assert_eq!(output.text, "SEE HTTPS://EXAMPLE.COM");
+        let relation = repository
+            .get_relation(&output.clip.unwrap().id)
+            .await
+            .unwrap();
+        assert_eq!(
+            relation.unwrap().transform_id.as_deref(),
+            Some("script:shout")
+        );
+        assert!(service
+            .run(&clip.id, "broken", &[], &ScriptOptions::default())
+            .await
+            .is_err());
+
+        std::fs::remove_file(dir_path.join("shout.rhai")).unwrap();
+        assert_eq!(service.list_all().len(), 2);
+    }
+}
@@ -1,28 +1,60 @@
+use crate::models::settings::{AppSettings, EmbeddingBackendKind, OpenAiEndpoint};
+use crate::models::{ClipItem, TextRange};
+use crate::repositories::ClipRepository;
+use crate::services::embedding_backend::{
+    EmbeddingBackend, EmbeddingModelInfo, FastEmbedBackend, OpenAiEmbeddingBackend,
+};
 use anyhow::{anyhow, Result};
-use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
 use std::sync::Arc;
 use std::sync::RwLock as StdRwLock;
 use tokio::task;
 
-/// Handes Local Semantic Search functionality using fastembed.
+/// Approximate size of the passages long clips are split into when looking
+/// for the part of a semantic hit that matched.
+const CHUNK_BYTES: usize = 400;
+
+/// Only the first chunks of very long clips are compared, keeps the number of
+/// embeddings per result page bounded.
+const MAX_CHUNKS_PER_CLIP: usize = 8;
+
+/// Handes Semantic Search functionality over a pluggable embedding backend
+/// (local fastembed models or an OpenAI-compatible endpoint).
 pub struct SemanticService {
-    /// Note: We use std::sync::RwLock because fastembed operations are blocking
-    /// and should be run inside task::spawn_blocking anyway.
-    model: Arc<StdRwLock<Option<TextEmbedding>>>,
-    // Track the name of the currently loaded model
-    loaded_model_name: Arc<StdRwLock<Option<String>>>,
+    /// Only held to swap or clone the backend: embedding calls (which block,
+    /// up to a network timeout) run on a clone, outside the lock.
+    backend: Arc<StdRwLock<Option<Arc<dyn EmbeddingBackend>>>>,
     app_data_dir: std::path::PathBuf,
 }
 
 impl SemanticService {
     pub fn new(app_data_dir: std::path::PathBuf) -> Self {
         Self {
-            model: Arc::new(StdRwLock::new(None)),
-            loaded_model_name: Arc::new(StdRwLock::new(None)),
+            backend: Arc::new(StdRwLock::new(None)),
             app_data_dir,
         }
     }
 
+    fn cache_dir(&self) -> std::path::PathBuf {
+        self.app_data_dir.join(".fastembed_cache")
+    }
+
+    /// Loads whichever backend the settings select.
+    pub async fn init_from_settings(
+        &self,
+        settings: &AppSettings,
+        app_handle: Option<tauri::AppHandle>,
+    ) -> Result<()> {
+        match settings.embedding_backend {
+            EmbeddingBackendKind::Fastembed => {
+                self.init_model(settings.semantic_model.clone(), app_handle)
+                    .await
+            }
+            EmbeddingBackendKind::Openai => {
+                self.init_openai(settings.embedding_endpoint.clone()).await
+            }
+        }
+    }
+
     /// Downloads (if necessary) and loads the ONNX model into memory.
     /// This is a blocking operation so it must be spawned on a blocking thread.
     pub async fn init_model(
@@ -30,9 +62,7 @@ impl SemanticService {
         model_name: String,
         app_handle: Option<tauri::AppHandle>,
     ) -> Result<()> {
-        let model_arc = self.model.clone();
-        let name_arc = self.loaded_model_name.clone();
-        let cache_dir = self.app_data_dir.join(".fastembed_cache");
+        let cache_dir = self.cache_dir();
 
         // We know the approximate sizes of the repositories for progress bars
         let expected_total_bytes: u64 = match model_name.as_str() {
@@ -44,7 +74,6 @@ impl SemanticService {
         // If we need to send progress events, spawn a poller
         let is_downloaded = self.get_downloaded_models().contains(&model_name);
         let progress_cancel = Arc::new(StdRwLock::new(false));
-
         if !is_downloaded {
             if let Some(app) = app_handle {
                 let cache_clone = cache_dir.clone();
@@ -95,156 +124,231 @@ impl SemanticService {
             }
         }
 
-        let res = task::spawn_blocking(move || -> Result<()> {
-            let model_enum = match model_name.as_str() {
-                "all-MiniLM-L6-v2" => EmbeddingModel::AllMiniLML6V2,
-                "paraphrase-multilingual-MiniLM-L12-v2" => EmbeddingModel::ParaphraseMLMiniLML12V2,
-                _ => EmbeddingModel::AllMiniLML6V2, // Default fallback
-            };
-
-            let mut options = InitOptions::new(model_enum);
-            options.cache_dir = cache_dir;
-
-            let model = TextEmbedding::try_new(options)
-                .map_err(|e| anyhow!("Failed to load embedding model: {}", e))?;
-
-            let mut lock = model_arc.write().unwrap();
-            *lock = Some(model);
-
-            let mut name_lock = name_arc.write().unwrap();
-            *name_lock = Some(model_name.clone());
-
-            Ok(())
-        })
-        .await?;
+        let res = task::spawn_blocking(move || FastEmbedBackend::load(&model_name, cache_dir)).await?;
 
         // Stop poller
         *progress_cancel.write().unwrap() = true;
 
-        // Flatten the double Result from spawn_blocking
-        res?;
+        self.set_backend(Box::new(res?));
+        Ok(())
+    }
 
+    /// Connects to an OpenAI-compatible embeddings endpoint (Ollama, llama.cpp, ...).
+    /// The vector size is probed from the server, so any model works.
+    pub async fn init_openai(&self, endpoint: OpenAiEndpoint) -> Result<()> {
+        let backend =
+            task::spawn_blocking(move || OpenAiEmbeddingBackend::connect(&endpoint)).await??;
+        self.set_backend(Box::new(backend));
         Ok(())
     }
 
+    /// Replaces the active backend.
+    pub fn set_backend(&self, backend: Box<dyn EmbeddingBackend>) {
+        *self.backend.write().unwrap() = Some(Arc::from(backend));
+    }
+
     /// Checks if the model is currently loaded in memory.
     pub fn is_ready(&self) -> bool {
-        self.model.read().unwrap().is_some()
+        self.backend.read().unwrap().is_some()
     }
 
     /// Unloads the model from memory to save RAM when semantic search is disabled.
     pub fn unload_model(&self) {
-        let mut lock = self.model.write().unwrap();
+        let mut lock = self.backend.write().unwrap();
         *lock = None;
-        let mut name_lock = self.loaded_model_name.write().unwrap();
-        *name_lock = None;
     }
 
-    /// Returns the currently loaded model name and its dimension size.
+    /// Returns the currently loaded model id and its dimension size.
     pub fn get_model_info(&self) -> Option<(String, i32)> {
-        let lock = self.loaded_model_name.read().unwrap();
-        if let Some(name) = lock.as_ref() {
-            let dim = match name.as_str() {
-                "paraphrase-multilingual-MiniLM-L12-v2" => 384,
-                "all-MiniLM-L6-v2" => 384,
-                _ => 384, // Default fallback dimension
-            };
-            Some((name.clone(), dim))
-        } else {
-            None
-        }
+        let lock = self.backend.read().unwrap();
+        lock.as_ref()
+            .map(|backend| (backend.model_id().to_string(), backend.dimensions() as i32))
+    }
+
+    /// Every local model fastembed can load, with its dimensions and download state.
+    pub fn get_available_models(&self) -> Vec<EmbeddingModelInfo> {
+        FastEmbedBackend::supported_models(&self.cache_dir())
     }
 
     /// Returns a list of model IDs (e.g., "all-MiniLM-L6-v2") that have been downloaded
-    /// by checking the .fastembed_cache directory for corresponding folders.
+    /// to the .fastembed_cache directory.
     pub fn get_downloaded_models(&self) -> Vec<String> {
-        let cache_dir = self.app_data_dir.join(".fastembed_cache");
-        let mut downloaded = Vec::new();
-
-        if let Ok(entries) = std::fs::read_dir(cache_dir) {
-            for entry in entries.flatten() {
-                if let Ok(file_type) = entry.file_type() {
-                    if file_type.is_dir() {
-                        let dir_name = entry.file_name().to_string_lossy().to_string();
-                        // fastembed usually creates folders like "fast-all-MiniLM-L6-v2"
-                        if dir_name.contains("all-MiniLM-L6-v2") {
-                            downloaded.push("all-MiniLM-L6-v2".to_string());
-                        } else if dir_name.contains("paraphrase-multilingual-MiniLM-L12-v2") {
-                            downloaded.push("paraphrase-multilingual-MiniLM-L12-v2".to_string());
-                        }
-                    }
-                }
-            }
-        }
-
-        // Deduplicate in case multiple folders match
-        downloaded.sort();
-        downloaded.dedup();
-        downloaded
+        self.get_available_models()
+            .into_iter()
+            .filter(|model| model.downloaded)
+            .map(|model| model.id)
+            .collect()
     }
 
     /// Deletes the cached model files for a given model ID to free up disk space.
     pub fn delete_model(&self, model_name: &str) -> Result<()> {
         // If the model to delete is currently loaded, unload it first
-        {
-            let lock = self.model.read().unwrap();
-            if lock.is_some() {
-                // We're just checking if any model is loaded.
-                // A more robust check would verify if the loaded model IS the one being deleted,
-                // but since fastembed doesn't expose the loaded model name easily, we can just
-                // unload whatever is in memory if we are doing a delete operation, leaving it safe.
-            }
+        let is_loaded = self
+            .get_model_info()
+            .is_some_and(|(loaded, _)| loaded == model_name);
+        if is_loaded {
+            self.unload_model();
         }
-        self.unload_model();
-
-        let cache_dir = self.app_data_dir.join(".fastembed_cache");
-        if let Ok(entries) = std::fs::read_dir(&cache_dir) {
-            for entry in entries.flatten() {
-                if let Ok(file_type) = entry.file_type() {
-                    if file_type.is_dir() {
-                        let dir_name = entry.file_name().to_string_lossy().to_string();
-                        if dir_name.contains(model_name) {
-                            let path = entry.path();
-                            std::fs::remove_dir_all(&path).map_err(|e| {
-                                anyhow!(
-                                    "Failed to delete model directory {}: {}",
-                                    path.display(),
-                                    e
-                                )
-                            })?;
-                        }
-                    }
-                }
-            }
+
+        let path = FastEmbedBackend::model_cache_path(&self.cache_dir(), model_name)
+            .ok_or_else(|| anyhow!("Unknown embedding model: {}", model_name))?;
+        if path.is_dir() {
+            std::fs::remove_dir_all(&path).map_err(|e| {
+                anyhow!("Failed to delete model directory {}: {}", path.display(), e)
+            })?;
         }
         Ok(())
     }
 
     /// Generates an embedding vector for the given text.
     pub async fn embed(&self, text: String) -> Result<Vec<f32>> {
-        let model_arc = self.model.clone();
+        self.embed_batch(vec![text])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("Model returned empty embedding array"))
+    }
 
-        task::spawn_blocking(move || -> Result<Vec<f32>> {
-            let mut lock = model_arc.write().unwrap();
+    /// Generates embedding vectors for several texts in one backend call.
+    pub async fn embed_batch(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        let backend = self.current_backend()?;
+        task::spawn_blocking(move || backend.embed(&texts)).await?
+    }
 
-            if let Some(model) = lock.as_mut() {
-                // fastembed expects an array of strings
-                let embeddings = model
-                    .embed(vec![text], None)
-                    .map_err(|e| anyhow!("Failed to generate embedding: {}", e))?;
+    fn current_backend(&self) -> Result<Arc<dyn EmbeddingBackend>> {
+        self.backend.read().unwrap().clone().ok_or_else(|| {
+            anyhow!("Semantic model is not loaded. Please initialize it first.")
+        })
+    }
 
-                if let Some(first) = embeddings.into_iter().next() {
-                    Ok(first)
-                } else {
-                    Err(anyhow!("Model returned empty embedding array"))
+    /// Embeds the clip's text in the background and saves it as the clip's
+    /// embedding (replacing an older one). Does nothing while no model is loaded.
+    pub fn spawn_clip_embedding(
+        self: &Arc<Self>,
+        repository: Arc<ClipRepository>,
+        clip: &ClipItem,
+    ) {
+        let (Some(text), Some((model_name, dimensions))) =
+            (clip.content_text.clone(), self.get_model_info())
+        else {
+            return;
+        };
+        let clip_id = clip.id.clone();
+        let semantic = self.clone();
+
+        tokio::spawn(async move {
+            match semantic.embed(text).await {
+                Ok(vector) => {
+                    if let Err(e) = repository
+                        .create_embedding(
+                            &clip_id,
+                            SemanticService::vector_to_bytes(&vector),
+                            &model_name,
+                            dimensions,
+                        )
+                        .await
+                    {
+                        eprintln!("[ERROR] Failed to save embedding: {}", e);
+                    }
                 }
+                Err(e) => eprintln!("[ERROR] Failed to generate embedding: {}", e),
+            }
+        });
+    }
+
+    /// For each text, the chunk (see `chunk_ranges`) closest to `query_vector`.
+    /// All chunks are embedded in a single batch. `model` is the model that
+    /// embedded the query; if another model has been loaded since, the chunk
+    /// vectors would not be comparable and no chunk is returned.
+    pub async fn best_chunks(
+        &self,
+        query_vector: &[f32],
+        model: &str,
+        texts: &[&str],
+    ) -> Result<Vec<Option<TextRange>>> {
+        let backend = self.current_backend()?;
+        if backend.model_id() != model {
+            return Ok(vec![None; texts.len()]);
+        }
+
+        let chunks: Vec<Vec<TextRange>> = texts
+            .iter()
+            .map(|text| {
+                let mut ranges = Self::chunk_ranges(text, CHUNK_BYTES);
+                ranges.truncate(MAX_CHUNKS_PER_CLIP);
+                ranges
+            })
+            .collect();
+
+        let inputs: Vec<String> = texts
+            .iter()
+            .zip(&chunks)
+            .flat_map(|(text, ranges)| ranges.iter().map(|r| text[r.start..r.end].to_string()))
+            .collect();
+        if inputs.is_empty() {
+            return Ok(vec![None; texts.len()]);
+        }
+
+        let mut vectors = task::spawn_blocking(move || backend.embed(&inputs))
+            .await??
+            .into_iter();
+        Ok(chunks
+            .into_iter()
+            .map(|ranges| {
+                ranges
+                    .into_iter()
+                    .zip(vectors.by_ref())
+                    .map(|(range, vector)| (Self::cosine_similarity(query_vector, &vector), range))
+                    .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+                    .map(|(_, range)| range)
+            })
+            .collect())
+    }
+
+    /// Splits `text` into consecutive chunks of at most `max_bytes`, preferably
+    /// at sentence ends, otherwise at whitespace. Whitespace between chunks is
+    /// dropped; a short text is a single chunk.
+    pub fn chunk_ranges(text: &str, max_bytes: usize) -> Vec<TextRange> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+
+        loop {
+            start += text[start..].len() - text[start..].trim_start().len();
+            if start >= text.len() {
+                break;
+            }
+
+            let rest = &text[start..];
+            let mut end = if rest.len() <= max_bytes {
+                rest.len()
             } else {
-                Err(anyhow!(
-                    "Semantic model is not loaded. Please initialize it first."
-                ))
+                let mut limit = max_bytes;
+                while !rest.is_char_boundary(limit) {
+                    limit -= 1;
+                }
+                let window = &rest[..limit];
+                // A sentence end in the second half of the window, else any whitespace
+                window
+                    .rmatch_indices(['.', '!', '?', '\n', '。', '！', '？'])
+                    .map(|(i, m)| i + m.len())
+                    .find(|i| *i > limit / 2)
+                    .or_else(|| window.rfind(char::is_whitespace).filter(|i| *i > 0))
+                    .unwrap_or(limit)
+            };
+            if end == 0 {
+                // Single character wider than max_bytes
+                end = rest.chars().next().map_or(rest.len(), char::len_utf8);
             }
-        })
-        .await?
+
+            let chunk = rest[..end].trim_end();
+            ranges.push(TextRange {
+                start,
+                end: start + chunk.len(),
+            });
+            start += end;
+        }
+
+        ranges
     }
 
     /// Calculate the cosine similarity between two vectors.
@@ -292,6 +396,58 @@ impl SemanticService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::services::embedding_backend::HashEmbeddingBackend;
+
+    #[test]
+    fn test_chunk_ranges() {
+        let text = "First sentence here. Second one is longer than that!  Third";
+        let chunks: Vec<&str> = SemanticService::chunk_ranges(text, 30)
+            .iter()
+            .map(|r| &text[r.start..r.end])
+            .collect();
+        assert_eq!(
+            chunks,
+            vec!["First sentence here.", "Second one is longer than", "that!  Third"]
+        );
+
+        assert_eq!(
+            SemanticService::chunk_ranges("  short  ", 30),
+            vec![TextRange { start: 2, end: 7 }]
+        );
+        assert!(SemanticService::chunk_ranges("   ", 30).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_best_chunks() {
+        let service = SemanticService::new(std::env::temp_dir());
+        service.set_backend(Box::new(HashEmbeddingBackend::new(64)));
+
+        let long = format!(
+            "{} kubernetes deployment manifest yaml. {}",
+            "grocery list bananas apples milk. ".repeat(20),
+            "weekend plans hiking trip. ".repeat(20)
+        );
+        let query = service
+            .embed("kubernetes deployment manifest".to_string())
+            .await
+            .unwrap();
+
+        let best = service
+            .best_chunks(&query, "test-hash", &[&long, "", "tiny"])
+            .await
+            .unwrap();
+        let range = best[0].unwrap();
+        assert!(long[range.start..range.end].contains("kubernetes"));
+        assert_eq!(best[1], None);
+        assert_eq!(best[2], Some(TextRange { start: 0, end: 4 }));
+
+        // A query embedded by another model is not compared against this one
+        let other = service
+            .best_chunks(&query, "other-model", &[&long, "tiny"])
+            .await
+            .unwrap();
+        assert_eq!(other, vec![None, None]);
+    }
 
     #[test]
     fn test_cosine_similarity() {
@@ -0,0 +1,195 @@
+use crate::models::settings::{AppSettings, ShortcutAction, ShortcutBinding};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Outcome of registering one shortcut, reported back to the settings UI.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShortcutStatus {
+    pub accelerator: String,
+    pub action: ShortcutAction,
+    pub registered: bool,
+    pub error: Option<String>,
+}
+
+/// Every enabled shortcut in priority order: the main toggle, the user's
+/// bindings, then the pin slots. On a conflict the earlier binding wins.
+pub fn collect_bindings(settings: &AppSettings) -> Vec<ShortcutBinding> {
+    let mut bindings = vec![ShortcutBinding {
+        accelerator: settings.global_shortcut.clone(),
+        action: ShortcutAction::ToggleWindow,
+        enabled: true,
+    }];
+
+    bindings.extend(settings.shortcuts.iter().filter(|b| b.enabled).cloned());
+
+    let modifiers = settings.pin_slot_modifiers.trim();
+    if !modifiers.is_empty() {
+        bindings.extend((1..=9).map(|slot| ShortcutBinding {
+            accelerator: format!("{}+{}", modifiers, slot),
+            action: ShortcutAction::PastePinSlot { slot },
+            enabled: true,
+        }));
+    }
+
+    bindings
+}
+
+/// Validates the bindings before anything is registered. Returns one entry
+/// per binding: `Some(error)` if it is malformed or uses the same keys as an
+/// earlier binding, `None` if it can be handed to the OS.
+pub fn check_conflicts(bindings: &[ShortcutBinding]) -> Vec<Option<String>> {
+    let mut seen: HashMap<String, &ShortcutBinding> = HashMap::new();
+
+    bindings
+        .iter()
+        .map(|binding| {
+            let key = normalize_accelerator(&binding.accelerator)
+                .ok_or_else(|| format!("Invalid shortcut \"{}\"", binding.accelerator))?;
+
+            if let Some(earlier) = seen.get(&key) {
+                return Err(format!(
+                    "Same keys as \"{}\" ({})",
+                    earlier.accelerator,
+                    earlier.action.name()
+                ));
+            }
+            seen.insert(key, binding);
+            Ok(())
+        })
+        .map(Result::err)
+        .collect()
+}
+
+/// Canonical form of an accelerator so that differently spelled but identical
+/// shortcuts compare equal: "control+shift+KeyV" and "Shift+Ctrl+V" both
+/// become "ctrl+shift+v". `None` unless there is exactly one non-modifier key.
+pub fn normalize_accelerator(accelerator: &str) -> Option<String> {
+    const ORDER: [&str; 4] = ["ctrl", "alt", "shift", "super"];
+
+    let mut modifiers = [false; 4];
+    let mut key = None;
+
+    for token in accelerator.split('+').map(|t| t.trim().to_lowercase()) {
+        let modifier = match token.as_str() {
+            "ctrl" | "control" => Some("ctrl"),
+            "alt" | "option" => Some("alt"),
+            "shift" => Some("shift"),
+            "cmd" | "command" | "super" | "meta" | "win" => Some("super"),
+            "cmdorctrl" | "cmdorcontrol" | "commandorctrl" | "commandorcontrol" => {
+                Some(if cfg!(target_os = "macos") {
+                    "super"
+                } else {
+                    "ctrl"
+                })
+            }
+            _ => None,
+        };
+
+        match modifier {
+            Some(m) => modifiers[ORDER.iter().position(|o| *o == m)?] = true,
+            None if token.is_empty() || key.is_some() => return None,
+            None => key = Some(canonical_key(&token)),
+        }
+    }
+
+    let mut parts: Vec<String> = ORDER
+        .iter()
+        .zip(modifiers)
+        .filter(|(_, set)| *set)
+        .map(|(name, _)| name.to_string())
+        .collect();
+    parts.push(key?);
+    Some(parts.join("+"))
+}
+
+/// "KeyV" → "v", "Digit1" → "1" (the W3C code names global-hotkey also accepts).
+fn canonical_key(token: &str) -> String {
+    let short = token
+        .strip_prefix("key")
+        .or_else(|| token.strip_prefix("digit"))
+        .filter(|rest| rest.chars().count() == 1);
+    short.unwrap_or(token).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binding(accelerator: &str, action: ShortcutAction) -> ShortcutBinding {
+        ShortcutBinding {
+            accelerator: accelerator.to_string(),
+            action,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn test_normalize_accelerator() {
+        assert_eq!(
+            normalize_accelerator("control+shift+KeyV").as_deref(),
+            Some("ctrl+shift+v")
+        );
+        assert_eq!(
+            normalize_accelerator("Shift + Ctrl + V"),
+            normalize_accelerator("ctrl+shift+v")
+        );
+        assert_eq!(
+            normalize_accelerator("Option+Command+Digit1").as_deref(),
+            Some("alt+super+1")
+        );
+        assert_eq!(normalize_accelerator("F5").as_deref(), Some("f5"));
+        assert_eq!(normalize_accelerator("Ctrl+Shift"), None);
+        assert_eq!(normalize_accelerator("Ctrl+A+B"), None);
+        assert_eq!(normalize_accelerator(""), None);
+    }
+
+    #[test]
+    fn test_check_conflicts() {
+        let bindings = vec![
+            binding("Ctrl+Shift+V", ShortcutAction::ToggleWindow),
+            binding("Ctrl+Alt+P", ShortcutAction::PauseCapture { minutes: None }),
+            binding("shift+control+v", ShortcutAction::PastePrevious),
+            binding("Ctrl+", ShortcutAction::ClearClipboard),
+        ];
+
+        let errors = check_conflicts(&bindings);
+        assert_eq!(errors[0], None);
+        assert_eq!(errors[1], None);
+        assert_eq!(
+            errors[2].as_deref(),
+            Some("Same keys as \"Ctrl+Shift+V\" (Toggle window)")
+        );
+        assert!(errors[3]
+            .as_deref()
+            .unwrap()
+            .starts_with("Invalid shortcut"));
+    }
+
+    #[test]
+    fn test_collect_bindings() {
+        let mut settings = AppSettings {
+            pin_slot_modifiers: "Ctrl+Alt+Shift".to_string(),
+            ..AppSettings::default()
+        };
+        settings.shortcuts = vec![
+            binding("Ctrl+Alt+P", ShortcutAction::PauseCapture { minutes: None }),
+            ShortcutBinding {
+                enabled: false,
+                ..binding("Ctrl+Alt+C", ShortcutAction::ClearClipboard)
+            },
+        ];
+
+        let bindings = collect_bindings(&settings);
+        assert_eq!(bindings.len(), 1 + 1 + 9);
+        assert_eq!(bindings[0].action, ShortcutAction::ToggleWindow);
+        assert_eq!(bindings[1].action, ShortcutAction::PauseCapture { minutes: None });
+        assert_eq!(bindings[10].accelerator, "Ctrl+Alt+Shift+9");
+
+        settings.pin_slot_modifiers = String::new();
+        assert_eq!(collect_bindings(&settings).len(), 2);
+
+        // Pin slots are opt-in
+        assert_eq!(collect_bindings(&AppSettings::default()).len(), 1);
+    }
+}
@@ -0,0 +1,373 @@
+use crate::models::{ClipFilters, ClipItem};
+use crate::repositories::ClipRepository;
+use crate::services::image_hash;
+use crate::services::semantic::SemanticService;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::task;
+
+/// Images further apart than this (more than 16 of 64 bits) are not "similar".
+const MIN_IMAGE_SIMILARITY: f32 = 0.75;
+
+/// Images without a stored hash decoded per request; the `image_hashes`
+/// backfill hashes the rest in the background.
+const MAX_HASHES_PER_REQUEST: usize = 20;
+
+/// How many keywords of the source clip feed the FTS fallback query.
+const MAX_KEYWORDS: usize = 12;
+
+const STOP_WORDS: &[&str] = &[
+    "the", "and", "for", "are", "but", "not", "you", "all", "any", "can", "had", "her", "was",
+    "one", "our", "out", "has", "have", "his", "how", "its", "let", "may", "she", "that", "this",
+    "with", "from", "they", "will", "would", "there", "their", "what", "when", "which", "your",
+    "into", "than", "then", "them", "these", "those", "been", "were", "also", "just", "more",
+    "some", "such", "only", "other", "about", "over", "very",
+];
+
+/// "More like this": ranks clips by how close they are to an existing clip.
+///
+/// Strategy depends on the source clip:
+/// - images → perceptual hash (dHash) distance
+/// - clips with a stored embedding → cosine similarity against other stored vectors
+/// - anything else → FTS query built from the clip's most frequent keywords
+pub struct SimilarityService {
+    repository: Arc<ClipRepository>,
+}
+
+impl SimilarityService {
+    pub fn new(repository: Arc<ClipRepository>) -> Self {
+        Self { repository }
+    }
+
+    /// Returns up to `limit` clips most similar to `id`, best first, with
+    /// `similarity_score` set (0.0–1.0). The source clip itself is excluded.
+    pub async fn find_similar(
+        &self,
+        id: &str,
+        limit: usize,
+        filters: &ClipFilters,
+    ) -> Result<Vec<ClipItem>> {
+        let clip = self
+            .repository
+            .get_by_id(id)
+            .await?
+            .ok_or_else(|| anyhow!("Clip not found"))?;
+
+        if clip.content_type == "image" {
+            return self.by_perceptual_hash(&clip, limit, filters).await;
+        }
+
+        if let Some(embedding) = self.repository.get_embedding(id).await? {
+            let results = self
+                .by_embedding(&clip.id, &embedding.vector, &embedding.model, limit, filters)
+                .await?;
+            if !results.is_empty() {
+                return Ok(results);
+            }
+        }
+
+        self.by_keywords(&clip, limit, filters).await
+    }
+
+    async fn by_embedding(
+        &self,
+        id: &str,
+        vector: &[u8],
+        model: &str,
+        limit: usize,
+        filters: &ClipFilters,
+    ) -> Result<Vec<ClipItem>> {
+        let source = SemanticService::bytes_to_vector(vector);
+
+        // Vectors from another model live in a different space, skip them
+        let scored: Vec<(String, f32)> = self
+            .repository
+            .get_embeddings_with_filters(filters)
+            .await?
+            .into_iter()
+            .filter(|emb| emb.clip_id != id && emb.model == model)
+            .map(|emb| {
+                let score = SemanticService::cosine_similarity(
+                    &source,
+                    &SemanticService::bytes_to_vector(&emb.vector),
+                );
+                (emb.clip_id, score)
+            })
+            .collect();
+
+        self.load_ranked(scored, limit).await
+    }
+
+    async fn by_perceptual_hash(
+        &self,
+        clip: &ClipItem,
+        limit: usize,
+        filters: &ClipFilters,
+    ) -> Result<Vec<ClipItem>> {
+        let Some(source) = perceptual_hash(&self.repository, clip).await else {
+            return Ok(Vec::new());
+        };
+
+        let mut scored = Vec::new();
+        let mut computed = 0;
+        for candidate in self.repository.get_image_clips(filters).await? {
+            if candidate.id == clip.id {
+                continue;
+            }
+            let hash = match image_hash::from_metadata(candidate.metadata.as_deref()) {
+                Some(hash) => Some(hash),
+                None if computed < MAX_HASHES_PER_REQUEST => {
+                    computed += 1;
+                    perceptual_hash(&self.repository, &candidate).await
+                }
+                None => None,
+            };
+            if let Some(hash) = hash {
+                let score = image_hash::similarity(source, hash);
+                if score >= MIN_IMAGE_SIMILARITY {
+                    scored.push((candidate.id, score));
+                }
+            }
+        }
+
+        self.load_ranked(scored, limit).await
+    }
+
+    async fn by_keywords(
+        &self,
+        clip: &ClipItem,
+        limit: usize,
+        filters: &ClipFilters,
+    ) -> Result<Vec<ClipItem>> {
+        let keywords = extract_keywords(clip.content_text.as_deref().unwrap_or(""), MAX_KEYWORDS);
+
+        let mut clips = self
+            .repository
+            .search_by_keywords(&keywords, filters, &clip.id, limit as i32)
+            .await?;
+
+        // bm25 is unbounded, squash it into 0..1 like the other strategies
+        for clip in &mut clips {
+            let relevance = clip.similarity_score.unwrap_or(0.0).max(0.0);
+            clip.similarity_score = Some(relevance / (relevance + 1.0));
+        }
+
+        Ok(clips)
+    }
+
+    async fn load_ranked(
+        &self,
+        mut scored: Vec<(String, f32)>,
+        limit: usize,
+    ) -> Result<Vec<ClipItem>> {
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+
+        let ids: Vec<String> = scored.iter().map(|(id, _)| id.clone()).collect();
+        let scores: HashMap<String, f32> = scored.into_iter().collect();
+
+        let mut clips = self.repository.get_clips_by_ids(&ids).await?;
+        for clip in &mut clips {
+            clip.similarity_score = scores.get(&clip.id).copied();
+        }
+
+        Ok(clips)
+    }
+}
+
+/// Hash from metadata, or computed from the image file and saved back for
+/// clips captured before hashes were stored (see also the `image_hashes`
+/// backfill).
+pub async fn perceptual_hash(repository: &ClipRepository, clip: &ClipItem) -> Option<u64> {
+    if let Some(hash) = image_hash::from_metadata(clip.metadata.as_deref()) {
+        return Some(hash);
+    }
+
+    let data = tokio::fs::read(clip.image_path.as_ref()?).await.ok()?;
+    let hash = task::spawn_blocking(move || image_hash::dhash(&data))
+        .await
+        .ok()?
+        .ok()?;
+
+    let mut metadata = clip
+        .metadata
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .filter(|v| v.is_object())
+        .unwrap_or_else(|| serde_json::json!({}));
+    metadata[image_hash::METADATA_KEY] = serde_json::json!(image_hash::to_hex(hash));
+    if let Err(e) = repository
+        .update_metadata(&clip.id, Some(metadata.to_string()))
+        .await
+    {
+        eprintln!("[ERROR] Failed to store perceptual hash: {}", e);
+    }
+
+    Some(hash)
+}
+
+/// Meaningful words of `text`: lowercased, with stop words and short or purely
+/// numeric tokens removed.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .map(|word| word.to_lowercase())
+        .filter(|word| {
+            word.chars().count() >= 3
+                && !word.chars().all(|c| c.is_ascii_digit())
+                && !STOP_WORDS.contains(&word.as_str())
+        })
+        .collect()
+}
+
+/// Most frequent `tokenize`d words of `text`, most frequent first.
+pub fn extract_keywords(text: &str, max: usize) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for word in tokenize(text) {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| {
+        b.1.cmp(&a.1)
+            .then_with(|| b.0.len().cmp(&a.0.len()))
+            .then_with(|| a.0.cmp(&b.0))
+    });
+
+    ranked.into_iter().take(max).map(|(word, _)| word).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::embedding_backend::{EmbeddingBackend, HashEmbeddingBackend};
+
+    fn clip(id: &str, content_type: &str, text: &str) -> ClipItem {
+        let mut clip = ClipItem::from_text(text.to_string(), "text".to_string(), None);
+        clip.id = id.to_string();
+        clip.content_type = content_type.to_string();
+        clip
+    }
+
+    #[test]
+    fn test_extract_keywords() {
+        let keywords = extract_keywords(
+            "The invoice total for the invoice #2024 is due. Invoice total: 42 EUR",
+            3,
+        );
+        assert_eq!(keywords, vec!["invoice", "total", "due"]);
+    }
+
+    #[tokio::test]
+    async fn test_similar_by_stored_embedding() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+        let repo = Arc::new(repo);
+        let backend = HashEmbeddingBackend::new(64);
+
+        let texts = [
+            ("a", "rust borrow checker lifetime error"),
+            ("b", "lifetime error from the rust borrow checker"),
+            ("c", "weekend grocery list bananas"),
+        ];
+        for (id, text) in texts {
+            repo.insert(&clip(id, "text", text)).await.unwrap();
+            let vector = backend.embed(&[text.to_string()]).unwrap().remove(0);
+            repo.create_embedding(id, SemanticService::vector_to_bytes(&vector), "test-hash", 64)
+                .await
+                .unwrap();
+        }
+
+        let service = SimilarityService::new(repo);
+        let results = service
+            .find_similar("a", 5, &ClipFilters::default())
+            .await
+            .unwrap();
+
+        let ids: Vec<&str> = results.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "c"]);
+        assert!(results[0].similarity_score.unwrap() > results[1].similarity_score.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_similar_images_hash_a_few_per_request() {
+        let (repo, dir) = ClipRepository::open_temp().await;
+        let repo = Arc::new(repo);
+
+        let path = dir.path().join("1.png");
+        image::RgbImage::from_fn(32, 32, |x, y| image::Rgb([(x * 8) as u8, (y * 8) as u8, 0]))
+            .save(&path)
+            .unwrap();
+        let count = MAX_HASHES_PER_REQUEST + 5;
+        for i in 0..=count {
+            let mut image = clip(&i.to_string(), "image", "");
+            image.content_text = None;
+            image.image_path = Some(path.to_string_lossy().to_string());
+            repo.insert(&image).await.unwrap();
+        }
+
+        let service = SimilarityService::new(repo.clone());
+        let filters = ClipFilters::default();
+        let first = service.find_similar("0", 100, &filters).await.unwrap();
+        assert_eq!(first.len(), MAX_HASHES_PER_REQUEST);
+        assert_eq!(first[0].similarity_score, Some(1.0));
+
+        // The next request hashes the rest
+        let second = service.find_similar("0", 100, &filters).await.unwrap();
+        assert_eq!(second.len(), count);
+    }
+
+    #[tokio::test]
+    async fn test_similar_falls_back_to_keywords() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+        let repo = Arc::new(repo);
+
+        repo.insert(&clip("a", "office", "Quarterly revenue forecast spreadsheet"))
+            .await
+            .unwrap();
+        repo.insert(&clip("b", "text", "revenue forecast for next quarter"))
+            .await
+            .unwrap();
+        repo.insert(&clip("c", "text", "unrelated note")).await.unwrap();
+
+        let service = SimilarityService::new(repo);
+        let results = service
+            .find_similar("a", 5, &ClipFilters::default())
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "b");
+        let score = results[0].similarity_score.unwrap();
+        assert!(score > 0.0 && score < 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_similar_images_by_perceptual_hash() {
+        let (repo, _dir) = ClipRepository::open_temp().await;
+        let repo = Arc::new(repo);
+
+        for (id, hash) in [
+            ("a", 0xffff_0000_ffff_0000u64),
+            ("b", 0xffff_0000_ffff_0001),
+            ("c", 0x0000_ffff_0000_ffff),
+        ] {
+            let mut image = clip(id, "image", "[Image]");
+            image.image_path = Some(format!("/nonexistent/{}.png", id));
+            image.metadata = Some(
+                serde_json::json!({ image_hash::METADATA_KEY: image_hash::to_hex(hash) })
+                    .to_string(),
+            );
+            repo.insert(&image).await.unwrap();
+        }
+
+        let service = SimilarityService::new(repo);
+        let results = service
+            .find_similar("a", 5, &ClipFilters::default())
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "b");
+        assert_eq!(results[0].similarity_score, Some(63.0 / 64.0));
+    }
+}
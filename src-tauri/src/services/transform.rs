@@ -0,0 +1,1010 @@
+use crate::models::ClipItem;
+use crate::repositories::ClipRepository;
+use crate::services::entities;
+use crate::services::intelligence::{self, ContentType, IntelligenceService};
+use crate::services::semantic::SemanticService;
+use anyhow::{anyhow, bail, Context, Result};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::{Arc, RwLock};
+
+/// Query parameters added by ad and analytics platforms.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid",
+    "gclid",
+    "gclsrc",
+    "dclid",
+    "gbraid",
+    "wbraid",
+    "msclkid",
+    "mc_cid",
+    "mc_eid",
+    "igshid",
+    "yclid",
+    "twclid",
+    "ttclid",
+    "li_fat_id",
+    "_ga",
+    "_gl",
+    "_hsenc",
+    "_hsmi",
+    "mkt_tok",
+    "oly_anon_id",
+    "oly_enc_id",
+    "vero_id",
+    "wickedid",
+    "s_cid",
+    "ref_src",
+];
+
+/// A transform offered for a clip, built-in or from a plugin.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+}
+
+/// Options of `apply_transform`. Each transform reads the ones it knows.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TransformOptions {
+    /// Spaces per level for `json_prettify` (default 2)
+    pub indent: Option<usize>,
+    /// CSV field separator (default: the detected one)
+    pub delimiter: Option<char>,
+    /// Save the result as a new clip derived from the source
+    pub save: bool,
+}
+
+/// Result of `apply_transform`; `clip` is set when the result was saved.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransformOutput {
+    pub text: String,
+    pub clip: Option<ClipItem>,
+}
+
+/// Transform function: clip text and detection metadata → new text
+type TransformFn = fn(&str, &Value, &TransformOptions) -> Result<String>;
+
+struct Transform {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    /// Detected types the transform applies to (empty = any text)
+    content_types: &'static [ContentType],
+    apply: TransformFn,
+}
+
+const fn transform(
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    content_types: &'static [ContentType],
+    apply: TransformFn,
+) -> Transform {
+    Transform {
+        id,
+        name,
+        description,
+        content_types,
+        apply,
+    }
+}
+
+/// Built-in transforms, in the order they are offered.
+const TRANSFORMS: &[Transform] = &[
+    transform(
+        "json_prettify",
+        "Prettify JSON",
+        "Indent JSON, keeping key order",
+        &[ContentType::Json],
+        json_prettify,
+    ),
+    transform(
+        "json_minify",
+        "Minify JSON",
+        "Remove all whitespace outside strings",
+        &[ContentType::Json],
+        json_minify,
+    ),
+    transform(
+        "csv_to_markdown",
+        "CSV → Markdown table",
+        "First row as the table header",
+        &[ContentType::Csv],
+        csv_to_markdown,
+    ),
+    transform(
+        "csv_to_json",
+        "CSV → JSON",
+        "Array of objects keyed by the first row",
+        &[ContentType::Csv],
+        csv_to_json,
+    ),
+    transform(
+        "color_to_hex",
+        "Color → HEX",
+        "#RRGGBB, or #RRGGBBAA with transparency",
+        &[ContentType::Color],
+        color_to_hex,
+    ),
+    transform(
+        "color_to_rgb",
+        "Color → RGB",
+        "rgb(r, g, b), or rgba() with transparency",
+        &[ContentType::Color],
+        color_to_rgb,
+    ),
+    transform(
+        "color_to_hsl",
+        "Color → HSL",
+        "hsl(h, s%, l%), or hsla() with transparency",
+        &[ContentType::Color],
+        color_to_hsl,
+    ),
+    transform(
+        "timestamp_to_iso",
+        "Timestamp → ISO 8601",
+        "Unix seconds or milliseconds as a UTC date",
+        &[ContentType::Timestamp],
+        timestamp_to_iso,
+    ),
+    transform(
+        "url_strip_tracking",
+        "Strip tracking parameters",
+        "Remove utm_*, fbclid, gclid and similar",
+        &[ContentType::Url],
+        url_strip_tracking,
+    ),
+    transform(
+        "escape_string_literal",
+        "Escape as string literal",
+        "Quoted, with quotes, backslashes and control characters escaped",
+        &[],
+        escape_string_literal,
+    ),
+];
+
+impl Transform {
+    fn info(&self) -> TransformInfo {
+        TransformInfo {
+            id: self.id.to_string(),
+            name: self.name.to_string(),
+            description: self.description.to_string(),
+        }
+    }
+}
+
+/// A find-and-replace transform declared by a plugin.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RegexTransformConfig {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Detected types it applies to (empty = any text)
+    #[serde(default)]
+    pub content_types: Vec<String>,
+    /// Regex replaced everywhere in the text
+    pub find: String,
+    /// Replacement; `$1` and `${name}` insert capture groups
+    pub replace: String,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+/// A plugin's `RegexTransformConfig`, compiled.
+#[derive(Debug, Clone)]
+pub struct RegexTransform {
+    info: TransformInfo,
+    content_types: Vec<String>,
+    find: Regex,
+    replace: String,
+}
+
+impl RegexTransform {
+    /// Compiles a transform of plugin `plugin_id`; its id becomes
+    /// `{plugin_id}/{id}`.
+    pub fn new(plugin_id: &str, config: &RegexTransformConfig) -> Result<Self> {
+        let find = RegexBuilder::new(&config.find)
+            .case_insensitive(config.case_insensitive)
+            .size_limit(1 << 20)
+            .build()
+            .map_err(|e| anyhow!("Invalid regex: {}", e))?;
+
+        Ok(Self {
+            info: TransformInfo {
+                id: format!("{}/{}", plugin_id, config.id),
+                name: config.name.clone(),
+                description: config.description.clone(),
+            },
+            content_types: config.content_types.clone(),
+            find,
+            replace: config.replace.clone(),
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        &self.info.id
+    }
+
+    fn applies_to(&self, detected_type: &str) -> bool {
+        self.content_types.is_empty() || self.content_types.iter().any(|t| t == detected_type)
+    }
+
+    fn apply(&self, text: &str) -> String {
+        self.find
+            .replace_all(text, self.replace.as_str())
+            .into_owned()
+    }
+}
+
+/// Rust-side transforms of clip text (JSON, CSV, colors, timestamps, URLs),
+/// offered by the clip's detected type, plus the regex transforms of plugins.
+pub struct TransformService {
+    repository: Arc<ClipRepository>,
+    semantic_service: Arc<SemanticService>,
+    plugin_transforms: RwLock<Vec<RegexTransform>>,
+}
+
+impl TransformService {
+    pub fn new(repository: Arc<ClipRepository>, semantic_service: Arc<SemanticService>) -> Self {
+        Self {
+            repository,
+            semantic_service,
+            plugin_transforms: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Replaces the transforms contributed by plugins.
+    pub fn set_plugin_transforms(&self, transforms: Vec<RegexTransform>) {
+        *self
+            .plugin_transforms
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = transforms;
+    }
+
+    /// Transforms that apply to any of the clip's types, built-in ones first,
+    /// empty for clips without text.
+    pub async fn list(&self, clip_id: &str) -> Result<Vec<TransformInfo>> {
+        let clip = self.get_clip(clip_id).await?;
+        if clip_text(&clip).is_none() {
+            return Ok(Vec::new());
+        }
+        let types = clip_types(&self.repository, &clip).await?;
+
+        let mut transforms: Vec<_> = transforms_for(&types).map(Transform::info).collect();
+        let plugin_transforms = self
+            .plugin_transforms
+            .read()
+            .unwrap_or_else(|e| e.into_inner());
+        transforms.extend(
+            plugin_transforms
+                .iter()
+                .filter(|t| types.iter().any(|ty| t.applies_to(ty)))
+                .map(|t| t.info.clone()),
+        );
+        Ok(transforms)
+    }
+
+    /// Runs a transform on the clip's text. With `options.save` the result is
+    /// also saved as a new clip (or an identical existing one is reused).
+    pub async fn apply(
+        &self,
+        clip_id: &str,
+        transform_id: &str,
+        options: &TransformOptions,
+    ) -> Result<TransformOutput> {
+        let clip = self.get_clip(clip_id).await?;
+        let text = clip_text(&clip).ok_or_else(|| anyhow!("Clip has no text to transform"))?;
+        let types = clip_types(&self.repository, &clip).await?;
+
+        let output = match builtin_transform(transform_id, &types)? {
+            Some(transform) => {
+                let metadata = clip
+                    .metadata
+                    .as_deref()
+                    .and_then(|m| serde_json::from_str(m).ok())
+                    .unwrap_or(Value::Null);
+                (transform.apply)(text, &metadata, options)?
+            }
+            None => {
+                let plugin_transforms = self
+                    .plugin_transforms
+                    .read()
+                    .unwrap_or_else(|e| e.into_inner());
+                let transform = plugin_transforms
+                    .iter()
+                    .find(|t| t.info.id == transform_id)
+                    .ok_or_else(|| anyhow!("Unknown transform: {}", transform_id))?;
+                if !types.iter().any(|ty| transform.applies_to(ty)) {
+                    bail!(
+                        "Transform {} does not apply to {} clips",
+                        transform_id,
+                        types.join("/")
+                    );
+                }
+                transform.apply(text)
+            }
+        };
+
+        let saved = if options.save {
+            Some(
+                save_derived(
+                    &self.repository,
+                    &self.semantic_service,
+                    &clip,
+                    transform_id,
+                    &output,
+                )
+                .await?,
+            )
+        } else {
+            None
+        };
+
+        Ok(TransformOutput {
+            text: output,
+            clip: saved,
+        })
+    }
+
+    async fn get_clip(&self, clip_id: &str) -> Result<ClipItem> {
+        self.repository
+            .get_by_id(clip_id)
+            .await?
+            .ok_or_else(|| anyhow!("Clip not found"))
+    }
+}
+
+/// Saves `text` as a text clip linked to the clip and transform it came from
+/// (or returns an identical existing clip). Also used for AI results.
+pub(crate) async fn save_derived(
+    repository: &Arc<ClipRepository>,
+    semantic_service: &Arc<SemanticService>,
+    source: &ClipItem,
+    transform_id: &str,
+    text: &str,
+) -> Result<ClipItem> {
+    let detections = IntelligenceService::detect_all(text);
+    let detection = &detections[0];
+    let mut clip = ClipItem::from_text(
+        text.to_string(),
+        detection.detected_type_str().to_string(),
+        detection.metadata_json(),
+    );
+    clip.app_name = source.app_name.clone();
+
+    // Same hash as copying the text, so it dedups against captured clips
+    if let Some(hash) = &clip.content_hash {
+        if let Some(existing) = repository.find_by_hash(hash).await? {
+            repository.touch(&existing.id).await?;
+            return repository
+                .get_by_id(&existing.id)
+                .await?
+                .ok_or_else(|| anyhow!("Clip not found"));
+        }
+    }
+
+    repository.insert(&clip).await?;
+    repository
+        .set_detected_types(
+            &clip.id,
+            &intelligence::secondary_types(&detections, &clip.detected_type),
+        )
+        .await?;
+    repository
+        .set_entities(&clip.id, &entities::extract_clip(&clip))
+        .await?;
+    repository
+        .add_relation(&clip.id, &source.id, Some(transform_id))
+        .await?;
+    semantic_service.spawn_clip_embedding(repository.clone(), &clip);
+
+    clip.parent_id = Some(source.id.clone());
+    Ok(clip)
+}
+
+pub(crate) fn clip_text(clip: &ClipItem) -> Option<&str> {
+    clip.content_text
+        .as_deref()
+        .filter(|text| !text.trim().is_empty())
+}
+
+/// The clip's primary type followed by its secondary types
+/// (`clip_detected_types`). Transforms, prompts and scripts for any of them
+/// are offered for the clip.
+pub(crate) async fn clip_types(
+    repository: &ClipRepository,
+    clip: &ClipItem,
+) -> Result<Vec<String>> {
+    let mut types = vec![clip.detected_type.clone()];
+    for secondary in repository.get_detected_types(&clip.id).await? {
+        if !types.contains(&secondary.detected_type) {
+            types.push(secondary.detected_type);
+        }
+    }
+    Ok(types)
+}
+
+fn transforms_for(types: &[String]) -> impl Iterator<Item = &'static Transform> + '_ {
+    TRANSFORMS.iter().filter(move |t| {
+        t.content_types.is_empty()
+            || t.content_types
+                .iter()
+                .any(|ct| types.iter().any(|ty| ct.as_str() == ty))
+    })
+}
+
+/// The built-in transform `id`, `None` if there is none (it may be a plugin
+/// transform). Fails if it exists but applies to none of `types`.
+fn builtin_transform(id: &str, types: &[String]) -> Result<Option<&'static Transform>> {
+    if let Some(transform) = transforms_for(types).find(|t| t.id == id) {
+        return Ok(Some(transform));
+    }
+    if TRANSFORMS.iter().any(|t| t.id == id) {
+        bail!(
+            "Transform {} does not apply to {} clips",
+            id,
+            types.join("/")
+        );
+    }
+    Ok(None)
+}
+
+// ============================================================================
+// JSON
+// ============================================================================
+
+fn json_prettify(text: &str, _: &Value, options: &TransformOptions) -> Result<String> {
+    let indent = " ".repeat(options.indent.unwrap_or(2));
+    reformat_json(text, Some(&indent))
+}
+
+fn json_minify(text: &str, _: &Value, _: &TransformOptions) -> Result<String> {
+    reformat_json(text, None)
+}
+
+/// Re-emits JSON token by token (`None` = minified), so key order and
+/// number spelling stay exactly as written.
+fn reformat_json(text: &str, indent: Option<&str>) -> Result<String> {
+    let text = text.trim();
+    serde_json::from_str::<serde::de::IgnoredAny>(text).context("Not valid JSON")?;
+
+    let mut out = String::with_capacity(text.len());
+    let mut depth = 0;
+    let mut chars = text.chars().peekable();
+
+    let newline = |out: &mut String, depth: usize| {
+        if let Some(indent) = indent {
+            out.push('\n');
+            out.push_str(&indent.repeat(depth));
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '{' | '[' => {
+                out.push(c);
+                while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                    chars.next();
+                }
+                if matches!(chars.peek(), Some('}' | ']')) {
+                    out.extend(chars.next());
+                } else {
+                    depth += 1;
+                    newline(&mut out, depth);
+                }
+            }
+            '}' | ']' => {
+                depth -= 1;
+                newline(&mut out, depth);
+                out.push(c);
+            }
+            ',' => {
+                out.push(c);
+                newline(&mut out, depth);
+            }
+            ':' => out.push_str(if indent.is_some() { ": " } else { ":" }),
+            c if c.is_whitespace() => {}
+            c => out.push(c),
+        }
+    }
+
+    Ok(out)
+}
+
+// ============================================================================
+// CSV
+// ============================================================================
+
+fn csv_to_markdown(text: &str, metadata: &Value, options: &TransformOptions) -> Result<String> {
+    let rows = parse_csv(text, csv_delimiter(metadata, options));
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if columns == 0 {
+        bail!("No CSV rows");
+    }
+
+    let line = |cells: &[String]| {
+        let cells: Vec<String> = (0..columns)
+            .map(|i| {
+                cells
+                    .get(i)
+                    .map(|cell| cell.replace('|', "\\|").replace('\n', "<br>"))
+                    .unwrap_or_default()
+            })
+            .collect();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let mut lines = vec![line(&rows[0]), format!("|{}", " --- |".repeat(columns))];
+    lines.extend(rows[1..].iter().map(|row| line(row)));
+    Ok(lines.join("\n"))
+}
+
+fn csv_to_json(text: &str, metadata: &Value, options: &TransformOptions) -> Result<String> {
+    let rows = parse_csv(text, csv_delimiter(metadata, options));
+    let Some((header, records)) = rows.split_first() else {
+        bail!("No CSV rows");
+    };
+
+    // Built by hand: serde_json maps would sort the columns
+    let objects: Vec<String> = records
+        .iter()
+        .map(|record| {
+            let fields: Vec<String> = header
+                .iter()
+                .enumerate()
+                .map(|(i, key)| {
+                    let value = record.get(i).map(String::as_str).unwrap_or("");
+                    format!("    {}: {}", Value::from(key.as_str()), csv_value(value))
+                })
+                .collect();
+            format!("  {{\n{}\n  }}", fields.join(",\n"))
+        })
+        .collect();
+
+    if objects.is_empty() {
+        return Ok("[]".to_string());
+    }
+    Ok(format!("[\n{}\n]", objects.join(",\n")))
+}
+
+/// A CSV field as JSON: numbers stay numbers, everything else is a string.
+fn csv_value(value: &str) -> String {
+    match serde_json::from_str::<serde_json::Number>(value) {
+        Ok(number) => number.to_string(),
+        Err(_) => Value::from(value).to_string(),
+    }
+}
+
+fn csv_delimiter(metadata: &Value, options: &TransformOptions) -> char {
+    options
+        .delimiter
+        .or_else(|| metadata.get("delimiter")?.as_str()?.chars().next())
+        .unwrap_or(',')
+}
+
+/// Splits CSV into rows of fields. Quoted fields may contain the delimiter,
+/// line breaks and `""` escaped quotes. Blank lines are skipped.
+fn parse_csv(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim().chars().peekable();
+
+    let mut end_row = |row: &mut Vec<String>, field: &mut String| {
+        row.push(std::mem::take(field).trim().to_string());
+        let row = std::mem::take(row);
+        if !(row.len() == 1 && row[0].is_empty()) {
+            rows.push(row);
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            c if quoted => field.push(c),
+            c if c == delimiter => row.push(std::mem::take(&mut field).trim().to_string()),
+            '\r' => {}
+            '\n' => end_row(&mut row, &mut field),
+            c => field.push(c),
+        }
+    }
+    end_row(&mut row, &mut field);
+
+    rows
+}
+
+// ============================================================================
+// Colors
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rgba {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: f32,
+}
+
+fn color_to_hex(text: &str, _: &Value, _: &TransformOptions) -> Result<String> {
+    let c = parse_color(text)?;
+    let hex = format!("#{:02X}{:02X}{:02X}", c.r, c.g, c.b);
+    Ok(if c.a < 1.0 {
+        format!("{}{:02X}", hex, (c.a * 255.0).round() as u8)
+    } else {
+        hex
+    })
+}
+
+fn color_to_rgb(text: &str, _: &Value, _: &TransformOptions) -> Result<String> {
+    let c = parse_color(text)?;
+    Ok(if c.a < 1.0 {
+        format!("rgba({}, {}, {}, {})", c.r, c.g, c.b, alpha(c.a))
+    } else {
+        format!("rgb({}, {}, {})", c.r, c.g, c.b)
+    })
+}
+
+fn color_to_hsl(text: &str, _: &Value, _: &TransformOptions) -> Result<String> {
+    let c = parse_color(text)?;
+    let (h, s, l) = rgb_to_hsl(c.r, c.g, c.b);
+    Ok(if c.a < 1.0 {
+        format!("hsla({}, {}%, {}%, {})", h, s, l, alpha(c.a))
+    } else {
+        format!("hsl({}, {}%, {}%)", h, s, l)
+    })
+}
+
+/// Alpha with at most two decimals, e.g. 0.5 or 0.33.
+fn alpha(a: f32) -> String {
+    let rounded = (a * 100.0).round() / 100.0;
+    rounded.to_string()
+}
+
+/// Parses `#rgb`, `#rgba`, `#rrggbb`, `#rrggbbaa`, `rgb()`/`rgba()` and
+/// `hsl()`/`hsla()`.
+fn parse_color(text: &str) -> Result<Rgba> {
+    let text = text.trim().to_ascii_lowercase();
+
+    if let Some(hex) = text.strip_prefix('#') {
+        // Checked first: the slicing below assumes one byte per character
+        if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            bail!("Not a color: {}", text);
+        }
+        let expanded: String = match hex.len() {
+            3 | 4 => hex.chars().flat_map(|c| [c, c]).collect(),
+            6 | 8 => hex.to_string(),
+            _ => bail!("Not a color: {}", text),
+        };
+        let byte = |i: usize| u8::from_str_radix(&expanded[i..i + 2], 16);
+        let (r, g, b) = (byte(0)?, byte(2)?, byte(4)?);
+        let a = if expanded.len() == 8 {
+            byte(6)? as f32 / 255.0
+        } else {
+            1.0
+        };
+        return Ok(Rgba { r, g, b, a });
+    }
+
+    let (name, args) = text
+        .strip_suffix(')')
+        .and_then(|t| t.split_once('('))
+        .ok_or_else(|| anyhow!("Not a color: {}", text))?;
+    let args: Vec<f32> = args
+        .split([',', ' ', '/'])
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| arg.trim_end_matches('%').parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| anyhow!("Not a color: {}", text))?;
+    if !(3..=4).contains(&args.len()) {
+        bail!("Not a color: {}", text);
+    }
+    let a = args.get(3).copied().unwrap_or(1.0).clamp(0.0, 1.0);
+
+    match name.trim() {
+        "rgb" | "rgba" => {
+            let channel = |v: f32| v.round().clamp(0.0, 255.0) as u8;
+            Ok(Rgba {
+                r: channel(args[0]),
+                g: channel(args[1]),
+                b: channel(args[2]),
+                a,
+            })
+        }
+        "hsl" | "hsla" => {
+            let (r, g, b) = hsl_to_rgb(args[0], args[1] / 100.0, args[2] / 100.0);
+            Ok(Rgba { r, g, b, a })
+        }
+        _ => bail!("Not a color: {}", text),
+    }
+}
+
+/// Hue in degrees, saturation and lightness in whole percent.
+fn rgb_to_hsl(r: u8, g: u8, b: u8) -> (u32, u32, u32) {
+    let (r, g, b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+
+    if d == 0.0 {
+        return (0, 0, (l * 100.0).round() as u32);
+    }
+
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        60.0 * ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / d + 2.0)
+    } else {
+        60.0 * ((r - g) / d + 4.0)
+    };
+
+    (
+        h.round() as u32 % 360,
+        (s * 100.0).round() as u32,
+        (l * 100.0).round() as u32,
+    )
+}
+
+fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (u8, u8, u8) {
+    let (s, l) = (s.clamp(0.0, 1.0), l.clamp(0.0, 1.0));
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    let channel = |v: f32| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+// ============================================================================
+// Timestamps, URLs, strings
+// ============================================================================
+
+fn timestamp_to_iso(text: &str, metadata: &Value, _: &TransformOptions) -> Result<String> {
+    let value: i64 = text.trim().parse().context("Not a Unix timestamp")?;
+
+    // Detection already decided the unit; otherwise 12+ digits means milliseconds
+    let millis = match metadata.get("unit").and_then(Value::as_str) {
+        Some(unit) => unit == "milliseconds",
+        None => value.abs() >= 100_000_000_000,
+    };
+
+    let (date, format) = if millis {
+        (
+            chrono::DateTime::from_timestamp_millis(value),
+            chrono::SecondsFormat::Millis,
+        )
+    } else {
+        (
+            chrono::DateTime::from_timestamp(value, 0),
+            chrono::SecondsFormat::Secs,
+        )
+    };
+    let date = date.ok_or_else(|| anyhow!("Timestamp out of range"))?;
+    Ok(date.to_rfc3339_opts(format, true))
+}
+
+fn url_strip_tracking(text: &str, _: &Value, _: &TransformOptions) -> Result<String> {
+    let mut url = url::Url::parse(text.trim()).context("Not a valid URL")?;
+
+    let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    let kept: Vec<&(String, String)> = pairs
+        .iter()
+        .filter(|(key, _)| {
+            let key = key.to_ascii_lowercase();
+            !key.starts_with("utm_") && !TRACKING_PARAMS.contains(&key.as_str())
+        })
+        .collect();
+
+    if kept.len() == pairs.len() {
+        return Ok(url.to_string());
+    }
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    Ok(url.to_string())
+}
+
+fn escape_string_literal(text: &str, _: &Value, _: &TransformOptions) -> Result<String> {
+    Ok(serde_json::to_string(text)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(id: &str, text: &str, metadata: Value) -> Result<String> {
+        let transform = TRANSFORMS.iter().find(|t| t.id == id).unwrap();
+        (transform.apply)(text, &metadata, &TransformOptions::default())
+    }
+
+    #[test]
+    fn test_transforms_for_type() {
+        let types =
+            |types: &[&str]| -> Vec<String> { types.iter().map(|t| t.to_string()).collect() };
+        let ids = |t: &[&str]| -> Vec<&str> { transforms_for(&types(t)).map(|t| t.id).collect() };
+        assert_eq!(
+            ids(&["json"]),
+            vec!["json_prettify", "json_minify", "escape_string_literal"]
+        );
+        assert_eq!(ids(&["text"]), vec!["escape_string_literal"]);
+        // A secondary type adds its transforms to those of the primary one
+        assert_eq!(
+            ids(&["text", "json"]),
+            vec!["json_prettify", "json_minify", "escape_string_literal"]
+        );
+
+        assert!(builtin_transform("json_minify", &types(&["json"]))
+            .unwrap()
+            .is_some());
+        assert!(builtin_transform("json_minify", &types(&["text", "json"]))
+            .unwrap()
+            .is_some());
+        assert!(builtin_transform("color_to_rgb", &types(&["text"])).is_err());
+        assert!(builtin_transform("plugin.upper", &types(&["text"]))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_json() {
+        let json = r#"{"b": [1, 2.50, {}], "a": {"s": "x, \"y\": z"}, "e": []}"#;
+        assert_eq!(
+            run("json_prettify", json, Value::Null).unwrap(),
+            "{\n  \"b\": [\n    1,\n    2.50,\n    {}\n  ],\n  \"a\": {\n    \"s\": \"x, \\\"y\\\": z\"\n  },\n  \"e\": []\n}"
+        );
+        assert_eq!(
+            run("json_minify", json, Value::Null).unwrap(),
+            r#"{"b":[1,2.50,{}],"a":{"s":"x, \"y\": z"},"e":[]}"#
+        );
+        assert!(run("json_minify", "{nope}", Value::Null).is_err());
+    }
+
+    #[test]
+    fn test_csv() {
+        let csv = "name;note;age\nAnn;\"says \"\"hi\"\"; twice\";30\nBob;a|b;";
+        let metadata = serde_json::json!({ "delimiter": ";" });
+        assert_eq!(
+            run("csv_to_markdown", csv, metadata.clone()).unwrap(),
+            "| name | note | age |\n| --- | --- | --- |\n| Ann | says \"hi\"; twice | 30 |\n| Bob | a\\|b |  |"
+        );
+        assert_eq!(
+            run("csv_to_json", csv, metadata).unwrap(),
+            "[\n  {\n    \"name\": \"Ann\",\n    \"note\": \"says \\\"hi\\\"; twice\",\n    \"age\": 30\n  },\n  {\n    \"name\": \"Bob\",\n    \"note\": \"a|b\",\n    \"age\": \"\"\n  }\n]"
+        );
+    }
+
+    #[test]
+    fn test_colors() {
+        assert_eq!(
+            run("color_to_rgb", "#3b82f6", Value::Null).unwrap(),
+            "rgb(59, 130, 246)"
+        );
+        assert_eq!(
+            run("color_to_hsl", "#3B82F6", Value::Null).unwrap(),
+            "hsl(217, 91%, 60%)"
+        );
+        assert_eq!(
+            run("color_to_hex", "hsl(217, 91%, 60%)", Value::Null).unwrap(),
+            "#3C83F6"
+        );
+        assert_eq!(
+            run("color_to_hex", "rgba(255, 0, 0, 0.5)", Value::Null).unwrap(),
+            "#FF000080"
+        );
+        assert_eq!(
+            run("color_to_rgb", "#f008", Value::Null).unwrap(),
+            "rgba(255, 0, 0, 0.53)"
+        );
+        assert!(run("color_to_hex", "#12345", Value::Null).is_err());
+        assert!(run("color_to_rgb", "#aaaéa", Value::Null).is_err());
+        assert!(run("color_to_rgb", "#ééé", Value::Null).is_err());
+    }
+
+    #[test]
+    fn test_timestamp_url_and_escape() {
+        assert_eq!(
+            run("timestamp_to_iso", "1710407520", Value::Null).unwrap(),
+            "2024-03-14T09:12:00Z"
+        );
+        assert_eq!(
+            run(
+                "timestamp_to_iso",
+                "1710407520123",
+                serde_json::json!({ "unit": "milliseconds" })
+            )
+            .unwrap(),
+            "2024-03-14T09:12:00.123Z"
+        );
+        assert_eq!(
+            run(
+                "url_strip_tracking",
+                "https://example.com/a?id=7&utm_source=x&UTM_Medium=y&fbclid=z#top",
+                Value::Null
+            )
+            .unwrap(),
+            "https://example.com/a?id=7#top"
+        );
+        assert_eq!(
+            run(
+                "url_strip_tracking",
+                "https://example.com/?gclid=1",
+                Value::Null
+            )
+            .unwrap(),
+            "https://example.com/"
+        );
+        assert_eq!(
+            run("escape_string_literal", "say \"hi\"\\\n\ttab", Value::Null).unwrap(),
+            r#""say \"hi\"\\\n\ttab""#
+        );
+    }
+
+    #[test]
+    fn test_regex_transform() {
+        let config = RegexTransformConfig {
+            id: "link".to_string(),
+            name: "Ticket Link".to_string(),
+            description: String::new(),
+            content_types: vec!["ticket".to_string()],
+            find: r"^(?P<key>[a-z]+-\d+)$".to_string(),
+            replace: "https://jira.example.com/browse/${key}".to_string(),
+            case_insensitive: true,
+        };
+        let transform = RegexTransform::new("jira", &config).unwrap();
+        assert_eq!(transform.id(), "jira/link");
+        assert!(transform.applies_to("ticket"));
+        assert!(!transform.applies_to("text"));
+        assert_eq!(
+            transform.apply("PROJ-12"),
+            "https://jira.example.com/browse/PROJ-12"
+        );
+
+        let config = RegexTransformConfig {
+            find: "(".to_string(),
+            ..config
+        };
+        assert!(RegexTransform::new("jira", &config).is_err());
+    }
+}
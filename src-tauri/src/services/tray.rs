@@ -0,0 +1,45 @@
+use crate::services::clipboard::CaptureState;
+use tauri::image::Image;
+use tauri::AppHandle;
+
+/// Id of the tray icon built in main.rs.
+pub const TRAY_ID: &str = "main";
+
+const TOOLTIP: &str = "Clips Next";
+
+/// App icon greyed out with a pause badge.
+const PAUSED_ICON: &[u8] = include_bytes!("../../icons/tray-paused.png");
+
+/// Shows the capture state on the tray icon: paused badge plus a tooltip
+/// saying until when.
+pub fn show_capture_state(app: &AppHandle, state: &CaptureState) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    let icon = if state.paused {
+        Image::from_bytes(PAUSED_ICON).ok()
+    } else {
+        app.default_window_icon().cloned()
+    };
+    if let Err(e) = tray.set_icon(icon) {
+        eprintln!("[TRAY] Failed to set icon: {}", e);
+    }
+    let _ = tray.set_tooltip(Some(tooltip(state)));
+}
+
+fn tooltip(state: &CaptureState) -> String {
+    if !state.paused {
+        return TOOLTIP.to_string();
+    }
+
+    let until = state
+        .resume_at
+        .and_then(|at| chrono::DateTime::from_timestamp(at, 0))
+        .map(|at| at.with_timezone(&chrono::Local).format("%H:%M").to_string());
+
+    match until {
+        Some(until) => format!("{} (capture paused until {})", TOOLTIP, until),
+        None => format!("{} (capture paused)", TOOLTIP),
+    }
+}
@@ -10,8 +10,9 @@ import { BottomBar } from '../../shared/components/BottomBar'
 import { ClipboardHistory } from '../clipboard/ClipboardHistory'
 import { Settings } from '../settings/Settings'
 import { Plugins } from '../settings/Plugins'
-import { useUIStore, useSettingsStore } from '../../stores'
+import { useUIStore, useSettingsStore, useClipboardStore } from '../../stores'
 import { useTheme } from '../../shared/hooks/useTheme'
+import type { SearchTab } from '../../shared/types'
 
 export const AppLayout = () => {
   const {
@@ -67,6 +68,18 @@ export const AppLayout = () => {
     }
   }, [setActiveView, resetSearch])
 
+  // Event Listener for the "Open Search" global shortcut
+  useEffect(() => {
+    const unlisten = listen<{ query: string; tab: SearchTab }>('open-search', event => {
+      setActiveView('clips')
+      void useClipboardStore.getState().setActiveTab(event.payload.tab)
+      setSearchQuery(event.payload.query)
+    })
+    return () => {
+      void unlisten.then(f => f())
+    }
+  }, [setActiveView, setSearchQuery])
+
   const handleClear = () => {
     resetSearch()
   }
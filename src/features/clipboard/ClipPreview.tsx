@@ -1,5 +1,6 @@
-import { useMemo } from 'react'
-import type { ClipItem } from '../../shared/types'
+import { useEffect, useMemo, useState } from 'react'
+import { invoke } from '@tauri-apps/api/core'
+import type { ClipItem, DetectedType } from '../../shared/types'
 import { ContentPreview, clipToContent, getTypeColor } from '../content'
 import { ClipActionsToolbar } from './ClipActionsToolbar'
 import { useClipboardStore } from '../../stores/clipboardStore'
@@ -14,6 +15,19 @@ export const ClipPreview = ({ clip }: ClipPreviewProps) => {
   // Convert ClipItem to unified Content
   const content = useMemo(() => clipToContent(clip), [clip])
 
+  // Types found besides the main one, which also decide the offered actions
+  const [secondaryTypes, setSecondaryTypes] = useState<DetectedType[]>([])
+  useEffect(() => {
+    let current = true
+    setSecondaryTypes([])
+    invoke<DetectedType[]>('get_detected_types', { id: clip.id })
+      .then(types => current && setSecondaryTypes(types))
+      .catch(() => {})
+    return () => {
+      current = false
+    }
+  }, [clip.id])
+
   const actionContext = useMemo(
     () => ({
       onDelete: (id: string) => deleteClip(id),
@@ -36,6 +50,15 @@ export const ClipPreview = ({ clip }: ClipPreviewProps) => {
               {content.type}
             </span>
           </div>
+          {secondaryTypes.map(t => (
+            <span
+              key={t.detectedType}
+              title={`${Math.round(t.confidence * 100)}% confidence`}
+              className="text-[10px] uppercase tracking-widest text-gray-500 dark:text-gray-500"
+            >
+              +{t.detectedType}
+            </span>
+          ))}
           <span className="text-xs text-gray-600 dark:text-gray-500 tabular-nums">
             {new Date(clip.createdAt * 1000).toLocaleString()}
           </span>
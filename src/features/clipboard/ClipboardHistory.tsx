@@ -3,7 +3,7 @@ import { listen } from '@tauri-apps/api/event'
 import { getCurrentWindow } from '@tauri-apps/api/window'
 import { useClipboardStore } from '../../stores'
 import { useSettingsStore } from '../../stores'
-import type { ClipItem } from '../../shared/types'
+import type { ClipItem, PasteFormat } from '../../shared/types'
 import { ClipboardListView } from './views'
 
 // Re-export for backwards compatibility
@@ -31,6 +31,7 @@ export const ClipboardHistory = ({
     deleteClip,
     toggleFavorite,
     togglePin,
+    addToPasteStack,
     copyToClipboard,
     pasteClip,
     enterSearchMode,
@@ -128,13 +129,13 @@ export const ClipboardHistory = ({
 
   // Unified action handler for Click and Enter
   const handleAction = useCallback(
-    async (text: string, clipId: string) => {
+    async (text: string, clipId: string, format?: PasteFormat) => {
       // Primary Action: Paste (default)
       if (settings?.paste_on_enter) {
-        await pasteClip(text, clipId)
+        await pasteClip(text, clipId, format)
       } else {
         // Primary Action: Copy
-        await copyToClipboard(text, clipId)
+        await copyToClipboard(text, clipId, format)
         // Hide if "Hide after Copy" is enabled
         if (settings?.hide_on_copy) {
           void getCurrentWindow().hide()
@@ -286,7 +287,8 @@ export const ClipboardHistory = ({
           e.preventDefault()
           const clip = clips[selectedIndex]
           if (clip) {
-            void handleAction(clip.contentText ?? '', clip.id)
+            // Shift+Enter: without formatting
+            void handleAction(clip.contentText ?? '', clip.id, e.shiftKey ? 'plain' : undefined)
           }
           break
         }
@@ -311,6 +313,11 @@ export const ClipboardHistory = ({
           if (clip) void togglePin(clip.id)
           break
         }
+        case 's': {
+          const clip = clips[selectedIndex]
+          if (clip) void addToPasteStack(clip.id)
+          break
+        }
         case '/': {
           // Focus search input - Handled by global layout now
           e.preventDefault()
@@ -330,6 +337,7 @@ export const ClipboardHistory = ({
     handleDelete,
     toggleFavorite,
     togglePin,
+    addToPasteStack,
   ])
 
   // ADDED: Notify parent of selection change for preview
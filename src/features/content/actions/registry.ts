@@ -25,6 +25,8 @@ import { useCallPhoneAction, useSmsAction } from './type-specific/PhoneActions'
 import { useCopyIsoDateAction, useCopyTimestampAction } from './type-specific/DateActions'
 import { useCsvToJsonAction, useCsvToMarkdownAction } from './type-specific/CSVActions'
 import { useRevealSecretAction } from './type-specific/SecretActions'
+import { useCopyAsMarkdownAction } from './type-specific/RichTextActions'
+import { useCopyOcrTextAction } from './type-specific/ImageActions'
 
 export const useActionRegistry = (context?: ActionContext) => {
   // 1. Initialize all action hooks
@@ -58,6 +60,10 @@ export const useActionRegistry = (context?: ActionContext) => {
 
   const revealSecret = useRevealSecretAction()
 
+  const copyAsMarkdown = useCopyAsMarkdownAction()
+
+  const copyOcrText = useCopyOcrTextAction()
+
   // 2. Define the master list of all available actions
   // Group 1: Standard Actions (Copy, Open)
   const standardActions = useMemo(
@@ -92,6 +98,8 @@ export const useActionRegistry = (context?: ActionContext) => {
       copyTimestamp,
       csvToJson,
       csvToMd,
+      copyAsMarkdown,
+      copyOcrText,
       formatCode,
       revealSecret,
     ],
@@ -107,6 +115,8 @@ export const useActionRegistry = (context?: ActionContext) => {
       copyTimestamp,
       csvToJson,
       csvToMd,
+      copyAsMarkdown,
+      copyOcrText,
       formatCode,
       revealSecret,
     ]
@@ -67,9 +67,12 @@ export const useOpenInDefaultEditorAction = (): SmartAction => ({
         extension = LANGUAGE_TO_EXTENSION[lang] || 'txt'
       }
 
+      // Saves in the editor are written back to plain-text clips; formatted
+      // ones open as a copy, since the saved text would drop their HTML/RTF
       await invoke('open_text_in_editor', {
         text: content.text,
         extension,
+        clipId: content.clip.contentType === 'text' ? content.clip.id : null,
       })
     } catch (error) {
       console.error('Failed to open in default editor:', error)
@@ -0,0 +1,23 @@
+import { ScanText } from 'lucide-react'
+import { invoke } from '@tauri-apps/api/core'
+import type { ClipItem } from '../../../../shared/types'
+import type { SmartAction } from '../../types'
+
+export const useCopyOcrTextAction = (): SmartAction => ({
+  id: 'copy-ocr-text',
+  label: 'Copy Text (OCR)',
+  icon: <ScanText size={16} />,
+  category: 'utility',
+  check: content => content.type === 'image',
+  execute: async content => {
+    try {
+      // Recognised in the background when the image was copied; run it now otherwise
+      const clip = content.metadata.ocr
+        ? content.clip
+        : await invoke<ClipItem>('ocr_clip', { id: content.clip.id })
+      await navigator.clipboard.writeText(clip.contentText ?? '')
+    } catch (error) {
+      console.error('Failed to read text from image:', error)
+    }
+  },
+})
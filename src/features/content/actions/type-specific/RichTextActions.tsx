@@ -0,0 +1,19 @@
+import { FileText } from 'lucide-react'
+import type { SmartAction } from '../../types'
+import { useClipboardStore } from '../../../../stores/clipboardStore'
+
+export const useCopyAsMarkdownAction = (): SmartAction => {
+  const { copyToClipboard } = useClipboardStore()
+
+  return {
+    id: 'copy-as-markdown',
+    label: 'Copy as Markdown',
+    icon: <FileText size={16} />,
+    category: 'transform',
+    // Converted by the backend (tables, lists, code blocks and links are kept)
+    check: content => content.clip.contentType === 'html' || content.clip.contentType === 'rtf',
+    execute: async content => {
+      await copyToClipboard(content.text, content.clip.id, 'markdown')
+    },
+  }
+}
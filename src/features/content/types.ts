@@ -58,6 +58,18 @@ export type ContentMetadata = {
   readonly pdf?: string
   readonly attachment_path?: string
   readonly source_app?: string
+
+  // Image text recognised by OCR (lines with bounding boxes in image pixels)
+  readonly ocr?: { readonly engine: string; readonly lines: OcrLine[] }
+}
+
+export type OcrLine = {
+  readonly text: string
+  readonly x: number
+  readonly y: number
+  readonly width: number
+  readonly height: number
+  readonly confidence: number // 0-100
 }
 
 // Unified content representation
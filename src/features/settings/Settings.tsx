@@ -4,8 +4,17 @@ import { getCurrentWindow } from '@tauri-apps/api/window'
 import { useSettingsStore } from '../../stores'
 import { useClipboardStore } from '../../stores'
 import { useTheme } from '../../shared/hooks/useTheme'
-import type { Theme, RetentionPolicy, PasteFormat, AppSettings } from '../../shared/types'
-import { Button, Switch, Select, Card } from '../../shared/components/ui'
+import type {
+  Theme,
+  RetentionPolicy,
+  PasteFormat,
+  AppSettings,
+  SearchTab,
+  ShortcutAction,
+  ShortcutBinding,
+  ShortcutStatus,
+} from '../../shared/types'
+import { Button, Switch, Select, Card, Input } from '../../shared/components/ui'
 import {
   Palette,
   Clipboard,
@@ -23,6 +32,8 @@ import {
   Timer,
   Clock,
   Calendar,
+  Plus,
+  X,
 } from 'lucide-react'
 
 type Tab = 'general' | 'clipboard' | 'storage' | 'privacy' | 'advanced'
@@ -207,6 +218,152 @@ const ShortcutRecorder = ({ value, onChange }: ShortcutRecorderProps) => {
   )
 }
 
+// --- ShortcutList: extra global shortcuts mapped to actions ---
+
+type ActionType = ShortcutAction['type']
+
+const actionOptions: { value: ActionType; label: string }[] = [
+  { value: 'toggle_window', label: 'Toggle Window' },
+  { value: 'paste_previous', label: 'Paste Previous Clip' },
+  { value: 'paste_plain_text', label: 'Paste as Plain Text' },
+  { value: 'open_search', label: 'Open Search' },
+  { value: 'pause_capture', label: 'Pause Capture' },
+  { value: 'clear_clipboard', label: 'Clear Clipboard' },
+  { value: 'cycle_history', label: 'Cycle Through History' },
+  { value: 'paste_stack_next', label: 'Paste Next from Stack' },
+]
+
+const tabOptions: { value: SearchTab; label: string }[] = [
+  { value: 'all', label: 'All' },
+  { value: 'favorites', label: 'Favorites' },
+  { value: 'pinned', label: 'Pinned' },
+]
+
+const pauseOptions = [
+  { value: '0', label: 'Until Resumed' },
+  { value: '15', label: '15 Minutes' },
+  { value: '60', label: '1 Hour' },
+]
+
+const actionFromType = (type: ActionType): ShortcutAction => {
+  switch (type) {
+    case 'open_search':
+      return { type, query: '', tab: 'all' }
+    case 'pause_capture':
+      return { type, minutes: null }
+    case 'paste_pin_slot':
+      return { type, slot: 1 }
+    default:
+      return { type }
+  }
+}
+
+type ShortcutListProps = {
+  readonly bindings: ShortcutBinding[]
+  readonly statuses: ShortcutStatus[]
+  readonly onChange: (bindings: ShortcutBinding[]) => void
+}
+
+const ShortcutList = ({ bindings, statuses, onChange }: ShortcutListProps) => {
+  const update = (index: number, changes: Partial<ShortcutBinding>) =>
+    onChange(bindings.map((b, i) => (i === index ? { ...b, ...changes } : b)))
+
+  const errorFor = (binding: ShortcutBinding) =>
+    statuses.find(
+      s =>
+        s.accelerator === binding.accelerator &&
+        JSON.stringify(s.action) === JSON.stringify(binding.action)
+    )?.error
+
+  return (
+    <div className="space-y-3">
+      {bindings.map((binding, index) => {
+        const error = binding.enabled ? errorFor(binding) : null
+        return (
+          <div key={index} className="space-y-1.5">
+            <div className="flex items-start justify-between gap-3">
+              <div className="flex items-center gap-2">
+                <Switch
+                  checked={binding.enabled}
+                  onChange={enabled => update(index, { enabled })}
+                />
+                <Select
+                  value={binding.action.type}
+                  onChange={type => update(index, { action: actionFromType(type) })}
+                  options={actionOptions}
+                  className="w-48"
+                />
+              </div>
+              <div className="flex items-start gap-1">
+                <ShortcutRecorder
+                  value={binding.accelerator}
+                  onChange={accelerator => update(index, { accelerator })}
+                />
+                <Button
+                  variant="ghost"
+                  size="sm"
+                  onClick={() => onChange(bindings.filter((_, i) => i !== index))}
+                >
+                  <X className="h-3.5 w-3.5" />
+                </Button>
+              </div>
+            </div>
+            {binding.action.type === 'open_search' && (
+              <div className="flex items-center gap-2 pl-12">
+                <Input
+                  value={binding.action.query}
+                  onChange={query =>
+                    binding.action.type === 'open_search' &&
+                    update(index, { action: { ...binding.action, query } })
+                  }
+                  placeholder="Search text, e.g. /image"
+                />
+                <Select
+                  value={binding.action.tab}
+                  onChange={tab =>
+                    binding.action.type === 'open_search' &&
+                    update(index, { action: { ...binding.action, tab } })
+                  }
+                  options={tabOptions}
+                  className="w-32"
+                />
+              </div>
+            )}
+            {binding.action.type === 'pause_capture' && (
+              <div className="flex items-center gap-2 pl-12">
+                <Select
+                  value={String(binding.action.minutes ?? 0)}
+                  onChange={value =>
+                    update(index, {
+                      action: { type: 'pause_capture', minutes: Number(value) || null },
+                    })
+                  }
+                  options={pauseOptions}
+                  className="w-40"
+                />
+              </div>
+            )}
+            {error && <p className="pl-12 text-xs text-red-500 dark:text-red-400">{error}</p>}
+          </div>
+        )
+      })}
+      <Button
+        variant="secondary"
+        size="sm"
+        leftIcon={<Plus className="h-3.5 w-3.5" />}
+        onClick={() =>
+          onChange([
+            ...bindings,
+            { accelerator: '', action: { type: 'paste_previous' }, enabled: true },
+          ])
+        }
+      >
+        Add Shortcut
+      </Button>
+    </div>
+  )
+}
+
 // --- Main Settings component ---
 
 export const Settings = () => {
@@ -214,6 +371,7 @@ export const Settings = () => {
   const clearAllClips = useClipboardStore(state => state.clearAllClips)
   const { setThemeMode } = useTheme()
   const [activeTab, setActiveTab] = useState<Tab>('general')
+  const [shortcutStatus, setShortcutStatus] = useState<ShortcutStatus[]>([])
 
   useEffect(() => {
     void loadSettings()
@@ -225,15 +383,19 @@ export const Settings = () => {
     }
   }, [settings?.theme, setThemeMode])
 
+  // Re-register all global shortcuts whenever any of them changes
+  const shortcutConfig = settings
+    ? JSON.stringify([settings.global_shortcut, settings.pin_slot_modifiers, settings.shortcuts])
+    : null
   useEffect(() => {
-    if (settings?.global_shortcut) {
-      invoke('register_global_shortcut', {
-        shortcut: settings.global_shortcut,
-      }).catch(err => {
-        console.error('Failed to register global shortcut:', err)
+    const current = useSettingsStore.getState().settings
+    if (!shortcutConfig || !current) return
+    invoke<ShortcutStatus[]>('register_shortcuts', { settings: current })
+      .then(setShortcutStatus)
+      .catch(err => {
+        console.error('Failed to register shortcuts:', err)
       })
-    }
-  }, [settings?.global_shortcut])
+  }, [shortcutConfig])
 
   useEffect(() => {
     if (settings?.always_on_top !== undefined) {
@@ -486,6 +648,20 @@ export const Settings = () => {
                     onChange={shortcut => void updateSettings({ global_shortcut: shortcut })}
                   />
                 </SettingRow>
+                {/* The first status is always the main toggle; pin slots come last */}
+                {shortcutStatus
+                  .filter((s, i) => s.error && (i === 0 || s.action.type === 'paste_pin_slot'))
+                  .map(s => (
+                    <p key={s.accelerator} className="text-xs text-red-500 dark:text-red-400">
+                      {s.accelerator}: {s.error}
+                    </p>
+                  ))}
+
+                <ShortcutList
+                  bindings={settings.shortcuts}
+                  statuses={shortcutStatus}
+                  onChange={shortcuts => void updateSettings({ shortcuts })}
+                />
               </SettingsSection>
 
               <SettingsSection
@@ -551,6 +727,16 @@ export const Settings = () => {
                     onChange={value => void updateSettings({ enable_office_formats: value })}
                   />
                 </SettingRow>
+
+                <SettingRow
+                  label="Recognize Text in Images"
+                  description="Make copied images searchable by their text (requires Tesseract)"
+                >
+                  <Switch
+                    checked={settings.enable_ocr}
+                    onChange={value => void updateSettings({ enable_ocr: value })}
+                  />
+                </SettingRow>
               </SettingsSection>
 
               <SettingsSection
@@ -580,7 +766,7 @@ export const Settings = () => {
                   />
                 </SettingRow>
 
-                <SettingRow label="Default Paste Format" description="Format to use when pasting">
+                <SettingRow label="Default Paste Format" description="Format to use when copying or pasting; Markdown converts rich text">
                   <Select
                     value={settings.default_paste_format}
                     onChange={value => void updateSettings({ default_paste_format: value })}
@@ -588,6 +774,19 @@ export const Settings = () => {
                     className="w-48"
                   />
                 </SettingRow>
+
+                <SettingRow
+                  label="External Editor"
+                  description="Command for Open in Editor, e.g. code --wait or $VISUAL; saves update the clip. Empty uses the default app"
+                >
+                  <input
+                    type="text"
+                    placeholder="Default app"
+                    value={settings.editor_command}
+                    onChange={e => void updateSettings({ editor_command: e.target.value })}
+                    className="w-48 rounded-lg border border-gray-300 dark:border-gray-700 bg-slate-100/10 dark:bg-slate-800 px-3 py-1.5 text-sm text-gray-900 dark:text-gray-100 focus:border-blue-500 focus:outline-none focus:ring-2 focus:ring-blue-500"
+                  />
+                </SettingRow>
               </SettingsSection>
 
               <SettingsSection
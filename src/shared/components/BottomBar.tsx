@@ -1,6 +1,9 @@
 import { useEffect, useState, type ReactNode } from 'react'
-import { Lightbulb } from 'lucide-react'
+import { invoke } from '@tauri-apps/api/core'
+import { listen } from '@tauri-apps/api/event'
+import { Layers, Lightbulb, PauseCircle, X } from 'lucide-react'
 import { useUIStore } from '../../stores'
+import type { CaptureState, PasteStack } from '../types'
 
 const Kbd = ({ children }: { children: ReactNode }) => (
   <span className="inline-flex items-center px-1.5 py-0.5 mx-0.5 rounded bg-slate-100/80 dark:bg-slate-100/10 border border-gray-300/70 dark:border-gray-100/10 text-[10px] font-mono font-semibold text-gray-700 dark:text-gray-200 leading-none">
@@ -13,6 +16,9 @@ const TIPS: ReactNode[] = [
   <>
     Press <Kbd>Enter</Kbd> to paste the selected clip.
   </>,
+  <>
+    Press <Kbd>Shift</Kbd> <Kbd>Enter</Kbd> to paste without formatting.
+  </>,
   <>
     Use <Kbd>↑</Kbd> <Kbd>↓</Kbd> arrows or <Kbd>J</Kbd> <Kbd>K</Kbd> to navigate.
   </>,
@@ -22,6 +28,9 @@ const TIPS: ReactNode[] = [
   <>
     Press <Kbd>F</Kbd> to favorite a clip or <Kbd>P</Kbd> to pin it.
   </>,
+  <>
+    Press <Kbd>S</Kbd> to queue clips, then paste them one by one with the paste stack shortcut.
+  </>,
   <>
     Press <Kbd>Delete</Kbd> or <Kbd>Backspace</Kbd> to remove a clip.
   </>,
@@ -31,6 +40,41 @@ export const BottomBar = () => {
   const { activeView } = useUIStore()
   const [currentTipIndex, setCurrentTipIndex] = useState(0)
   const [isFading, setIsFading] = useState(false)
+  const [capture, setCapture] = useState<CaptureState>({ paused: false, resumeAt: null })
+
+  // Capture can be paused from the tray, a shortcut or here
+  useEffect(() => {
+    invoke<CaptureState>('get_capture_state')
+      .then(setCapture)
+      .catch(() => {})
+    const unlisten = listen<CaptureState>('capture-state', event => setCapture(event.payload))
+    return () => {
+      void unlisten.then(f => f())
+    }
+  }, [])
+
+  const [pasteStack, setPasteStack] = useState<PasteStack | null>(null)
+
+  // Same queue with a different order or Tab option
+  const restartPasteStack = async (changes: Partial<Pick<PasteStack, 'order' | 'sendTab'>>) => {
+    if (!pasteStack) return
+    const next = { ...pasteStack, ...changes }
+    await invoke('start_paste_stack', {
+      ids: next.clipIds,
+      order: next.order,
+      sendTab: next.sendTab,
+    })
+  }
+
+  useEffect(() => {
+    invoke<PasteStack>('get_paste_stack')
+      .then(setPasteStack)
+      .catch(() => {})
+    const unlisten = listen<PasteStack>('paste-stack', event => setPasteStack(event.payload))
+    return () => {
+      void unlisten.then(f => f())
+    }
+  }, [])
 
   // Rotate tips every 10 seconds
   useEffect(() => {
@@ -51,18 +95,72 @@ export const BottomBar = () => {
 
   return (
     <div className="flex h-8 w-full shrink-0 select-none items-center justify-between px-4 text-[11px] text-gray-600 dark:text-gray-500">
-      {/* Left: Rotating Tips */}
-      <div className="flex items-center gap-2 overflow-hidden flex-1">
-        <Lightbulb className="h-3.5 w-3.5 text-yellow-600 dark:text-yellow-500/80 shrink-0" />
-        <span className="font-medium text-gray-700 dark:text-gray-400">Pro Tip:</span>
-        <span
-          className={`text-gray-700 dark:text-gray-300 truncate transition-opacity duration-500 ease-in-out ${
-            isFading ? 'opacity-0' : 'opacity-100'
-          }`}
-        >
-          {TIPS[currentTipIndex]}
-        </span>
-      </div>
+      {/* Left: Capture paused notice, otherwise rotating tips */}
+      {capture.paused ? (
+        <div className="flex items-center gap-2 overflow-hidden flex-1">
+          <PauseCircle className="h-3.5 w-3.5 text-amber-500 shrink-0" />
+          <span className="font-medium text-amber-600 dark:text-amber-400 truncate">
+            Capture paused
+            {capture.resumeAt &&
+              ` until ${new Date(capture.resumeAt * 1000).toLocaleTimeString([], {
+                hour: '2-digit',
+                minute: '2-digit',
+              })}`}
+          </span>
+          <button
+            type="button"
+            onClick={() => void invoke('resume_capture')}
+            className="text-blue-600 dark:text-blue-400 hover:underline shrink-0"
+          >
+            Resume
+          </button>
+        </div>
+      ) : (
+        <div className="flex items-center gap-2 overflow-hidden flex-1">
+          <Lightbulb className="h-3.5 w-3.5 text-yellow-600 dark:text-yellow-500/80 shrink-0" />
+          <span className="font-medium text-gray-700 dark:text-gray-400">Pro Tip:</span>
+          <span
+            className={`text-gray-700 dark:text-gray-300 truncate transition-opacity duration-500 ease-in-out ${
+              isFading ? 'opacity-0' : 'opacity-100'
+            }`}
+          >
+            {TIPS[currentTipIndex]}
+          </span>
+        </div>
+      )}
+
+      {/* Queued paste stack */}
+      {pasteStack && pasteStack.clipIds.length > 0 && (
+        <div className="flex items-center gap-1.5 shrink-0 pl-4 text-blue-600 dark:text-blue-400">
+          <Layers className="h-3.5 w-3.5" />
+          <span className="font-medium">{pasteStack.clipIds.length} queued</span>
+          <button
+            type="button"
+            title="Paste order"
+            onClick={() =>
+              void restartPasteStack({ order: pasteStack.order === 'fifo' ? 'lifo' : 'fifo' })
+            }
+            className="uppercase hover:underline"
+          >
+            {pasteStack.order}
+          </button>
+          <button
+            type="button"
+            title="Press Tab after each paste"
+            onClick={() => void restartPasteStack({ sendTab: !pasteStack.sendTab })}
+            className={`hover:underline ${pasteStack.sendTab ? '' : 'line-through opacity-60'}`}
+          >
+            Tab
+          </button>
+          <button
+            type="button"
+            onClick={() => void invoke('clear_paste_stack')}
+            className="hover:text-blue-800 dark:hover:text-blue-200"
+          >
+            <X className="h-3 w-3" />
+          </button>
+        </div>
+      )}
 
       {/* Right: Icon and Active View Indicator */}
       <div className="hidden sm:flex items-center gap-1 opacity-60 dark:opacity-40 uppercase shrink-0 pl-4">
@@ -16,6 +16,7 @@ export type ClipItem = {
   readonly contentText: string | null
   readonly contentHtml: string | null
   readonly contentRtf: string | null
+  readonly contentMarkdown: string | null // Derived from contentHtml for html clips
   readonly svgPath: string | null // SVG XML from Office apps
   readonly pdfPath: string | null // PDF document from Office apps
   readonly imagePath: string | null
@@ -27,11 +28,118 @@ export type ClipItem = {
   readonly updatedAt: number // Last access timestamp
   readonly appName: string | null
   readonly isPinned: boolean
+  readonly pinPosition: number | null // 1-based pin order, slots 1-9 have hotkeys
   readonly isFavorite: boolean
   readonly accessCount: number
   readonly contentHash: string | null
   readonly hasEmbedding?: boolean
   readonly similarityScore?: number
+  readonly parentId?: string | null // Clip this one was derived from (transform result, ...)
+}
+
+// Content type of a clip besides its detectedType (get_detected_types)
+export type DetectedType = {
+  readonly detectedType: string
+  readonly confidence: number // 0..1, lower for types found inside the text
+}
+
+export type EntityKind =
+  | 'url'
+  | 'email'
+  | 'phone'
+  | 'ipv4'
+  | 'ipv6'
+  | 'path'
+  | 'color'
+  | 'uuid'
+  | 'hash'
+  | 'ticket'
+
+// URL, email, ... found in a clip's text (get_clip_entities)
+export type ClipEntity = {
+  readonly kind: EntityKind
+  readonly value: string
+  readonly start: number // Byte offsets into contentText, not UTF-16 indices
+  readonly end: number
+}
+
+// A distinct entity value across clips (search_entities)
+export type EntitySummary = {
+  readonly kind: EntityKind
+  readonly value: string
+  readonly clipCount: number
+  readonly lastSeen: number // Latest updatedAt of those clips
+  readonly latestClipId: string
+}
+
+// Earlier version of an edited clip (get_clip_revisions / revert_clip_revision)
+export type ClipRevision = {
+  readonly id: number
+  readonly clipId: string
+  readonly contentType: ClipContentType
+  readonly contentText: string | null
+  readonly contentHtml: string | null
+  readonly contentRtf: string | null
+  readonly contentMarkdown: string | null
+  readonly detectedType: string | null
+  readonly metadata: string | null
+  readonly contentHash: string | null
+  readonly createdAt: number // When it was replaced
+}
+
+// User-defined AI prompt (list_prompt_templates); offered by list_ai_prompts
+// as `template:{id}` for clips whose detectedType is in contentTypes
+export type PromptTemplate = {
+  readonly id: number
+  readonly name: string
+  readonly description: string | null
+  readonly template: string // {{text}}, {{language}}, {{app_name}}
+  readonly contentTypes: readonly string[] // Empty = any text clip
+  readonly createdAt: number
+  readonly updatedAt: number
+}
+
+export type NewPromptTemplate = Pick<PromptTemplate, 'name' | 'template'> &
+  Partial<Pick<PromptTemplate, 'description' | 'contentTypes'>>
+
+// User script in the scripts directory (list_scripts)
+export type ScriptInfo = {
+  readonly id: string // File name without .rhai
+  readonly name: string
+  readonly description: string
+  readonly contentTypes: readonly string[] // Empty = any text clip
+  readonly grants: readonly string[] // Capabilities it asks for ("read_file", "http")
+  readonly error: string | null // Why it failed to load
+}
+
+// User-declared detector from detectors.toml / detectors.json (list_detectors)
+export type DetectorInfo = {
+  readonly name: string
+  readonly detectedType: string // Stored as the clip's detectedType; usable as a /filter
+  readonly pattern: string
+  readonly priority: number // Runs before built-ins with the same or a higher number
+  readonly file: string
+}
+
+export type DetectorList = {
+  readonly detectors: readonly DetectorInfo[]
+  readonly errors: readonly string[] // Skipped detectors and unreadable files
+}
+
+// Installed action pack (list_plugins), or one about to be (inspect_plugin)
+export type PluginInfo = {
+  readonly id: string
+  readonly name: string
+  readonly version: string
+  readonly description: string
+  readonly author: string | null
+  readonly permissions: readonly string[] // Grants its scripts may use ("read_file", "http")
+  readonly detectors: number // Contributions of each kind
+  readonly transforms: number
+  readonly promptTemplates: number
+  readonly scripts: number
+  readonly enabled: boolean
+  readonly error: string | null // Why it failed to load; it contributes nothing then
 }
 
 export type Tag = {
@@ -55,6 +163,35 @@ export type ClipWithTags = ClipItem & {
   readonly collections: Collection[]
 }
 
+// One page of a listing; pass nextCursor back to load the next page
+export type Page<T> = {
+  readonly items: T[]
+  readonly nextCursor: string | null
+}
+
+export type ClipChanges = {
+  readonly upserted: ClipItem[]
+  readonly deleted: string[]
+  readonly cursor: string
+  readonly hasMore: boolean
+  readonly reset: boolean // Deletes since the cursor were pruned: reload all clips, then continue from cursor
+}
+
+// Clipboard capture pause state (get_capture_state / "capture-state" event)
+export type CaptureState = {
+  readonly paused: boolean
+  readonly resumeAt: number | null // Unix seconds; null = paused until resumed
+}
+
+export type StackOrder = 'fifo' | 'lifo'
+
+// Clips queued for sequential pasting (get_paste_stack / "paste-stack" event)
+export type PasteStack = {
+  readonly clipIds: string[] // In queueing order
+  readonly order: StackOrder
+  readonly sendTab: boolean // Press Tab after each paste except the last
+}
+
 export type Result<T, E = string> = { ok: true; value: T } | { ok: false; error: E }
 
 // Helper functions using functional patterns
@@ -2,6 +2,41 @@ export type Theme = 'light' | 'dark' | 'auto'
 export type ViewMode = 'list' | 'grid'
 export type RetentionPolicy = 'unlimited' | 'days' | 'count'
 export type PasteFormat = 'auto' | 'plain' | 'html' | 'markdown'
+export type EmbeddingBackendKind = 'fastembed' | 'openai'
+
+export type SearchTab = 'all' | 'favorites' | 'pinned'
+
+// Mirrors the Rust ShortcutAction enum (tagged by `type`)
+export type ShortcutAction =
+  | { type: 'toggle_window' }
+  | { type: 'paste_previous' }
+  | { type: 'paste_plain_text' }
+  | { type: 'open_search'; query: string; tab: SearchTab } // query may contain /type filters
+  | { type: 'pause_capture'; minutes: number | null } // null = until resumed
+  | { type: 'clear_clipboard' }
+  | { type: 'cycle_history' }
+  | { type: 'paste_pin_slot'; slot: number }
+  | { type: 'paste_stack_next' }
+
+export interface ShortcutBinding {
+  accelerator: string
+  action: ShortcutAction
+  enabled: boolean
+}
+
+// Result of registering one shortcut (returned by register_shortcuts)
+export interface ShortcutStatus {
+  accelerator: string
+  action: ShortcutAction
+  registered: boolean
+  error: string | null
+}
+
+export interface OpenAiEndpoint {
+  base_url: string
+  model: string
+  api_key: string | null
+}
 
 export interface AppSettings {
   // General
@@ -10,12 +45,15 @@ export interface AppSettings {
 
   // Shortcuts
   global_shortcut: string
+  pin_slot_modifiers: string // Pin slots 1-9 are "<modifiers>+<digit>", empty = disabled (default)
+  shortcuts: ShortcutBinding[] // Extra shortcuts on top of global_shortcut and the pin slots
 
   // Clipboard monitoring
   enable_images: boolean
   enable_files: boolean
   enable_rich_text: boolean
   enable_office_formats: boolean
+  enable_ocr: boolean // Needs tesseract installed
   excluded_apps: string[]
 
   // Storage & History
@@ -38,6 +76,9 @@ export interface AppSettings {
   hide_on_blur: boolean
   always_on_top: boolean
 
+  // External editor
+  editor_command: string // e.g. "code --wait" or "$VISUAL"; empty = system default app
+
   // Notifications
   show_copy_toast: boolean
 
@@ -47,16 +88,24 @@ export interface AppSettings {
   // Plugins
   semantic_search_enabled: boolean
   semantic_model: string
+  embedding_backend: EmbeddingBackendKind
+  embedding_endpoint: OpenAiEndpoint
+  ai_endpoint: OpenAiEndpoint // Chat API for the AI prompts
+  script_grants: Record<string, string[]> // Script id -> allowed capabilities ("read_file", "http")
+  disabled_plugins: string[] // Ids of installed plugins that are turned off
 }
 
 export const DEFAULT_SETTINGS: AppSettings = {
   theme: 'auto',
   language: 'en',
   global_shortcut: 'Cmd+Shift+V',
+  pin_slot_modifiers: '',
+  shortcuts: [],
   enable_images: true,
   enable_files: true,
   enable_rich_text: true,
   enable_office_formats: true,
+  enable_ocr: true,
   excluded_apps: [],
   history_limit: 1000,
   retention_policy: 'unlimited',
@@ -72,8 +121,22 @@ export const DEFAULT_SETTINGS: AppSettings = {
   paste_on_enter: true,
   hide_on_blur: true,
   always_on_top: false,
+  editor_command: '',
   show_copy_toast: true,
   has_seen_welcome: false,
   semantic_search_enabled: false,
   semantic_model: 'all-MiniLM-L6-v2',
+  embedding_backend: 'fastembed',
+  embedding_endpoint: {
+    base_url: 'http://localhost:11434/v1',
+    model: 'nomic-embed-text',
+    api_key: null,
+  },
+  ai_endpoint: {
+    base_url: 'http://localhost:11434/v1',
+    model: 'llama3.2',
+    api_key: null,
+  },
+  script_grants: {},
+  disabled_plugins: [],
 }
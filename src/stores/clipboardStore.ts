@@ -2,14 +2,14 @@ import { create } from 'zustand'
 import { invoke } from '@tauri-apps/api/core'
 import { useSettingsStore } from './settingsStore'
 import { useUIStore } from './uiStore'
-import type { ClipItem, Result } from '../shared/types'
+import type { ClipItem, Page, PasteFormat, PasteStack, Result } from '../shared/types'
 
 type ClipboardState = {
   clips: ClipItem[]
   loading: boolean
   error: string | null
   hasMore: boolean
-  currentOffset: number
+  cursor: string | null // nextCursor of the last loaded page
   // Search mode state
   mode: 'browse' | 'search' // Track whether we're browsing or searching
   searchQuery: string // Current search query (empty = browse mode)
@@ -27,9 +27,11 @@ type ClipboardActions = {
   deleteClip: (id: string) => Promise<void>
   toggleFavorite: (id: string) => Promise<void>
   togglePin: (id: string) => Promise<void>
+  addToPasteStack: (id: string) => Promise<void>
   clearAllClips: () => Promise<void>
-  copyToClipboard: (text: string, id?: string) => Promise<Result<void>>
-  pasteClip: (text: string, id?: string) => Promise<Result<void>>
+  // format overrides settings.default_paste_format for this call
+  copyToClipboard: (text: string, id?: string, format?: PasteFormat) => Promise<Result<void>>
+  pasteClip: (text: string, id?: string, format?: PasteFormat) => Promise<Result<void>>
   resetPagination: () => void
   generateEmbedding: (id: string) => Promise<void>
 }
@@ -41,7 +43,7 @@ const initialState: ClipboardState = {
   loading: false,
   error: null,
   hasMore: true,
-  currentOffset: 0,
+  cursor: null,
   mode: 'browse',
   searchQuery: '',
   activeTab: 'all',
@@ -49,8 +51,9 @@ const initialState: ClipboardState = {
 
 // Helper to parse slash commands from query
 // Example: "apple /image" -> { query: "apple", filterTypes: ["image"] }
+// Custom detector types work too: "/order_number"
 const parseSearchQuery = (input: string): { query: string; filterTypes: string[] | null } => {
-  const typeRegex = /\/([a-z]+)/g
+  const typeRegex = /\/([a-z][a-z0-9_]*)/g
   const matches = input.match(typeRegex)
 
   if (!matches) {
@@ -74,13 +77,13 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
   // - if searchQuery starts with "semantic:" -> use semantic_search_paginated
   // - otherwise -> use FTS search_clips_paginated
   loadMoreClips: async (limit = 50) => {
-    const { currentOffset, hasMore, loading, mode, searchQuery, activeTab } =
+    const { cursor, hasMore, loading, mode, searchQuery, activeTab } =
       useClipboardStore.getState()
     if (!hasMore || loading) return
 
     set({ loading: true, error: null })
     try {
-      let newClips: ClipItem[]
+      let page: Page<ClipItem>
       const favoritesOnly = activeTab === 'favorites'
       const pinnedOnly = activeTab === 'pinned'
 
@@ -89,30 +92,30 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
         const { query, filterTypes } = parseSearchQuery(searchQuery)
 
         const isSemanticActive = useUIStore.getState().isSemanticActive
-        newClips = await invoke<ClipItem[]>('search_clips_paginated', {
+        page = await invoke<Page<ClipItem>>('search_clips_paginated', {
           query,
           filterTypes,
           limit,
-          offset: currentOffset,
+          cursor,
           favoritesOnly,
           pinnedOnly,
           useSemanticSearch: isSemanticActive && (!filterTypes || filterTypes.length === 0),
         })
       } else {
         // Browse mode: Standard chronological pagination
-        newClips = await invoke<ClipItem[]>('get_recent_clips_paginated', {
+        page = await invoke<Page<ClipItem>>('get_recent_clips_paginated', {
           limit,
-          offset: currentOffset,
+          cursor,
           favoritesOnly,
           pinnedOnly,
         })
       }
 
       set(state => ({
-        clips: [...state.clips, ...newClips],
+        clips: [...state.clips, ...page.items],
         loading: false,
-        hasMore: newClips.length === limit,
-        currentOffset: state.currentOffset + newClips.length,
+        hasMore: page.nextCursor !== null,
+        cursor: page.nextCursor,
       }))
     } catch (error) {
       console.error('Failed to load more clips:', error)
@@ -133,17 +136,16 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
           clips: state.clips.map((c, i) => (i === existingIndex ? clip : c)),
         }
       } else {
-        // New clip - prepend and increment offset
+        // New clip - prepend (the cursor is unaffected)
         return {
           clips: [clip, ...state.clips],
-          currentOffset: state.currentOffset + 1,
         }
       }
     })
   },
 
   resetPagination: () => {
-    set({ currentOffset: 0, hasMore: true })
+    set({ cursor: null, hasMore: true })
   },
 
   setActiveTab: async (tab: 'all' | 'favorites' | 'pinned') => {
@@ -153,7 +155,7 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
     set({
       activeTab: tab,
       clips: [],
-      currentOffset: 0,
+      cursor: null,
       hasMore: true,
     })
 
@@ -168,7 +170,7 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
       mode: 'search',
       searchQuery: rawQuery,
       clips: [],
-      currentOffset: 0,
+      cursor: null,
       hasMore: true,
       loading: true,
       error: null,
@@ -182,21 +184,21 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
       const favoritesOnly = activeTab === 'favorites'
       const pinnedOnly = activeTab === 'pinned'
 
-      const clips = await invoke<ClipItem[]>('search_clips_paginated', {
+      const page = await invoke<Page<ClipItem>>('search_clips_paginated', {
         query,
         filterTypes,
         limit: 50,
-        offset: 0,
+        cursor: null,
         favoritesOnly,
         pinnedOnly,
         useSemanticSearch: isSemanticActive && (!filterTypes || filterTypes.length === 0),
         similarityThreshold: 0.3,
       })
       set({
-        clips,
+        clips: page.items,
         loading: false,
-        hasMore: clips.length === 50,
-        currentOffset: clips.length,
+        hasMore: page.nextCursor !== null,
+        cursor: page.nextCursor,
       })
     } catch (error) {
       console.error('Failed to search clips:', error)
@@ -210,7 +212,7 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
       mode: 'browse',
       searchQuery: '',
       clips: [],
-      currentOffset: 0,
+      cursor: null,
       hasMore: true,
     })
     // Automatically load first page of browse results
@@ -249,7 +251,6 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
       await invoke('delete_clip', { id })
       set(state => ({
         clips: state.clips.filter(clip => clip.id !== id),
-        currentOffset: state.currentOffset - 1,
       }))
     } catch (error) {
       console.error('Failed to delete clip:', error)
@@ -272,8 +273,15 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
   togglePin: async (id: string) => {
     try {
       const isPinned = await invoke<boolean>('toggle_pin', { id })
+      // Unpinning shifts the positions of later pins, so refresh them all
+      const pinned = await invoke<ClipItem[]>('get_pinned_clips')
+      const positions = new Map(pinned.map(clip => [clip.id, clip.pinPosition]))
       set(state => ({
-        clips: state.clips.map(clip => (clip.id === id ? { ...clip, isPinned } : clip)),
+        clips: state.clips.map(clip => ({
+          ...clip,
+          isPinned: clip.id === id ? isPinned : clip.isPinned,
+          pinPosition: positions.get(clip.id) ?? null,
+        })),
       }))
     } catch (error) {
       console.error('Failed to toggle pin:', error)
@@ -281,6 +289,15 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
     }
   },
 
+  addToPasteStack: async (id: string) => {
+    try {
+      await invoke<PasteStack>('push_paste_stack', { id })
+    } catch (error) {
+      console.error('Failed to add to paste stack:', error)
+      set({ error: String(error) })
+    }
+  },
+
   clearAllClips: async () => {
     set({ loading: true, error: null })
     try {
@@ -291,9 +308,13 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
     }
   },
 
-  copyToClipboard: async (text: string, id?: string): Promise<Result<void>> => {
+  copyToClipboard: async (
+    text: string,
+    id?: string,
+    format?: PasteFormat
+  ): Promise<Result<void>> => {
     try {
-      await invoke('copy_to_clipboard', { text, id })
+      await invoke('copy_to_clipboard', { text, id, format })
       return { ok: true, value: undefined }
     } catch (error) {
       console.error('Failed to copy:', error)
@@ -301,9 +322,9 @@ export const useClipboardStore = create<ClipboardStore>(set => ({
     }
   },
 
-  pasteClip: async (text: string, id?: string): Promise<Result<void>> => {
+  pasteClip: async (text: string, id?: string, format?: PasteFormat): Promise<Result<void>> => {
     try {
-      await invoke('paste_clip', { text, id })
+      await invoke('paste_clip', { text, id, format })
       return { ok: true, value: undefined }
     } catch (error) {
       console.error('Failed to paste:', error)